@@ -0,0 +1,6 @@
+fn main() {
+    // A bundled protoc is used so no system install is required
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().unwrap());
+
+    tonic_build::compile_protos("proto/configd.proto").unwrap();
+}
@@ -2,7 +2,10 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::domain::{
-    configs::Password, errors::Error, events::Publisher, schemas::SchemaRepository, shared::Id,
+    configs::{ConfigRepository, Password, PasswordLockout},
+    errors::Error,
+    events::Publisher,
+    shared::Id,
 };
 
 #[derive(Deserialize)]
@@ -24,41 +27,48 @@ pub struct ChangeConfigPasswordResponse {
 
 pub struct ChangeConfigPassword {
     event_publisher: Arc<dyn Publisher + Sync + Send>,
-    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    password_lockout: Arc<PasswordLockout>,
 }
 
 impl ChangeConfigPassword {
     pub fn new(
         event_publisher: Arc<dyn Publisher + Sync + Send>,
-        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+        password_lockout: Arc<PasswordLockout>,
     ) -> ChangeConfigPassword {
         ChangeConfigPassword {
             event_publisher,
-            schema_repository,
+            config_repository,
+            password_lockout,
         }
     }
 
+    #[tracing::instrument(name = "change_config_password", skip_all, fields(schema_id = %cmd.schema_id, config_id = %cmd.config_id))]
     pub async fn exec(
         &self,
         cmd: ChangeConfigPasswordCommand,
     ) -> Result<ChangeConfigPasswordResponse, Error> {
         let schema_id = Id::new(cmd.schema_id)?;
-
-        let mut schema = self
-            .schema_repository
-            .find_by_id(&schema_id)
-            .await?
-            .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
-
         let config_id = Id::new(cmd.config_id)?;
         let old_password = cmd.old_password.map(Password::new).transpose()?;
         let new_password = Password::new(cmd.new_password)?;
 
-        schema.change_config_password(&config_id, old_password.as_ref(), new_password)?;
+        let mut config = self
+            .config_repository
+            .find_by_id(&schema_id, &config_id)
+            .await?
+            .ok_or_else(|| Error::ConfigNotFound(config_id.clone()))?;
+
+        self.password_lockout
+            .verify(&config, old_password.as_ref())
+            .await?;
+
+        config.change_password(old_password.as_ref(), new_password)?;
 
-        self.schema_repository.save(&mut schema).await?;
+        self.config_repository.save(&mut config).await?;
 
-        self.event_publisher.publish(schema.events()).await?;
+        self.event_publisher.publish(config.events()).await?;
 
         Ok(ChangeConfigPasswordResponse {
             schema_id: schema_id.to_string(),
@@ -2,51 +2,41 @@ use async_trait::async_trait;
 use std::sync::Arc;
 
 use crate::domain::{
+    configs::{AccessStore, ConfigDeleted, ConfigPurged},
     errors::Error,
-    events::{Event, Handler, Publisher},
-    schemas::{ConfigAccessed, SchemaRepository},
+    events::{Event, Handler},
     shared::Id,
 };
 
+// Deleted and purged configs don't keep their accesses
 pub struct CleanConfigAccesses {
-    event_publisher: Arc<dyn Publisher + Sync + Send>,
-    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    access_store: Arc<AccessStore>,
 }
 
 impl CleanConfigAccesses {
-    pub fn new(
-        event_publisher: Arc<dyn Publisher + Sync + Send>,
-        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
-    ) -> CleanConfigAccesses {
-        CleanConfigAccesses {
-            event_publisher,
-            schema_repository,
-        }
+    pub fn new(access_store: Arc<AccessStore>) -> CleanConfigAccesses {
+        CleanConfigAccesses { access_store }
     }
 }
 
 #[async_trait]
 impl Handler for CleanConfigAccesses {
+    #[tracing::instrument(name = "clean_config_accesses", skip_all, fields(schema_id = event.entity_id()))]
     async fn handle(&self, event: &Event) -> Result<(), Error> {
-        if event.topic() == "config.accessed" {
-            let payload: ConfigAccessed = event.deserialize_payload()?;
-
-            let schema_id = Id::new(payload.schema_id)?;
-            let mut schema = self
-                .schema_repository
-                .find_by_id(&schema_id)
-                .await?
-                .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
-
-            let config_id = Id::new(payload.id)?;
-
-            schema.clean_config_accesses(&config_id)?;
-
-            self.schema_repository.save(&mut schema).await?;
-
-            self.event_publisher.publish(schema.events()).await?;
-        }
-
-        Ok(())
+        let (schema_id, config_id) = match event.topic() {
+            "config.deleted" => {
+                let payload: ConfigDeleted = event.deserialize_payload()?;
+                (payload.schema_id, payload.id)
+            }
+            "config.purged" => {
+                let payload: ConfigPurged = event.deserialize_payload()?;
+                (payload.schema_id, payload.id)
+            }
+            _ => return Ok(()),
+        };
+
+        self.access_store
+            .remove(&Id::new(schema_id)?, &Id::new(config_id)?)
+            .await
     }
 }
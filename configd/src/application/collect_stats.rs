@@ -0,0 +1,51 @@
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::domain::{
+    configs::{AccessStore, ConfigRepository},
+    errors::Error,
+    schemas::SchemaRepository,
+};
+
+#[derive(Serialize)]
+pub struct StatsResponse {
+    pub schemas: u64,
+    pub configs: u64,
+    pub invalid_configs: u64,
+    pub active_instances: u64,
+}
+
+// Counted by the repositories, so scraping doesn't load every config
+pub struct CollectStats {
+    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    access_store: Arc<AccessStore>,
+}
+
+impl CollectStats {
+    pub fn new(
+        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+        access_store: Arc<AccessStore>,
+    ) -> CollectStats {
+        CollectStats {
+            schema_repository,
+            config_repository,
+            access_store,
+        }
+    }
+
+    #[tracing::instrument(name = "collect_stats", skip_all)]
+    pub async fn exec(&self) -> Result<StatsResponse, Error> {
+        let schemas = self.schema_repository.count().await?;
+        let configs = self.config_repository.count().await?;
+        let active_instances = self.access_store.active_instances().await?;
+
+        Ok(StatsResponse {
+            schemas,
+            configs: configs.total,
+            invalid_configs: configs.invalid,
+            active_instances,
+        })
+    }
+}
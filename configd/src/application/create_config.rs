@@ -3,7 +3,11 @@ use serde_json::Value as JsonValue;
 use std::sync::Arc;
 
 use crate::domain::{
-    configs::Password, errors::Error, events::Publisher, schemas::SchemaRepository, shared::Id,
+    configs::{ConfigRepository, ConfigService, Password},
+    errors::Error,
+    events::Publisher,
+    schemas::SchemaRepository,
+    shared::Id,
 };
 
 #[derive(Deserialize)]
@@ -24,23 +28,27 @@ pub struct CreateConfigResponse {
 pub struct CreateConfig {
     event_publisher: Arc<dyn Publisher + Sync + Send>,
     schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
 }
 
 impl CreateConfig {
     pub fn new(
         event_publisher: Arc<dyn Publisher + Sync + Send>,
         schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
     ) -> CreateConfig {
         CreateConfig {
             event_publisher,
             schema_repository,
+            config_repository,
         }
     }
 
+    #[tracing::instrument(name = "create_config", skip_all, fields(schema_id = %cmd.schema_id, name = %cmd.name))]
     pub async fn exec(&self, cmd: CreateConfigCommand) -> Result<CreateConfigResponse, Error> {
         let schema_id = Id::new(cmd.schema_id)?;
 
-        let mut schema = self
+        let schema = self
             .schema_repository
             .find_by_id(&schema_id)
             .await?
@@ -49,11 +57,19 @@ impl CreateConfig {
         let config_id = Id::slug(&cmd.name)?;
         let password = cmd.password.map(Password::new).transpose()?;
 
-        schema.add_config(config_id.clone(), cmd.name, cmd.data.into(), password)?;
+        let mut config = ConfigService::new(self.config_repository.clone())
+            .create_config(
+                &schema,
+                config_id.clone(),
+                cmd.name,
+                cmd.data.into(),
+                password,
+            )
+            .await?;
 
-        self.schema_repository.save(&mut schema).await?;
+        self.config_repository.save(&mut config).await?;
 
-        self.event_publisher.publish(schema.events()).await?;
+        self.event_publisher.publish(config.events()).await?;
 
         Ok(CreateConfigResponse {
             schema_id: schema_id.to_string(),
@@ -7,12 +7,15 @@ use crate::domain::{
     events::Publisher,
     schemas::{Schema, SchemaRepository},
     shared::Id,
+    values::rules_from_json,
 };
 
 #[derive(Deserialize)]
 pub struct CreateSchemaCommand {
     pub name: String,
     pub schema: JsonValue,
+    #[serde(default)]
+    pub rules: JsonValue,
 }
 
 #[derive(Serialize)]
@@ -36,6 +39,7 @@ impl CreateSchema {
         }
     }
 
+    #[tracing::instrument(name = "create_schema", skip_all, fields(name = %cmd.name))]
     pub async fn exec(&self, cmd: CreateSchemaCommand) -> Result<CreateSchemaResponse, Error> {
         let id = Id::slug(&cmd.name)?;
 
@@ -44,8 +48,9 @@ impl CreateSchema {
         }
 
         let prop = cmd.schema.try_into()?;
+        let rules = rules_from_json(cmd.rules)?;
 
-        let mut schema = Schema::create(id, cmd.name, prop)?;
+        let mut schema = Schema::create(id, cmd.name, prop, rules)?;
 
         self.schema_repository.save(&mut schema).await?;
 
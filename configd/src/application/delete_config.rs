@@ -2,7 +2,10 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::domain::{
-    configs::Password, errors::Error, events::Publisher, schemas::SchemaRepository, shared::Id,
+    configs::{ConfigRepository, Password, PasswordLockout},
+    errors::Error,
+    events::Publisher,
+    shared::Id,
 };
 
 #[derive(Deserialize)]
@@ -23,37 +26,44 @@ pub struct DeleteConfigResponse {
 
 pub struct DeleteConfig {
     event_publisher: Arc<dyn Publisher + Sync + Send>,
-    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    password_lockout: Arc<PasswordLockout>,
 }
 
 impl DeleteConfig {
     pub fn new(
         event_publisher: Arc<dyn Publisher + Sync + Send>,
-        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+        password_lockout: Arc<PasswordLockout>,
     ) -> DeleteConfig {
         DeleteConfig {
             event_publisher,
-            schema_repository,
+            config_repository,
+            password_lockout,
         }
     }
 
+    #[tracing::instrument(name = "delete_config", skip_all, fields(schema_id = %cmd.schema_id, config_id = %cmd.config_id))]
     pub async fn exec(&self, cmd: DeleteConfigCommand) -> Result<DeleteConfigResponse, Error> {
         let schema_id = Id::new(cmd.schema_id)?;
+        let config_id = Id::new(cmd.config_id)?;
+        let password = cmd.password.map(Password::new).transpose()?;
 
-        let mut schema = self
-            .schema_repository
-            .find_by_id(&schema_id)
+        let mut config = self
+            .config_repository
+            .find_by_id(&schema_id, &config_id)
             .await?
-            .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
+            .ok_or_else(|| Error::ConfigNotFound(config_id.clone()))?;
 
-        let config_id = Id::new(cmd.config_id)?;
-        let password = cmd.password.map(Password::new).transpose()?;
+        self.password_lockout
+            .verify(&config, password.as_ref())
+            .await?;
 
-        schema.delete_config(&config_id, password.as_ref())?;
+        config.delete(password.as_ref())?;
 
-        self.schema_repository.save(&mut schema).await?;
+        self.config_repository.save(&mut config).await?;
 
-        self.event_publisher.publish(schema.events()).await?;
+        self.event_publisher.publish(config.events()).await?;
 
         Ok(DeleteConfigResponse {
             schema_id: schema_id.to_string(),
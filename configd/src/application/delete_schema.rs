@@ -1,7 +1,13 @@
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-use crate::domain::{errors::Error, events::Publisher, schemas::SchemaRepository, shared::Id};
+use crate::domain::{
+    configs::{ConfigRepository, ConfigService},
+    errors::Error,
+    events::Publisher,
+    schemas::SchemaRepository,
+    shared::Id,
+};
 
 #[derive(Deserialize)]
 pub struct DeleteSchemaCommand {
@@ -17,19 +23,23 @@ pub struct DeleteSchemaResponse {
 pub struct DeleteSchema {
     event_publisher: Arc<dyn Publisher + Sync + Send>,
     schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
 }
 
 impl DeleteSchema {
     pub fn new(
         event_publisher: Arc<dyn Publisher + Sync + Send>,
         schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
     ) -> DeleteSchema {
         DeleteSchema {
             event_publisher,
             schema_repository,
+            config_repository,
         }
     }
 
+    #[tracing::instrument(name = "delete_schema", skip_all, fields(schema_id = %cmd.schema_id))]
     pub async fn exec(&self, cmd: DeleteSchemaCommand) -> Result<DeleteSchemaResponse, Error> {
         let schema_id = Id::new(cmd.schema_id)?;
 
@@ -39,7 +49,9 @@ impl DeleteSchema {
             .await?
             .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
 
-        schema.delete()?;
+        ConfigService::new(self.config_repository.clone())
+            .delete_schema(&mut schema)
+            .await?;
 
         self.schema_repository.save(&mut schema).await?;
 
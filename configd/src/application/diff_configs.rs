@@ -0,0 +1,103 @@
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::domain::{
+    configs::{AccessStore, ConfigRepository, Password, PasswordLockout},
+    errors::Error,
+    schemas::SchemaRepository,
+    shared::Id,
+    values::Change,
+};
+
+pub struct DiffConfigsCommand {
+    pub schema_id: String,
+    pub config_id: String,
+    pub other_config_id: String,
+    pub populate: Option<bool>,
+    pub password: Option<String>,
+    // The same password is used for both configs when missing
+    pub other_password: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DiffConfigsResponse {
+    pub schema_id: String,
+    pub config_id: String,
+    pub other_config_id: String,
+    pub changes: Vec<Change>,
+}
+
+pub struct DiffConfigs {
+    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    access_store: Arc<AccessStore>,
+    password_lockout: Arc<PasswordLockout>,
+}
+
+impl DiffConfigs {
+    pub fn new(
+        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+        access_store: Arc<AccessStore>,
+        password_lockout: Arc<PasswordLockout>,
+    ) -> DiffConfigs {
+        DiffConfigs {
+            schema_repository,
+            config_repository,
+            access_store,
+            password_lockout,
+        }
+    }
+
+    #[tracing::instrument(name = "diff_configs", skip_all, fields(schema_id = %cmd.schema_id, config_id = %cmd.config_id, other_config_id = %cmd.other_config_id))]
+    pub async fn exec(&self, cmd: DiffConfigsCommand) -> Result<DiffConfigsResponse, Error> {
+        let schema_id = Id::new(cmd.schema_id)?;
+
+        let schema = self
+            .schema_repository
+            .find_by_id(&schema_id)
+            .await?
+            .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
+
+        let config_id = Id::new(cmd.config_id)?;
+        let other_config_id = Id::new(cmd.other_config_id)?;
+        let password = cmd.password.map(Password::new).transpose()?;
+        let other_password = match cmd.other_password {
+            Some(other_password) => Some(Password::new(other_password)?),
+            None => password.clone(),
+        };
+
+        // Comparing configs is not a consumer read, so accesses are not
+        // registered. Only the current data is compared, as previous versions
+        // of a config are not kept.
+        let mut data = Vec::with_capacity(2);
+        for (config_id, password) in [(&config_id, &password), (&other_config_id, &other_password)]
+        {
+            let config = self
+                .config_repository
+                .find_by_id(&schema_id, config_id)
+                .await?
+                .ok_or_else(|| Error::ConfigNotFound(config_id.clone()))?;
+
+            self.password_lockout
+                .verify(&config, password.as_ref())
+                .await?;
+
+            data.push(if cmd.populate.unwrap_or(false) {
+                let accesses = self.access_store.active(&schema_id, config_id).await?;
+                schema.populate_config(&config, accesses.len())
+            } else {
+                config.data().clone()
+            });
+        }
+
+        let changes = Change::between(&data[0], &data[1], schema.root_prop());
+
+        Ok(DiffConfigsResponse {
+            schema_id: schema_id.to_string(),
+            config_id: config_id.to_string(),
+            other_config_id: other_config_id.to_string(),
+            changes,
+        })
+    }
+}
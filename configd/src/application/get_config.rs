@@ -4,9 +4,8 @@ use serde_json::Value as JsonValue;
 use std::sync::Arc;
 
 use crate::domain::{
-    configs::{Access, Password},
+    configs::{Access, AccessStore, ConfigRepository, Password, PasswordLockout},
     errors::Error,
-    events::Publisher,
     schemas::SchemaRepository,
     shared::Id,
 };
@@ -25,6 +24,9 @@ pub struct GetConfigCommand {
     pub password: Option<String>,
     #[serde(skip_deserializing)]
     pub populate: Option<bool>,
+    // Re-reads of a watched config, only its first read is an access
+    #[serde(skip_deserializing)]
+    pub reread: bool,
 }
 
 #[derive(Serialize)]
@@ -50,31 +52,39 @@ pub struct GetConfigResponse {
     pub version: i64,
 }
 
+impl GetConfigResponse {
+    // Accesses change on every read, so the tag is weak. The version changes
+    // with everything else but the populated data.
+    pub fn etag(&self) -> String {
+        format!("W/\"{}-{}\"", self.version, self.checksum)
+    }
+}
+
 pub struct GetConfig {
-    event_publisher: Arc<dyn Publisher + Sync + Send>,
     schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    access_store: Arc<AccessStore>,
+    password_lockout: Arc<PasswordLockout>,
 }
 
 impl GetConfig {
     pub fn new(
-        event_publisher: Arc<dyn Publisher + Sync + Send>,
         schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+        access_store: Arc<AccessStore>,
+        password_lockout: Arc<PasswordLockout>,
     ) -> GetConfig {
         GetConfig {
-            event_publisher,
             schema_repository,
+            config_repository,
+            access_store,
+            password_lockout,
         }
     }
 
+    #[tracing::instrument(name = "get_config", skip_all, fields(schema_id = %cmd.schema_id, config_id = %cmd.config_id, source = ?cmd.source, instance = ?cmd.instance))]
     pub async fn exec(&self, cmd: GetConfigCommand) -> Result<GetConfigResponse, Error> {
         let schema_id = Id::new(cmd.schema_id)?;
-
-        let mut schema = self
-            .schema_repository
-            .find_by_id(&schema_id)
-            .await?
-            .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
-
         let config_id = Id::new(cmd.config_id)?;
         let source = cmd.source.map(Id::new).transpose()?;
         let instance = cmd.instance.map(Id::new).transpose()?;
@@ -87,10 +97,33 @@ impl GetConfig {
             (None, None) => Access::unknown(),
         };
 
-        let config = schema.get_config(&config_id, access, password.as_ref())?;
+        let config = self
+            .config_repository
+            .find_by_id(&schema_id, &config_id)
+            .await?
+            .ok_or_else(|| Error::ConfigNotFound(config_id.clone()))?;
 
+        self.password_lockout
+            .verify(&config, password.as_ref())
+            .await?;
+
+        let accesses = if cmd.reread {
+            self.access_store.active(&schema_id, &config_id).await?
+        } else {
+            self.access_store
+                .register(&schema_id, &config_id, access)
+                .await?
+        };
+
+        // The schema is only needed to populate the data
         let (data, checksum) = if cmd.populate.unwrap_or(false) {
-            let data = schema.populate_config(&config);
+            let schema = self
+                .schema_repository
+                .find_by_id(&schema_id)
+                .await?
+                .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
+
+            let data = schema.populate_config(&config, accesses.len());
             let checksum = data.checksum();
 
             (data, checksum)
@@ -98,13 +131,6 @@ impl GetConfig {
             (config.data().clone(), config.data().checksum())
         };
 
-        self.event_publisher.publish(schema.events()).await?;
-
-        let schema_repository = self.schema_repository.clone();
-        tokio::spawn(async move {
-            schema_repository.save(&mut schema).await.unwrap();
-        });
-
         Ok(GetConfigResponse {
             schema_id: schema_id.to_string(),
             id: config.id().to_string(),
@@ -113,8 +139,7 @@ impl GetConfig {
             valid: config.is_valid(),
             checksum,
             requires_password: config.password().is_some(),
-            accesses: config
-                .accesses()
+            accesses: accesses
                 .iter()
                 .map(|access| ConfigAccessDto {
                     source: access.source().to_string(),
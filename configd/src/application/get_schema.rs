@@ -1,14 +1,42 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
 
-use crate::domain::{errors::Error, schemas::SchemaRepository, shared::Id};
+use crate::domain::{
+    configs::{ConfigRepository, ConfigService},
+    errors::Error,
+    schemas::SchemaRepository,
+    shared::Id,
+    values::rules_to_json,
+};
 
 #[derive(Deserialize)]
 pub struct GetSchemaCommand {
     #[serde(skip_deserializing)]
     pub schema_id: String,
+    // Comma-separated relations to embed, all of them when missing
+    pub include: Option<String>,
+}
+
+// Only configs can be embedded in schemas for now
+pub fn includes_configs(include: Option<&str>) -> Result<bool, Error> {
+    let include = match include {
+        Some(include) => include,
+        None => return Ok(true),
+    };
+
+    let mut configs = false;
+    for relation in include.split(',').map(str::trim) {
+        match relation {
+            "" => {}
+            "configs" => configs = true,
+            _ => return Err(Error::InvalidInclude(relation.to_string())),
+        }
+    }
+
+    Ok(configs)
 }
 
 #[derive(Serialize)]
@@ -28,23 +56,62 @@ pub struct GetSchemaResponse {
     pub id: String,
     pub name: String,
     pub schema: JsonValue,
-    pub configs: Vec<SchemaConfigDto>,
+    pub rules: JsonValue,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub configs: Option<Vec<SchemaConfigDto>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub version: i64,
 }
 
+impl GetSchemaResponse {
+    // Config changes are not always reflected in the schema version, so the
+    // version of each config is part of the tag too
+    pub fn etag(&self) -> String {
+        let configs = match &self.configs {
+            Some(configs) => configs,
+            None => return format!("W/\"{}\"", self.version),
+        };
+
+        let mut configs: Vec<(&str, i64)> = configs
+            .iter()
+            .map(|config| (config.id.as_str(), config.version))
+            .collect();
+        configs.sort();
+
+        let mut hasher = Sha256::new();
+        for (id, version) in configs.into_iter() {
+            hasher.update(format!("{}:{};", id, version));
+        }
+
+        format!(
+            "W/\"{}-{}\"",
+            self.version,
+            &hex::encode(hasher.finalize())[..16]
+        )
+    }
+}
+
 pub struct GetSchema {
     schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
 }
 
 impl GetSchema {
-    pub fn new(schema_repository: Arc<dyn SchemaRepository + Sync + Send>) -> GetSchema {
-        GetSchema { schema_repository }
+    pub fn new(
+        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    ) -> GetSchema {
+        GetSchema {
+            schema_repository,
+            config_repository,
+        }
     }
 
+    #[tracing::instrument(name = "get_schema", skip_all, fields(schema_id = %cmd.schema_id))]
     pub async fn exec(&self, cmd: GetSchemaCommand) -> Result<GetSchemaResponse, Error> {
         let schema_id = Id::new(cmd.schema_id)?;
+        let include_configs = includes_configs(cmd.include.as_deref())?;
 
         let schema = self
             .schema_repository
@@ -52,24 +119,36 @@ impl GetSchema {
             .await?
             .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
 
+        let configs = if include_configs {
+            Some(
+                ConfigService::new(self.config_repository.clone())
+                    .configs(&schema_id)
+                    .await?,
+            )
+        } else {
+            None
+        };
+
         Ok(GetSchemaResponse {
             id: schema.id().to_string(),
             name: schema.name().to_string(),
             schema: schema.root_prop().clone().try_into()?,
-            configs: schema
-                .configs()
-                .values()
-                .map(|config| SchemaConfigDto {
-                    id: config.id().to_string(),
-                    name: config.name().to_string(),
-                    valid: config.is_valid(),
-                    checksum: config.data().checksum(),
-                    requires_password: config.password().is_some(),
-                    created_at: *config.timestamps().created_at(),
-                    updated_at: *config.timestamps().updated_at(),
-                    version: config.version().value(),
-                })
-                .collect(),
+            rules: rules_to_json(schema.rules())?,
+            configs: configs.map(|configs| {
+                configs
+                    .iter()
+                    .map(|config| SchemaConfigDto {
+                        id: config.id().to_string(),
+                        name: config.name().to_string(),
+                        valid: config.is_valid(),
+                        checksum: config.data().checksum(),
+                        requires_password: config.password().is_some(),
+                        created_at: *config.timestamps().created_at(),
+                        updated_at: *config.timestamps().updated_at(),
+                        version: config.version().value(),
+                    })
+                    .collect()
+            }),
             created_at: *schema.timestamps().created_at(),
             updated_at: *schema.timestamps().updated_at(),
             version: schema.version().value(),
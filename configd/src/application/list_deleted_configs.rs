@@ -0,0 +1,139 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::domain::{
+    configs::{ConfigQuery, ConfigRepository},
+    errors::Error,
+    schemas::SchemaRepository,
+    shared::{page_limit, Id},
+};
+
+#[derive(Deserialize, Default)]
+pub struct ListDeletedConfigsCommand {
+    #[serde(skip_deserializing)]
+    pub schema_id: String,
+    // Part of the name, case-insensitive
+    pub name: Option<String>,
+    // name, created_at or updated_at
+    pub sort: Option<String>,
+    // asc or desc
+    pub order: Option<String>,
+    // next_cursor of the previous page
+    pub cursor: Option<String>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct DeletedConfigDto {
+    pub id: String,
+    pub name: String,
+    pub valid: bool,
+    pub requires_password: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: DateTime<Utc>,
+    pub version: i64,
+}
+
+#[derive(Serialize)]
+pub struct ListDeletedConfigsResponse {
+    pub offset: u64,
+    pub limit: u64,
+    pub total: u64,
+    pub data: Vec<DeletedConfigDto>,
+    // Set while the page is full, so the last page may be empty
+    pub next_cursor: Option<String>,
+}
+
+pub struct ListDeletedConfigs {
+    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+}
+
+impl ListDeletedConfigs {
+    pub fn new(
+        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    ) -> ListDeletedConfigs {
+        ListDeletedConfigs {
+            schema_repository,
+            config_repository,
+        }
+    }
+
+    #[tracing::instrument(name = "list_deleted_configs", skip_all, fields(schema_id = %cmd.schema_id))]
+    pub async fn exec(
+        &self,
+        cmd: ListDeletedConfigsCommand,
+    ) -> Result<ListDeletedConfigsResponse, Error> {
+        let schema_id = Id::new(cmd.schema_id)?;
+
+        let query = ConfigQuery {
+            name: cmd.name.filter(|name| !name.is_empty()),
+            valid: None,
+            requires_password: None,
+            deleted: true,
+            deleted_before: None,
+            sort: cmd
+                .sort
+                .as_deref()
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            order: cmd
+                .order
+                .as_deref()
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            cursor: None,
+            offset: cmd.offset.unwrap_or(0),
+            limit: page_limit(cmd.limit)?,
+        };
+        let query = match cmd.cursor {
+            Some(cursor) => query.with_cursor(&cursor)?,
+            None => query,
+        };
+
+        // The schema may be deleted too
+        if !self.schema_repository.exists(&schema_id).await? {
+            return Err(Error::SchemaNotFound(schema_id));
+        }
+
+        let configs_page = self.config_repository.find(&schema_id, &query).await?;
+
+        let offset = configs_page.offset();
+        let limit = configs_page.limit();
+        let total = configs_page.total();
+
+        let configs = configs_page.into_data();
+        let next_cursor = match configs.last() {
+            Some(last) if configs.len() as u64 == limit => Some(query.cursor_of(last).encode()),
+            _ => None,
+        };
+
+        Ok(ListDeletedConfigsResponse {
+            offset,
+            limit,
+            total,
+            data: configs
+                .iter()
+                .filter_map(|config| {
+                    Some(DeletedConfigDto {
+                        id: config.id().to_string(),
+                        name: config.name().to_string(),
+                        valid: config.is_valid(),
+                        requires_password: config.password().is_some(),
+                        created_at: *config.timestamps().created_at(),
+                        updated_at: *config.timestamps().updated_at(),
+                        deleted_at: *config.timestamps().deleted_at()?,
+                        version: config.version().value(),
+                    })
+                })
+                .collect(),
+            next_cursor,
+        })
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::domain::{
+    errors::Error,
+    schemas::{SchemaQuery, SchemaRepository},
+    shared::page_limit,
+};
+
+#[derive(Deserialize, Default)]
+pub struct ListDeletedSchemasCommand {
+    // Part of the name, case-insensitive
+    pub name: Option<String>,
+    // name, created_at or updated_at
+    pub sort: Option<String>,
+    // asc or desc
+    pub order: Option<String>,
+    // next_cursor of the previous page
+    pub cursor: Option<String>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct DeletedSchemaDto {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: DateTime<Utc>,
+    pub version: i64,
+}
+
+#[derive(Serialize)]
+pub struct ListDeletedSchemasResponse {
+    pub offset: u64,
+    pub limit: u64,
+    pub total: u64,
+    pub data: Vec<DeletedSchemaDto>,
+    // Set while the page is full, so the last page may be empty
+    pub next_cursor: Option<String>,
+}
+
+pub struct ListDeletedSchemas {
+    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+}
+
+impl ListDeletedSchemas {
+    pub fn new(schema_repository: Arc<dyn SchemaRepository + Sync + Send>) -> ListDeletedSchemas {
+        ListDeletedSchemas { schema_repository }
+    }
+
+    #[tracing::instrument(name = "list_deleted_schemas", skip_all)]
+    pub async fn exec(
+        &self,
+        cmd: ListDeletedSchemasCommand,
+    ) -> Result<ListDeletedSchemasResponse, Error> {
+        let query = SchemaQuery {
+            name: cmd.name.filter(|name| !name.is_empty()),
+            has_invalid_configs: None,
+            updated_since: None,
+            deleted: true,
+            deleted_before: None,
+            configs_deleted_before: None,
+            sort: cmd
+                .sort
+                .as_deref()
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            order: cmd
+                .order
+                .as_deref()
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            cursor: None,
+            offset: cmd.offset.unwrap_or(0),
+            limit: page_limit(cmd.limit)?,
+        };
+        let query = match cmd.cursor {
+            Some(cursor) => query.with_cursor(&cursor)?,
+            None => query,
+        };
+
+        let schemas_page = self.schema_repository.find(&query).await?;
+
+        let offset = schemas_page.offset();
+        let limit = schemas_page.limit();
+        let total = schemas_page.total();
+
+        let schemas = schemas_page.into_data();
+        let next_cursor = match schemas.last() {
+            Some(last) if schemas.len() as u64 == limit => Some(query.cursor_of(last).encode()),
+            _ => None,
+        };
+
+        Ok(ListDeletedSchemasResponse {
+            offset,
+            limit,
+            total,
+            data: schemas
+                .iter()
+                .filter_map(|schema| {
+                    Some(DeletedSchemaDto {
+                        id: schema.id().to_string(),
+                        name: schema.name().to_string(),
+                        created_at: *schema.timestamps().created_at(),
+                        updated_at: *schema.timestamps().updated_at(),
+                        deleted_at: *schema.timestamps().deleted_at()?,
+                        version: schema.version().value(),
+                    })
+                })
+                .collect(),
+            next_cursor,
+        })
+    }
+}
@@ -3,12 +3,33 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::sync::Arc;
 
-use crate::domain::{errors::Error, schemas::SchemaRepository};
+use crate::{
+    application::includes_configs,
+    domain::{
+        configs::{ConfigRepository, ConfigService},
+        errors::Error,
+        schemas::{SchemaQuery, SchemaRepository},
+        shared::page_limit,
+        values::rules_to_json,
+    },
+};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 pub struct ListSchemasCommand {
+    // Part of the name, case-insensitive
+    pub name: Option<String>,
+    pub has_invalid_configs: Option<bool>,
+    pub updated_since: Option<DateTime<Utc>>,
+    // name, created_at or updated_at
+    pub sort: Option<String>,
+    // asc or desc
+    pub order: Option<String>,
+    // next_cursor of the previous page
+    pub cursor: Option<String>,
     pub offset: Option<u64>,
     pub limit: Option<u64>,
+    // Comma-separated relations to embed, all of them when missing
+    pub include: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -28,7 +49,9 @@ pub struct SchemaDto {
     pub id: String,
     pub name: String,
     pub schema: JsonValue,
-    pub configs: Vec<SchemaConfigDto>,
+    pub rules: JsonValue,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub configs: Option<Vec<SchemaConfigDto>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub version: i64,
@@ -40,52 +63,111 @@ pub struct ListSchemasResponse {
     pub limit: u64,
     pub total: u64,
     pub data: Vec<SchemaDto>,
+    // Set while the page is full, so the last page may be empty
+    pub next_cursor: Option<String>,
 }
 
 pub struct ListSchemas {
     schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
 }
 
 impl ListSchemas {
-    pub fn new(schema_repository: Arc<dyn SchemaRepository + Sync + Send>) -> ListSchemas {
-        ListSchemas { schema_repository }
+    pub fn new(
+        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    ) -> ListSchemas {
+        ListSchemas {
+            schema_repository,
+            config_repository,
+        }
     }
 
+    #[tracing::instrument(name = "list_schemas", skip_all)]
     pub async fn exec(&self, cmd: ListSchemasCommand) -> Result<ListSchemasResponse, Error> {
-        let schemas_page = self.schema_repository.find(cmd.offset, cmd.limit).await?;
+        let include_configs = includes_configs(cmd.include.as_deref())?;
+        let query = SchemaQuery {
+            name: cmd.name.filter(|name| !name.is_empty()),
+            has_invalid_configs: cmd.has_invalid_configs,
+            updated_since: cmd.updated_since,
+            deleted: false,
+            deleted_before: None,
+            configs_deleted_before: None,
+            sort: cmd
+                .sort
+                .as_deref()
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            order: cmd
+                .order
+                .as_deref()
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            cursor: None,
+            offset: cmd.offset.unwrap_or(0),
+            limit: page_limit(cmd.limit)?,
+        };
+        let query = match cmd.cursor {
+            Some(cursor) => query.with_cursor(&cursor)?,
+            None => query,
+        };
+
+        let schemas_page = self.schema_repository.find(&query).await?;
+
+        let config_service = ConfigService::new(self.config_repository.clone());
+
+        let offset = schemas_page.offset();
+        let limit = schemas_page.limit();
+        let total = schemas_page.total();
+
+        let schemas = schemas_page.into_data();
+        let next_cursor = match schemas.last() {
+            Some(last) if schemas.len() as u64 == limit => Some(query.cursor_of(last).encode()),
+            _ => None,
+        };
+
+        let mut data = Vec::new();
+        for schema in schemas.into_iter() {
+            let configs = if include_configs {
+                Some(config_service.configs(schema.id()).await?)
+            } else {
+                None
+            };
+
+            data.push(SchemaDto {
+                id: schema.id().to_string(),
+                name: schema.name().to_string(),
+                schema: schema.root_prop().clone().try_into()?,
+                rules: rules_to_json(schema.rules())?,
+                configs: configs.map(|configs| {
+                    configs
+                        .iter()
+                        .map(|config| SchemaConfigDto {
+                            id: config.id().to_string(),
+                            name: config.name().to_string(),
+                            valid: config.is_valid(),
+                            checksum: config.data().checksum(),
+                            requires_password: config.password().is_some(),
+                            created_at: *config.timestamps().created_at(),
+                            updated_at: *config.timestamps().updated_at(),
+                            version: config.version().value(),
+                        })
+                        .collect()
+                }),
+                created_at: *schema.timestamps().created_at(),
+                updated_at: *schema.timestamps().updated_at(),
+                version: schema.version().value(),
+            });
+        }
 
         Ok(ListSchemasResponse {
-            offset: schemas_page.offset(),
-            limit: schemas_page.limit(),
-            total: schemas_page.total(),
-            data: schemas_page
-                .into_data()
-                .into_iter()
-                .map(|schema| {
-                    Ok(SchemaDto {
-                        id: schema.id().to_string(),
-                        name: schema.name().to_string(),
-                        schema: schema.root_prop().clone().try_into()?,
-                        configs: schema
-                            .configs()
-                            .values()
-                            .map(|config| SchemaConfigDto {
-                                id: config.id().to_string(),
-                                name: config.name().to_string(),
-                                valid: config.is_valid(),
-                                checksum: config.data().checksum(),
-                                requires_password: config.password().is_some(),
-                                created_at: *config.timestamps().created_at(),
-                                updated_at: *config.timestamps().updated_at(),
-                                version: config.version().value(),
-                            })
-                            .collect(),
-                        created_at: *schema.timestamps().created_at(),
-                        updated_at: *schema.timestamps().updated_at(),
-                        version: schema.version().value(),
-                    })
-                })
-                .collect::<Result<Vec<SchemaDto>, Error>>()?,
+            offset,
+            limit,
+            total,
+            data,
+            next_cursor,
         })
     }
 }
@@ -1,13 +1,24 @@
 mod change_config_password;
 mod clean_config_accesses;
+mod collect_stats;
 mod create_config;
 mod create_schema;
 mod delete_config;
 mod delete_config_password;
 mod delete_schema;
+mod diff_configs;
 mod get_config;
 mod get_schema;
+mod list_configs;
+mod list_deleted_configs;
+mod list_deleted_schemas;
 mod list_schemas;
+mod patch_config;
+mod purge_config;
+mod purge_schema;
+mod purge_trash;
+mod restore_config;
+mod restore_schema;
 mod revalidate_configs;
 mod update_config;
 mod update_schema;
@@ -15,14 +26,25 @@ mod validate_config;
 
 pub use change_config_password::*;
 pub use clean_config_accesses::*;
+pub use collect_stats::*;
 pub use create_config::*;
 pub use create_schema::*;
 pub use delete_config::*;
 pub use delete_config_password::*;
 pub use delete_schema::*;
+pub use diff_configs::*;
 pub use get_config::*;
 pub use get_schema::*;
+pub use list_configs::*;
+pub use list_deleted_configs::*;
+pub use list_deleted_schemas::*;
 pub use list_schemas::*;
+pub use patch_config::*;
+pub use purge_config::*;
+pub use purge_schema::*;
+pub use purge_trash::*;
+pub use restore_config::*;
+pub use restore_schema::*;
 pub use revalidate_configs::*;
 pub use update_config::*;
 pub use update_schema::*;
@@ -0,0 +1,101 @@
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+
+use crate::domain::{
+    configs::{ConfigRepository, ConfigService, Password, PasswordLockout},
+    errors::Error,
+    events::Publisher,
+    schemas::SchemaRepository,
+    shared::Id,
+    values::Patch,
+};
+
+pub enum PatchFormat {
+    // RFC 6902
+    JsonPatch,
+    // RFC 7396
+    MergePatch,
+}
+
+pub struct PatchConfigCommand {
+    pub schema_id: String,
+    pub config_id: String,
+    pub format: PatchFormat,
+    pub patch: JsonValue,
+    pub password: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PatchConfigResponse {
+    pub schema_id: String,
+    pub config_id: String,
+}
+
+pub struct PatchConfig {
+    event_publisher: Arc<dyn Publisher + Sync + Send>,
+    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    password_lockout: Arc<PasswordLockout>,
+}
+
+impl PatchConfig {
+    pub fn new(
+        event_publisher: Arc<dyn Publisher + Sync + Send>,
+        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+        password_lockout: Arc<PasswordLockout>,
+    ) -> PatchConfig {
+        PatchConfig {
+            event_publisher,
+            schema_repository,
+            config_repository,
+            password_lockout,
+        }
+    }
+
+    #[tracing::instrument(name = "patch_config", skip_all, fields(schema_id = %cmd.schema_id, config_id = %cmd.config_id))]
+    pub async fn exec(&self, cmd: PatchConfigCommand) -> Result<PatchConfigResponse, Error> {
+        let schema_id = Id::new(cmd.schema_id)?;
+
+        let schema = self
+            .schema_repository
+            .find_by_id(&schema_id)
+            .await?
+            .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
+
+        let config_id = Id::new(cmd.config_id)?;
+        let password = cmd.password.map(Password::new).transpose()?;
+
+        let patch = match cmd.format {
+            PatchFormat::JsonPatch => Patch::json(cmd.patch)?,
+            PatchFormat::MergePatch => Patch::merge(cmd.patch),
+        };
+
+        let mut config = self
+            .config_repository
+            .find_by_id(&schema_id, &config_id)
+            .await?
+            .ok_or_else(|| Error::ConfigNotFound(config_id.clone()))?;
+
+        self.password_lockout
+            .verify(&config, password.as_ref())
+            .await?;
+
+        ConfigService::new(self.config_repository.clone()).patch_config(
+            &schema,
+            &mut config,
+            &patch,
+            password.as_ref(),
+        )?;
+
+        self.config_repository.save(&mut config).await?;
+
+        self.event_publisher.publish(config.events()).await?;
+
+        Ok(PatchConfigResponse {
+            schema_id: schema_id.to_string(),
+            config_id: config_id.to_string(),
+        })
+    }
+}
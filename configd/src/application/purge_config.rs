@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::domain::{
+    configs::{ConfigRepository, Password, PasswordLockout},
+    errors::Error,
+    events::Publisher,
+    shared::Id,
+};
+
+#[derive(Deserialize)]
+pub struct PurgeConfigCommand {
+    #[serde(skip_deserializing)]
+    pub schema_id: String,
+    #[serde(skip_deserializing)]
+    pub config_id: String,
+    #[serde(skip_deserializing)]
+    pub password: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PurgeConfigResponse {
+    pub schema_id: String,
+    pub config_id: String,
+}
+
+pub struct PurgeConfig {
+    event_publisher: Arc<dyn Publisher + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    password_lockout: Arc<PasswordLockout>,
+}
+
+impl PurgeConfig {
+    pub fn new(
+        event_publisher: Arc<dyn Publisher + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+        password_lockout: Arc<PasswordLockout>,
+    ) -> PurgeConfig {
+        PurgeConfig {
+            event_publisher,
+            config_repository,
+            password_lockout,
+        }
+    }
+
+    #[tracing::instrument(name = "purge_config", skip_all, fields(schema_id = %cmd.schema_id, config_id = %cmd.config_id))]
+    pub async fn exec(&self, cmd: PurgeConfigCommand) -> Result<PurgeConfigResponse, Error> {
+        let schema_id = Id::new(cmd.schema_id)?;
+        let config_id = Id::new(cmd.config_id)?;
+        let password = cmd.password.map(Password::new).transpose()?;
+
+        let mut config = self
+            .config_repository
+            .find_deleted_by_id(&schema_id, &config_id)
+            .await?
+            .ok_or_else(|| Error::ConfigNotFound(config_id.clone()))?;
+
+        self.password_lockout
+            .verify(&config, password.as_ref())
+            .await?;
+
+        config.purge()?;
+
+        self.config_repository.save(&mut config).await?;
+
+        self.event_publisher.publish(config.events()).await?;
+
+        Ok(PurgeConfigResponse {
+            schema_id: schema_id.to_string(),
+            config_id: config_id.to_string(),
+        })
+    }
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::domain::{
+    configs::{ConfigRepository, ConfigService},
+    errors::Error,
+    events::Publisher,
+    schemas::SchemaRepository,
+    shared::Id,
+};
+
+#[derive(Deserialize)]
+pub struct PurgeSchemaCommand {
+    #[serde(skip_deserializing)]
+    pub schema_id: String,
+}
+
+#[derive(Serialize)]
+pub struct PurgeSchemaResponse {
+    pub schema_id: String,
+    pub purged_configs: u64,
+}
+
+pub struct PurgeSchema {
+    event_publisher: Arc<dyn Publisher + Sync + Send>,
+    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+}
+
+impl PurgeSchema {
+    pub fn new(
+        event_publisher: Arc<dyn Publisher + Sync + Send>,
+        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    ) -> PurgeSchema {
+        PurgeSchema {
+            event_publisher,
+            schema_repository,
+            config_repository,
+        }
+    }
+
+    #[tracing::instrument(name = "purge_schema", skip_all, fields(schema_id = %cmd.schema_id))]
+    pub async fn exec(&self, cmd: PurgeSchemaCommand) -> Result<PurgeSchemaResponse, Error> {
+        let schema_id = Id::new(cmd.schema_id)?;
+
+        let mut schema = self
+            .schema_repository
+            .find_deleted_by_id(&schema_id)
+            .await?
+            .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
+
+        let mut configs = ConfigService::new(self.config_repository.clone())
+            .purge_schema(&mut schema)
+            .await?;
+
+        // Purged together, so a failure leaves no orphaned configs
+        self.schema_repository
+            .save_with_configs(&mut schema, &mut configs)
+            .await?;
+
+        for config in configs.iter() {
+            self.event_publisher.publish(config.events()).await?;
+        }
+        self.event_publisher.publish(schema.events()).await?;
+
+        Ok(PurgeSchemaResponse {
+            schema_id: schema_id.to_string(),
+            purged_configs: configs.len() as u64,
+        })
+    }
+}
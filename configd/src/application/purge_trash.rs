@@ -0,0 +1,140 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::domain::{
+    configs::{ConfigRepository, ConfigService},
+    errors::Error,
+    events::Publisher,
+    schemas::{Schema, SchemaQuery, SchemaRepository},
+    shared::MAX_PAGE_LIMIT,
+};
+
+pub struct PurgeTrashCommand {
+    // Schemas and configs deleted before it are purged
+    pub deleted_before: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct PurgeTrashResponse {
+    pub schemas: u64,
+    pub configs: u64,
+}
+
+// Run periodically to purge what stayed deleted longer than the retention
+pub struct PurgeTrash {
+    event_publisher: Arc<dyn Publisher + Sync + Send>,
+    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+}
+
+impl PurgeTrash {
+    pub fn new(
+        event_publisher: Arc<dyn Publisher + Sync + Send>,
+        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    ) -> PurgeTrash {
+        PurgeTrash {
+            event_publisher,
+            schema_repository,
+            config_repository,
+        }
+    }
+
+    #[tracing::instrument(name = "purge_trash", skip_all)]
+    pub async fn exec(&self, cmd: PurgeTrashCommand) -> Result<PurgeTrashResponse, Error> {
+        let config_service = ConfigService::new(self.config_repository.clone());
+        let mut res = PurgeTrashResponse {
+            schemas: 0,
+            configs: 0,
+        };
+
+        // Expired schemas take their deleted configs with them
+        let expired_schemas = self
+            .schemas(SchemaQuery {
+                deleted: true,
+                deleted_before: Some(cmd.deleted_before),
+                ..SchemaQuery::default()
+            })
+            .await?;
+
+        for mut schema in expired_schemas.into_iter() {
+            let mut configs = config_service.purge_schema(&mut schema).await?;
+
+            // Purged together, so a failure leaves no orphaned configs
+            self.schema_repository
+                .save_with_configs(&mut schema, &mut configs)
+                .await?;
+
+            for config in configs.iter() {
+                self.event_publisher.publish(config.events()).await?;
+            }
+            self.event_publisher.publish(schema.events()).await?;
+
+            res.schemas += 1;
+            res.configs += configs.len() as u64;
+        }
+
+        // Expired configs of the remaining schemas, deleted or not. Only the
+        // schemas holding any are loaded.
+        let mut schemas = self
+            .schemas(SchemaQuery {
+                configs_deleted_before: Some(cmd.deleted_before),
+                ..SchemaQuery::default()
+            })
+            .await?;
+        schemas.extend(
+            self.schemas(SchemaQuery {
+                deleted: true,
+                configs_deleted_before: Some(cmd.deleted_before),
+                ..SchemaQuery::default()
+            })
+            .await?,
+        );
+
+        for schema in schemas.iter() {
+            let configs = config_service
+                .deleted_configs(schema.id(), Some(cmd.deleted_before))
+                .await?;
+
+            for mut config in configs.into_iter() {
+                config.purge()?;
+
+                self.config_repository.save(&mut config).await?;
+                self.event_publisher.publish(config.events()).await?;
+
+                res.configs += 1;
+            }
+        }
+
+        Ok(res)
+    }
+
+    async fn schemas(&self, query: SchemaQuery) -> Result<Vec<Schema>, Error> {
+        let mut schemas = Vec::new();
+
+        loop {
+            let page = self
+                .schema_repository
+                .find(&SchemaQuery {
+                    offset: schemas.len() as u64,
+                    limit: MAX_PAGE_LIMIT,
+                    ..query.clone()
+                })
+                .await?;
+
+            let total = page.total();
+            let data = page.into_data();
+            if data.is_empty() {
+                break;
+            }
+
+            schemas.extend(data);
+            if schemas.len() as u64 >= total {
+                break;
+            }
+        }
+
+        Ok(schemas)
+    }
+}
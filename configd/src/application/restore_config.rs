@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::domain::{
+    configs::{ConfigRepository, Password, PasswordLockout},
+    errors::Error,
+    events::Publisher,
+    schemas::SchemaRepository,
+    shared::Id,
+};
+
+#[derive(Deserialize)]
+pub struct RestoreConfigCommand {
+    #[serde(skip_deserializing)]
+    pub schema_id: String,
+    #[serde(skip_deserializing)]
+    pub config_id: String,
+    #[serde(skip_deserializing)]
+    pub password: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RestoreConfigResponse {
+    pub schema_id: String,
+    pub config_id: String,
+    pub valid: bool,
+}
+
+pub struct RestoreConfig {
+    event_publisher: Arc<dyn Publisher + Sync + Send>,
+    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    password_lockout: Arc<PasswordLockout>,
+}
+
+impl RestoreConfig {
+    pub fn new(
+        event_publisher: Arc<dyn Publisher + Sync + Send>,
+        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+        password_lockout: Arc<PasswordLockout>,
+    ) -> RestoreConfig {
+        RestoreConfig {
+            event_publisher,
+            schema_repository,
+            config_repository,
+            password_lockout,
+        }
+    }
+
+    #[tracing::instrument(name = "restore_config", skip_all, fields(schema_id = %cmd.schema_id, config_id = %cmd.config_id))]
+    pub async fn exec(&self, cmd: RestoreConfigCommand) -> Result<RestoreConfigResponse, Error> {
+        let schema_id = Id::new(cmd.schema_id)?;
+        let config_id = Id::new(cmd.config_id)?;
+        let password = cmd.password.map(Password::new).transpose()?;
+
+        // Deleted schemas have to be restored first
+        let schema = self
+            .schema_repository
+            .find_by_id(&schema_id)
+            .await?
+            .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
+
+        let mut config = self
+            .config_repository
+            .find_deleted_by_id(&schema_id, &config_id)
+            .await?
+            .ok_or_else(|| Error::ConfigNotFound(config_id.clone()))?;
+
+        self.password_lockout
+            .verify(&config, password.as_ref())
+            .await?;
+
+        config.restore(password.as_ref())?;
+
+        // The schema may have changed while the config was deleted
+        config.revalidate(schema.validate(config.data()).is_empty())?;
+
+        self.config_repository.save(&mut config).await?;
+
+        self.event_publisher.publish(config.events()).await?;
+
+        Ok(RestoreConfigResponse {
+            schema_id: schema_id.to_string(),
+            config_id: config_id.to_string(),
+            valid: config.is_valid(),
+        })
+    }
+}
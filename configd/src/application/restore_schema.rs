@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::domain::{errors::Error, events::Publisher, schemas::SchemaRepository, shared::Id};
+
+#[derive(Deserialize)]
+pub struct RestoreSchemaCommand {
+    #[serde(skip_deserializing)]
+    pub schema_id: String,
+}
+
+#[derive(Serialize)]
+pub struct RestoreSchemaResponse {
+    pub schema_id: String,
+}
+
+pub struct RestoreSchema {
+    event_publisher: Arc<dyn Publisher + Sync + Send>,
+    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+}
+
+impl RestoreSchema {
+    pub fn new(
+        event_publisher: Arc<dyn Publisher + Sync + Send>,
+        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    ) -> RestoreSchema {
+        RestoreSchema {
+            event_publisher,
+            schema_repository,
+        }
+    }
+
+    #[tracing::instrument(name = "restore_schema", skip_all, fields(schema_id = %cmd.schema_id))]
+    pub async fn exec(&self, cmd: RestoreSchemaCommand) -> Result<RestoreSchemaResponse, Error> {
+        let schema_id = Id::new(cmd.schema_id)?;
+
+        let mut schema = self
+            .schema_repository
+            .find_deleted_by_id(&schema_id)
+            .await?
+            .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
+
+        schema.restore()?;
+
+        self.schema_repository.save(&mut schema).await?;
+
+        self.event_publisher.publish(schema.events()).await?;
+
+        Ok(RestoreSchemaResponse {
+            schema_id: schema_id.to_string(),
+        })
+    }
+}
@@ -2,48 +2,68 @@ use async_trait::async_trait;
 use std::sync::Arc;
 
 use crate::domain::{
+    configs::{ConfigRepository, ConfigService},
     errors::Error,
     events::{Event, Handler, Publisher},
-    schemas::{SchemaRepository, SchemaRootPropChanged},
+    schemas::{SchemaRepository, SchemaRootPropChanged, SchemaRulesChanged},
     shared::Id,
 };
 
+#[derive(Clone)]
 pub struct RevalidateConfigs {
     event_publisher: Arc<dyn Publisher + Sync + Send>,
     schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
 }
 
 impl RevalidateConfigs {
     pub fn new(
         event_publisher: Arc<dyn Publisher + Sync + Send>,
         schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
     ) -> RevalidateConfigs {
         RevalidateConfigs {
             event_publisher,
             schema_repository,
+            config_repository,
         }
     }
 }
 
 #[async_trait]
 impl Handler for RevalidateConfigs {
+    #[tracing::instrument(name = "revalidate_configs", skip_all, fields(schema_id = event.entity_id()))]
     async fn handle(&self, event: &Event) -> Result<(), Error> {
-        if event.topic() == "schema.root_prop_changed" {
-            let payload: SchemaRootPropChanged = event.deserialize_payload()?;
+        let schema_id = match event.topic() {
+            "schema.root_prop_changed" => {
+                let payload: SchemaRootPropChanged = event.deserialize_payload()?;
+                Some(payload.id)
+            }
+            "schema.rules_changed" => {
+                let payload: SchemaRulesChanged = event.deserialize_payload()?;
+                Some(payload.id)
+            }
+            _ => None,
+        };
 
-            let schema_id = Id::new(payload.id).unwrap();
+        if let Some(schema_id) = schema_id {
+            let schema_id = Id::new(schema_id)?;
 
-            let mut schema = self
+            let schema = self
                 .schema_repository
                 .find_by_id(&schema_id)
                 .await?
                 .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
 
-            schema.revalidate_configs()?;
+            let configs = ConfigService::new(self.config_repository.clone())
+                .revalidate_configs(&schema)
+                .await?;
 
-            self.schema_repository.save(&mut schema).await.unwrap();
+            for mut config in configs.into_iter() {
+                self.config_repository.save(&mut config).await?;
 
-            self.event_publisher.publish(schema.events()).await?;
+                self.event_publisher.publish(config.events()).await?;
+            }
         }
 
         Ok(())
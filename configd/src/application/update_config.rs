@@ -3,7 +3,11 @@ use serde_json::Value as JsonValue;
 use std::sync::Arc;
 
 use crate::domain::{
-    configs::Password, errors::Error, events::Publisher, schemas::SchemaRepository, shared::Id,
+    configs::{ConfigRepository, ConfigService, Password, PasswordLockout},
+    errors::Error,
+    events::Publisher,
+    schemas::SchemaRepository,
+    shared::Id,
 };
 
 #[derive(Deserialize)]
@@ -26,23 +30,30 @@ pub struct UpdateConfigResponse {
 pub struct UpdateConfig {
     event_publisher: Arc<dyn Publisher + Sync + Send>,
     schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    password_lockout: Arc<PasswordLockout>,
 }
 
 impl UpdateConfig {
     pub fn new(
         event_publisher: Arc<dyn Publisher + Sync + Send>,
         schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+        password_lockout: Arc<PasswordLockout>,
     ) -> UpdateConfig {
         UpdateConfig {
             event_publisher,
             schema_repository,
+            config_repository,
+            password_lockout,
         }
     }
 
+    #[tracing::instrument(name = "update_config", skip_all, fields(schema_id = %cmd.schema_id, config_id = %cmd.config_id))]
     pub async fn exec(&self, cmd: UpdateConfigCommand) -> Result<UpdateConfigResponse, Error> {
         let schema_id = Id::new(cmd.schema_id)?;
 
-        let mut schema = self
+        let schema = self
             .schema_repository
             .find_by_id(&schema_id)
             .await?
@@ -51,11 +62,26 @@ impl UpdateConfig {
         let config_id = Id::new(cmd.config_id)?;
         let password = cmd.password.map(Password::new).transpose()?;
 
-        schema.update_config(&config_id, cmd.data.into(), password.as_ref())?;
+        let mut config = self
+            .config_repository
+            .find_by_id(&schema_id, &config_id)
+            .await?
+            .ok_or_else(|| Error::ConfigNotFound(config_id.clone()))?;
+
+        self.password_lockout
+            .verify(&config, password.as_ref())
+            .await?;
+
+        ConfigService::new(self.config_repository.clone()).update_config(
+            &schema,
+            &mut config,
+            cmd.data.into(),
+            password.as_ref(),
+        )?;
 
-        self.schema_repository.save(&mut schema).await?;
+        self.config_repository.save(&mut config).await?;
 
-        self.event_publisher.publish(schema.events()).await?;
+        self.event_publisher.publish(config.events()).await?;
 
         Ok(UpdateConfigResponse {
             schema_id: schema_id.to_string(),
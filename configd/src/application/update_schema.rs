@@ -1,37 +1,68 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
-use crate::domain::{errors::Error, events::Publisher, schemas::SchemaRepository, shared::Id};
+use crate::domain::{
+    configs::{ConfigRepository, ConfigService},
+    errors::Error,
+    events::Publisher,
+    schemas::SchemaRepository,
+    shared::Id,
+    values::{migrations_from_json, rules_from_json, Compatibility, Prop, Violation},
+};
 
 #[derive(Deserialize)]
 pub struct UpdateSchemaCommand {
     #[serde(skip_deserializing)]
     pub schema_id: String,
     pub schema: JsonValue,
+    pub rules: Option<JsonValue>,
+    // Applied to every config so they remain valid under the new schema
+    #[serde(default)]
+    pub migrations: JsonValue,
+    // Applies the change even if it invalidates existing configs
+    #[serde(skip_deserializing)]
+    pub force: bool,
+    // Reports the effect of the change without applying it
+    #[serde(skip_deserializing)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize)]
+pub struct AffectedConfigDto {
+    pub id: String,
+    pub name: String,
+    pub diffs: BTreeMap<String, Vec<Violation>>,
 }
 
 #[derive(Serialize)]
 pub struct UpdateSchemaResponse {
     pub id: String,
+    pub compatibility: Compatibility,
+    pub applied: bool,
+    pub affected_configs: Vec<AffectedConfigDto>,
 }
 
 pub struct UpdateSchema {
     event_publisher: Arc<dyn Publisher + Sync + Send>,
     schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
 }
 
 impl UpdateSchema {
     pub fn new(
         event_publisher: Arc<dyn Publisher + Sync + Send>,
         schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
     ) -> UpdateSchema {
         UpdateSchema {
             event_publisher,
             schema_repository,
+            config_repository,
         }
     }
 
+    #[tracing::instrument(name = "update_schema", skip_all, fields(schema_id = %cmd.schema_id))]
     pub async fn exec(&self, cmd: UpdateSchemaCommand) -> Result<UpdateSchemaResponse, Error> {
         let schema_id = Id::new(cmd.schema_id)?;
 
@@ -41,14 +72,72 @@ impl UpdateSchema {
             .await?
             .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
 
-        schema.change_root_prop(cmd.schema.try_into()?)?;
+        let root_prop: Prop = cmd.schema.try_into()?;
+        let rules = match cmd.rules {
+            Some(rules) => Some(rules_from_json(rules)?),
+            None => None,
+        };
+        let migrations = migrations_from_json(cmd.migrations)?;
 
-        self.schema_repository.save(&mut schema).await?;
+        let config_service = ConfigService::new(self.config_repository.clone());
 
-        self.event_publisher.publish(schema.events()).await?;
+        let evolution = config_service
+            .evolution(
+                &schema,
+                &root_prop,
+                rules.as_deref().unwrap_or_else(|| schema.rules()),
+                &migrations,
+            )
+            .await?;
+
+        if evolution.is_breaking() && !cmd.force && !cmd.dry_run {
+            return Err(Error::BreakingSchemaChange(evolution.affected_configs));
+        }
+
+        let affected_configs = evolution
+            .affected_configs
+            .iter()
+            .map(|(id, diff)| AffectedConfigDto {
+                id: id.to_string(),
+                name: evolution
+                    .affected_names
+                    .get(id)
+                    .cloned()
+                    .unwrap_or_default(),
+                diffs: diff.diffs().clone(),
+            })
+            .collect();
+
+        if !cmd.dry_run {
+            // Unchanged parts keep the version
+            if &root_prop != schema.root_prop() {
+                schema.change_root_prop(root_prop)?;
+            }
+
+            if let Some(rules) = rules.filter(|rules| rules.as_slice() != schema.rules()) {
+                schema.change_rules(rules)?;
+            }
+
+            let mut configs = config_service.migrate_configs(&schema, &migrations).await?;
+
+            // Saved together, so a failure leaves neither the schema nor any
+            // config changed and the update can be retried. Configs are saved
+            // before publishing so revalidation sees the migrated data.
+            self.schema_repository
+                .save_with_configs(&mut schema, &mut configs)
+                .await?;
+
+            self.event_publisher.publish(schema.events()).await?;
+            for config in configs.iter() {
+                self.event_publisher.publish(config.events()).await?;
+            }
+        }
 
         Ok(UpdateSchemaResponse {
             id: schema.id().to_string(),
+            compatibility: evolution.compatibility,
+            applied: !cmd.dry_run,
+            affected_configs,
         })
     }
 }
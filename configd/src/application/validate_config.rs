@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::BTreeMap, sync::Arc};
 
-use crate::domain::{errors::Error, schemas::SchemaRepository, shared::Id, values::Reason};
+use crate::domain::{errors::Error, schemas::SchemaRepository, shared::Id, values::Violation};
 
 #[derive(Deserialize)]
 pub struct ValidateConfigCommand {
@@ -13,7 +13,7 @@ pub struct ValidateConfigCommand {
 
 #[derive(Serialize)]
 pub struct ValidateConfigResponse {
-    diffs: HashMap<String, Vec<Reason>>,
+    pub diffs: BTreeMap<String, Vec<Violation>>,
 }
 
 pub struct ValidateConfig {
@@ -25,6 +25,7 @@ impl ValidateConfig {
         ValidateConfig { schema_repository }
     }
 
+    #[tracing::instrument(name = "validate_config", skip_all, fields(schema_id = %cmd.schema_id))]
     pub async fn exec(&self, cmd: ValidateConfigCommand) -> Result<ValidateConfigResponse, Error> {
         let schema_id = Id::new(cmd.schema_id)?;
 
@@ -34,7 +35,7 @@ impl ValidateConfig {
             .await?
             .ok_or_else(|| Error::SchemaNotFound(schema_id.clone()))?;
 
-        let diff = schema.root_prop().validate(&cmd.data.into());
+        let diff = schema.validate(&cmd.data.into());
 
         Ok(ValidateConfigResponse {
             diffs: diff.diffs().clone(),
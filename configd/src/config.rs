@@ -1,15 +1,43 @@
-use std::{env, str::FromStr};
+use clap::Parser;
+use serde::Deserialize;
+use std::{
+    fs,
+    net::{SocketAddr, ToSocketAddrs},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
 use thiserror::Error;
+use tracing_subscriber::EnvFilter;
 
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum ConfigError {
     #[error("invalid environment: {0}")]
     InvalidEnvironment(String),
     #[error("invalid storage: {0}")]
     InvalidStorage(String),
+    #[error("invalid event bus: {0}")]
+    InvalidEventBus(String),
+    #[error("invalid log format: {0}")]
+    InvalidLogFormat(String),
+    #[error("invalid config file {path}: {reason}")]
+    InvalidFile { path: String, reason: String },
+    #[error("invalid {key}: {reason}")]
+    InvalidValue { key: &'static str, reason: String },
+    #[error("invalid TLS file {path}: {reason}")]
+    InvalidTls { path: String, reason: String },
+}
+
+fn invalid(key: &'static str, reason: impl Into<String>) -> ConfigError {
+    ConfigError::InvalidValue {
+        key,
+        reason: reason.into(),
+    }
 }
 
 // Environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Environment {
     Dev,
     Stg,
@@ -36,46 +64,511 @@ pub enum Storage {
     Postgres { url: String },
 }
 
-impl FromStr for Storage {
+// Event bus
+pub enum EventBus {
+    Local,
+    // Events are also sent to the other nodes sharing the database
+    Postgres { url: String },
+}
+
+// Log format
+pub enum LogFormat {
+    Json,
+    Text,
+}
+
+impl FromStr for LogFormat {
     type Err = ConfigError;
 
-    fn from_str(s: &str) -> Result<Storage, Self::Err> {
+    fn from_str(s: &str) -> Result<LogFormat, Self::Err> {
         match s {
-            "in-mem" => Ok(Storage::InMem),
-            "sqlite" => Ok(Storage::SQLite {
-                filename: env::var("SQLITE_FILENAME").unwrap_or("configd.db".to_string()),
-            }),
-            "postgres" => Ok(Storage::Postgres {
-                url: env::var("POSTGRES_URL")
-                    .unwrap_or("postgresql://localhost:5432/configd".to_string()),
-            }),
-            _ => Err(ConfigError::InvalidStorage(s.to_string())),
+            "json" => Ok(LogFormat::Json),
+            "text" => Ok(LogFormat::Text),
+            _ => Err(ConfigError::InvalidLogFormat(s.to_string())),
         }
     }
 }
 
+// CORS
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cors {
+    Any,
+    // Empty disables cross-origin requests
+    Origins(Vec<String>),
+}
+
+// Settings
+#[derive(Parser)]
+#[command(name = "configd", about = "Configuration server", version)]
+pub struct Args {
+    /// TOML file with settings, overridden by environment variables and flags
+    #[arg(long, env = "CONFIGD_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Validate the configuration and exit
+    #[arg(long)]
+    pub check_config: bool,
+    #[command(flatten)]
+    pub settings: Settings,
+}
+
+// Every source of settings: the config file, environment variables and flags.
+// Unset values fall back to the next source and finally to the defaults.
+#[derive(clap::Args, Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    /// dev, stg or prod
+    #[arg(long, env = "ENV")]
+    pub env: Option<String>,
+    #[arg(long, env = "HOST")]
+    pub host: Option<String>,
+    #[arg(long, env = "PORT")]
+    pub port: Option<u16>,
+    #[arg(long, env = "GRPC_PORT")]
+    pub grpc_port: Option<u16>,
+    /// in-mem, sqlite or postgres
+    #[arg(long, env = "STORAGE")]
+    pub storage: Option<String>,
+    #[arg(long, env = "SQLITE_FILENAME")]
+    pub sqlite_filename: Option<String>,
+    #[arg(long, env = "POSTGRES_URL", hide_env_values = true)]
+    pub postgres_url: Option<String>,
+    /// local or postgres
+    #[arg(long, env = "EVENT_BUS")]
+    pub event_bus: Option<String>,
+    #[arg(long, env = "POOL_MAX_CONNECTIONS")]
+    pub pool_max_connections: Option<u32>,
+    /// Comma separated allowed origins, every origin is allowed in dev if unset
+    #[arg(long, env = "CORS_ORIGINS", value_delimiter = ',')]
+    pub cors_origins: Option<Vec<String>>,
+    #[arg(long, env = "TLS_CERT")]
+    pub tls_cert: Option<String>,
+    #[arg(long, env = "TLS_KEY")]
+    pub tls_key: Option<String>,
+    /// CA of accepted client certificates, enables mutual TLS
+    #[arg(long, env = "TLS_CLIENT_CA")]
+    pub tls_client_ca: Option<String>,
+    /// Bytes
+    #[arg(long, env = "MAX_BODY_SIZE")]
+    pub max_body_size: Option<usize>,
+    /// Seconds
+    #[arg(long, env = "REQUEST_TIMEOUT")]
+    pub request_timeout: Option<u64>,
+    /// Config reads per second of every client certificate, or of every source
+    /// and instance without one, zero disables it
+    #[arg(long, env = "RATE_LIMIT")]
+    pub rate_limit: Option<u32>,
+    /// Config reads per second of every IP address, zero disables it. Clients
+    /// behind a proxy or NAT share theirs.
+    #[arg(long, env = "IP_RATE_LIMIT")]
+    pub ip_rate_limit: Option<u32>,
+    /// Failed password attempts before a config is locked, zero disables it
+    #[arg(long, env = "PASSWORD_MAX_ATTEMPTS")]
+    pub password_max_attempts: Option<u32>,
+    /// Seconds, doubled on every further failure
+    #[arg(long, env = "PASSWORD_LOCKOUT")]
+    pub password_lockout: Option<u64>,
+    /// Seconds
+    #[arg(long, env = "ACCESS_FLUSH_INTERVAL")]
+    pub access_flush_interval: Option<u64>,
+    /// Seconds deleted schemas and configs are kept before purging them, zero
+    /// keeps them
+    #[arg(long, env = "TRASH_RETENTION")]
+    pub trash_retention: Option<u64>,
+    /// Cached schemas and configs, zero disables the cache
+    #[arg(long, env = "CACHE_CAPACITY")]
+    pub cache_capacity: Option<usize>,
+    /// Seconds. Without the postgres event bus, other nodes' changes are only
+    /// seen once cached entries expire, so they may be stale for this long.
+    #[arg(long, env = "CACHE_TTL")]
+    pub cache_ttl: Option<u64>,
+    #[arg(long, env = "LOG_LEVEL")]
+    pub log_level: Option<String>,
+    /// json or text
+    #[arg(long, env = "LOG_FORMAT")]
+    pub log_format: Option<String>,
+    /// Seconds
+    #[arg(long, env = "SHUTDOWN_TIMEOUT")]
+    pub shutdown_timeout: Option<u64>,
+}
+
+impl Settings {
+    pub fn from_file(path: &PathBuf) -> Result<Settings, ConfigError> {
+        let file_error = |reason: String| ConfigError::InvalidFile {
+            path: path.display().to_string(),
+            reason,
+        };
+
+        let content = fs::read_to_string(path).map_err(|err| file_error(err.to_string()))?;
+
+        toml::from_str(&content).map_err(|err| file_error(err.to_string()))
+    }
+
+    // Values set in other take precedence
+    pub fn merge(self, other: Settings) -> Settings {
+        Settings {
+            env: other.env.or(self.env),
+            host: other.host.or(self.host),
+            port: other.port.or(self.port),
+            grpc_port: other.grpc_port.or(self.grpc_port),
+            storage: other.storage.or(self.storage),
+            sqlite_filename: other.sqlite_filename.or(self.sqlite_filename),
+            postgres_url: other.postgres_url.or(self.postgres_url),
+            event_bus: other.event_bus.or(self.event_bus),
+            pool_max_connections: other.pool_max_connections.or(self.pool_max_connections),
+            cors_origins: other.cors_origins.or(self.cors_origins),
+            tls_cert: other.tls_cert.or(self.tls_cert),
+            tls_key: other.tls_key.or(self.tls_key),
+            tls_client_ca: other.tls_client_ca.or(self.tls_client_ca),
+            max_body_size: other.max_body_size.or(self.max_body_size),
+            request_timeout: other.request_timeout.or(self.request_timeout),
+            rate_limit: other.rate_limit.or(self.rate_limit),
+            ip_rate_limit: other.ip_rate_limit.or(self.ip_rate_limit),
+            password_max_attempts: other.password_max_attempts.or(self.password_max_attempts),
+            password_lockout: other.password_lockout.or(self.password_lockout),
+            access_flush_interval: other.access_flush_interval.or(self.access_flush_interval),
+            trash_retention: other.trash_retention.or(self.trash_retention),
+            cache_capacity: other.cache_capacity.or(self.cache_capacity),
+            cache_ttl: other.cache_ttl.or(self.cache_ttl),
+            log_level: other.log_level.or(self.log_level),
+            log_format: other.log_format.or(self.log_format),
+            shutdown_timeout: other.shutdown_timeout.or(self.shutdown_timeout),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Tls {
+    pub cert: String,
+    pub key: String,
+    // Clients must present a certificate signed by this CA
+    pub client_ca: Option<String>,
+}
+
 pub struct Config {
     pub env: Environment,
-    pub host: String,
-    pub port: u16,
+    pub addr: SocketAddr,
+    pub grpc_addr: SocketAddr,
     pub storage: Storage,
+    pub event_bus: EventBus,
+    // Per database pool
+    pub pool_max_connections: u32,
+    pub cors: Cors,
+    pub tls: Option<Tls>,
+    pub max_body_size: usize,
+    pub request_timeout: Duration,
+    // Config reads per second, zero disables them
+    pub rate_limit: u32,
+    pub ip_rate_limit: u32,
+    pub password_max_attempts: u32,
+    pub password_lockout: Duration,
+    // How often registered accesses are written
+    pub access_flush_interval: Duration,
+    // Deleted schemas and configs are purged after it, zero keeps them
+    pub trash_retention: Duration,
+    // Maximum cached schemas and configs, zero disables the cache
+    pub cache_capacity: usize,
+    // Entries are invalidated by the changes of this node, and of the others
+    // only through the postgres event bus. Otherwise changes made on other
+    // nodes are served stale until the entries expire.
+    pub cache_ttl: Duration,
+    // Directives like "info" or "configd=debug,sqlx=warn"
+    pub log_level: String,
+    pub log_format: LogFormat,
+    // Time given to in-flight requests and background tasks on shutdown
+    pub shutdown_timeout: Duration,
 }
 
 impl Config {
-    pub fn load() -> Result<Config, ConfigError> {
-        Ok(Config {
-            env: if let Ok(env) = env::var("ENV") {
-                Environment::from_str(&env)?
-            } else {
-                Environment::Dev
+    pub fn load(args: Args) -> Result<Config, ConfigError> {
+        let settings = match args.config {
+            Some(ref path) => Settings::from_file(path)?.merge(args.settings),
+            None => args.settings,
+        };
+
+        Config::resolve(settings)
+    }
+
+    // Applies the defaults and validates the settings
+    pub fn resolve(settings: Settings) -> Result<Config, ConfigError> {
+        let env = match settings.env {
+            Some(env) => Environment::from_str(&env)?,
+            None => Environment::Dev,
+        };
+
+        let port = settings.port.unwrap_or(8080);
+        let grpc_port = settings.grpc_port.unwrap_or(8081);
+        if port == grpc_port {
+            return Err(invalid(
+                "grpc_port",
+                format!("{} is already the port", port),
+            ));
+        }
+
+        // Host names are resolved once, the first address is listened on
+        let host = settings.host.unwrap_or_else(|| "127.0.0.1".to_string());
+        let resolve = |port: u16| {
+            (host.as_str(), port)
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addrs| addrs.next())
+                .ok_or_else(|| invalid("host", format!("{} could not be resolved", host)))
+        };
+        let addr = resolve(port)?;
+        let grpc_addr = resolve(grpc_port)?;
+
+        let postgres_url = settings
+            .postgres_url
+            .unwrap_or_else(|| "postgresql://localhost:5432/configd".to_string());
+
+        let storage = match settings.storage.as_deref().unwrap_or("in-mem") {
+            "in-mem" => Storage::InMem,
+            "sqlite" => Storage::SQLite {
+                filename: settings
+                    .sqlite_filename
+                    .unwrap_or_else(|| "configd.db".to_string()),
             },
-            host: env::var("HOST").unwrap_or("127.0.0.1".to_string()),
-            port: env::var("PORT")
-                .map(|port| port.parse().unwrap())
-                .unwrap_or(8080),
-            storage: env::var("STORAGE")
-                .map(|storage| Storage::from_str(&storage).unwrap())
-                .unwrap_or(Storage::InMem),
+            "postgres" => Storage::Postgres {
+                url: postgres_url.clone(),
+            },
+            storage => return Err(ConfigError::InvalidStorage(storage.to_string())),
+        };
+
+        let event_bus = match settings.event_bus.as_deref().unwrap_or("local") {
+            "local" => EventBus::Local,
+            "postgres" => EventBus::Postgres { url: postgres_url },
+            event_bus => return Err(ConfigError::InvalidEventBus(event_bus.to_string())),
+        };
+
+        let pool_max_connections = settings.pool_max_connections.unwrap_or(10);
+        if pool_max_connections == 0 {
+            return Err(invalid("pool_max_connections", "must be greater than 0"));
+        }
+
+        // Every origin is allowed in dev unless restricted
+        let origins = settings.cors_origins.unwrap_or_default();
+        for origin in origins.iter() {
+            if origin == "*" {
+                if env != Environment::Dev {
+                    return Err(invalid("cors_origins", "* is only allowed in dev"));
+                }
+            } else if !(origin.starts_with("http://") || origin.starts_with("https://"))
+                || !origin.chars().all(|c| c.is_ascii_graphic())
+            {
+                return Err(invalid(
+                    "cors_origins",
+                    format!("{} is not an http or https origin", origin),
+                ));
+            }
+        }
+        let cors = if env == Environment::Dev
+            && (origins.is_empty() || origins.contains(&"*".to_string()))
+        {
+            Cors::Any
+        } else {
+            Cors::Origins(origins)
+        };
+
+        let tls = match (settings.tls_cert, settings.tls_key) {
+            (Some(cert), Some(key)) => {
+                let files = [
+                    ("tls_cert", Some(&cert)),
+                    ("tls_key", Some(&key)),
+                    ("tls_client_ca", settings.tls_client_ca.as_ref()),
+                ];
+                for (key_name, path) in files {
+                    if let Some(path) = path {
+                        if fs::metadata(path).is_err() {
+                            return Err(invalid(key_name, format!("{} does not exist", path)));
+                        }
+                    }
+                }
+
+                Some(Tls {
+                    cert,
+                    key,
+                    client_ca: settings.tls_client_ca,
+                })
+            }
+            (Some(_), None) => return Err(invalid("tls_key", "required with tls_cert")),
+            (None, Some(_)) => return Err(invalid("tls_cert", "required with tls_key")),
+            (None, None) => {
+                if settings.tls_client_ca.is_some() {
+                    return Err(invalid("tls_client_ca", "requires tls_cert and tls_key"));
+                }
+
+                None
+            }
+        };
+
+        let max_body_size = settings.max_body_size.unwrap_or(1024 * 1024);
+        if max_body_size == 0 {
+            return Err(invalid("max_body_size", "must be greater than 0"));
+        }
+
+        let request_timeout = settings.request_timeout.unwrap_or(30);
+        if request_timeout == 0 {
+            return Err(invalid("request_timeout", "must be greater than 0"));
+        }
+
+        let password_lockout = settings.password_lockout.unwrap_or(30);
+        if password_lockout == 0 {
+            return Err(invalid("password_lockout", "must be greater than 0"));
+        }
+
+        let access_flush_interval = settings.access_flush_interval.unwrap_or(5);
+        if access_flush_interval == 0 {
+            return Err(invalid("access_flush_interval", "must be greater than 0"));
+        }
+
+        let log_level = settings
+            .log_level
+            .unwrap_or_else(|| "info,sqlx=warn".to_string());
+        if let Err(err) = EnvFilter::try_new(&log_level) {
+            return Err(invalid("log_level", err.to_string()));
+        }
+
+        // Readable logs while developing
+        let log_format = match settings.log_format {
+            Some(log_format) => LogFormat::from_str(&log_format)?,
+            None if env == Environment::Dev => LogFormat::Text,
+            None => LogFormat::Json,
+        };
+
+        Ok(Config {
+            env,
+            addr,
+            grpc_addr,
+            storage,
+            event_bus,
+            pool_max_connections,
+            cors,
+            tls,
+            max_body_size,
+            request_timeout: Duration::from_secs(request_timeout),
+            rate_limit: settings.rate_limit.unwrap_or(20),
+            ip_rate_limit: settings.ip_rate_limit.unwrap_or(0),
+            password_max_attempts: settings.password_max_attempts.unwrap_or(5),
+            password_lockout: Duration::from_secs(password_lockout),
+            access_flush_interval: Duration::from_secs(access_flush_interval),
+            trash_retention: Duration::from_secs(
+                settings.trash_retention.unwrap_or(30 * 24 * 60 * 60),
+            ),
+            cache_capacity: settings.cache_capacity.unwrap_or(1000),
+            cache_ttl: Duration::from_secs(settings.cache_ttl.unwrap_or(60)),
+            log_level,
+            log_format,
+            shutdown_timeout: Duration::from_secs(settings.shutdown_timeout.unwrap_or(30)),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers() {
+        let file: Settings = toml::from_str(
+            r#"
+            env = "prod"
+            port = 9000
+            storage = "sqlite"
+            sqlite_filename = "file.db"
+            cors_origins = ["https://app.example.com"]
+            "#,
+        )
+        .unwrap();
+        let flags = Settings {
+            port: Some(9100),
+            ..Settings::default()
+        };
+
+        let config = Config::resolve(file.merge(flags)).unwrap();
+
+        assert_eq!(config.env, Environment::Prod);
+        assert_eq!(config.addr, "127.0.0.1:9100".parse().unwrap());
+        assert_eq!(config.grpc_addr, "127.0.0.1:8081".parse().unwrap());
+        assert!(
+            matches!(config.storage, Storage::SQLite { ref filename } if filename == "file.db")
+        );
+        assert_eq!(
+            config.cors,
+            Cors::Origins(vec!["https://app.example.com".to_string()])
+        );
+        assert!(matches!(config.log_format, LogFormat::Json));
+
+        // Unknown keys are rejected
+        assert!(toml::from_str::<Settings>("prot = 9000").is_err());
+    }
+
+    #[test]
+    fn environment_defaults() {
+        let config = Config::resolve(Settings::default()).unwrap();
+        assert_eq!(config.cors, Cors::Any);
+        assert!(matches!(config.log_format, LogFormat::Text));
+
+        let config = Config::resolve(Settings {
+            env: Some("stg".to_string()),
+            ..Settings::default()
+        })
+        .unwrap();
+        assert_eq!(config.cors, Cors::Origins(Vec::new()));
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let err = |settings: Settings| Config::resolve(settings).err().unwrap().to_string();
+
+        assert_eq!(
+            err(Settings {
+                port: Some(9000),
+                grpc_port: Some(9000),
+                ..Settings::default()
+            }),
+            "invalid grpc_port: 9000 is already the port"
+        );
+        assert_eq!(
+            err(Settings {
+                host: Some("not a host".to_string()),
+                ..Settings::default()
+            }),
+            "invalid host: not a host could not be resolved"
+        );
+        assert_eq!(
+            err(Settings {
+                storage: Some("mysql".to_string()),
+                ..Settings::default()
+            }),
+            "invalid storage: mysql"
+        );
+        assert_eq!(
+            err(Settings {
+                env: Some("prod".to_string()),
+                cors_origins: Some(vec!["*".to_string()]),
+                ..Settings::default()
+            }),
+            "invalid cors_origins: * is only allowed in dev"
+        );
+        assert_eq!(
+            err(Settings {
+                tls_cert: Some("cert.pem".to_string()),
+                ..Settings::default()
+            }),
+            "invalid tls_key: required with tls_cert"
+        );
+        assert_eq!(
+            err(Settings {
+                tls_client_ca: Some("ca.pem".to_string()),
+                ..Settings::default()
+            }),
+            "invalid tls_client_ca: requires tls_cert and tls_key"
+        );
+        assert_eq!(
+            err(Settings {
+                pool_max_connections: Some(0),
+                ..Settings::default()
+            }),
+            "invalid pool_max_connections: must be greater than 0"
+        );
+    }
+}
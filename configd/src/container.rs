@@ -1,58 +1,365 @@
-use sqlx::{PgPool, SqlitePool};
-use std::sync::Arc;
+use chrono::Utc;
+use sqlx::{postgres::PgPoolOptions, sqlite::SqlitePoolOptions, PgPool, SqlitePool};
+use std::{net::IpAddr, sync::Arc, time::Duration};
 
 use crate::{
-    application::{CleanConfigAccesses, RevalidateConfigs},
-    config::{Config, Storage},
-    domain::{errors::Error, events::Subscriber, schemas::SchemaRepository},
+    application::{CleanConfigAccesses, PurgeTrash, PurgeTrashCommand, RevalidateConfigs},
+    config::{Config, EventBus, Storage},
+    domain::{
+        configs::{self, AccessRepository, AccessStore, ConfigRepository, PasswordLockout},
+        errors::Error,
+        events::{Publisher, Subscriber},
+        schemas::{Schema, SchemaRepository},
+        shared::Id,
+    },
     infrastructure::{
-        InMemSchemaRepository, LocalEventBus, PostgresSchemaRepository, SQLiteSchemaRepository,
+        BackgroundTasks, Cache, CachedConfigRepository, CachedSchemaRepository,
+        InMemAccessRepository, InMemConfigRepository, InMemSchemaRepository, LocalEventBus,
+        MeteredAccessRepository, MeteredConfigRepository, MeteredEventBus, MeteredSchemaRepository,
+        PostgresAccessRepository, PostgresConfigRepository, PostgresEventBus,
+        PostgresSchemaRepository, RateLimiter, SQLiteAccessRepository, SQLiteConfigRepository,
+        SQLiteSchemaRepository,
     },
+    tls::ClientIdentity,
 };
 
 pub struct Container {
-    pub event_publisher: Arc<LocalEventBus>,
+    pub event_publisher: Arc<dyn Publisher + Sync + Send>,
+    // Receives the events of every node
+    pub event_subscriber: Arc<dyn Subscriber + Sync + Send>,
     pub schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    pub config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    pub access_store: Arc<AccessStore>,
+    pub password_lockout: Arc<PasswordLockout>,
+    // Config reads by client, and by IP address
+    client_rate_limiter: Option<RateLimiter>,
+    ip_rate_limiter: Option<RateLimiter>,
+    pub schema_cache: Option<Arc<Cache<Id, Schema>>>,
+    pub config_cache: Option<Arc<Cache<(Id, Id), configs::Config>>>,
+    pub background_tasks: Arc<BackgroundTasks>,
+    sqlite_pool: Option<SqlitePool>,
+    postgres_pools: Vec<PgPool>,
 }
 
 impl Container {
     pub async fn build(config: &Config) -> Result<Container, Error> {
-        let event_publisher = Arc::new(LocalEventBus::new_async());
+        let background_tasks = Arc::new(BackgroundTasks::new());
+        let mut sqlite_pool = None;
+        let mut postgres_pools = Vec::new();
+
+        // Only receives the events published by this node
+        let local_event_bus = Arc::new(LocalEventBus::new_async(background_tasks.clone()));
+        let metered_local_event_bus = Arc::new(MeteredEventBus::new(
+            local_event_bus.clone(),
+            local_event_bus.clone(),
+        ));
+
+        let (event_publisher, event_subscriber): (
+            Arc<dyn Publisher + Sync + Send>,
+            Arc<dyn Subscriber + Sync + Send>,
+        ) = match config.event_bus {
+            EventBus::Local => (local_event_bus.clone(), local_event_bus.clone()),
+            EventBus::Postgres { ref url } => {
+                let postgres_pool = PgPoolOptions::new()
+                    .max_connections(config.pool_max_connections)
+                    .connect(url)
+                    .await
+                    .map_err(Error::Database)?;
+                postgres_pools.push(postgres_pool.clone());
+                let event_bus = Arc::new(
+                    PostgresEventBus::new(
+                        postgres_pool,
+                        local_event_bus.clone(),
+                        background_tasks.clone(),
+                    )
+                    .await?,
+                );
+                (event_bus.clone(), event_bus)
+            }
+        };
+        let event_bus = Arc::new(MeteredEventBus::new(event_publisher, event_subscriber));
+        let (event_publisher, event_subscriber): (
+            Arc<dyn Publisher + Sync + Send>,
+            Arc<dyn Subscriber + Sync + Send>,
+        ) = (event_bus.clone(), event_bus);
 
-        let schema_repository: Arc<dyn SchemaRepository + Sync + Send> = match config.storage {
-            Storage::InMem => Arc::new(InMemSchemaRepository::new()),
+        let (schema_repository, config_repository, access_repository): (
+            Arc<dyn SchemaRepository + Sync + Send>,
+            Arc<dyn ConfigRepository + Sync + Send>,
+            Arc<dyn AccessRepository + Sync + Send>,
+        ) = match config.storage {
+            Storage::InMem => {
+                let config_repository = Arc::new(InMemConfigRepository::new());
+                (
+                    Arc::new(InMemSchemaRepository::new(config_repository.clone())),
+                    config_repository,
+                    Arc::new(InMemAccessRepository::new()),
+                )
+            }
             Storage::SQLite { ref filename } => {
-                let sqlite_pool = SqlitePool::connect(filename)
+                let pool = SqlitePoolOptions::new()
+                    .max_connections(config.pool_max_connections)
+                    .connect(filename)
                     .await
                     .map_err(Error::Database)?;
-                Arc::new(SQLiteSchemaRepository::new(sqlite_pool).await?)
+                sqlite_pool = Some(pool.clone());
+                (
+                    Arc::new(SQLiteSchemaRepository::new(pool.clone()).await?),
+                    Arc::new(SQLiteConfigRepository::new(pool.clone()).await?),
+                    Arc::new(SQLiteAccessRepository::new(pool).await?),
+                )
             }
             Storage::Postgres { ref url } => {
-                let postgres_pool = PgPool::connect(url).await.map_err(Error::Database)?;
-                Arc::new(PostgresSchemaRepository::new(postgres_pool).await?)
+                let postgres_pool = PgPoolOptions::new()
+                    .max_connections(config.pool_max_connections)
+                    .connect(url)
+                    .await
+                    .map_err(Error::Database)?;
+                postgres_pools.push(postgres_pool.clone());
+                (
+                    Arc::new(PostgresSchemaRepository::new(postgres_pool.clone()).await?),
+                    Arc::new(PostgresConfigRepository::new(postgres_pool.clone()).await?),
+                    Arc::new(PostgresAccessRepository::new(postgres_pool).await?),
+                )
             }
         };
 
-        // Handlers
-        let clean_config_accesses =
-            CleanConfigAccesses::new(event_publisher.clone(), schema_repository.clone());
+        // Queries are measured against the storage itself, cache hits aside
+        let (schema_repository, config_repository, access_repository): (
+            Arc<dyn SchemaRepository + Sync + Send>,
+            Arc<dyn ConfigRepository + Sync + Send>,
+            Arc<dyn AccessRepository + Sync + Send>,
+        ) = (
+            Arc::new(MeteredSchemaRepository::new(schema_repository)),
+            Arc::new(MeteredConfigRepository::new(config_repository)),
+            Arc::new(MeteredAccessRepository::new(access_repository)),
+        );
+
+        // Caches are disabled when their capacity is zero
+        let (schema_repository, config_repository, schema_cache, config_cache): (
+            Arc<dyn SchemaRepository + Sync + Send>,
+            Arc<dyn ConfigRepository + Sync + Send>,
+            _,
+            _,
+        ) = if config.cache_capacity > 0 {
+            let schema_cache = Arc::new(Cache::new(config.cache_capacity, config.cache_ttl));
+            let config_cache = Arc::new(Cache::new(config.cache_capacity, config.cache_ttl));
+
+            let cached_schema_repository = CachedSchemaRepository::new(
+                schema_repository,
+                schema_cache.clone(),
+                config_cache.clone(),
+            );
+            let cached_config_repository =
+                CachedConfigRepository::new(config_repository, config_cache.clone());
+
+            event_subscriber
+                .subscribe("schema.*", Box::new(cached_schema_repository.clone()))
+                .await
+                .unwrap();
+            event_subscriber
+                .subscribe("config.*", Box::new(cached_config_repository.clone()))
+                .await
+                .unwrap();
+
+            // Invalidations of other nodes could have been missed
+            event_subscriber
+                .subscribe(
+                    "events.resynced",
+                    Box::new(cached_schema_repository.clone()),
+                )
+                .await
+                .unwrap();
+            event_subscriber
+                .subscribe(
+                    "events.resynced",
+                    Box::new(cached_config_repository.clone()),
+                )
+                .await
+                .unwrap();
+
+            (
+                Arc::new(cached_schema_repository),
+                Arc::new(cached_config_repository),
+                Some(schema_cache),
+                Some(config_cache),
+            )
+        } else {
+            (schema_repository, config_repository, None, None)
+        };
+
+        let access_store = Arc::new(AccessStore::new(access_repository));
+        let password_lockout = Arc::new(PasswordLockout::new(
+            config.password_max_attempts,
+            config.password_lockout,
+        ));
+        let client_rate_limiter =
+            (config.rate_limit > 0).then(|| RateLimiter::new(config.rate_limit));
+        let ip_rate_limiter =
+            (config.ip_rate_limit > 0).then(|| RateLimiter::new(config.ip_rate_limit));
+
+        // Accesses are written in batches, the last one on shutdown
+        let flushed_access_store = access_store.clone();
+        let access_flush_interval = config.access_flush_interval;
+        let flush_tasks = background_tasks.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(access_flush_interval);
+            loop {
+                tokio::select! {
+                    _ = flush_tasks.closed() => return,
+                    _ = interval.tick() => {}
+                }
 
-        let revalidate_configs =
-            RevalidateConfigs::new(event_publisher.clone(), schema_repository.clone());
+                if let Err(err) = flushed_access_store.flush().await {
+                    tracing::error!(error = %err, "could not flush accesses");
+                }
+            }
+        });
+
+        // Deleted schemas and configs are purged once their retention expires
+        if !config.trash_retention.is_zero() {
+            let purge_trash = PurgeTrash::new(
+                event_publisher.clone(),
+                schema_repository.clone(),
+                config_repository.clone(),
+            );
+            let trash_retention = config.trash_retention;
+            let purge_tasks = background_tasks.clone();
+            tokio::spawn(async move {
+                let mut interval =
+                    tokio::time::interval(trash_retention.min(Duration::from_secs(60 * 60)));
+                loop {
+                    tokio::select! {
+                        _ = purge_tasks.closed() => return,
+                        _ = interval.tick() => {}
+                    }
+
+                    let deleted_before = match chrono::Duration::from_std(trash_retention) {
+                        Ok(retention) => Utc::now() - retention,
+                        Err(_) => continue,
+                    };
+                    match purge_trash.exec(PurgeTrashCommand { deleted_before }).await {
+                        Ok(res) if res.schemas > 0 || res.configs > 0 => {
+                            tracing::info!(
+                                schemas = res.schemas,
+                                configs = res.configs,
+                                "trash purged"
+                            );
+                        }
+                        Ok(_) => {}
+                        Err(err) => tracing::error!(error = %err, "could not purge trash"),
+                    }
+                }
+            });
+        }
+
+        // Handlers
+        let revalidate_configs = RevalidateConfigs::new(
+            event_publisher.clone(),
+            schema_repository.clone(),
+            config_repository.clone(),
+        );
 
-        // Subscriptions
-        event_publisher
-            .subscribe("config.accessed", Box::new(clean_config_accesses))
+        // Subscriptions. Configs are revalidated only by the node that changed
+        // the schema.
+        metered_local_event_bus
+            .subscribe(
+                "schema.root_prop_changed",
+                Box::new(revalidate_configs.clone()),
+            )
             .await
             .unwrap();
-        event_publisher
-            .subscribe("schema.root_prop_changed", Box::new(revalidate_configs))
+        metered_local_event_bus
+            .subscribe("schema.rules_changed", Box::new(revalidate_configs))
+            .await
+            .unwrap();
+
+        // Every node forgets the pending accesses of deleted configs
+        event_subscriber
+            .subscribe(
+                "config.*",
+                Box::new(CleanConfigAccesses::new(access_store.clone())),
+            )
             .await
             .unwrap();
 
         Ok(Container {
             event_publisher,
+            event_subscriber,
             schema_repository,
+            config_repository,
+            access_store,
+            password_lockout,
+            client_rate_limiter,
+            ip_rate_limiter,
+            schema_cache,
+            config_cache,
+            background_tasks,
+            sqlite_pool,
+            postgres_pools,
         })
     }
+
+    // Clients are told apart by their verified certificate, otherwise by the
+    // source and instance headers. Anonymous reads are only limited by IP
+    // address.
+    pub async fn limit_config_read(
+        &self,
+        ip: Option<IpAddr>,
+        identity: Option<&ClientIdentity>,
+        source: Option<&str>,
+        instance: Option<&str>,
+    ) -> Result<(), Error> {
+        if let (Some(limiter), Some(ip)) = (&self.ip_rate_limiter, ip) {
+            limiter.check(&ip.to_string()).await?;
+        }
+
+        if let Some(limiter) = &self.client_rate_limiter {
+            let key = match identity {
+                Some(identity) => Some(format!("identity:{}", identity.0)),
+                None if source.is_some() || instance.is_some() => Some(format!(
+                    "headers:{}/{}",
+                    source.unwrap_or_default(),
+                    instance.unwrap_or_default()
+                )),
+                None => None,
+            };
+            if let Some(key) = key {
+                limiter.check(&key).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Waits for the running handlers, which could publish more events, then
+    // writes the pending accesses and closes the pools
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.background_tasks.close();
+
+        if !self.background_tasks.wait(timeout).await {
+            tracing::warn!(
+                running = self.background_tasks.running(),
+                "background tasks still running at the deadline"
+            );
+        }
+
+        if let Err(err) = self.access_store.flush().await {
+            tracing::error!(error = %err, "could not flush accesses");
+        }
+
+        // Otherwise the last writes could remain only in the WAL file
+        if let Some(pool) = &self.sqlite_pool {
+            if let Err(err) = sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+                .execute(pool)
+                .await
+            {
+                tracing::error!(error = %err, "could not checkpoint");
+            }
+            pool.close().await;
+        }
+        for pool in self.postgres_pools.iter() {
+            pool.close().await;
+        }
+    }
 }
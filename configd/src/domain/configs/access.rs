@@ -1,6 +1,25 @@
+use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
 
-use crate::domain::shared::Id;
+use crate::domain::{errors::Error, shared::Id};
+
+#[async_trait]
+pub trait AccessRepository {
+    // Accesses not expired at the given time
+    async fn find_active_by_config(
+        &self,
+        schema_id: &Id,
+        config_id: &Id,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Access>, Error>;
+    // Inserts or updates the given accesses, keeping the most recent ones and
+    // leaving the other accesses of the config untouched
+    async fn save(&self, schema_id: &Id, config_id: &Id, accesses: &[Access]) -> Result<(), Error>;
+    async fn delete_by_config(&self, schema_id: &Id, config_id: &Id) -> Result<(), Error>;
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<(), Error>;
+    // Distinct source and instance pairs with an access not expired
+    async fn count_active_instances(&self, now: DateTime<Utc>) -> Result<u64, Error>;
+}
 
 #[derive(Debug, Clone)]
 pub struct Access {
@@ -71,14 +90,27 @@ impl Access {
         )
     }
 
-    pub fn elapsed_time(&self) -> Duration {
-        Utc::now() - self.timestamp
-    }
-
     pub fn elapsed_time_from_previous(&self) -> Option<Duration> {
         self.previous.map(|previous| self.timestamp - previous)
     }
 
+    // Instances are expected to access again within twice their last
+    // interval, or 30 seconds after their first access
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        let max_duration = self
+            .elapsed_time_from_previous()
+            .map(|previous| {
+                if previous.num_seconds() < 2 {
+                    previous + Duration::seconds(2)
+                } else {
+                    previous * 2
+                }
+            })
+            .unwrap_or_else(|| Duration::seconds(30));
+
+        self.timestamp + max_duration
+    }
+
     pub fn equals(&self, other: &Access) -> bool {
         self.source == other.source && self.instance == other.instance
     }
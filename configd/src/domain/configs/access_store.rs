@@ -0,0 +1,531 @@
+use chrono::Utc;
+use std::{collections::HashMap, mem, sync::Arc};
+use tokio::sync::Mutex;
+
+use crate::domain::{
+    configs::{Access, AccessRepository},
+    errors::Error,
+    shared::Id,
+};
+
+type ConfigKey = (Id, Id);
+
+#[derive(Default)]
+struct State {
+    // Loaded from the repository and dropped on every flush
+    accesses: HashMap<ConfigKey, Vec<Access>>,
+    // Accesses registered by this node and not written yet
+    pending: HashMap<ConfigKey, Vec<Access>>,
+}
+
+// Accesses are kept in memory and written in batches on flush, so reading a
+// config never writes to the schema repository. Only the accesses registered
+// here are written, other nodes may be saving the same configs.
+pub struct AccessStore {
+    access_repository: Arc<dyn AccessRepository + Sync + Send>,
+    state: Mutex<State>,
+    // Held while flushing, so removed accesses cannot be saved again by a
+    // flush already in progress
+    flushing: Mutex<()>,
+}
+
+impl AccessStore {
+    pub fn new(access_repository: Arc<dyn AccessRepository + Sync + Send>) -> AccessStore {
+        AccessStore {
+            access_repository,
+            state: Mutex::new(State::default()),
+            flushing: Mutex::new(()),
+        }
+    }
+
+    // Returns the live accesses of the config, including the registered one
+    pub async fn register(
+        &self,
+        schema_id: &Id,
+        config_id: &Id,
+        access: Access,
+    ) -> Result<Vec<Access>, Error> {
+        let key = (schema_id.clone(), config_id.clone());
+        self.load(&key).await?;
+
+        let mut state = self.state.lock().await;
+
+        let accesses = state.accesses.entry(key.clone()).or_default();
+        let access = register_access(accesses, access);
+        clean_old_accesses(accesses);
+        let accesses = accesses.clone();
+
+        replace_access(state.pending.entry(key).or_default(), access);
+
+        Ok(accesses)
+    }
+
+    // Accesses still considered alive
+    pub async fn active(&self, schema_id: &Id, config_id: &Id) -> Result<Vec<Access>, Error> {
+        let key = (schema_id.clone(), config_id.clone());
+        self.load(&key).await?;
+
+        let mut accesses = self
+            .state
+            .lock()
+            .await
+            .accesses
+            .get(&key)
+            .cloned()
+            .unwrap_or_default();
+        clean_old_accesses(&mut accesses);
+
+        Ok(accesses)
+    }
+
+    // Instances seen by any node, as of the last flush
+    pub async fn active_instances(&self) -> Result<u64, Error> {
+        self.access_repository
+            .count_active_instances(Utc::now())
+            .await
+    }
+
+    // Forgets the accesses of a deleted or purged config, including the ones
+    // not written yet
+    pub async fn remove(&self, schema_id: &Id, config_id: &Id) -> Result<(), Error> {
+        let key = (schema_id.clone(), config_id.clone());
+        let _flushing = self.flushing.lock().await;
+
+        {
+            let mut state = self.state.lock().await;
+            state.accesses.remove(&key);
+            state.pending.remove(&key);
+        }
+
+        self.access_repository
+            .delete_by_config(schema_id, config_id)
+            .await
+    }
+
+    pub async fn flush(&self) -> Result<(), Error> {
+        let _flushing = self.flushing.lock().await;
+
+        let batch: Vec<(ConfigKey, Vec<Access>)> = {
+            let mut state = self.state.lock().await;
+            mem::take(&mut state.pending).into_iter().collect()
+        };
+
+        let mut res = Ok(());
+
+        for (i, ((schema_id, config_id), accesses)) in batch.iter().enumerate() {
+            if let Err(err) = self
+                .access_repository
+                .save(schema_id, config_id, accesses)
+                .await
+            {
+                // Unsaved accesses are retried on the next flush, unless they
+                // were registered again meanwhile
+                let mut state = self.state.lock().await;
+                for (key, accesses) in batch[i..].iter() {
+                    let pending = state.pending.entry(key.clone()).or_default();
+                    for access in accesses {
+                        if !pending.iter().any(|a| a.equals(access)) {
+                            pending.push(access.clone());
+                        }
+                    }
+                }
+
+                res = Err(err);
+                break;
+            }
+        }
+
+        // Expired accesses are not loaded anymore, so they are deleted
+        if res.is_ok() {
+            res = self.access_repository.delete_expired(Utc::now()).await;
+        }
+
+        // Accesses are reloaded to see the ones saved by other nodes
+        self.state.lock().await.accesses.clear();
+
+        res
+    }
+
+    // The repository is not queried while holding the lock
+    async fn load(&self, key: &ConfigKey) -> Result<(), Error> {
+        if self.state.lock().await.accesses.contains_key(key) {
+            return Ok(());
+        }
+
+        let mut accesses = self
+            .access_repository
+            .find_active_by_config(&key.0, &key.1, Utc::now())
+            .await?;
+
+        let mut state = self.state.lock().await;
+
+        if !state.accesses.contains_key(key) {
+            for access in state.pending.get(key).into_iter().flatten() {
+                replace_access(&mut accesses, access.clone());
+            }
+
+            state.accesses.insert(key.clone(), accesses);
+        }
+
+        Ok(())
+    }
+}
+
+// Returns the registered access, pinged if it already existed
+fn register_access(accesses: &mut Vec<Access>, access: Access) -> Access {
+    if let Some(existing) = accesses.iter_mut().find(|a| a.equals(&access)) {
+        *existing = existing.ping();
+        existing.clone()
+    } else {
+        accesses.push(access.clone());
+        access
+    }
+}
+
+fn replace_access(accesses: &mut Vec<Access>, access: Access) {
+    if let Some(existing) = accesses.iter_mut().find(|a| a.equals(&access)) {
+        *existing = access;
+    } else {
+        accesses.push(access);
+    }
+}
+
+fn clean_old_accesses(accesses: &mut Vec<Access>) {
+    let now = Utc::now();
+    accesses.retain(|access| access.expires_at() >= now);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use async_trait::async_trait;
+    use chrono::{DateTime, Duration};
+
+    use crate::infrastructure::InMemAccessRepository;
+
+    // Saves slowly, so accesses can be removed while flushing
+    struct SlowAccessRepository(InMemAccessRepository);
+
+    #[async_trait]
+    impl AccessRepository for SlowAccessRepository {
+        async fn find_active_by_config(
+            &self,
+            schema_id: &Id,
+            config_id: &Id,
+            now: DateTime<Utc>,
+        ) -> Result<Vec<Access>, Error> {
+            self.0
+                .find_active_by_config(schema_id, config_id, now)
+                .await
+        }
+
+        async fn save(
+            &self,
+            schema_id: &Id,
+            config_id: &Id,
+            accesses: &[Access],
+        ) -> Result<(), Error> {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            self.0.save(schema_id, config_id, accesses).await
+        }
+
+        async fn delete_by_config(&self, schema_id: &Id, config_id: &Id) -> Result<(), Error> {
+            self.0.delete_by_config(schema_id, config_id).await
+        }
+
+        async fn delete_expired(&self, now: DateTime<Utc>) -> Result<(), Error> {
+            self.0.delete_expired(now).await
+        }
+
+        async fn count_active_instances(&self, now: DateTime<Utc>) -> Result<u64, Error> {
+            self.0.count_active_instances(now).await
+        }
+    }
+
+    #[test]
+    fn register() {
+        let mut accesses = Vec::new();
+
+        // New sources
+        register_access(
+            &mut accesses,
+            Access::create(
+                Id::new("Source 1").unwrap(),
+                Id::new("instance#01").unwrap(),
+            ),
+        );
+        register_access(
+            &mut accesses,
+            Access::create(
+                Id::new("Source 2").unwrap(),
+                Id::new("instance#01").unwrap(),
+            ),
+        );
+
+        assert_eq!(accesses[0].source().value(), "Source 1");
+        assert_eq!(accesses[1].source().value(), "Source 2");
+
+        // Existing source
+        register_access(
+            &mut accesses,
+            Access::create(
+                Id::new("Source 1").unwrap(),
+                Id::new("instance#01").unwrap(),
+            ),
+        );
+
+        assert_eq!(accesses.len(), 2);
+        assert_eq!(accesses[0].source().value(), "Source 1");
+        assert!(accesses[0].previous().is_some());
+        assert_eq!(accesses[1].source().value(), "Source 2");
+
+        // New instance
+        register_access(
+            &mut accesses,
+            Access::create(
+                Id::new("Source 1").unwrap(),
+                Id::new("instance#02").unwrap(),
+            ),
+        );
+
+        assert_eq!(accesses.len(), 3);
+        assert_eq!(accesses[0].instance().value(), "instance#01");
+        assert_eq!(accesses[2].source().value(), "Source 1");
+        assert_eq!(accesses[2].instance().value(), "instance#02");
+    }
+
+    #[test]
+    fn clean_old() {
+        let mut accesses = vec![
+            Access::new(
+                Id::new("Source 1").unwrap(),
+                Id::new("instance#01").unwrap(),
+                DateTime::parse_from_rfc3339("2022-07-25T19:00:00Z")
+                    .unwrap()
+                    .into(),
+                None,
+            ),
+            Access::new(
+                Id::new("Source 2").unwrap(),
+                Id::new("instance#01").unwrap(),
+                DateTime::parse_from_rfc3339("2022-07-25T19:30:00Z")
+                    .unwrap()
+                    .into(),
+                None,
+            ),
+            Access::new(
+                Id::new("Source 1").unwrap(),
+                Id::new("instance#02").unwrap(),
+                Utc::now(),
+                None,
+            ),
+        ];
+
+        clean_old_accesses(&mut accesses);
+
+        assert_eq!(accesses.len(), 1);
+        assert_eq!(accesses[0].source().value(), "Source 1");
+        assert_eq!(accesses[0].instance().value(), "instance#02");
+    }
+
+    #[tokio::test]
+    async fn flush() {
+        let access_repository = Arc::new(InMemAccessRepository::new());
+        let store = AccessStore::new(access_repository.clone());
+
+        let schema_id = Id::new("schema-01").unwrap();
+        let config_id = Id::new("config-01").unwrap();
+
+        for instance in ["instance#01", "instance#02", "instance#01"] {
+            store
+                .register(
+                    &schema_id,
+                    &config_id,
+                    Access::create_with_instance(Id::new(instance).unwrap()),
+                )
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(store.active(&schema_id, &config_id).await.unwrap().len(), 2);
+
+        // Nothing is written until flushed
+        assert!(access_repository
+            .find_active_by_config(&schema_id, &config_id, Utc::now())
+            .await
+            .unwrap()
+            .is_empty());
+
+        store.flush().await.unwrap();
+
+        assert_eq!(
+            access_repository
+                .find_active_by_config(&schema_id, &config_id, Utc::now())
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Accesses are loaded from the repository
+        let store = AccessStore::new(access_repository);
+        assert_eq!(store.active(&schema_id, &config_id).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn flush_from_several_stores() {
+        let access_repository = Arc::new(InMemAccessRepository::new());
+        let store_1 = AccessStore::new(access_repository.clone());
+        let store_2 = AccessStore::new(access_repository.clone());
+
+        let schema_id = Id::new("schema-01").unwrap();
+        let config_id = Id::new("config-01").unwrap();
+        let access = |instance| Access::create_with_instance(Id::new(instance).unwrap());
+
+        // Both stores load the config before any access is saved
+        store_1
+            .register(&schema_id, &config_id, access("instance#01"))
+            .await
+            .unwrap();
+        store_2
+            .register(&schema_id, &config_id, access("instance#02"))
+            .await
+            .unwrap();
+
+        store_1.flush().await.unwrap();
+        store_2.flush().await.unwrap();
+
+        // Flushing one store doesn't remove the accesses of the other one
+        assert_eq!(
+            access_repository
+                .find_active_by_config(&schema_id, &config_id, Utc::now())
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Accesses are reloaded after a flush
+        assert_eq!(
+            store_1.active(&schema_id, &config_id).await.unwrap().len(),
+            2
+        );
+
+        // Only registered accesses are written again
+        store_1
+            .register(&schema_id, &config_id, access("instance#02"))
+            .await
+            .unwrap();
+        store_2
+            .register(&schema_id, &config_id, access("instance#03"))
+            .await
+            .unwrap();
+
+        store_2.flush().await.unwrap();
+        store_1.flush().await.unwrap();
+
+        let accesses = access_repository
+            .find_active_by_config(&schema_id, &config_id, Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(accesses.len(), 3);
+        assert!(accesses
+            .iter()
+            .find(|a| a.instance().value() == "instance#02")
+            .unwrap()
+            .previous()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn expire_and_remove() {
+        let access_repository = Arc::new(InMemAccessRepository::new());
+        let store = AccessStore::new(access_repository.clone());
+
+        let schema_id = Id::new("schema-01").unwrap();
+        let config_id = Id::new("config-01").unwrap();
+        let access = |instance, timestamp| {
+            Access::new(
+                Id::new("unknown").unwrap(),
+                Id::new(instance).unwrap(),
+                timestamp,
+                None,
+            )
+        };
+
+        access_repository
+            .save(
+                &schema_id,
+                &config_id,
+                &[
+                    access("instance#01", Utc::now()),
+                    access("instance#02", Utc::now() - Duration::minutes(5)),
+                ],
+            )
+            .await
+            .unwrap();
+
+        // Expired accesses are neither loaded nor kept after a flush
+        assert_eq!(store.active(&schema_id, &config_id).await.unwrap().len(), 1);
+
+        store.flush().await.unwrap();
+        assert_eq!(
+            access_repository
+                .find_active_by_config(&schema_id, &config_id, Utc::now() - Duration::hours(1))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Removed accesses are not written by the next flush
+        store
+            .register(&schema_id, &config_id, access("instance#03", Utc::now()))
+            .await
+            .unwrap();
+        store.remove(&schema_id, &config_id).await.unwrap();
+        store.flush().await.unwrap();
+
+        assert!(store
+            .active(&schema_id, &config_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn remove_while_flushing() {
+        let access_repository = Arc::new(SlowAccessRepository(InMemAccessRepository::new()));
+        let store = Arc::new(AccessStore::new(access_repository.clone()));
+
+        let schema_id = Id::new("schema-01").unwrap();
+        let config_id = Id::new("config-01").unwrap();
+
+        store
+            .register(
+                &schema_id,
+                &config_id,
+                Access::create_with_instance(Id::new("instance#01").unwrap()),
+            )
+            .await
+            .unwrap();
+
+        let flush = tokio::spawn({
+            let store = store.clone();
+            async move { store.flush().await }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        // Waits for the flush, then deletes what it saved
+        store.remove(&schema_id, &config_id).await.unwrap();
+        flush.await.unwrap().unwrap();
+
+        assert!(access_repository
+            .find_active_by_config(&schema_id, &config_id, Utc::now())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
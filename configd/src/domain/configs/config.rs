@@ -1,14 +1,38 @@
-use chrono::Duration;
+use async_trait::async_trait;
 
 use crate::domain::{
-    configs::{Access, Password},
+    configs::{
+        ConfigCreated, ConfigDataChanged, ConfigDeleted, ConfigPasswordChanged,
+        ConfigPasswordDeleted, ConfigPurged, ConfigQuery, ConfigRestored, ConfigRevalidated,
+        Password,
+    },
     errors::Error,
-    shared::{Id, Timestamps, Version},
+    events::{Event, EventCollector},
+    shared::{Id, Page, Timestamps, Version},
     values::Value,
 };
 
+#[async_trait]
+pub trait ConfigRepository {
+    async fn find(&self, schema_id: &Id, query: &ConfigQuery) -> Result<Page<Config>, Error>;
+    async fn find_by_id(&self, schema_id: &Id, id: &Id) -> Result<Option<Config>, Error>;
+    async fn find_deleted_by_id(&self, schema_id: &Id, id: &Id) -> Result<Option<Config>, Error>;
+    // Deleted configs too, their ids are taken until purged
+    async fn exists(&self, schema_id: &Id, id: &Id) -> Result<bool, Error>;
+    // Configs not deleted, of every schema
+    async fn count(&self) -> Result<ConfigCount, Error>;
+    async fn save(&self, config: &mut Config) -> Result<(), Error>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConfigCount {
+    pub total: u64,
+    pub invalid: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
+    schema_id: Id,
     id: Id,
     name: String,
 
@@ -16,56 +40,77 @@ pub struct Config {
     valid: bool,
     password: Option<Password>,
 
-    accesses: Vec<Access>,
-
     timestamps: Timestamps,
     version: Version,
+
+    event_collector: EventCollector,
 }
 
 impl Config {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
+        schema_id: Id,
         id: Id,
         name: String,
         data: Value,
         valid: bool,
         password: Option<Password>,
-        accesses: Vec<Access>,
         timestamps: Timestamps,
         version: Version,
+        event_collector: Option<EventCollector>,
     ) -> Result<Config, Error> {
         if name.is_empty() {
             return Err(Error::EmptyName);
         }
 
         Ok(Config {
+            schema_id,
             id,
             name,
             password,
             data,
             valid,
-            accesses,
             timestamps,
             version,
+            event_collector: event_collector.unwrap_or_else(EventCollector::create),
         })
     }
 
+    // Data is validated against the schema by the ConfigService
     pub fn create(
+        schema_id: Id,
         id: Id,
         name: String,
         data: Value,
         valid: bool,
         password: Option<Password>,
     ) -> Result<Config, Error> {
-        Config::new(
+        let mut config = Config::new(
+            schema_id,
             id,
             name,
             data,
             valid,
             password.map(|password| password.hash()).transpose()?,
-            Vec::new(),
             Timestamps::create(),
             Version::init_version(),
-        )
+            Some(EventCollector::create()),
+        )?;
+
+        config.event_collector.record(ConfigCreated {
+            schema_id: config.schema_id.to_string(),
+            id: config.id.to_string(),
+            name: config.name.clone(),
+            data: config.data().into(),
+            valid: config.valid,
+            password: config.password().map(ToString::to_string),
+        })?;
+
+        Ok(config)
+    }
+
+    pub fn schema_id(&self) -> &Id {
+        &self.schema_id
     }
 
     pub fn id(&self) -> &Id {
@@ -100,10 +145,6 @@ impl Config {
         }
     }
 
-    pub fn accesses(&self) -> &[Access] {
-        &self.accesses
-    }
-
     pub fn timestamps(&self) -> &Timestamps {
         &self.timestamps
     }
@@ -112,22 +153,46 @@ impl Config {
         &self.version
     }
 
+    pub fn events(&self) -> &[Event] {
+        self.event_collector.all()
+    }
+
     // Mutations
     pub fn change_data(&mut self, data: Value, valid: bool) -> Result<(), Error> {
         self.data = data;
         self.valid = valid;
 
+        self.event_collector.record(ConfigDataChanged {
+            schema_id: self.schema_id.to_string(),
+            id: self.id.to_string(),
+            data: self.data().into(),
+            valid: self.valid,
+        })?;
+
         self.timestamps = self.timestamps.update();
         self.version = self.version.incr();
 
         Ok(())
     }
 
-    pub fn mark_as_invalid(&mut self) {
-        self.valid = false;
+    // Only records a change if the validity is different
+    pub fn revalidate(&mut self, valid: bool) -> Result<(), Error> {
+        if self.valid == valid {
+            return Ok(());
+        }
+
+        self.valid = valid;
+
+        self.event_collector.record(ConfigRevalidated {
+            schema_id: self.schema_id.to_string(),
+            id: self.id.to_string(),
+            valid: self.valid,
+        })?;
 
         self.timestamps = self.timestamps.update();
         self.version = self.version.incr();
+
+        Ok(())
     }
 
     pub fn change_password(
@@ -139,7 +204,15 @@ impl Config {
             return Err(Error::Unauthorized);
         }
 
-        self.password = Some(new_password.hash()?);
+        let password = new_password.hash()?;
+
+        self.event_collector.record(ConfigPasswordChanged {
+            schema_id: self.schema_id.to_string(),
+            id: self.id.to_string(),
+            password: password.to_string(),
+        })?;
+
+        self.password = Some(password);
 
         self.timestamps = self.timestamps.update();
         self.version = self.version.incr();
@@ -154,173 +227,66 @@ impl Config {
 
         self.password = None;
 
+        self.event_collector.record(ConfigPasswordDeleted {
+            schema_id: self.schema_id.to_string(),
+            id: self.id.to_string(),
+        })?;
+
         self.timestamps = self.timestamps.update();
         self.version = self.version.incr();
 
         Ok(())
     }
 
-    pub fn register_access(&mut self, access: Access) -> &Access {
-        let index: usize;
-        if let Some((i, access)) = self
-            .accesses
-            .iter_mut()
-            .enumerate()
-            .find(|(_, a)| a.equals(&access))
-        {
-            *access = access.ping();
-            index = i;
-        } else {
-            index = self.accesses.len();
-            self.accesses.push(access);
+    pub fn delete(&mut self, password: Option<&Password>) -> Result<(), Error> {
+        if !self.can_access(password) {
+            return Err(Error::Unauthorized);
         }
 
-        &self.accesses[index]
-    }
-
-    pub fn clean_old_accesses(&mut self) -> Vec<Access> {
-        let access_indexes_to_remove: Vec<usize> = self
-            .accesses
-            .iter()
-            .enumerate()
-            .filter(|(_, access)| {
-                let max_duration = access
-                    .elapsed_time_from_previous()
-                    .map(|previous| {
-                        if previous.num_seconds() < 2 {
-                            previous + Duration::seconds(2)
-                        } else {
-                            previous * 2
-                        }
-                    })
-                    .unwrap_or_else(|| Duration::seconds(30));
-
-                access.elapsed_time() > max_duration
-            })
-            .map(|(i, _)| i)
-            .collect();
-
-        access_indexes_to_remove
-            .into_iter()
-            .rev() // reverse to not re-calculate indexes
-            .map(|i| self.accesses.remove(i))
-            .collect()
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    use chrono::{DateTime, Utc};
+        self.event_collector.record(ConfigDeleted {
+            schema_id: self.schema_id.to_string(),
+            id: self.id.to_string(),
+        })?;
 
-    #[test]
-    fn register_access() {
-        let mut config = Config::create(
-            Id::new("config#01").unwrap(),
-            "Config".to_string(),
-            Value::String("data".to_string()),
-            true,
-            None,
-        )
-        .unwrap();
+        self.timestamps = self.timestamps.delete();
 
-        // New sources
-        config.register_access(Access::create(
-            Id::new("Source 1").unwrap(),
-            Id::new("instance#01").unwrap(),
-        ));
-        config.register_access(Access::create(
-            Id::new("Source 2").unwrap(),
-            Id::new("instance#01").unwrap(),
-        ));
-
-        assert_eq!(config.accesses()[0].source().value(), "Source 1");
-        assert_eq!(config.accesses()[1].source().value(), "Source 2");
-
-        // Existing source
-        config.register_access(Access::create(
-            Id::new("Source 1").unwrap(),
-            Id::new("instance#01").unwrap(),
-        ));
-
-        assert_eq!(config.accesses()[0].source().value(), "Source 1");
-        assert_eq!(config.accesses()[1].source().value(), "Source 2");
-
-        // New source
-        config.register_access(Access::create(
-            Id::new("Source 3").unwrap(),
-            Id::new("instance#01").unwrap(),
-        ));
-
-        assert_eq!(config.accesses().len(), 3);
-        assert_eq!(config.accesses()[0].source().value(), "Source 1");
-        assert_eq!(config.accesses()[1].source().value(), "Source 2");
-        assert_eq!(config.accesses()[2].source().value(), "Source 3");
-
-        // New instance
-        config.register_access(Access::create(
-            Id::new("Source 1").unwrap(),
-            Id::new("instance#02").unwrap(),
-        ));
-
-        assert_eq!(config.accesses().len(), 4);
-        assert_eq!(config.accesses()[0].source().value(), "Source 1");
-        assert_eq!(config.accesses()[0].instance().value(), "instance#01");
-        assert_eq!(config.accesses()[1].source().value(), "Source 2");
-        assert_eq!(config.accesses()[2].source().value(), "Source 3");
-        assert_eq!(config.accesses()[3].source().value(), "Source 1");
-        assert_eq!(config.accesses()[3].instance().value(), "instance#02");
+        Ok(())
     }
 
-    #[test]
-    fn clean_old_accesses() {
-        let mut config = Config::create(
-            Id::new("config#01").unwrap(),
-            "Config".to_string(),
-            Value::String("data".to_string()),
-            true,
-            None,
-        )
-        .unwrap();
+    pub fn restore(&mut self, password: Option<&Password>) -> Result<(), Error> {
+        if !self.can_access(password) {
+            return Err(Error::Unauthorized);
+        }
 
-        config.register_access(Access::new(
-            Id::new("Source 1").unwrap(),
-            Id::new("instance#01").unwrap(),
-            DateTime::parse_from_rfc3339("2022-07-25T19:00:00Z")
-                .unwrap()
-                .into(),
-            None,
-        ));
-
-        config.register_access(Access::new(
-            Id::new("Source 2").unwrap(),
-            Id::new("instance#01").unwrap(),
-            DateTime::parse_from_rfc3339("2022-07-25T19:30:00Z")
-                .unwrap()
-                .into(),
-            None,
-        ));
+        self.event_collector.record(ConfigRestored {
+            schema_id: self.schema_id.to_string(),
+            id: self.id.to_string(),
+        })?;
 
-        config.register_access(Access::new(
-            Id::new("Source 1").unwrap(),
-            Id::new("instance#02").unwrap(),
-            Utc::now(),
-            None,
-        ));
+        self.timestamps = self.timestamps.restore();
 
-        let removed_accesses = config.clean_old_accesses();
+        Ok(())
+    }
 
-        assert_eq!(removed_accesses.len(), 2);
-        assert_eq!(config.accesses().len(), 1);
-        assert_eq!(config.accesses()[0].source().value(), "Source 1");
-        assert_eq!(config.accesses()[0].instance().value(), "instance#02");
+    // Removes the deleted config for good. The password is checked by the
+    // caller, as retention purges configs without it.
+    pub fn purge(&mut self) -> Result<(), Error> {
+        self.event_collector.record(ConfigPurged {
+            schema_id: self.schema_id.to_string(),
+            id: self.id.to_string(),
+        })
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     #[test]
     fn can_access() {
         // No password
         let config = Config::create(
+            Id::new("schema#01").unwrap(),
             Id::new("config#01").unwrap(),
             "Config".to_string(),
             Value::String("data".to_string()),
@@ -334,6 +300,7 @@ mod tests {
 
         // With password
         let config = Config::create(
+            Id::new("schema#01").unwrap(),
             Id::new("config#01").unwrap(),
             "Config".to_string(),
             Value::String("data".to_string()),
@@ -0,0 +1,232 @@
+use chrono::{DateTime, Utc};
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use crate::domain::{
+    configs::Config,
+    errors::Error,
+    shared::{Cursor, SortOrder, SortValue, DEFAULT_PAGE_LIMIT},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfigSort {
+    #[default]
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl FromStr for ConfigSort {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ConfigSort, Self::Err> {
+        match s {
+            "name" => Ok(ConfigSort::Name),
+            "created_at" => Ok(ConfigSort::CreatedAt),
+            "updated_at" => Ok(ConfigSort::UpdatedAt),
+            _ => Err(Error::InvalidSort(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for ConfigSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSort::Name => write!(f, "name"),
+            ConfigSort::CreatedAt => write!(f, "created_at"),
+            ConfigSort::UpdatedAt => write!(f, "updated_at"),
+        }
+    }
+}
+
+// Configs of a schema, sorted by the sort value and then by id like schemas
+#[derive(Debug, Clone)]
+pub struct ConfigQuery {
+    // Case-insensitive part of the name
+    pub name: Option<String>,
+    pub valid: Option<bool>,
+    pub requires_password: Option<bool>,
+    // Deleted configs instead of the live ones
+    pub deleted: bool,
+    // Only configs deleted before it
+    pub deleted_before: Option<DateTime<Utc>>,
+    pub sort: ConfigSort,
+    pub order: SortOrder,
+    // Only configs after it, the offset is applied from there
+    pub cursor: Option<Cursor>,
+    pub offset: u64,
+    pub limit: u64,
+}
+
+impl Default for ConfigQuery {
+    fn default() -> ConfigQuery {
+        ConfigQuery {
+            name: None,
+            valid: None,
+            requires_password: None,
+            deleted: false,
+            deleted_before: None,
+            sort: ConfigSort::default(),
+            order: SortOrder::default(),
+            cursor: None,
+            offset: 0,
+            limit: DEFAULT_PAGE_LIMIT,
+        }
+    }
+}
+
+impl ConfigQuery {
+    // Identifies the sort of the cursors
+    pub fn sort_key(&self) -> String {
+        format!("{}.{}", self.sort, self.order)
+    }
+
+    pub fn with_cursor(mut self, cursor: &str) -> Result<ConfigQuery, Error> {
+        let cursor = Cursor::decode(cursor)?;
+        let valid_value = matches!(
+            (self.sort, cursor.value()),
+            (ConfigSort::Name, SortValue::Text(_))
+                | (
+                    ConfigSort::CreatedAt | ConfigSort::UpdatedAt,
+                    SortValue::Timestamp(_)
+                )
+        );
+        if cursor.sort() != self.sort_key() || !valid_value {
+            return Err(Error::InvalidCursor);
+        }
+
+        self.cursor = Some(cursor);
+
+        Ok(self)
+    }
+
+    pub fn sort_value(&self, config: &Config) -> SortValue {
+        match self.sort {
+            ConfigSort::Name => SortValue::Text(config.name().to_string()),
+            ConfigSort::CreatedAt => SortValue::Timestamp(*config.timestamps().created_at()),
+            ConfigSort::UpdatedAt => SortValue::Timestamp(*config.timestamps().updated_at()),
+        }
+    }
+
+    pub fn cursor_of(&self, config: &Config) -> Cursor {
+        Cursor::new(
+            self.sort_key(),
+            self.sort_value(config),
+            config.id().value(),
+        )
+    }
+
+    pub fn matches(&self, config: &Config) -> bool {
+        let deleted_at = config.timestamps().deleted_at();
+        if deleted_at.is_some() != self.deleted {
+            return false;
+        }
+
+        if let (Some(deleted_before), Some(deleted_at)) = (&self.deleted_before, deleted_at) {
+            if deleted_at >= deleted_before {
+                return false;
+            }
+        }
+
+        if let Some(name) = &self.name {
+            if !config.name().to_lowercase().contains(&name.to_lowercase()) {
+                return false;
+            }
+        }
+
+        if let Some(valid) = self.valid {
+            if config.is_valid() != valid {
+                return false;
+            }
+        }
+
+        if let Some(requires_password) = self.requires_password {
+            if config.password().is_some() != requires_password {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn compare(&self, a: &Config, b: &Config) -> Ordering {
+        let ordering = self
+            .sort_value(a)
+            .cmp(&self.sort_value(b))
+            .then_with(|| a.id().value().cmp(b.id().value()));
+
+        match self.order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    }
+
+    pub fn is_after_cursor(&self, config: &Config) -> bool {
+        let cursor = match &self.cursor {
+            Some(cursor) => cursor,
+            None => return true,
+        };
+
+        let ordering = self
+            .sort_value(config)
+            .cmp(cursor.value())
+            .then_with(|| config.id().value().cmp(cursor.id()));
+
+        match self.order {
+            SortOrder::Asc => ordering == Ordering::Greater,
+            SortOrder::Desc => ordering == Ordering::Less,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::{configs::Password, shared::Id, values::Value};
+
+    fn config(id: &str, name: &str, valid: bool, password: Option<&str>) -> Config {
+        Config::create(
+            Id::new("schema#01").unwrap(),
+            Id::new(id).unwrap(),
+            name.to_string(),
+            Value::Null,
+            valid,
+            password.map(|password| Password::new(password.to_string()).unwrap()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn filters_and_cursors() {
+        let query = ConfigQuery {
+            name: Some("PROD".to_string()),
+            valid: Some(true),
+            requires_password: Some(false),
+            ..ConfigQuery::default()
+        };
+
+        assert!(query.matches(&config("config#01", "Production", true, None)));
+        assert!(!query.matches(&config("config#02", "Production", false, None)));
+        assert!(!query.matches(&config("config#03", "Production", true, Some("passwd123"))));
+        assert!(!query.matches(&config("config#04", "Development", true, None)));
+
+        let cursor = query
+            .cursor_of(&config("config#02", "Production", true, None))
+            .encode();
+        let query = query.with_cursor(&cursor).unwrap();
+
+        assert!(!query.is_after_cursor(&config("config#01", "Production", true, None)));
+        assert!(query.is_after_cursor(&config("config#03", "Production", true, None)));
+        assert!(query.is_after_cursor(&config("config#04", "Staging", true, None)));
+
+        // Cursors of other sorts are rejected
+        let other = ConfigQuery {
+            sort: ConfigSort::CreatedAt,
+            ..ConfigQuery::default()
+        };
+        assert!(matches!(
+            other.with_cursor(&cursor),
+            Err(Error::InvalidCursor)
+        ));
+    }
+}
@@ -0,0 +1,605 @@
+use chrono::{DateTime, Utc};
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::domain::{
+    configs::{Config, ConfigQuery, ConfigRepository, Password},
+    errors::Error,
+    schemas::{validate_against, Schema, SchemaEvolution},
+    shared::{Id, MAX_PAGE_LIMIT},
+    values::{apply_migrations, Compatibility, Migration, Patch, Prop, Rule, Value},
+};
+
+// Keeps configs consistent with the root prop and rules of their schema
+pub struct ConfigService {
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+}
+
+impl ConfigService {
+    pub fn new(config_repository: Arc<dyn ConfigRepository + Sync + Send>) -> ConfigService {
+        ConfigService { config_repository }
+    }
+
+    pub async fn configs(&self, schema_id: &Id) -> Result<Vec<Config>, Error> {
+        self.all_configs(schema_id, ConfigQuery::default()).await
+    }
+
+    // Deleted configs of the schema, only those deleted before the given time
+    // if any
+    pub async fn deleted_configs(
+        &self,
+        schema_id: &Id,
+        deleted_before: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Config>, Error> {
+        self.all_configs(
+            schema_id,
+            ConfigQuery {
+                deleted: true,
+                deleted_before,
+                ..ConfigQuery::default()
+            },
+        )
+        .await
+    }
+
+    async fn all_configs(&self, schema_id: &Id, query: ConfigQuery) -> Result<Vec<Config>, Error> {
+        let mut configs = Vec::new();
+
+        loop {
+            let page = self
+                .config_repository
+                .find(
+                    schema_id,
+                    &ConfigQuery {
+                        offset: configs.len() as u64,
+                        limit: MAX_PAGE_LIMIT,
+                        ..query.clone()
+                    },
+                )
+                .await?;
+
+            let total = page.total();
+            let data = page.into_data();
+            if data.is_empty() {
+                break;
+            }
+
+            configs.extend(data);
+            if configs.len() as u64 >= total {
+                break;
+            }
+        }
+
+        Ok(configs)
+    }
+
+    pub async fn create_config(
+        &self,
+        schema: &Schema,
+        id: Id,
+        name: String,
+        data: Value,
+        password: Option<Password>,
+    ) -> Result<Config, Error> {
+        if self.config_repository.exists(schema.id(), &id).await? {
+            return Err(Error::ConfigAlreadyExists(id));
+        }
+
+        let diff = schema.validate(&data);
+        if !diff.is_empty() {
+            return Err(Error::InvalidConfig(diff));
+        }
+
+        Config::create(schema.id().clone(), id, name, data, true, password)
+    }
+
+    pub fn update_config(
+        &self,
+        schema: &Schema,
+        config: &mut Config,
+        data: Value,
+        password: Option<&Password>,
+    ) -> Result<(), Error> {
+        if !config.can_access(password) {
+            return Err(Error::Unauthorized);
+        }
+
+        let diff = schema.validate(&data);
+        if !diff.is_empty() {
+            return Err(Error::InvalidConfig(diff));
+        }
+
+        config.change_data(data, true)
+    }
+
+    // Access is checked before applying the patch so failed tests do not leak
+    // the current data
+    pub fn patch_config(
+        &self,
+        schema: &Schema,
+        config: &mut Config,
+        patch: &Patch,
+        password: Option<&Password>,
+    ) -> Result<(), Error> {
+        if !config.can_access(password) {
+            return Err(Error::Unauthorized);
+        }
+
+        let data = patch.apply_with_prop(config.data(), Some(schema.root_prop()))?;
+
+        self.update_config(schema, config, data, password)
+    }
+
+    // Checks the existing configs, after applying the migrations, against a
+    // new root prop and rules without changing anything
+    pub async fn evolution(
+        &self,
+        schema: &Schema,
+        root_prop: &Prop,
+        rules: &[Rule],
+        migrations: &[Migration],
+    ) -> Result<SchemaEvolution, Error> {
+        let compatibility = Compatibility::between_props(schema.root_prop(), root_prop)
+            .max(Compatibility::between_rules(schema.rules(), rules));
+
+        let mut affected_configs = BTreeMap::new();
+        let mut affected_names = BTreeMap::new();
+
+        for config in self.configs(schema.id()).await?.iter() {
+            if !schema.validate(config.data()).is_empty() {
+                continue;
+            }
+
+            let data = apply_migrations(migrations, config.data())?;
+
+            let diff = validate_against(root_prop, rules, &data);
+            if !diff.is_empty() {
+                affected_configs.insert(config.id().to_string(), diff);
+                affected_names.insert(config.id().to_string(), config.name().to_string());
+            }
+        }
+
+        Ok(SchemaEvolution {
+            compatibility,
+            affected_configs,
+            affected_names,
+        })
+    }
+
+    // Migrations are applied to every config or to none of them. Only the
+    // changed configs are returned, ready to be saved.
+    pub async fn migrate_configs(
+        &self,
+        schema: &Schema,
+        migrations: &[Migration],
+    ) -> Result<Vec<Config>, Error> {
+        if migrations.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut migrated = Vec::new();
+        for config in self.configs(schema.id()).await?.into_iter() {
+            let data = apply_migrations(migrations, config.data())?;
+
+            if &data != config.data() {
+                let valid = schema.validate(&data).is_empty();
+                migrated.push((config, data, valid));
+            }
+        }
+
+        migrated
+            .into_iter()
+            .map(|(mut config, data, valid)| {
+                config.change_data(data, valid)?;
+                Ok(config)
+            })
+            .collect()
+    }
+
+    // Only the configs whose validity changed are returned
+    pub async fn revalidate_configs(&self, schema: &Schema) -> Result<Vec<Config>, Error> {
+        let mut revalidated = Vec::new();
+
+        for mut config in self.configs(schema.id()).await?.into_iter() {
+            let valid = schema.validate(config.data()).is_empty();
+
+            if config.is_valid() != valid {
+                config.revalidate(valid)?;
+                revalidated.push(config);
+            }
+        }
+
+        Ok(revalidated)
+    }
+
+    // Deleted configs are purged along with their deleted schema
+    pub async fn purge_schema(&self, schema: &mut Schema) -> Result<Vec<Config>, Error> {
+        let mut configs = self.deleted_configs(schema.id(), None).await?;
+        for config in configs.iter_mut() {
+            config.purge()?;
+        }
+
+        schema.purge()?;
+
+        Ok(configs)
+    }
+
+    pub async fn delete_schema(&self, schema: &mut Schema) -> Result<(), Error> {
+        let page = self
+            .config_repository
+            .find(
+                schema.id(),
+                &ConfigQuery {
+                    limit: 1,
+                    ..ConfigQuery::default()
+                },
+            )
+            .await?;
+
+        if page.total() > 0 {
+            return Err(Error::SchemaContainsConfigs(schema.id().clone()));
+        }
+
+        schema.delete()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        domain::values::{Interval, Path},
+        infrastructure::InMemConfigRepository,
+    };
+
+    fn port_schema() -> Schema {
+        Schema::create(
+            Id::new("schema-01").unwrap(),
+            "Schema 01".to_string(),
+            Prop::object(BTreeMap::from([(
+                "port".to_string(),
+                Prop::int(
+                    true,
+                    None,
+                    None,
+                    Some(Interval::new(1, None).unwrap()),
+                    false,
+                )
+                .unwrap(),
+            )])),
+            Vec::new(),
+        )
+        .unwrap()
+    }
+
+    fn port(port: i64) -> Value {
+        Value::Object(BTreeMap::from([("port".to_string(), Value::Int(port))]))
+    }
+
+    async fn add_configs(
+        service: &ConfigService,
+        config_repository: &InMemConfigRepository,
+        schema: &Schema,
+        configs: &[(&str, i64)],
+    ) {
+        for (id, value) in configs.iter() {
+            let mut config = service
+                .create_config(
+                    schema,
+                    Id::new(*id).unwrap(),
+                    id.to_string(),
+                    port(*value),
+                    None,
+                )
+                .await
+                .unwrap();
+            config_repository.save(&mut config).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn create_and_patch_config() {
+        let config_repository = Arc::new(InMemConfigRepository::new());
+        let service = ConfigService::new(config_repository.clone());
+        let schema = port_schema();
+
+        let config_id = Id::new("config-01").unwrap();
+        let password = Password::new("secret".to_string()).unwrap();
+
+        assert!(matches!(
+            service
+                .create_config(
+                    &schema,
+                    config_id.clone(),
+                    "Config 01".to_string(),
+                    port(0),
+                    None
+                )
+                .await,
+            Err(Error::InvalidConfig(_))
+        ));
+
+        let mut config = service
+            .create_config(
+                &schema,
+                config_id.clone(),
+                "Config 01".to_string(),
+                port(80),
+                Some(password.clone()),
+            )
+            .await
+            .unwrap();
+        config_repository.save(&mut config).await.unwrap();
+
+        assert!(matches!(
+            service
+                .create_config(
+                    &schema,
+                    config_id.clone(),
+                    "Config 01".to_string(),
+                    port(80),
+                    None
+                )
+                .await,
+            Err(Error::ConfigAlreadyExists(_))
+        ));
+
+        let mut config = config_repository
+            .find_by_id(schema.id(), &config_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let patch = Patch::merge(serde_json::json!({ "port": 8080 }));
+        assert!(matches!(
+            service.patch_config(&schema, &mut config, &patch, None),
+            Err(Error::Unauthorized)
+        ));
+
+        service
+            .patch_config(&schema, &mut config, &patch, Some(&password))
+            .unwrap();
+        assert_eq!(config.data(), &port(8080));
+
+        // The patched data is validated
+        let patch = Patch::json(serde_json::json!([
+            { "op": "replace", "path": "/port", "value": 0 }
+        ]))
+        .unwrap();
+        assert!(matches!(
+            service.patch_config(&schema, &mut config, &patch, Some(&password)),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn revalidate_and_delete_schema() {
+        let config_repository = Arc::new(InMemConfigRepository::new());
+        let service = ConfigService::new(config_repository.clone());
+        let mut schema = port_schema();
+
+        add_configs(
+            &service,
+            &config_repository,
+            &schema,
+            &[("config-01", 80), ("config-02", 8080)],
+        )
+        .await;
+
+        // Stricter rules invalidate existing configs
+        schema
+            .change_rules(vec![
+                Rule::new("port > 1000".to_string(), None, None).unwrap()
+            ])
+            .unwrap();
+
+        let revalidated = service.revalidate_configs(&schema).await.unwrap();
+        assert_eq!(revalidated.len(), 1);
+        assert_eq!(revalidated[0].id().value(), "config-01");
+        assert!(!revalidated[0].is_valid());
+
+        assert!(matches!(
+            service.delete_schema(&mut schema).await,
+            Err(Error::SchemaContainsConfigs(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn trash() {
+        let config_repository = Arc::new(InMemConfigRepository::new());
+        let service = ConfigService::new(config_repository.clone());
+        let mut schema = port_schema();
+        let schema_id = schema.id().clone();
+        let config_id = Id::new("config-01").unwrap();
+
+        add_configs(&service, &config_repository, &schema, &[("config-01", 80)]).await;
+
+        let mut config = config_repository
+            .find_by_id(&schema_id, &config_id)
+            .await
+            .unwrap()
+            .unwrap();
+        config.delete(None).unwrap();
+        config_repository.save(&mut config).await.unwrap();
+
+        // Deleted configs are hidden but keep their ids
+        assert!(config_repository
+            .find_by_id(&schema_id, &config_id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(config_repository
+            .exists(&schema_id, &config_id)
+            .await
+            .unwrap());
+        assert!(service.configs(&schema_id).await.unwrap().is_empty());
+        assert_eq!(
+            service
+                .deleted_configs(&schema_id, None)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(service
+            .deleted_configs(&schema_id, Some(Utc::now() - chrono::Duration::hours(1)))
+            .await
+            .unwrap()
+            .is_empty());
+
+        let mut config = config_repository
+            .find_deleted_by_id(&schema_id, &config_id)
+            .await
+            .unwrap()
+            .unwrap();
+        config.restore(None).unwrap();
+        config_repository.save(&mut config).await.unwrap();
+        assert_eq!(service.configs(&schema_id).await.unwrap().len(), 1);
+
+        // Purging the schema purges its deleted configs
+        config.delete(None).unwrap();
+        config_repository.save(&mut config).await.unwrap();
+        service.delete_schema(&mut schema).await.unwrap();
+
+        let mut purged = service.purge_schema(&mut schema).await.unwrap();
+        assert_eq!(purged.len(), 1);
+        config_repository.save(&mut purged[0]).await.unwrap();
+        assert!(!config_repository
+            .exists(&schema_id, &config_id)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn evolution() {
+        let config_repository = Arc::new(InMemConfigRepository::new());
+        let service = ConfigService::new(config_repository.clone());
+        let mut schema = port_schema();
+
+        add_configs(
+            &service,
+            &config_repository,
+            &schema,
+            &[("config-01", 80), ("config-02", 8080)],
+        )
+        .await;
+
+        // Compatible: existing configs remain valid
+        let evolution = service
+            .evolution(
+                &schema,
+                &Prop::object(BTreeMap::from([(
+                    "port".to_string(),
+                    Prop::float(false, None, None, None, false).unwrap(),
+                )])),
+                &[],
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(evolution.compatibility, Compatibility::Compatible);
+        assert!(!evolution.is_breaking());
+
+        // Additive: configs must include the new prop
+        let evolution = service
+            .evolution(
+                &schema,
+                &Prop::object(BTreeMap::from([
+                    (
+                        "port".to_string(),
+                        Prop::int(
+                            true,
+                            None,
+                            None,
+                            Some(Interval::new(1, None).unwrap()),
+                            false,
+                        )
+                        .unwrap(),
+                    ),
+                    ("debug".to_string(), Prop::bool(false, None).unwrap()),
+                ])),
+                &[],
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(evolution.compatibility, Compatibility::Additive);
+        assert_eq!(evolution.affected_configs.len(), 2);
+
+        // Breaking: only the config out of the interval is affected
+        let evolution = service
+            .evolution(
+                &schema,
+                &Prop::object(BTreeMap::from([(
+                    "port".to_string(),
+                    Prop::int(
+                        true,
+                        None,
+                        None,
+                        Some(Interval::new(1024, None).unwrap()),
+                        false,
+                    )
+                    .unwrap(),
+                )])),
+                &[],
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(evolution.compatibility, Compatibility::Breaking);
+        assert!(evolution.is_breaking());
+        assert_eq!(
+            evolution.affected_configs.keys().collect::<Vec<&String>>(),
+            vec!["config-01"]
+        );
+        assert_eq!(evolution.affected_names["config-01"], "config-01");
+
+        // Rules count as well
+        let evolution = service
+            .evolution(
+                &schema,
+                schema.root_prop(),
+                &[Rule::new("port < 1000".to_string(), None, None).unwrap()],
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            evolution.affected_configs.keys().collect::<Vec<&String>>(),
+            vec!["config-02"]
+        );
+
+        // Migrations keep configs valid
+        let root_prop = Prop::object(BTreeMap::from([
+            (
+                "port".to_string(),
+                Prop::int(true, None, None, None, false).unwrap(),
+            ),
+            ("debug".to_string(), Prop::bool(true, None).unwrap()),
+        ]));
+        let migrations =
+            vec![
+                Migration::set_default(Path::parse("debug").unwrap(), Value::Bool(false)).unwrap(),
+            ];
+
+        let evolution = service
+            .evolution(&schema, &root_prop, &[], &migrations)
+            .await
+            .unwrap();
+        assert_eq!(evolution.compatibility, Compatibility::Breaking);
+        assert!(!evolution.is_breaking());
+
+        schema.change_root_prop(root_prop).unwrap();
+        let migrated = service.migrate_configs(&schema, &migrations).await.unwrap();
+
+        assert_eq!(migrated.len(), 2);
+        for config in migrated.iter() {
+            assert!(config.is_valid());
+            assert_eq!(
+                Path::parse("debug").unwrap().resolve(config.data()),
+                Some(&Value::Bool(false))
+            );
+            assert_eq!(config.events()[0].topic(), "config.data_changed");
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::domain::events::Publishable;
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfigCreated {
+    pub schema_id: String,
+    pub id: String,
+    pub name: String,
+    pub data: JsonValue,
+    pub valid: bool,
+    pub password: Option<String>,
+}
+
+impl Publishable for ConfigCreated {
+    fn entity_id(&self) -> &str {
+        &self.schema_id
+    }
+
+    fn topic(&self) -> &str {
+        "config.created"
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfigDataChanged {
+    pub schema_id: String,
+    pub id: String,
+    pub data: JsonValue,
+    pub valid: bool,
+}
+
+impl Publishable for ConfigDataChanged {
+    fn entity_id(&self) -> &str {
+        &self.schema_id
+    }
+
+    fn topic(&self) -> &str {
+        "config.data_changed"
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfigRevalidated {
+    pub schema_id: String,
+    pub id: String,
+    pub valid: bool,
+}
+
+impl Publishable for ConfigRevalidated {
+    fn entity_id(&self) -> &str {
+        &self.schema_id
+    }
+
+    fn topic(&self) -> &str {
+        "config.revalidated"
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfigPasswordChanged {
+    pub schema_id: String,
+    pub id: String,
+    pub password: String,
+}
+
+impl Publishable for ConfigPasswordChanged {
+    fn entity_id(&self) -> &str {
+        &self.schema_id
+    }
+
+    fn topic(&self) -> &str {
+        "config.password_changed"
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfigPasswordDeleted {
+    pub schema_id: String,
+    pub id: String,
+}
+
+impl Publishable for ConfigPasswordDeleted {
+    fn entity_id(&self) -> &str {
+        &self.schema_id
+    }
+
+    fn topic(&self) -> &str {
+        "config.password_deleted"
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfigDeleted {
+    pub schema_id: String,
+    pub id: String,
+}
+
+impl Publishable for ConfigDeleted {
+    fn entity_id(&self) -> &str {
+        &self.schema_id
+    }
+
+    fn topic(&self) -> &str {
+        "config.deleted"
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfigRestored {
+    pub schema_id: String,
+    pub id: String,
+}
+
+impl Publishable for ConfigRestored {
+    fn entity_id(&self) -> &str {
+        &self.schema_id
+    }
+
+    fn topic(&self) -> &str {
+        "config.restored"
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfigPurged {
+    pub schema_id: String,
+    pub id: String,
+}
+
+impl Publishable for ConfigPurged {
+    fn entity_id(&self) -> &str {
+        &self.schema_id
+    }
+
+    fn topic(&self) -> &str {
+        "config.purged"
+    }
+}
@@ -1,7 +1,17 @@
 mod access;
+mod access_store;
 mod config;
+mod config_query;
+mod config_service;
+mod events;
 mod password;
+mod password_lockout;
 
 pub use access::*;
+pub use access_store::*;
 pub use config::*;
+pub use config_query::*;
+pub use config_service::*;
+pub use events::*;
 pub use password::*;
+pub use password_lockout::*;
@@ -17,6 +17,7 @@ impl Password {
         Ok(Password { password })
     }
 
+    #[allow(dead_code)]
     pub fn value(&self) -> &str {
         &self.password
     }
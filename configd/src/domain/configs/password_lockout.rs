@@ -0,0 +1,361 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+use crate::domain::{
+    configs::{Config, Password},
+    errors::Error,
+    shared::Id,
+};
+
+// Upper bound of the doubled lockouts
+const MAX_LOCKOUT: Duration = Duration::from_secs(60 * 60);
+
+// Failures of every client together lock a config after this many times
+// max_attempts within one lockout
+const CONFIG_ATTEMPTS_FACTOR: u32 = 10;
+
+// Past these, the least recent failures of clients that are not locked out are
+// forgotten, a tenth at a time
+const MAX_ATTEMPTS_ENTRIES: usize = 10_000;
+
+tokio::task_local! {
+    static CLIENT: Option<String>;
+}
+
+// Runs the future on behalf of a client, its certificate identity or IP
+// address, so its failed attempts only lock itself out
+pub async fn with_client<F>(client: Option<String>, f: F) -> F::Output
+where
+    F: Future,
+{
+    CLIENT.scope(client, f).await
+}
+
+pub fn current_client() -> Option<String> {
+    CLIENT.try_with(|client| client.clone()).ok().flatten()
+}
+
+type ConfigKey = (Id, Id);
+
+type ClientKey = (Option<String>, Id, Id);
+
+struct Attempts {
+    failures: u32,
+    locked_until: Option<Instant>,
+    failed_at: Instant,
+}
+
+struct State {
+    clients: HashMap<ClientKey, Attempts>,
+    // Failures of every client within the current window of each config
+    configs: HashMap<ConfigKey, Attempts>,
+}
+
+// Failed password attempts per client and config. After max_attempts failures
+// in a row the config is locked for the client, and every further failure
+// doubles the lockout. Failures are also counted per config whatever the
+// client, so rotating addresses or certificates still locks the config for
+// everyone once they add up to ten times max_attempts within one lockout.
+// Zero max_attempts disables it.
+pub struct PasswordLockout {
+    max_attempts: u32,
+    lockout: Duration,
+    state: Mutex<State>,
+}
+
+impl PasswordLockout {
+    pub fn new(max_attempts: u32, lockout: Duration) -> PasswordLockout {
+        PasswordLockout {
+            max_attempts,
+            lockout,
+            state: Mutex::new(State {
+                clients: HashMap::new(),
+                configs: HashMap::new(),
+            }),
+        }
+    }
+
+    // Passwords are not even compared while the config is locked, so guesses
+    // cannot be confirmed
+    pub async fn verify(&self, config: &Config, password: Option<&Password>) -> Result<(), Error> {
+        if self.max_attempts == 0 {
+            return if config.can_access(password) {
+                Ok(())
+            } else {
+                Err(Error::Unauthorized)
+            };
+        }
+
+        let config_key = (config.schema_id().clone(), config.id().clone());
+        let client_key = (
+            current_client(),
+            config.schema_id().clone(),
+            config.id().clone(),
+        );
+        let mut state = self.state.lock().await;
+        let now = Instant::now();
+
+        let locked_until = [
+            state.configs.get(&config_key),
+            state.clients.get(&client_key),
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|attempts| attempts.locked_until)
+        .max();
+        if let Some(locked_until) = locked_until {
+            if locked_until > now {
+                // Whole seconds, rounded up
+                let retry_after = (locked_until - now).as_secs_f64().ceil() as u64;
+                return Err(Error::RateLimited(retry_after));
+            }
+        }
+
+        if config.can_access(password) {
+            state.clients.remove(&client_key);
+            return Ok(());
+        }
+
+        // Missing passwords are not guesses
+        if password.is_some() {
+            if state.clients.len() >= MAX_ATTEMPTS_ENTRIES
+                && !state.clients.contains_key(&client_key)
+            {
+                evict(&mut state.clients, now);
+            }
+            // Windows end with their lockout
+            state.configs.retain(|_, attempts| {
+                attempts.failed_at + self.lockout > now
+                    || attempts
+                        .locked_until
+                        .is_some_and(|locked_until| locked_until > now)
+            });
+
+            let attempts = state.clients.entry(client_key).or_insert(Attempts {
+                failures: 0,
+                locked_until: None,
+                failed_at: now,
+            });
+            attempts.failures += 1;
+            attempts.failed_at = now;
+
+            if attempts.failures >= self.max_attempts {
+                let exponent = attempts.failures - self.max_attempts;
+                let lockout = self
+                    .lockout
+                    .saturating_mul(2u32.saturating_pow(exponent))
+                    .min(MAX_LOCKOUT);
+                attempts.locked_until = Some(now + lockout);
+            }
+
+            // failed_at is when the window started
+            let attempts = state.configs.entry(config_key).or_insert(Attempts {
+                failures: 0,
+                locked_until: None,
+                failed_at: now,
+            });
+            attempts.failures += 1;
+
+            if attempts.failures >= self.max_attempts.saturating_mul(CONFIG_ATTEMPTS_FACTOR) {
+                let locked_until = attempts.failed_at + self.lockout;
+                attempts.locked_until = Some(locked_until);
+                // The next window starts when this one ends
+                attempts.failures = 0;
+                attempts.failed_at = locked_until;
+            }
+        }
+
+        Err(Error::Unauthorized)
+    }
+}
+
+// Forgets the least recent tenth of the clients that are not locked out, so
+// filling the map cannot reset a recent failure count
+fn evict(clients: &mut HashMap<ClientKey, Attempts>, now: Instant) {
+    let mut failed_at: Vec<_> = clients
+        .values()
+        .filter(|attempts| {
+            attempts
+                .locked_until
+                .is_none_or(|locked_until| locked_until <= now)
+        })
+        .map(|attempts| attempts.failed_at)
+        .collect();
+    if failed_at.is_empty() {
+        return;
+    }
+
+    let index = (MAX_ATTEMPTS_ENTRIES / 10).min(failed_at.len() - 1);
+    let (_, &mut threshold, _) = failed_at.select_nth_unstable(index);
+    clients.retain(|_, attempts| {
+        attempts.failed_at > threshold
+            || attempts
+                .locked_until
+                .is_some_and(|locked_until| locked_until > now)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::values::Value;
+
+    fn password(password: &str) -> Password {
+        Password::new(password.to_string()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn lockout() {
+        let config = Config::create(
+            Id::new("schema#01").unwrap(),
+            Id::new("config#01").unwrap(),
+            "Config 01".to_string(),
+            Value::Null,
+            true,
+            Some(password("passwd123")),
+        )
+        .unwrap();
+        let lockout = PasswordLockout::new(2, Duration::from_millis(100));
+
+        // Missing passwords do not count
+        for _ in 0..3 {
+            assert!(matches!(
+                lockout.verify(&config, None).await,
+                Err(Error::Unauthorized)
+            ));
+        }
+
+        assert!(matches!(
+            lockout.verify(&config, Some(&password("wrong"))).await,
+            Err(Error::Unauthorized)
+        ));
+        assert!(matches!(
+            lockout.verify(&config, Some(&password("wrong"))).await,
+            Err(Error::Unauthorized)
+        ));
+
+        // Locked even for the right password
+        assert!(matches!(
+            lockout.verify(&config, Some(&password("passwd123"))).await,
+            Err(Error::RateLimited(1))
+        ));
+
+        // Other clients are not locked out
+        assert!(with_client(
+            Some("10.0.0.2".to_string()),
+            lockout.verify(&config, Some(&password("passwd123")))
+        )
+        .await
+        .is_ok());
+
+        tokio::time::sleep(Duration::from_millis(110)).await;
+
+        // One more failure doubles the lockout
+        assert!(matches!(
+            lockout.verify(&config, Some(&password("wrong"))).await,
+            Err(Error::Unauthorized)
+        ));
+        tokio::time::sleep(Duration::from_millis(110)).await;
+        assert!(matches!(
+            lockout.verify(&config, Some(&password("passwd123"))).await,
+            Err(Error::RateLimited(_))
+        ));
+
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert!(lockout
+            .verify(&config, Some(&password("passwd123")))
+            .await
+            .is_ok());
+
+        // Successes reset the failures
+        assert!(matches!(
+            lockout.verify(&config, Some(&password("wrong"))).await,
+            Err(Error::Unauthorized)
+        ));
+        assert!(lockout
+            .verify(&config, Some(&password("passwd123")))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn config_lockout() {
+        let config = Config::create(
+            Id::new("schema#01").unwrap(),
+            Id::new("config#01").unwrap(),
+            "Config 01".to_string(),
+            Value::Null,
+            true,
+            Some(password("passwd123")),
+        )
+        .unwrap();
+        let lockout = PasswordLockout::new(2, Duration::from_millis(100));
+
+        // Every client fails once, short of its own lockout
+        for client in 0..CONFIG_ATTEMPTS_FACTOR * 2 {
+            assert!(matches!(
+                with_client(
+                    Some(format!("10.0.0.{client}")),
+                    lockout.verify(&config, Some(&password("wrong")))
+                )
+                .await,
+                Err(Error::Unauthorized)
+            ));
+        }
+
+        // Locked for a new client too
+        assert!(matches!(
+            with_client(
+                Some("10.0.1.1".to_string()),
+                lockout.verify(&config, Some(&password("passwd123")))
+            )
+            .await,
+            Err(Error::RateLimited(1))
+        ));
+
+        tokio::time::sleep(Duration::from_millis(110)).await;
+        assert!(with_client(
+            Some("10.0.1.1".to_string()),
+            lockout.verify(&config, Some(&password("passwd123")))
+        )
+        .await
+        .is_ok());
+    }
+
+    #[test]
+    fn evict_least_recent() {
+        let now = Instant::now();
+        let key = |index: usize| {
+            (
+                Some(format!("10.0.{}.{}", index / 256, index % 256)),
+                Id::new("schema#01").unwrap(),
+                Id::new("config#01").unwrap(),
+            )
+        };
+        let mut clients: HashMap<_, _> = (0..MAX_ATTEMPTS_ENTRIES)
+            .map(|index| {
+                let attempts = Attempts {
+                    failures: 1,
+                    locked_until: (index == 0).then(|| now + Duration::from_secs(60)),
+                    failed_at: now + Duration::from_millis(index as u64),
+                };
+                (key(index), attempts)
+            })
+            .collect();
+
+        evict(&mut clients, now);
+
+        // Locked clients are kept however old
+        assert!(clients.contains_key(&key(0)));
+        assert!(!clients.contains_key(&key(1)));
+        assert!(!clients.contains_key(&key(MAX_ATTEMPTS_ENTRIES / 10)));
+        assert!(clients.contains_key(&key(MAX_ATTEMPTS_ENTRIES / 10 + 2)));
+        assert!(clients.contains_key(&key(MAX_ATTEMPTS_ENTRIES - 1)));
+        assert!(clients.len() < MAX_ATTEMPTS_ENTRIES * 9 / 10 + 2);
+    }
+}
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use std::collections::BTreeMap;
+
 use crate::domain::{
     shared::Id,
     values::{Diff, Kind},
@@ -20,6 +22,8 @@ pub enum Error {
     InvalidVersion,
     #[error("unauthorized")]
     Unauthorized,
+    #[error("too many requests, retry after {0} seconds")]
+    RateLimited(u64),
 
     // Props
     #[error("mismatched kinds: expected {expected}, found {found}")]
@@ -29,6 +33,16 @@ pub enum Error {
     #[error("root prop is not an object or array")]
     UnknownRootProp,
 
+    // Rules
+    #[error("invalid rule: {0}")]
+    InvalidRule(String),
+    #[error("invalid path: {0}")]
+    InvalidPath(String),
+
+    // Migrations
+    #[error("invalid migration: {0}")]
+    InvalidMigration(String),
+
     // Domain & Entities
     #[error("schema not found: {0}")]
     SchemaNotFound(Id),
@@ -36,19 +50,51 @@ pub enum Error {
     SchemaAlreadyExists(Id),
     #[error("schema contains configs: {0}")]
     SchemaContainsConfigs(Id),
+    #[error("schema modified concurrently: {0}")]
+    SchemaModified(Id),
     #[error("config not found: {0}")]
     ConfigNotFound(Id),
     #[error("config already exists: {0}")]
     ConfigAlreadyExists(Id),
+    #[error("config modified concurrently: {0}")]
+    ConfigModified(Id),
     #[error("page out of range")]
     PageOutOfRange,
+    #[error("invalid limit {0}: must be between 1 and 100")]
+    InvalidLimit(u64),
+    #[error("invalid cursor")]
+    InvalidCursor,
+    #[error("invalid sort: {0}")]
+    InvalidSort(String),
+    #[error("invalid include: {0}")]
+    InvalidInclude(String),
     #[error("invalid password")]
     InvalidPassword,
+    #[error("schema change would invalidate {} configs", .0.len())]
+    BreakingSchemaChange(BTreeMap<String, Diff>),
 
     // Config validation
     #[error("invalid config")]
     InvalidConfig(Diff),
 
+    // Formats
+    #[error("unsupported format: {0}")]
+    UnsupportedFormat(String),
+    #[error("value cannot be represented as {format}: {reason}")]
+    UnrepresentableValue { format: String, reason: String },
+    #[error("unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+    #[error("invalid body: {0}")]
+    InvalidBody(String),
+    #[error("payload larger than {0} bytes")]
+    PayloadTooLarge(usize),
+
+    // Patches
+    #[error("invalid patch: {0}")]
+    InvalidPatch(String),
+    #[error("patch test failed: {0}")]
+    PatchTestFailed(String),
+
     // Events
     #[error("invalid event")]
     InvalidEvent,
@@ -69,21 +115,43 @@ impl Error {
             Error::InvalidTimestamps => "invalid_timestamps",
             Error::InvalidVersion => "invalid_version",
             Error::Unauthorized => "unauthorized",
+            Error::RateLimited(_) => "rate_limited",
 
             Error::MismatchedKinds { .. } => "mismatched_kinds",
             Error::InvalidArray => "invalid_array",
             Error::UnknownRootProp => "unknown_root_prop",
 
+            Error::InvalidRule(_) => "invalid_rule",
+            Error::InvalidPath(_) => "invalid_path",
+
+            Error::InvalidMigration(_) => "invalid_migration",
+
             Error::SchemaNotFound(_) => "schema_not_found",
             Error::SchemaAlreadyExists(_) => "schema_already_exists",
             Error::SchemaContainsConfigs(_) => "schema_contains_configs",
+            Error::SchemaModified(_) => "schema_modified",
             Error::ConfigNotFound(_) => "config_not_found",
             Error::ConfigAlreadyExists(_) => "config_already_exists",
+            Error::ConfigModified(_) => "config_modified",
             Error::PageOutOfRange => "page_out_of_range",
+            Error::InvalidLimit(_) => "invalid_limit",
+            Error::InvalidCursor => "invalid_cursor",
+            Error::InvalidSort(_) => "invalid_sort",
+            Error::InvalidInclude(_) => "invalid_include",
             Error::InvalidPassword => "invalid_password",
+            Error::BreakingSchemaChange(_) => "breaking_schema_change",
 
             Error::InvalidConfig(_) => "invalid_config",
 
+            Error::UnsupportedFormat(_) => "unsupported_format",
+            Error::UnrepresentableValue { .. } => "unrepresentable_value",
+            Error::UnsupportedMediaType(_) => "unsupported_media_type",
+            Error::InvalidBody(_) => "invalid_body",
+            Error::PayloadTooLarge(_) => "payload_too_large",
+
+            Error::InvalidPatch(_) => "invalid_patch",
+            Error::PatchTestFailed(_) => "patch_test_failed",
+
             Error::InvalidEvent => "invalid_event",
 
             Error::Serde(_) => "serde",
@@ -0,0 +1,33 @@
+use std::future::Future;
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+// Runs the future with the id of the request or event that caused it, so the
+// events created meanwhile carry it
+pub async fn with_correlation_id<F>(correlation_id: String, f: F) -> F::Output
+where
+    F: Future,
+{
+    CORRELATION_ID.scope(correlation_id, f).await
+}
+
+pub fn correlation_id() -> Option<String> {
+    CORRELATION_ID.try_with(|id| id.clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn scope() {
+        assert!(correlation_id().is_none());
+
+        let id = with_correlation_id("request#01".to_string(), async { correlation_id() }).await;
+        assert_eq!(id.as_deref(), Some("request#01"));
+
+        assert!(correlation_id().is_none());
+    }
+}
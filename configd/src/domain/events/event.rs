@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::domain::{errors::Error, shared::Id};
+use crate::domain::{errors::Error, events::correlation_id, shared::Id};
 
 // Publisher and subscriber
 #[async_trait]
@@ -28,6 +28,8 @@ pub struct Event {
     topic: String,
     payload: Vec<u8>,
     timestamp: DateTime<Utc>,
+    // Request that caused the event
+    correlation_id: Option<String>,
 }
 
 impl Event {
@@ -37,6 +39,7 @@ impl Event {
         topic: String,
         payload: Vec<u8>,
         timestamp: DateTime<Utc>,
+        correlation_id: Option<String>,
     ) -> Result<Event, Error> {
         if id.is_empty() {
             return Err(Error::InvalidEvent);
@@ -60,6 +63,7 @@ impl Event {
             topic,
             payload,
             timestamp,
+            correlation_id,
         })
     }
 
@@ -80,6 +84,7 @@ impl Event {
             topic,
             payload,
             Utc::now(),
+            correlation_id(),
         )
     }
 
@@ -109,12 +114,18 @@ impl Event {
     pub fn timestamp(&self) -> &DateTime<Utc> {
         &self.timestamp
     }
+
+    pub fn correlation_id(&self) -> Option<&str> {
+        self.correlation_id.as_deref()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::domain::events::with_correlation_id;
+
     #[derive(Debug, Serialize, Deserialize)]
     struct Data {
         msg: String,
@@ -137,6 +148,18 @@ mod tests {
         assert!(!event.payload().is_empty());
     }
 
+    #[tokio::test]
+    async fn create_with_correlation_id() {
+        let event = Event::create("entity#01", "topic.code", &1).unwrap();
+        assert!(event.correlation_id().is_none());
+
+        let event = with_correlation_id("request#01".to_string(), async {
+            Event::create("entity#01", "topic.code", &1).unwrap()
+        })
+        .await;
+        assert_eq!(event.correlation_id(), Some("request#01"));
+    }
+
     #[test]
     fn payload_serialization_and_deserialization() {
         let event = Event::create(
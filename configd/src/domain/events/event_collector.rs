@@ -37,6 +37,7 @@ impl EventCollector {
         &self.events
     }
 
+    #[allow(dead_code)]
     pub fn drain(&mut self) -> Vec<Event> {
         mem::take(&mut self.events)
     }
@@ -1,5 +1,9 @@
+mod correlation;
 mod event;
 mod event_collector;
+mod resynced;
 
+pub use correlation::*;
 pub use event::*;
 pub use event_collector::*;
+pub use resynced::*;
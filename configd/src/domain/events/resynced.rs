@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::events::Publishable;
+
+// Published to the subscribers of a node when events of other nodes could
+// have been missed, so anything derived from them must be reloaded
+#[derive(Serialize, Deserialize)]
+pub struct EventsResynced {
+    pub node: String,
+}
+
+impl Publishable for EventsResynced {
+    fn entity_id(&self) -> &str {
+        &self.node
+    }
+
+    fn topic(&self) -> &str {
+        "events.resynced"
+    }
+}
@@ -1,4 +1,3 @@
-use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
@@ -10,6 +9,7 @@ pub struct SchemaCreated {
     pub id: String,
     pub name: String,
     pub root_prop: JsonValue,
+    pub rules: JsonValue,
 }
 
 impl Publishable for SchemaCreated {
@@ -39,159 +39,62 @@ impl Publishable for SchemaRootPropChanged {
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct SchemaDeleted {
+pub struct SchemaRulesChanged {
     pub id: String,
+    pub rules: JsonValue,
 }
 
-impl Publishable for SchemaDeleted {
+impl Publishable for SchemaRulesChanged {
     fn entity_id(&self) -> &str {
         &self.id
     }
 
     fn topic(&self) -> &str {
-        "schema.deleted"
+        "schema.rules_changed"
     }
 }
 
-// Config
 #[derive(Serialize, Deserialize)]
-pub struct ConfigCreated {
-    pub schema_id: String,
-    pub id: String,
-    pub name: String,
-    pub data: JsonValue,
-    pub valid: bool,
-    pub password: Option<String>,
-}
-
-impl Publishable for ConfigCreated {
-    fn entity_id(&self) -> &str {
-        &self.schema_id
-    }
-
-    fn topic(&self) -> &str {
-        "config.created"
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct ConfigDataChanged {
-    pub schema_id: String,
-    pub id: String,
-    pub data: JsonValue,
-    pub valid: bool,
-}
-
-impl Publishable for ConfigDataChanged {
-    fn entity_id(&self) -> &str {
-        &self.schema_id
-    }
-
-    fn topic(&self) -> &str {
-        "config.data_changed"
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct ConfigRevalidated {
-    pub schema_id: String,
-    pub id: String,
-    pub valid: bool,
-}
-
-impl Publishable for ConfigRevalidated {
-    fn entity_id(&self) -> &str {
-        &self.schema_id
-    }
-
-    fn topic(&self) -> &str {
-        "config.revalidated"
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct ConfigPasswordChanged {
-    pub schema_id: String,
-    pub id: String,
-    pub password: String,
-}
-
-impl Publishable for ConfigPasswordChanged {
-    fn entity_id(&self) -> &str {
-        &self.schema_id
-    }
-
-    fn topic(&self) -> &str {
-        "config.password_changed"
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct ConfigPasswordDeleted {
-    pub schema_id: String,
-    pub id: String,
-}
-
-impl Publishable for ConfigPasswordDeleted {
-    fn entity_id(&self) -> &str {
-        &self.schema_id
-    }
-
-    fn topic(&self) -> &str {
-        "config.password_deleted"
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct ConfigDeleted {
-    pub schema_id: String,
+pub struct SchemaDeleted {
     pub id: String,
 }
 
-impl Publishable for ConfigDeleted {
+impl Publishable for SchemaDeleted {
     fn entity_id(&self) -> &str {
-        &self.schema_id
+        &self.id
     }
 
     fn topic(&self) -> &str {
-        "config.deleted"
+        "schema.deleted"
     }
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct ConfigAccessed {
-    pub schema_id: String,
+pub struct SchemaRestored {
     pub id: String,
-    pub source: String,
-    pub instance: String,
-    pub timestamp: DateTime<Utc>,
-    pub previous: Option<DateTime<Utc>>,
 }
 
-impl Publishable for ConfigAccessed {
+impl Publishable for SchemaRestored {
     fn entity_id(&self) -> &str {
-        &self.schema_id
+        &self.id
     }
 
     fn topic(&self) -> &str {
-        "config.accessed"
+        "schema.restored"
     }
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct ConfigAccessRemoved {
-    pub schema_id: String,
+pub struct SchemaPurged {
     pub id: String,
-    pub source: String,
-    pub instance: String,
 }
 
-impl Publishable for ConfigAccessRemoved {
+impl Publishable for SchemaPurged {
     fn entity_id(&self) -> &str {
-        &self.schema_id
+        &self.id
     }
 
     fn topic(&self) -> &str {
-        "config.access_removed"
+        "schema.purged"
     }
 }
@@ -1,5 +1,7 @@
 mod events;
 mod schema;
+mod schema_query;
 
 pub use events::*;
 pub use schema::*;
+pub use schema_query::*;
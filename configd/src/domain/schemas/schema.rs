@@ -1,25 +1,51 @@
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use crate::domain::{
-    configs::{Access, Config, Password},
+    configs::Config,
     errors::Error,
     events::{Event, EventCollector},
     schemas::{
-        ConfigAccessRemoved, ConfigAccessed, ConfigCreated, ConfigDataChanged, ConfigDeleted,
-        ConfigPasswordChanged, ConfigPasswordDeleted, ConfigRevalidated, SchemaCreated,
-        SchemaDeleted, SchemaRootPropChanged,
+        SchemaCreated, SchemaDeleted, SchemaPurged, SchemaQuery, SchemaRestored,
+        SchemaRootPropChanged, SchemaRulesChanged,
     },
     shared::{Id, Page, Timestamps, Version},
-    values::{Prop, Value},
+    values::{rules_to_json, Compatibility, Diff, Prop, Rule, Value},
 };
 
 #[async_trait]
 pub trait SchemaRepository {
-    async fn find(&self, offset: Option<u64>, limit: Option<u64>) -> Result<Page<Schema>, Error>;
+    async fn find(&self, query: &SchemaQuery) -> Result<Page<Schema>, Error>;
     async fn find_by_id(&self, id: &Id) -> Result<Option<Schema>, Error>;
+    async fn find_deleted_by_id(&self, id: &Id) -> Result<Option<Schema>, Error>;
+    // Deleted schemas too, their ids are taken until purged
     async fn exists(&self, id: &Id) -> Result<bool, Error>;
+    // Schemas not deleted
+    async fn count(&self) -> Result<u64, Error>;
     async fn save(&self, schema: &mut Schema) -> Result<(), Error>;
+    // Saves the schema with the configs changed along with it, all of them
+    // or none
+    async fn save_with_configs(
+        &self,
+        schema: &mut Schema,
+        configs: &mut [Config],
+    ) -> Result<(), Error>;
+}
+
+// Effect of a root prop or rules change over the existing configs
+#[derive(Debug, Clone)]
+pub struct SchemaEvolution {
+    pub compatibility: Compatibility,
+    // Configs valid under the current schema that would become invalid
+    pub affected_configs: BTreeMap<String, Diff>,
+    // Names of the affected configs by id
+    pub affected_names: BTreeMap<String, String>,
+}
+
+impl SchemaEvolution {
+    pub fn is_breaking(&self) -> bool {
+        !self.affected_configs.is_empty()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -28,8 +54,7 @@ pub struct Schema {
     name: String,
 
     root_prop: Prop,
-
-    configs: HashMap<Id, Config>,
+    rules: Vec<Rule>,
 
     timestamps: Timestamps,
     version: Version,
@@ -42,7 +67,7 @@ impl Schema {
         id: Id,
         name: String,
         root_prop: Prop,
-        configs: HashMap<Id, Config>,
+        rules: Vec<Rule>,
         timestamps: Timestamps,
         version: Version,
         event_collector: Option<EventCollector>,
@@ -55,19 +80,24 @@ impl Schema {
             id,
             name,
             root_prop,
-            configs,
+            rules,
             timestamps,
             version,
             event_collector: event_collector.unwrap_or_else(EventCollector::create),
         })
     }
 
-    pub fn create(id: Id, name: String, root_prop: Prop) -> Result<Schema, Error> {
+    pub fn create(
+        id: Id,
+        name: String,
+        root_prop: Prop,
+        rules: Vec<Rule>,
+    ) -> Result<Schema, Error> {
         let mut schema = Schema::new(
             id,
             name,
             root_prop,
-            HashMap::new(),
+            rules,
             Timestamps::create(),
             Version::init_version(),
             Some(EventCollector::create()),
@@ -77,6 +107,7 @@ impl Schema {
             id: schema.id().to_string(),
             name: schema.name().to_string(),
             root_prop: schema.root_prop().clone().try_into()?,
+            rules: rules_to_json(schema.rules())?,
         })?;
 
         Ok(schema)
@@ -94,13 +125,20 @@ impl Schema {
         &self.root_prop
     }
 
-    pub fn configs(&self) -> &HashMap<Id, Config> {
-        &self.configs
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
     }
 
-    pub fn populate_config(&self, config: &Config) -> Value {
-        self.root_prop
-            .populate(config.data(), config.accesses().len() as i64)
+    // Validates data against the root prop and then the rules. Rules are
+    // evaluated over the data populated with default values.
+    pub fn validate(&self, data: &Value) -> Diff {
+        validate_against(&self.root_prop, &self.rules, data)
+    }
+
+    // Instances is the number of live consumers the split props are
+    // distributed between
+    pub fn populate_config(&self, config: &Config, instances: usize) -> Value {
+        self.root_prop.populate(config.data(), instances as i64)
     }
 
     pub fn timestamps(&self) -> &Timestamps {
@@ -130,229 +168,68 @@ impl Schema {
         Ok(())
     }
 
-    pub fn get_config(
-        &mut self,
-        id: &Id,
-        access: Access,
-        password: Option<&Password>,
-    ) -> Result<Config, Error> {
-        let config = self
-            .configs
-            .get_mut(id)
-            .ok_or_else(|| Error::ConfigNotFound(id.clone()))?;
-
-        if !config.can_access(password) {
-            return Err(Error::Unauthorized);
-        }
+    pub fn change_rules(&mut self, rules: Vec<Rule>) -> Result<(), Error> {
+        self.rules = rules;
 
-        let last_access = config.register_access(access);
-
-        self.event_collector.record(ConfigAccessed {
-            schema_id: self.id.to_string(),
-            id: id.to_string(),
-            source: last_access.source().to_string(),
-            instance: last_access.instance().to_string(),
-            timestamp: *last_access.timestamp(),
-            previous: last_access.previous().copied(),
-        })?;
-
-        Ok(config.clone())
-    }
-
-    pub fn add_config(
-        &mut self,
-        id: Id,
-        name: String,
-        data: Value,
-        password: Option<Password>,
-    ) -> Result<(), Error> {
-        if self.configs.contains_key(&id) {
-            return Err(Error::ConfigAlreadyExists(id));
-        }
-
-        let diff = self.root_prop.validate(&data);
-        if !diff.is_empty() {
-            return Err(Error::InvalidConfig(diff));
-        }
-
-        let config = Config::create(id, name, data, diff.is_empty(), password)?;
-
-        self.event_collector.record(ConfigCreated {
-            schema_id: self.id.to_string(),
-            id: config.id().to_string(),
-            name: config.name().to_string(),
-            data: config.data().into(),
-            valid: config.is_valid(),
-            password: config.password().map(ToString::to_string),
+        self.event_collector.record(SchemaRulesChanged {
+            id: self.id.to_string(),
+            rules: rules_to_json(&self.rules)?,
         })?;
 
-        self.configs.insert(config.id().clone(), config);
-
         self.timestamps = self.timestamps.update();
         self.version = self.version.incr();
 
         Ok(())
     }
 
-    pub fn update_config(
-        &mut self,
-        id: &Id,
-        data: Value,
-        password: Option<&Password>,
-    ) -> Result<(), Error> {
-        let config = self
-            .configs
-            .get_mut(id)
-            .ok_or_else(|| Error::ConfigNotFound(id.clone()))?;
-
-        if !config.can_access(password) {
-            return Err(Error::Unauthorized);
-        }
-
-        let diff = self.root_prop.validate(&data);
-        if !diff.is_empty() {
-            return Err(Error::InvalidConfig(diff));
-        }
-
-        config.change_data(data, diff.is_empty())?;
-
-        self.event_collector.record(ConfigDataChanged {
-            schema_id: self.id.to_string(),
-            id: config.id().to_string(),
-            data: config.data().into(),
-            valid: config.is_valid(),
+    // The ConfigService checks the schema has no configs
+    pub fn delete(&mut self) -> Result<(), Error> {
+        self.event_collector.record(SchemaDeleted {
+            id: self.id.to_string(),
         })?;
 
-        self.timestamps = self.timestamps.update();
-        self.version = self.version.incr();
-
-        Ok(())
-    }
-
-    pub fn revalidate_configs(&mut self) -> Result<(), Error> {
-        for config in self.configs.values_mut() {
-            let diff = self.root_prop.validate(config.data());
-            if !diff.is_empty() {
-                config.mark_as_invalid();
-            }
-
-            self.event_collector.record(ConfigRevalidated {
-                schema_id: self.id.to_string(),
-                id: config.id().to_string(),
-                valid: diff.is_empty(),
-            })?;
-        }
+        self.timestamps = self.timestamps.delete();
 
         Ok(())
     }
 
-    pub fn change_config_password(
-        &mut self,
-        id: &Id,
-        old_password: Option<&Password>,
-        new_password: Password,
-    ) -> Result<(), Error> {
-        let config = self
-            .configs
-            .get_mut(id)
-            .ok_or_else(|| Error::ConfigNotFound(id.clone()))?;
-
-        config.change_password(old_password, new_password)?;
-
-        self.event_collector.record(ConfigPasswordChanged {
-            schema_id: self.id.to_string(),
-            id: config.id().to_string(),
-            password: config.password().unwrap().to_string(),
+    pub fn restore(&mut self) -> Result<(), Error> {
+        self.event_collector.record(SchemaRestored {
+            id: self.id.to_string(),
         })?;
 
-        Ok(())
-    }
-
-    pub fn delete_config_password(
-        &mut self,
-        id: &Id,
-        password: Option<&Password>,
-    ) -> Result<(), Error> {
-        let config = self
-            .configs
-            .get_mut(id)
-            .ok_or_else(|| Error::ConfigNotFound(id.clone()))?;
-
-        config.delete_password(password)?;
-
-        self.event_collector.record(ConfigPasswordDeleted {
-            schema_id: self.id.to_string(),
-            id: config.id().to_string(),
-        })?;
+        self.timestamps = self.timestamps.restore();
 
         Ok(())
     }
 
-    pub fn clean_config_accesses(&mut self, id: &Id) -> Result<(), Error> {
-        let config = self
-            .configs
-            .get_mut(id)
-            .ok_or_else(|| Error::ConfigNotFound(id.clone()))?;
-
-        let removed_accesses = config.clean_old_accesses();
-
-        for access in removed_accesses.into_iter() {
-            self.event_collector.record(ConfigAccessRemoved {
-                id: config.id().to_string(),
-                schema_id: self.id.to_string(),
-                source: access.source().to_string(),
-                instance: access.instance().to_string(),
-            })?;
-        }
-
-        Ok(())
+    // Removes the deleted schema for good
+    pub fn purge(&mut self) -> Result<(), Error> {
+        self.event_collector.record(SchemaPurged {
+            id: self.id.to_string(),
+        })
     }
+}
 
-    pub fn delete_config(&mut self, id: &Id, password: Option<&Password>) -> Result<(), Error> {
-        let config = self
-            .configs
-            .get_mut(id)
-            .ok_or_else(|| Error::ConfigNotFound(id.clone()))?;
-
-        if !config.can_access(password) {
-            return Err(Error::Unauthorized);
-        }
-
-        self.configs.remove(id);
-
-        self.event_collector.record(ConfigDeleted {
-            id: id.to_string(),
-            schema_id: self.id.to_string(),
-        })?;
-
-        self.timestamps = self.timestamps.update();
-        self.version = self.version.incr();
+pub fn validate_against(root_prop: &Prop, rules: &[Rule], data: &Value) -> Diff {
+    let mut diff = root_prop.validate(data);
 
-        Ok(())
-    }
+    if !rules.is_empty() {
+        let data = root_prop.populate(data, 1);
 
-    pub fn delete(&mut self) -> Result<(), Error> {
-        if !self.configs.is_empty() {
-            return Err(Error::SchemaContainsConfigs(self.id.clone()));
+        for rule in rules.iter() {
+            diff.extend(rule.validate(&data));
         }
-
-        self.event_collector.record(SchemaDeleted {
-            id: self.id.to_string(),
-        })?;
-
-        self.timestamps = self.timestamps.delete();
-
-        Ok(())
     }
+
+    diff
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use std::collections::BTreeMap;
-
-    use crate::domain::values::{Interval, Value};
+    use crate::domain::values::Interval;
 
     #[test]
     fn create() {
@@ -360,6 +237,7 @@ mod tests {
             Id::new("schema-01").unwrap(),
             "Schema 01".to_string(),
             Prop::bool(true, None).unwrap(),
+            Vec::new(),
         )
         .unwrap();
 
@@ -385,6 +263,7 @@ mod tests {
                             Value::String("prod".to_string()),
                         ]),
                         None,
+                        false,
                     )
                     .unwrap(),
                 ),
@@ -393,6 +272,7 @@ mod tests {
                     Prop::int(true, None, None, Some(Interval::new(1, 5).unwrap()), false).unwrap(),
                 ),
             ])),
+            Vec::new(),
         )
         .unwrap();
 
@@ -443,37 +323,68 @@ mod tests {
     }
 
     #[test]
-    fn add_and_get_populated_config() {
-        let mut schema = Schema::create(
+    fn populate_config() {
+        let schema = Schema::create(
             Id::new("schema-01").unwrap(),
             "Schema 01".to_string(),
-            Prop::string(true, Some(Value::String("default".to_string())), None, None).unwrap(),
+            Prop::string(
+                true,
+                Some(Value::String("default".to_string())),
+                None,
+                None,
+                false,
+            )
+            .unwrap(),
+            Vec::new(),
         )
         .unwrap();
 
-        // Create config
-        let config_id = Id::new("config-01").unwrap();
+        let config = Config::create(
+            schema.id().clone(),
+            Id::new("config-01").unwrap(),
+            "Config 01".to_string(),
+            Value::Null,
+            true,
+            None,
+        )
+        .unwrap();
 
-        schema
-            .add_config(
-                config_id.clone(),
-                "Config 01".to_string(),
-                Value::Null,
-                None,
-            )
-            .unwrap();
+        let data = schema.populate_config(&config, 1);
+        assert_eq!(data, Value::String("default".to_string()));
+    }
 
-        // Get config
-        let config = schema
-            .get_config(&config_id, Access::unknown(), None)
-            .unwrap();
+    #[test]
+    fn validate_rules() {
+        let schema = Schema::create(
+            Id::new("schema-01").unwrap(),
+            "Schema 01".to_string(),
+            Prop::object(BTreeMap::from([
+                (
+                    "min".to_string(),
+                    Prop::int(false, Some(Value::Int(1)), None, None, false).unwrap(),
+                ),
+                (
+                    "max".to_string(),
+                    Prop::int(true, None, None, None, false).unwrap(),
+                ),
+            ])),
+            vec![Rule::new("max >= min".to_string(), None, None).unwrap()],
+        )
+        .unwrap();
 
-        assert_eq!(config.id(), &config_id);
-        assert_eq!(config.name(), "Config 01");
-        assert_eq!(config.data(), &Value::Null);
+        // Rules see default values
+        assert!(schema
+            .validate(&Value::Object(BTreeMap::from([
+                ("min".to_string(), Value::Null),
+                ("max".to_string(), Value::Int(4)),
+            ])))
+            .is_empty());
 
-        // Populate data
-        let data = schema.populate_config(&config);
-        assert_eq!(data, Value::String("default".to_string()));
+        assert!(!schema
+            .validate(&Value::Object(BTreeMap::from([
+                ("min".to_string(), Value::Int(5)),
+                ("max".to_string(), Value::Int(4)),
+            ])))
+            .is_empty());
     }
 }
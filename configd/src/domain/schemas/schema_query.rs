@@ -0,0 +1,235 @@
+use chrono::{DateTime, Utc};
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use crate::domain::{
+    errors::Error,
+    schemas::Schema,
+    shared::{Cursor, SortOrder, SortValue, DEFAULT_PAGE_LIMIT},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchemaSort {
+    #[default]
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl FromStr for SchemaSort {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<SchemaSort, Self::Err> {
+        match s {
+            "name" => Ok(SchemaSort::Name),
+            "created_at" => Ok(SchemaSort::CreatedAt),
+            "updated_at" => Ok(SchemaSort::UpdatedAt),
+            _ => Err(Error::InvalidSort(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for SchemaSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaSort::Name => write!(f, "name"),
+            SchemaSort::CreatedAt => write!(f, "created_at"),
+            SchemaSort::UpdatedAt => write!(f, "updated_at"),
+        }
+    }
+}
+
+// Schemas are always sorted by the sort value and then by id, so every
+// repository returns the same pages
+#[derive(Debug, Clone)]
+pub struct SchemaQuery {
+    // Case-insensitive part of the name
+    pub name: Option<String>,
+    pub has_invalid_configs: Option<bool>,
+    pub updated_since: Option<DateTime<Utc>>,
+    // Deleted schemas instead of the live ones
+    pub deleted: bool,
+    // Only schemas deleted before it
+    pub deleted_before: Option<DateTime<Utc>>,
+    // Only schemas with configs deleted before it
+    pub configs_deleted_before: Option<DateTime<Utc>>,
+    pub sort: SchemaSort,
+    pub order: SortOrder,
+    // Only schemas after it, the offset is applied from there
+    pub cursor: Option<Cursor>,
+    pub offset: u64,
+    pub limit: u64,
+}
+
+impl Default for SchemaQuery {
+    fn default() -> SchemaQuery {
+        SchemaQuery {
+            name: None,
+            has_invalid_configs: None,
+            updated_since: None,
+            deleted: false,
+            deleted_before: None,
+            configs_deleted_before: None,
+            sort: SchemaSort::default(),
+            order: SortOrder::default(),
+            cursor: None,
+            offset: 0,
+            limit: DEFAULT_PAGE_LIMIT,
+        }
+    }
+}
+
+impl SchemaQuery {
+    // Identifies the sort of the cursors
+    pub fn sort_key(&self) -> String {
+        format!("{}.{}", self.sort, self.order)
+    }
+
+    pub fn with_cursor(mut self, cursor: &str) -> Result<SchemaQuery, Error> {
+        let cursor = Cursor::decode(cursor)?;
+        let valid_value = matches!(
+            (self.sort, cursor.value()),
+            (SchemaSort::Name, SortValue::Text(_))
+                | (
+                    SchemaSort::CreatedAt | SchemaSort::UpdatedAt,
+                    SortValue::Timestamp(_)
+                )
+        );
+        if cursor.sort() != self.sort_key() || !valid_value {
+            return Err(Error::InvalidCursor);
+        }
+
+        self.cursor = Some(cursor);
+
+        Ok(self)
+    }
+
+    pub fn sort_value(&self, schema: &Schema) -> SortValue {
+        match self.sort {
+            SchemaSort::Name => SortValue::Text(schema.name().to_string()),
+            SchemaSort::CreatedAt => SortValue::Timestamp(*schema.timestamps().created_at()),
+            SchemaSort::UpdatedAt => SortValue::Timestamp(*schema.timestamps().updated_at()),
+        }
+    }
+
+    pub fn cursor_of(&self, schema: &Schema) -> Cursor {
+        Cursor::new(
+            self.sort_key(),
+            self.sort_value(schema),
+            schema.id().value(),
+        )
+    }
+
+    // Filters except the invalid and deleted configs, which depend on the
+    // configs
+    pub fn matches(&self, schema: &Schema) -> bool {
+        let deleted_at = schema.timestamps().deleted_at();
+        if deleted_at.is_some() != self.deleted {
+            return false;
+        }
+
+        if let (Some(deleted_before), Some(deleted_at)) = (&self.deleted_before, deleted_at) {
+            if deleted_at >= deleted_before {
+                return false;
+            }
+        }
+
+        if let Some(name) = &self.name {
+            if !schema.name().to_lowercase().contains(&name.to_lowercase()) {
+                return false;
+            }
+        }
+
+        if let Some(updated_since) = &self.updated_since {
+            if schema.timestamps().updated_at() < updated_since {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn compare(&self, a: &Schema, b: &Schema) -> Ordering {
+        let ordering = self
+            .sort_value(a)
+            .cmp(&self.sort_value(b))
+            .then_with(|| a.id().value().cmp(b.id().value()));
+
+        match self.order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    }
+
+    pub fn is_after_cursor(&self, schema: &Schema) -> bool {
+        let cursor = match &self.cursor {
+            Some(cursor) => cursor,
+            None => return true,
+        };
+
+        let ordering = self
+            .sort_value(schema)
+            .cmp(cursor.value())
+            .then_with(|| schema.id().value().cmp(cursor.id()));
+
+        match self.order {
+            SortOrder::Asc => ordering == Ordering::Greater,
+            SortOrder::Desc => ordering == Ordering::Less,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::{shared::Id, values::Prop};
+
+    fn schema(id: &str, name: &str) -> Schema {
+        Schema::create(
+            Id::new(id).unwrap(),
+            name.to_string(),
+            Prop::bool(true, None).unwrap(),
+            Vec::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn cursors() {
+        let query = SchemaQuery {
+            sort: SchemaSort::Name,
+            order: SortOrder::Desc,
+            ..SchemaQuery::default()
+        };
+        let cursor = query.cursor_of(&schema("schema#02", "Schema")).encode();
+        let query = query.with_cursor(&cursor).unwrap();
+
+        // Ties are broken by id
+        assert!(query.is_after_cursor(&schema("schema#01", "Schema")));
+        assert!(!query.is_after_cursor(&schema("schema#02", "Schema")));
+        assert!(!query.is_after_cursor(&schema("schema#03", "Schema")));
+        assert!(query.is_after_cursor(&schema("schema#04", "Other")));
+
+        // Cursors of other sorts are rejected
+        let other = SchemaQuery {
+            sort: SchemaSort::UpdatedAt,
+            order: SortOrder::Desc,
+            ..SchemaQuery::default()
+        };
+        assert!(matches!(
+            other.with_cursor(&cursor),
+            Err(Error::InvalidCursor)
+        ));
+    }
+
+    #[test]
+    fn filters() {
+        let query = SchemaQuery {
+            name: Some("BACK".to_string()),
+            ..SchemaQuery::default()
+        };
+
+        assert!(query.matches(&schema("schema#01", "Backend")));
+        assert!(!query.matches(&schema("schema#02", "Frontend")));
+    }
+}
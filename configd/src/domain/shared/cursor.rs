@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::errors::Error;
+
+// Value of the sorted field of an item
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SortValue {
+    Text(String),
+    Timestamp(DateTime<Utc>),
+}
+
+// Position after an item of a sorted listing. The id breaks ties, so pages are
+// stable even when items share the sorted value. Clients get it encoded and
+// must not rely on its content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    sort: String,
+    value: SortValue,
+    id: String,
+}
+
+impl Cursor {
+    pub fn new(sort: impl Into<String>, value: SortValue, id: impl Into<String>) -> Cursor {
+        Cursor {
+            sort: sort.into(),
+            value,
+            id: id.into(),
+        }
+    }
+
+    pub fn decode(cursor: &str) -> Result<Cursor, Error> {
+        let bytes = hex::decode(cursor).map_err(|_| Error::InvalidCursor)?;
+
+        serde_json::from_slice(&bytes).map_err(|_| Error::InvalidCursor)
+    }
+
+    pub fn encode(&self) -> String {
+        hex::encode(serde_json::to_vec(self).unwrap())
+    }
+
+    // Sort the cursor was created for, cursors are only valid for it
+    pub fn sort(&self) -> &str {
+        &self.sort
+    }
+
+    pub fn value(&self) -> &SortValue {
+        &self.value
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_and_decode() {
+        let cursor = Cursor::new(
+            "updated_at.desc",
+            SortValue::Timestamp(Utc::now()),
+            "schema#01",
+        );
+
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(matches!(
+            Cursor::decode("invalid"),
+            Err(Error::InvalidCursor)
+        ));
+        assert!(matches!(
+            Cursor::decode(&hex::encode("{}")),
+            Err(Error::InvalidCursor)
+        ));
+    }
+}
@@ -1,8 +1,10 @@
+mod cursor;
 mod id;
 mod page;
 mod timestamps;
 mod version;
 
+pub use cursor::*;
 pub use id::*;
 pub use page::*;
 pub use timestamps::*;
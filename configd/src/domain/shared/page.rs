@@ -1,5 +1,47 @@
+use std::{fmt, str::FromStr};
+
 use crate::domain::errors::Error;
 
+// Every listing has the same limits
+pub const DEFAULT_PAGE_LIMIT: u64 = 10;
+pub const MAX_PAGE_LIMIT: u64 = 100;
+
+pub fn page_limit(limit: Option<u64>) -> Result<u64, Error> {
+    match limit {
+        None => Ok(DEFAULT_PAGE_LIMIT),
+        Some(limit) if limit == 0 || limit > MAX_PAGE_LIMIT => Err(Error::InvalidLimit(limit)),
+        Some(limit) => Ok(limit),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl FromStr for SortOrder {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<SortOrder, Self::Err> {
+        match s {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            _ => Err(Error::InvalidSort(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortOrder::Asc => write!(f, "asc"),
+            SortOrder::Desc => write!(f, "desc"),
+        }
+    }
+}
+
 pub struct Page<T> {
     offset: u64,
     limit: u64,
@@ -37,3 +79,19 @@ impl<T> Page<T> {
         self.data
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits() {
+        assert_eq!(page_limit(None).unwrap(), DEFAULT_PAGE_LIMIT);
+        assert_eq!(page_limit(Some(MAX_PAGE_LIMIT)).unwrap(), MAX_PAGE_LIMIT);
+        assert!(matches!(page_limit(Some(0)), Err(Error::InvalidLimit(0))));
+        assert!(matches!(
+            page_limit(Some(MAX_PAGE_LIMIT + 1)),
+            Err(Error::InvalidLimit(_))
+        ));
+    }
+}
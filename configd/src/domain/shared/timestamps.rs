@@ -69,4 +69,11 @@ impl Timestamps {
         timestamps.deleted_at = Some(Utc::now());
         timestamps
     }
+
+    pub fn restore(&self) -> Timestamps {
+        let mut timestamps = self.clone();
+        timestamps.updated_at = Utc::now();
+        timestamps.deleted_at = None;
+        timestamps
+    }
 }
@@ -29,6 +29,15 @@ impl Version {
         self.version
     }
 
+    // Version it was loaded with, zero when it was just created
+    pub fn original(&self) -> i64 {
+        if self.updated {
+            self.version - 1
+        } else {
+            self.version
+        }
+    }
+
     pub fn incr(&self) -> Version {
         if self.updated {
             return self.clone();
@@ -0,0 +1,224 @@
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::BTreeSet;
+
+use crate::domain::values::{display_value, Prop, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+// Structural difference between two values at a path, using the same
+// notation as Diff keys
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    path: String,
+    kind: ChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<JsonValue>,
+}
+
+impl Change {
+    pub fn added(path: String, to: &Value, secret: bool) -> Change {
+        Change {
+            path,
+            kind: ChangeKind::Added,
+            from: None,
+            to: Some(display_value(to, secret)),
+        }
+    }
+
+    pub fn removed(path: String, from: &Value, secret: bool) -> Change {
+        Change {
+            path,
+            kind: ChangeKind::Removed,
+            from: Some(display_value(from, secret)),
+            to: None,
+        }
+    }
+
+    pub fn changed(path: String, from: &Value, to: &Value, secret: bool) -> Change {
+        Change {
+            path,
+            kind: ChangeKind::Changed,
+            from: Some(display_value(from, secret)),
+            to: Some(display_value(to, secret)),
+        }
+    }
+
+    // Changes needed to go from one value to the other, ordered by path.
+    // Values of the secret props of the root prop are masked.
+    pub fn between(from: &Value, to: &Value, root_prop: &Prop) -> Vec<Change> {
+        let mut changes = Vec::new();
+        compare(from, to, Some(root_prop), "$".to_string(), &mut changes);
+        changes
+    }
+}
+
+// Values out of the prop, unknown props, are never secret. Secrets are
+// compared whole, so nothing under them is exposed.
+fn compare(from: &Value, to: &Value, prop: Option<&Prop>, path: String, changes: &mut Vec<Change>) {
+    let secret = prop.is_some_and(Prop::is_secret);
+
+    match (from, to) {
+        (Value::Object(from_object), Value::Object(to_object)) if !secret => {
+            let keys: BTreeSet<&String> = from_object.keys().chain(to_object.keys()).collect();
+
+            for key in keys.into_iter() {
+                let path = format!("{}.{}", path, key);
+                let prop = match prop {
+                    Some(Prop::Object(props)) => props.get(key),
+                    _ => None,
+                };
+                let secret = prop.is_some_and(Prop::is_secret);
+
+                match (from_object.get(key), to_object.get(key)) {
+                    (Some(from), Some(to)) => compare(from, to, prop, path, changes),
+                    (Some(from), None) => changes.push(Change::removed(path, from, secret)),
+                    (None, Some(to)) => changes.push(Change::added(path, to, secret)),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(from_items), Value::Array(to_items)) if !secret => {
+            let prop = match prop {
+                Some(Prop::Array(prop)) => Some(prop.as_ref()),
+                _ => None,
+            };
+            let secret = prop.is_some_and(Prop::is_secret);
+
+            for i in 0..from_items.len().max(to_items.len()) {
+                let path = format!("{}.{}", path, i);
+
+                match (from_items.get(i), to_items.get(i)) {
+                    (Some(from), Some(to)) => compare(from, to, prop, path, changes),
+                    (Some(from), None) => changes.push(Change::removed(path, from, secret)),
+                    (None, Some(to)) => changes.push(Change::added(path, to, secret)),
+                    (None, None) => {}
+                }
+            }
+        }
+        (from, to) => {
+            if !from.equals(to) {
+                changes.push(Change::changed(path, from, to, secret));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn between() {
+        // Unknown props are compared as well
+        let root_prop = Prop::object(BTreeMap::new());
+
+        let from: Value = json!({
+            "env": "stg",
+            "port": 8080,
+            "hosts": ["a.local", "b.local"],
+            "tls": { "enabled": false },
+            "debug": true,
+        })
+        .into();
+        let to: Value = json!({
+            "env": "prod",
+            "port": 8080,
+            "hosts": ["a.local"],
+            "tls": { "enabled": true, "cert_path": "/etc/cert" },
+        })
+        .into();
+
+        assert_eq!(
+            Change::between(&from, &to, &root_prop),
+            vec![
+                Change::removed("$.debug".to_string(), &Value::Bool(true), false),
+                Change::changed(
+                    "$.env".to_string(),
+                    &Value::from("stg"),
+                    &Value::from("prod"),
+                    false
+                ),
+                Change::removed("$.hosts.1".to_string(), &Value::from("b.local"), false),
+                Change::added(
+                    "$.tls.cert_path".to_string(),
+                    &Value::from("/etc/cert"),
+                    false
+                ),
+                Change::changed(
+                    "$.tls.enabled".to_string(),
+                    &Value::Bool(false),
+                    &Value::Bool(true),
+                    false
+                ),
+            ]
+        );
+
+        assert!(Change::between(&from, &from, &root_prop).is_empty());
+
+        // Numbers are compared by value
+        assert!(Change::between(
+            &json!({ "port": 1, "ratio": [0.5] }).into(),
+            &json!({ "port": 1.0, "ratio": [0.5] }).into(),
+            &root_prop
+        )
+        .is_empty());
+
+        // Different kinds are replaced as a whole
+        assert_eq!(
+            Change::between(&Value::Null, &to, &root_prop),
+            vec![Change::changed("$".to_string(), &Value::Null, &to, false)]
+        );
+
+        assert_eq!(
+            serde_json::to_value(Change::added("$.a".to_string(), &Value::Int(1), false)).unwrap(),
+            json!({ "path": "$.a", "kind": "added", "to": 1 })
+        );
+    }
+
+    #[test]
+    fn between_secrets() {
+        let secret = Prop::string(false, None, None, None, true).unwrap();
+        let root_prop = Prop::object(BTreeMap::from([
+            ("password".to_string(), secret.clone()),
+            ("keys".to_string(), Prop::array(secret)),
+            (
+                "user".to_string(),
+                Prop::string(false, None, None, None, false).unwrap(),
+            ),
+        ]));
+        let from: Value = json!({
+            "password": "hunter2",
+            "keys": ["k1", "k2"],
+            "user": "admin",
+        })
+        .into();
+        let to: Value = json!({
+            "password": { "nested": "s3cr3t" },
+            "keys": ["k3"],
+            "user": "root",
+        })
+        .into();
+
+        assert_eq!(
+            serde_json::to_value(Change::between(&from, &to, &root_prop)).unwrap(),
+            json!([
+                { "path": "$.keys.0", "kind": "changed", "from": "********", "to": "********" },
+                { "path": "$.keys.1", "kind": "removed", "from": "********" },
+                { "path": "$.password", "kind": "changed", "from": "********", "to": "********" },
+                { "path": "$.user", "kind": "changed", "from": "admin", "to": "root" },
+            ])
+        );
+    }
+}
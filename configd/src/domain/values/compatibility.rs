@@ -0,0 +1,285 @@
+use serde::Serialize;
+
+use crate::domain::values::{Interval, Prop, Rule};
+
+// How a schema change affects existing configs. Ordered from the least to the
+// most disruptive one, so changes can be combined with `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compatibility {
+    // Every config valid before remains valid
+    Compatible,
+    // New optional props were added. Configs still have to include them (even
+    // as null) to be valid.
+    Additive,
+    // Props were removed or retyped, or constraints were tightened
+    Breaking,
+}
+
+impl Compatibility {
+    pub fn between_props(old: &Prop, new: &Prop) -> Compatibility {
+        match (old, new) {
+            (Prop::Object(old_props), Prop::Object(new_props)) => {
+                let mut compatibility = Compatibility::Compatible;
+
+                for (key, old_prop) in old_props.iter() {
+                    compatibility = compatibility.max(match new_props.get(key) {
+                        Some(new_prop) => Compatibility::between_props(old_prop, new_prop),
+                        None => Compatibility::Breaking,
+                    });
+                }
+
+                for (key, new_prop) in new_props.iter() {
+                    if !old_props.contains_key(key) {
+                        compatibility = compatibility.max(if is_mandatory(new_prop) {
+                            Compatibility::Breaking
+                        } else {
+                            Compatibility::Additive
+                        });
+                    }
+                }
+
+                compatibility
+            }
+            (Prop::Array(old_prop), Prop::Array(new_prop)) => {
+                Compatibility::between_props(old_prop, new_prop)
+            }
+            (Prop::Bool { .. }, Prop::Bool { .. })
+            | (Prop::Int { .. }, Prop::Int { .. })
+            | (Prop::Int { .. }, Prop::Float { .. })
+            | (Prop::Float { .. }, Prop::Float { .. })
+            | (Prop::String { .. }, Prop::String { .. }) => {
+                if is_tightened(old, new) {
+                    Compatibility::Breaking
+                } else {
+                    Compatibility::Compatible
+                }
+            }
+            _ => Compatibility::Breaking,
+        }
+    }
+
+    // Removing rules is compatible, adding new ones is not
+    pub fn between_rules(old: &[Rule], new: &[Rule]) -> Compatibility {
+        if new.iter().all(|rule| old.contains(rule)) {
+            Compatibility::Compatible
+        } else {
+            Compatibility::Breaking
+        }
+    }
+}
+
+// A null value is rejected only when the prop is required without a default
+fn is_mandatory(prop: &Prop) -> bool {
+    prop.is_required() && prop.default_value().is_none()
+}
+
+fn is_tightened(old: &Prop, new: &Prop) -> bool {
+    if is_mandatory(new) && !is_mandatory(old) {
+        return true;
+    }
+
+    // Int values are not coerced when compared with float allowed values
+    if let Some(new_values) = new.allowed_values() {
+        match old.allowed_values() {
+            Some(old_values) => {
+                if !old_values.iter().all(|value| new_values.contains(value)) {
+                    return true;
+                }
+            }
+            None => return true,
+        }
+    }
+
+    if let Some(new_interval) = new.interval() {
+        match old.interval() {
+            Some(old_interval) => {
+                if !contains(new_interval, old_interval) {
+                    return true;
+                }
+            }
+            None => return true,
+        }
+    }
+
+    if let Some(new_regex) = new.regex() {
+        if old.regex() != Some(new_regex) {
+            return true;
+        }
+    }
+
+    false
+}
+
+fn contains(outer: &Interval, inner: &Interval) -> bool {
+    let min = match (outer.min(), inner.min()) {
+        (None, _) => true,
+        (Some(outer), Some(inner)) => inner >= outer,
+        (Some(_), None) => false,
+    };
+
+    let max = match (outer.max(), inner.max()) {
+        (None, _) => true,
+        (Some(outer), Some(inner)) => inner <= outer,
+        (Some(_), None) => false,
+    };
+
+    min && max
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::values::Value;
+
+    fn object(props: Vec<(&str, Prop)>) -> Prop {
+        Prop::object(
+            props
+                .into_iter()
+                .map(|(key, prop)| (key.to_string(), prop))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn classify_prop_changes() {
+        let port = Prop::int(
+            true,
+            None,
+            None,
+            Some(Interval::new(1, 9999).unwrap()),
+            false,
+        )
+        .unwrap();
+        let old = object(vec![
+            ("port", port.clone()),
+            ("host", Prop::string(true, None, None, None, false).unwrap()),
+        ]);
+
+        assert_eq!(
+            Compatibility::between_props(&old, &old),
+            Compatibility::Compatible
+        );
+
+        // Widened interval and relaxed requirement
+        let new = object(vec![
+            (
+                "port",
+                Prop::float(
+                    false,
+                    None,
+                    None,
+                    Some(Interval::new(0, None).unwrap()),
+                    false,
+                )
+                .unwrap(),
+            ),
+            ("host", Prop::string(true, None, None, None, false).unwrap()),
+        ]);
+        assert_eq!(
+            Compatibility::between_props(&old, &new),
+            Compatibility::Compatible
+        );
+
+        // Optional and defaulted props
+        let new = object(vec![
+            ("port", port.clone()),
+            ("host", Prop::string(true, None, None, None, false).unwrap()),
+            ("debug", Prop::bool(false, None).unwrap()),
+            (
+                "env",
+                Prop::string(true, Some(Value::from("dev")), None, None, false).unwrap(),
+            ),
+        ]);
+        assert_eq!(
+            Compatibility::between_props(&old, &new),
+            Compatibility::Additive
+        );
+
+        // Required prop without default
+        let new = object(vec![
+            ("port", port.clone()),
+            ("host", Prop::string(true, None, None, None, false).unwrap()),
+            ("env", Prop::string(true, None, None, None, false).unwrap()),
+        ]);
+        assert_eq!(
+            Compatibility::between_props(&old, &new),
+            Compatibility::Breaking
+        );
+
+        // Removed prop
+        let new = object(vec![("port", port.clone())]);
+        assert_eq!(
+            Compatibility::between_props(&old, &new),
+            Compatibility::Breaking
+        );
+
+        // Retyped prop
+        let new = object(vec![
+            ("port", port.clone()),
+            (
+                "host",
+                Prop::array(Prop::string(true, None, None, None, false).unwrap()),
+            ),
+        ]);
+        assert_eq!(
+            Compatibility::between_props(&old, &new),
+            Compatibility::Breaking
+        );
+
+        // Tightened constraints
+        let new = object(vec![
+            ("port", port),
+            (
+                "host",
+                Prop::string(true, None, None, Some("^[a-z]+$".to_string()), false).unwrap(),
+            ),
+        ]);
+        assert_eq!(
+            Compatibility::between_props(&old, &new),
+            Compatibility::Breaking
+        );
+
+        let old = Prop::array(Prop::int(true, None, None, None, false).unwrap());
+        let new = Prop::array(
+            Prop::int(
+                true,
+                None,
+                Some(vec![Value::from(1), Value::from(2)]),
+                None,
+                false,
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            Compatibility::between_props(&old, &new),
+            Compatibility::Breaking
+        );
+        assert_eq!(
+            Compatibility::between_props(&new, &old),
+            Compatibility::Compatible
+        );
+    }
+
+    #[test]
+    fn classify_rule_changes() {
+        let rules = vec![
+            Rule::new("$.a > 1".to_string(), None, None).unwrap(),
+            Rule::new("$.b > 1".to_string(), None, None).unwrap(),
+        ];
+
+        assert_eq!(
+            Compatibility::between_rules(&rules, &[]),
+            Compatibility::Compatible
+        );
+        assert_eq!(
+            Compatibility::between_rules(&rules, &rules[1..]),
+            Compatibility::Compatible
+        );
+        assert_eq!(
+            Compatibility::between_rules(&rules[..1], &rules),
+            Compatibility::Breaking
+        );
+    }
+}
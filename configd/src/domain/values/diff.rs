@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+use crate::domain::values::{Interval, Kind, Rule, Value};
+
+const MASK: &str = "********";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Reason {
     NullValue,
@@ -16,48 +21,272 @@ pub enum Reason {
     NotAnObject,
     MissingProp,
     UnknownProp,
+    UnsatisfiedRule,
+}
+
+// Value as shown in a violation or a change. Secrets are never exposed.
+pub fn display_value(value: &Value, secret: bool) -> JsonValue {
+    if secret {
+        JsonValue::String(MASK.to_string())
+    } else {
+        value.into()
+    }
+}
+
+fn format_value(value: &JsonValue) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+// A single validation failure with the context needed to fix it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    reason: Reason,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<Kind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    found: Option<Kind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allowed_values: Option<Vec<JsonValue>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    regex: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<JsonValue>,
+}
+
+impl Violation {
+    fn new(reason: Reason, message: String) -> Violation {
+        Violation {
+            reason,
+            message,
+            expected: None,
+            found: None,
+            min: None,
+            max: None,
+            allowed_values: None,
+            regex: None,
+            rule: None,
+            value: None,
+        }
+    }
+
+    pub fn null_value(expected: Kind) -> Violation {
+        Violation {
+            expected: Some(expected),
+            found: Some(Kind::Null),
+            ..Violation::new(
+                Reason::NullValue,
+                format!("value is required: expected {}, found null", expected),
+            )
+        }
+    }
+
+    pub fn not_allowed_value(allowed_values: &[Value], value: &Value, secret: bool) -> Violation {
+        let allowed_values: Vec<JsonValue> = allowed_values
+            .iter()
+            .map(|value| display_value(value, secret))
+            .collect();
+        let value = display_value(value, secret);
+
+        let message = format!(
+            "value {} is not one of the allowed values: {}",
+            format_value(&value),
+            allowed_values
+                .iter()
+                .map(format_value)
+                .collect::<Vec<String>>()
+                .join(", "),
+        );
+
+        Violation {
+            allowed_values: Some(allowed_values),
+            value: Some(value),
+            ..Violation::new(Reason::NotAllowedValue, message)
+        }
+    }
+
+    pub fn not_in_interval(interval: &Interval, value: &Value) -> Violation {
+        let bounds = match (interval.min(), interval.max()) {
+            (Some(min), Some(max)) => format!("between {} and {}", min, max),
+            (Some(min), None) => format!("greater than or equal to {}", min),
+            (None, Some(max)) => format!("less than or equal to {}", max),
+            (None, None) => "in interval".to_string(),
+        };
+        let value: JsonValue = value.into();
+        let message = format!("value {} must be {}", format_value(&value), bounds);
+
+        Violation {
+            min: interval.min(),
+            max: interval.max(),
+            value: Some(value),
+            ..Violation::new(Reason::NotInInterval, message)
+        }
+    }
+
+    pub fn unmatched_regex(regex: &str, value: &Value, secret: bool) -> Violation {
+        let value = display_value(value, secret);
+        let message = format!(
+            "value {} does not match regex {}",
+            format_value(&value),
+            regex
+        );
+
+        Violation {
+            regex: Some(regex.to_string()),
+            value: Some(value),
+            ..Violation::new(Reason::UnmatchedRegex, message)
+        }
+    }
+
+    pub fn mismatched_kind(expected: Kind, value: &Value, secret: bool) -> Violation {
+        let reason = match expected {
+            Kind::Bool => Reason::NotABool,
+            Kind::Int => Reason::NotAnInt,
+            Kind::Float => Reason::NotAFloat,
+            Kind::Array => Reason::NotAnArray,
+            Kind::Object => Reason::NotAnObject,
+            _ => Reason::NotAString,
+        };
+
+        Violation {
+            expected: Some(expected),
+            found: Some(value.kind()),
+            value: Some(display_value(value, secret)),
+            ..Violation::new(
+                reason,
+                format!("expected {}, found {}", expected, value.kind()),
+            )
+        }
+    }
+
+    pub fn missing_prop() -> Violation {
+        Violation::new(Reason::MissingProp, "property is missing".to_string())
+    }
+
+    pub fn unknown_prop() -> Violation {
+        Violation::new(
+            Reason::UnknownProp,
+            "property is not defined in the schema".to_string(),
+        )
+    }
+
+    pub fn unsatisfied_rule(rule: &Rule) -> Violation {
+        Violation {
+            rule: Some(rule.source().to_string()),
+            ..Violation::new(
+                Reason::UnsatisfiedRule,
+                rule.message()
+                    .map(ToString::to_string)
+                    .unwrap_or_else(|| format!("rule is not satisfied: {}", rule.source())),
+            )
+        }
+    }
+
+    pub fn reason(&self) -> Reason {
+        self.reason
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn expected(&self) -> Option<Kind> {
+        self.expected
+    }
+
+    pub fn found(&self) -> Option<Kind> {
+        self.found
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.max
+    }
+
+    pub fn allowed_values(&self) -> Option<&[JsonValue]> {
+        self.allowed_values.as_deref()
+    }
+
+    pub fn regex(&self) -> Option<&str> {
+        self.regex.as_deref()
+    }
+
+    pub fn rule(&self) -> Option<&str> {
+        self.rule.as_deref()
+    }
+
+    pub fn value(&self) -> Option<&JsonValue> {
+        self.value.as_ref()
+    }
 }
 
+// Violations by path. Keys are ordered so responses are stable.
 #[derive(Debug, Clone)]
 pub struct Diff {
     root_key: String,
-    diffs: HashMap<String, Vec<Reason>>,
+    diffs: BTreeMap<String, Vec<Violation>>,
 }
 
 impl Diff {
     pub fn new(root_key: String) -> Diff {
         Diff {
             root_key,
-            diffs: HashMap::new(),
+            diffs: BTreeMap::new(),
         }
     }
 
-    pub fn diffs(&self) -> &HashMap<String, Vec<Reason>> {
+    pub fn diffs(&self) -> &BTreeMap<String, Vec<Violation>> {
         &self.diffs
     }
 
+    pub fn reasons(&self) -> BTreeMap<String, Vec<Reason>> {
+        self.diffs
+            .iter()
+            .map(|(key, violations)| {
+                (
+                    key.to_string(),
+                    violations.iter().map(Violation::reason).collect(),
+                )
+            })
+            .collect()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.diffs.is_empty()
     }
 
-    pub fn add(&mut self, reason: Reason, key: Option<String>) {
+    pub fn add(&mut self, violation: Violation, key: Option<String>) {
         let key = if let Some(key) = key {
             format!("{}.{}", self.root_key, key)
         } else {
             self.root_key.to_string()
         };
 
-        if let Some(diff) = self.diffs.get_mut(&key) {
-            diff.push(reason);
-        } else {
-            self.diffs.insert(key, vec![reason]);
+        self.diffs.entry(key).or_default().push(violation);
+    }
+
+    // Appends the violations of a diff sharing the same root key
+    pub fn extend(&mut self, diff: Diff) {
+        for (key, violations) in diff.diffs.into_iter() {
+            self.diffs.entry(key).or_default().extend(violations);
         }
     }
 
     pub fn merge(&mut self, diff: Diff) {
-        for (key, value) in diff.diffs.into_iter() {
+        for (key, violations) in diff.diffs.into_iter() {
             self.diffs
-                .insert(format!("{}.{}", self.root_key, key), value);
+                .entry(format!("{}.{}", self.root_key, key))
+                .or_default()
+                .extend(violations);
         }
     }
 }
@@ -69,17 +298,26 @@ mod tests {
     #[test]
     fn root_and_sub_affixes() {
         let mut diff = Diff::new("$".to_string());
-        diff.add(Reason::NotAString, Some("env".to_string()));
-        diff.add(Reason::NotInInterval, Some("port".to_string()));
+        diff.add(
+            Violation::mismatched_kind(Kind::String, &Value::Int(1), false),
+            Some("env".to_string()),
+        );
+        diff.add(
+            Violation::not_in_interval(&Interval::new(1, 5).unwrap(), &Value::Int(8)),
+            Some("port".to_string()),
+        );
 
         let mut subdiff = Diff::new("database_urls".to_string());
-        subdiff.add(Reason::UnmatchedRegex, Some("host".to_string()));
+        subdiff.add(
+            Violation::unmatched_regex("^http", &Value::String("ftp".to_string()), false),
+            Some("host".to_string()),
+        );
 
         diff.merge(subdiff);
 
         assert_eq!(diff.root_key, "$");
 
-        let diffs = diff.diffs();
+        let diffs = diff.reasons();
         assert_eq!(diffs.len(), 3);
         assert_eq!(diffs.get("$.env").unwrap(), &vec![Reason::NotAString]);
         assert_eq!(diffs.get("$.port").unwrap(), &vec![Reason::NotInInterval]);
@@ -87,5 +325,50 @@ mod tests {
             diffs.get("$.database_urls.host").unwrap(),
             &vec![Reason::UnmatchedRegex]
         );
+
+        // Ordered keys
+        let keys: Vec<&String> = diff.diffs().keys().collect();
+        assert_eq!(keys, vec!["$.database_urls.host", "$.env", "$.port"]);
+    }
+
+    #[test]
+    fn violation_context() {
+        let violation = Violation::not_in_interval(&Interval::new(1, 5).unwrap(), &Value::Int(8));
+        assert_eq!(violation.message(), "value 8 must be between 1 and 5");
+        assert_eq!(violation.min(), Some(1.0));
+        assert_eq!(violation.max(), Some(5.0));
+        assert_eq!(violation.value(), Some(&JsonValue::from(8)));
+
+        let violation = Violation::mismatched_kind(Kind::Int, &Value::Bool(true), false);
+        assert_eq!(violation.reason(), Reason::NotAnInt);
+        assert_eq!(violation.message(), "expected int, found bool");
+        assert_eq!(violation.expected(), Some(Kind::Int));
+        assert_eq!(violation.found(), Some(Kind::Bool));
+
+        let violation = Violation::not_allowed_value(
+            &[Value::from("dev"), Value::from("prod")],
+            &Value::from("local"),
+            false,
+        );
+        assert_eq!(
+            violation.message(),
+            r#"value "local" is not one of the allowed values: "dev", "prod""#
+        );
+
+        // Secrets are masked
+        let violation = Violation::unmatched_regex("^[a-z]{12}$", &Value::from("hunter2"), true);
+        assert_eq!(violation.value(), Some(&JsonValue::from(MASK)));
+        assert!(!violation.message().contains("hunter2"));
+
+        let violation =
+            Violation::not_allowed_value(&[Value::from("s3cr3t")], &Value::from("hunter2"), true);
+        assert!(!violation.message().contains("hunter2"));
+        assert!(!violation.message().contains("s3cr3t"));
+
+        let json = serde_json::to_value(Violation::missing_prop()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "reason": "missing_prop", "message": "property is missing" })
+        );
     }
 }
@@ -0,0 +1,742 @@
+use std::cmp::Ordering;
+
+use crate::domain::{
+    errors::Error,
+    values::{Path, PathSegment, Value},
+};
+
+// Limits to keep rule evaluation bounded. Expressions have no loops nor side
+// effects, so evaluation cost is proportional to the size of the tree.
+const MAX_LENGTH: usize = 1024;
+const MAX_DEPTH: usize = 32;
+
+// Token
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Ident(String),
+    Dollar,
+    Dot,
+    Comma,
+    LParen,
+    RParen,
+    Not,
+    And,
+    Or,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let next = chars.get(i + 1).copied();
+
+        let (token, len) = match (c, next) {
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('=', Some('=')) => (Token::Eq, 2),
+            ('!', Some('=')) => (Token::NotEq, 2),
+            ('<', Some('=')) => (Token::LtEq, 2),
+            ('>', Some('=')) => (Token::GtEq, 2),
+            ('!', _) => (Token::Not, 1),
+            ('<', _) => (Token::Lt, 1),
+            ('>', _) => (Token::Gt, 1),
+            ('+', _) => (Token::Plus, 1),
+            ('-', _) => (Token::Minus, 1),
+            ('*', _) => (Token::Star, 1),
+            ('/', _) => (Token::Slash, 1),
+            ('%', _) => (Token::Percent, 1),
+            ('$', _) => (Token::Dollar, 1),
+            ('.', _) => (Token::Dot, 1),
+            (',', _) => (Token::Comma, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            ('"', _) | ('\'', _) => {
+                let mut value = String::new();
+                let mut j = i + 1;
+
+                loop {
+                    match chars.get(j) {
+                        Some('\\') => {
+                            if let Some(escaped) = chars.get(j + 1) {
+                                value.push(*escaped);
+                                j += 2;
+                            } else {
+                                return Err("unterminated string".to_string());
+                            }
+                        }
+                        Some(ch) if *ch == c => break,
+                        Some(ch) => {
+                            value.push(*ch);
+                            j += 1;
+                        }
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+
+                (Token::String(value), j + 1 - i)
+            }
+            (c, _) if c.is_ascii_digit() => {
+                // Path segments like `items.0.name` are always integers
+                let after_dot = tokens.last() == Some(&Token::Dot);
+
+                let mut j = i;
+                while j < chars.len() && chars[j].is_ascii_digit() {
+                    j += 1;
+                }
+
+                let is_float = !after_dot
+                    && chars.get(j) == Some(&'.')
+                    && chars.get(j + 1).is_some_and(char::is_ascii_digit);
+
+                if is_float {
+                    j += 1;
+                    while j < chars.len() && chars[j].is_ascii_digit() {
+                        j += 1;
+                    }
+
+                    let literal: String = chars[i..j].iter().collect();
+                    let value = literal
+                        .parse()
+                        .map_err(|_| format!("invalid number: {}", literal))?;
+
+                    (Token::Float(value), j - i)
+                } else {
+                    let literal: String = chars[i..j].iter().collect();
+                    let value = literal
+                        .parse()
+                        .map_err(|_| format!("invalid number: {}", literal))?;
+
+                    (Token::Int(value), j - i)
+                }
+            }
+            (c, _) if c.is_alphabetic() || c == '_' => {
+                let mut j = i;
+                while j < chars.len() && (chars[j].is_alphanumeric() || chars[j] == '_') {
+                    j += 1;
+                }
+
+                let ident: String = chars[i..j].iter().collect();
+                let token = match ident.as_str() {
+                    "null" => Token::Null,
+                    "true" => Token::Bool(true),
+                    "false" => Token::Bool(false),
+                    _ => Token::Ident(ident),
+                };
+
+                (token, j - i)
+            }
+            (c, _) => return Err(format!("unexpected character: {}", c)),
+        };
+
+        tokens.push(token);
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+// Operators and functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Not,
+    Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    And,
+    Or,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Len,
+    Exists,
+    Matches,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        match name {
+            "len" => Some(Function::Len),
+            "exists" => Some(Function::Exists),
+            "matches" => Some(Function::Matches),
+            _ => None,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Function::Len | Function::Exists => 1,
+            Function::Matches => 2,
+        }
+    }
+}
+
+// Expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Literal(Value),
+    Path(Path),
+    Unary(UnaryOp, Box<Expression>),
+    Binary(BinaryOp, Box<Expression>, Box<Expression>),
+    Call(Function, Vec<Expression>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        match self.next() {
+            Some(t) if t == token => Ok(()),
+            Some(t) => Err(format!("expected {:?}, found {:?}", token, t)),
+            None => Err(format!("expected {:?}, found end of expression", token)),
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("expression is too deeply nested".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn parse_binary<F>(
+        &mut self,
+        ops: &[(Token, BinaryOp)],
+        mut operand: F,
+    ) -> Result<Expression, String>
+    where
+        F: FnMut(&mut Parser) -> Result<Expression, String>,
+    {
+        let mut left = operand(self)?;
+
+        while let Some(op) = self
+            .peek()
+            .and_then(|token| ops.iter().find(|(t, _)| t == token))
+            .map(|(_, op)| *op)
+        {
+            self.pos += 1;
+            let right = operand(self)?;
+            left = Expression::Binary(op, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, String> {
+        self.enter()?;
+        let expr = self.parse_binary(&[(Token::Or, BinaryOp::Or)], Parser::parse_and);
+        self.leave();
+        expr
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, String> {
+        self.parse_binary(&[(Token::And, BinaryOp::And)], Parser::parse_comparison)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expression, String> {
+        self.parse_binary(
+            &[
+                (Token::Eq, BinaryOp::Eq),
+                (Token::NotEq, BinaryOp::NotEq),
+                (Token::Lt, BinaryOp::Lt),
+                (Token::LtEq, BinaryOp::LtEq),
+                (Token::Gt, BinaryOp::Gt),
+                (Token::GtEq, BinaryOp::GtEq),
+            ],
+            Parser::parse_additive,
+        )
+    }
+
+    fn parse_additive(&mut self) -> Result<Expression, String> {
+        self.parse_binary(
+            &[(Token::Plus, BinaryOp::Add), (Token::Minus, BinaryOp::Sub)],
+            Parser::parse_multiplicative,
+        )
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expression, String> {
+        self.parse_binary(
+            &[
+                (Token::Star, BinaryOp::Mul),
+                (Token::Slash, BinaryOp::Div),
+                (Token::Percent, BinaryOp::Rem),
+            ],
+            Parser::parse_unary,
+        )
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, String> {
+        let op = match self.peek() {
+            Some(Token::Not) => UnaryOp::Not,
+            Some(Token::Minus) => UnaryOp::Neg,
+            _ => return self.parse_primary(),
+        };
+        self.pos += 1;
+
+        self.enter()?;
+        let expr = self.parse_unary();
+        self.leave();
+
+        Ok(Expression::Unary(op, Box::new(expr?)))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, String> {
+        match self.next() {
+            Some(Token::Null) => Ok(Expression::Literal(Value::Null)),
+            Some(Token::Bool(value)) => Ok(Expression::Literal(Value::Bool(value))),
+            Some(Token::Int(value)) => Ok(Expression::Literal(Value::Int(value))),
+            Some(Token::Float(value)) => Ok(Expression::Literal(Value::Float(value))),
+            Some(Token::String(value)) => Ok(Expression::Literal(Value::String(value))),
+            Some(Token::LParen) => {
+                let expr = self.parse_expression()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Dollar) => self.parse_path(Vec::new()),
+            Some(Token::Ident(ident)) => {
+                if self.peek() == Some(&Token::LParen) {
+                    self.pos += 1;
+
+                    let function = Function::from_name(&ident)
+                        .ok_or_else(|| format!("unknown function: {}", ident))?;
+
+                    let mut args = Vec::new();
+                    if self.peek() != Some(&Token::RParen) {
+                        loop {
+                            args.push(self.parse_expression()?);
+                            if self.peek() == Some(&Token::Comma) {
+                                self.pos += 1;
+                            } else {
+                                break;
+                            }
+                        }
+                    }
+                    self.expect(Token::RParen)?;
+
+                    if args.len() != function.arity() {
+                        return Err(format!(
+                            "{} expects {} argument(s), found {}",
+                            ident,
+                            function.arity(),
+                            args.len()
+                        ));
+                    }
+
+                    if function == Function::Exists
+                        && !matches!(args.first(), Some(Expression::Path(_)))
+                    {
+                        return Err("exists expects a path".to_string());
+                    }
+
+                    Ok(Expression::Call(function, args))
+                } else {
+                    self.parse_path(vec![PathSegment::Key(ident)])
+                }
+            }
+            Some(token) => Err(format!("unexpected token: {:?}", token)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+
+    fn parse_path(&mut self, mut segments: Vec<PathSegment>) -> Result<Expression, String> {
+        while self.peek() == Some(&Token::Dot) {
+            self.pos += 1;
+
+            let segment = match self.next() {
+                Some(Token::Ident(key)) => PathSegment::Key(key),
+                Some(Token::Int(index)) if index >= 0 => PathSegment::Index(index as usize),
+                Some(Token::Null) => PathSegment::Key("null".to_string()),
+                Some(Token::Bool(value)) => PathSegment::Key(value.to_string()),
+                Some(token) => return Err(format!("invalid path segment: {:?}", token)),
+                None => return Err("unexpected end of path".to_string()),
+            };
+
+            segments.push(segment);
+        }
+
+        Ok(Expression::Path(Path::new(segments)))
+    }
+}
+
+impl Expression {
+    pub fn parse(input: &str) -> Result<Expression, Error> {
+        if input.trim().is_empty() {
+            return Err(Error::InvalidRule("empty expression".to_string()));
+        }
+
+        if input.len() > MAX_LENGTH {
+            return Err(Error::InvalidRule(format!(
+                "expression is longer than {} characters",
+                MAX_LENGTH
+            )));
+        }
+
+        let tokens = tokenize(input).map_err(Error::InvalidRule)?;
+
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+
+        let expr = parser.parse_expression().map_err(Error::InvalidRule)?;
+
+        if let Some(token) = parser.peek() {
+            return Err(Error::InvalidRule(format!("unexpected token: {:?}", token)));
+        }
+
+        Ok(expr)
+    }
+
+    pub fn paths(&self) -> Vec<&Path> {
+        let mut paths = Vec::new();
+        self.collect_paths(&mut paths);
+        paths
+    }
+
+    fn collect_paths<'a>(&'a self, paths: &mut Vec<&'a Path>) {
+        match self {
+            Expression::Literal(_) => {}
+            Expression::Path(path) => {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+            Expression::Unary(_, expr) => expr.collect_paths(paths),
+            Expression::Binary(_, left, right) => {
+                left.collect_paths(paths);
+                right.collect_paths(paths);
+            }
+            Expression::Call(_, args) => {
+                for arg in args.iter() {
+                    arg.collect_paths(paths);
+                }
+            }
+        }
+    }
+
+    pub fn evaluate(&self, value: &Value) -> Result<Value, String> {
+        match self {
+            Expression::Literal(literal) => Ok(literal.clone()),
+            Expression::Path(path) => Ok(path.resolve(value).cloned().unwrap_or(Value::Null)),
+            Expression::Unary(op, expr) => {
+                let operand = expr.evaluate(value)?;
+
+                match (op, operand) {
+                    (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+                    (UnaryOp::Neg, Value::Int(n)) => n
+                        .checked_neg()
+                        .map(Value::Int)
+                        .ok_or_else(|| "integer overflow".to_string()),
+                    (UnaryOp::Neg, Value::Float(n)) => Ok(Value::Float(-n)),
+                    (op, operand) => Err(format!("cannot apply {:?} to {}", op, operand.kind())),
+                }
+            }
+            Expression::Binary(BinaryOp::And, left, right) => {
+                if !to_bool(left.evaluate(value)?)? {
+                    return Ok(Value::Bool(false));
+                }
+
+                Ok(Value::Bool(to_bool(right.evaluate(value)?)?))
+            }
+            Expression::Binary(BinaryOp::Or, left, right) => {
+                if to_bool(left.evaluate(value)?)? {
+                    return Ok(Value::Bool(true));
+                }
+
+                Ok(Value::Bool(to_bool(right.evaluate(value)?)?))
+            }
+            Expression::Binary(op, left, right) => {
+                let left = left.evaluate(value)?;
+                let right = right.evaluate(value)?;
+
+                match op {
+                    BinaryOp::Eq => Ok(Value::Bool(equals(&left, &right))),
+                    BinaryOp::NotEq => Ok(Value::Bool(!equals(&left, &right))),
+                    BinaryOp::Lt | BinaryOp::LtEq | BinaryOp::Gt | BinaryOp::GtEq => {
+                        // Ordering against null is always false, so rules over
+                        // optional fields do not fail with type errors.
+                        let ordering = match compare(&left, &right)? {
+                            Some(ordering) => ordering,
+                            None => return Ok(Value::Bool(false)),
+                        };
+
+                        Ok(Value::Bool(match op {
+                            BinaryOp::Lt => ordering == Ordering::Less,
+                            BinaryOp::LtEq => ordering != Ordering::Greater,
+                            BinaryOp::Gt => ordering == Ordering::Greater,
+                            _ => ordering != Ordering::Less,
+                        }))
+                    }
+                    _ => arithmetic(*op, left, right),
+                }
+            }
+            Expression::Call(function, args) => match function {
+                Function::Len => match args[0].evaluate(value)? {
+                    Value::String(s) => Ok(Value::Int(s.chars().count() as i64)),
+                    Value::Array(items) => Ok(Value::Int(items.len() as i64)),
+                    Value::Object(object) => Ok(Value::Int(object.len() as i64)),
+                    Value::Null => Ok(Value::Int(0)),
+                    other => Err(format!("cannot get length of {}", other.kind())),
+                },
+                Function::Exists => Ok(Value::Bool(!args[0].evaluate(value)?.is_null())),
+                Function::Matches => match (args[0].evaluate(value)?, args[1].evaluate(value)?) {
+                    (Value::Null, _) => Ok(Value::Bool(false)),
+                    (Value::String(s), Value::String(pattern)) => {
+                        let regex = regex::Regex::new(&pattern)
+                            .map_err(|_| format!("invalid regex: {}", pattern))?;
+                        Ok(Value::Bool(regex.is_match(&s)))
+                    }
+                    (s, pattern) => Err(format!(
+                        "cannot match {} against {}",
+                        s.kind(),
+                        pattern.kind()
+                    )),
+                },
+            },
+        }
+    }
+}
+
+fn to_bool(value: Value) -> Result<bool, String> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        other => Err(format!("expected bool, found {}", other.kind())),
+    }
+}
+
+fn equals(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Int(a), Value::Float(b)) | (Value::Float(b), Value::Int(a)) => *a as f64 == *b,
+        _ => left == right,
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> Result<Option<Ordering>, String> {
+    match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => Ok(None),
+        (Value::Int(a), Value::Int(b)) => Ok(Some(a.cmp(b))),
+        (Value::Int(a), Value::Float(b)) => Ok((*a as f64).partial_cmp(b)),
+        (Value::Float(a), Value::Int(b)) => Ok(a.partial_cmp(&(*b as f64))),
+        (Value::Float(a), Value::Float(b)) => Ok(a.partial_cmp(b)),
+        (Value::String(a), Value::String(b)) => Ok(Some(a.cmp(b))),
+        _ => Err(format!(
+            "cannot compare {} with {}",
+            left.kind(),
+            right.kind()
+        )),
+    }
+}
+
+fn arithmetic(op: BinaryOp, left: Value, right: Value) -> Result<Value, String> {
+    match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (Value::String(a), Value::String(b)) if op == BinaryOp::Add => {
+            Ok(Value::String(format!("{}{}", a, b)))
+        }
+        (Value::Int(a), Value::Int(b)) => {
+            let result = match op {
+                BinaryOp::Add => a.checked_add(b),
+                BinaryOp::Sub => a.checked_sub(b),
+                BinaryOp::Mul => a.checked_mul(b),
+                BinaryOp::Div => a.checked_div(b),
+                BinaryOp::Rem => a.checked_rem(b),
+                _ => None,
+            };
+
+            result
+                .map(Value::Int)
+                .ok_or_else(|| "invalid integer operation".to_string())
+        }
+        (left, right) => {
+            let (a, b) = match (&left, &right) {
+                (Value::Int(a), Value::Float(b)) => (*a as f64, *b),
+                (Value::Float(a), Value::Int(b)) => (*a, *b as f64),
+                (Value::Float(a), Value::Float(b)) => (*a, *b),
+                _ => {
+                    return Err(format!(
+                        "cannot apply {:?} to {} and {}",
+                        op,
+                        left.kind(),
+                        right.kind()
+                    ))
+                }
+            };
+
+            Ok(Value::Float(match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div => a / b,
+                _ => a % b,
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    fn data() -> Value {
+        Value::Object(BTreeMap::from([
+            ("min_connections".to_string(), Value::Int(2)),
+            ("max_connections".to_string(), Value::Int(10)),
+            ("ratio".to_string(), Value::Float(0.5)),
+            (
+                "tls".to_string(),
+                Value::Object(BTreeMap::from([
+                    ("enabled".to_string(), Value::Bool(true)),
+                    ("cert_path".to_string(), Value::Null),
+                ])),
+            ),
+            (
+                "hosts".to_string(),
+                Value::Array(vec![
+                    Value::String("a.local".to_string()),
+                    Value::String("b.local".to_string()),
+                ]),
+            ),
+        ]))
+    }
+
+    fn eval(input: &str) -> Result<Value, String> {
+        Expression::parse(input).unwrap().evaluate(&data())
+    }
+
+    #[test]
+    fn parse() {
+        assert!(Expression::parse("max_connections >= min_connections").is_ok());
+        assert!(Expression::parse("$.tls.enabled && exists($.tls.cert_path)").is_ok());
+        assert!(Expression::parse("hosts.0 == 'a.local'").is_ok());
+        assert!(Expression::parse("len(hosts) > 0 && !(ratio < 0.1)").is_ok());
+
+        assert!(Expression::parse("").is_err());
+        assert!(Expression::parse("a >=").is_err());
+        assert!(Expression::parse("(a == b").is_err());
+        assert!(Expression::parse("a == b)").is_err());
+        assert!(Expression::parse("unknown(a)").is_err());
+        assert!(Expression::parse("len(a, b)").is_err());
+        assert!(Expression::parse("exists(1)").is_err());
+        assert!(Expression::parse("'unterminated").is_err());
+        assert!(Expression::parse(&"(".repeat(64)).is_err());
+        assert!(Expression::parse(&"a".repeat(MAX_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn paths() {
+        let expr =
+            Expression::parse("!tls.enabled || exists(tls.cert_path) || tls.enabled == false")
+                .unwrap();
+
+        let paths: Vec<String> = expr.paths().iter().map(ToString::to_string).collect();
+        assert_eq!(paths, vec!["$.tls.enabled", "$.tls.cert_path"]);
+
+        let expr = Expression::parse("$.hosts.1 != null").unwrap();
+        let path = expr.paths()[0];
+        assert_eq!(path.to_string(), "$.hosts.1");
+        assert_eq!(
+            path.resolve(&data()),
+            Some(&Value::String("b.local".to_string()))
+        );
+    }
+
+    #[test]
+    fn evaluate() {
+        assert_eq!(
+            eval("max_connections >= min_connections"),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(
+            eval("max_connections < min_connections"),
+            Ok(Value::Bool(false))
+        );
+        assert_eq!(
+            eval("!tls.enabled || exists(tls.cert_path)"),
+            Ok(Value::Bool(false))
+        );
+        assert_eq!(eval("tls.cert_path == null"), Ok(Value::Bool(true)));
+        assert_eq!(eval("missing.path > 3"), Ok(Value::Bool(false)));
+        assert_eq!(eval("len(hosts) == 2"), Ok(Value::Bool(true)));
+        assert_eq!(eval("hosts.1 == \"b.local\""), Ok(Value::Bool(true)));
+        assert_eq!(
+            eval("matches(hosts.0, '^[a-z]+\\.local$')"),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(eval("max_connections * ratio"), Ok(Value::Float(5.0)));
+        assert_eq!(
+            eval("max_connections - min_connections * 2"),
+            Ok(Value::Int(6))
+        );
+        assert_eq!(eval("-min_connections + 1.5"), Ok(Value::Float(-0.5)));
+        assert_eq!(eval("max_connections == 10.0"), Ok(Value::Bool(true)));
+
+        // Short-circuit avoids evaluating invalid operands
+        assert_eq!(eval("false && hosts > 1"), Ok(Value::Bool(false)));
+
+        // Type errors
+        assert!(eval("hosts > 1").is_err());
+        assert!(eval("tls && true").is_err());
+        assert!(eval("max_connections / 0").is_err());
+    }
+}
@@ -0,0 +1,507 @@
+use serde_json::Value as JsonValue;
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use crate::domain::{errors::Error, values::Value};
+
+// Formats for config data. JSON, YAML and TOML can also be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+    // Flattened `DATABASE__HOST=...` keys
+    Dotenv,
+    // Java properties with `database.host=...` keys
+    Properties,
+}
+
+impl Format {
+    // Picks the first known media type of an Accept header. Quality values
+    // are not taken into account.
+    pub fn from_accept(accept: &str) -> Option<Format> {
+        accept
+            .split(',')
+            .filter_map(|media_type| media_type.split(';').next())
+            .find_map(|media_type| Format::from_media_type(media_type.trim()))
+    }
+
+    pub fn from_media_type(media_type: &str) -> Option<Format> {
+        match media_type.to_lowercase().as_str() {
+            "application/json" => Some(Format::Json),
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Some(Format::Yaml)
+            }
+            "application/toml" | "text/toml" => Some(Format::Toml),
+            "text/x-dotenv" | "application/x-dotenv" => Some(Format::Dotenv),
+            "text/x-java-properties" | "text/x-properties" => Some(Format::Properties),
+            // Structured syntax suffix, as in application/merge-patch+json
+            media_type
+                if media_type.starts_with("application/") && media_type.ends_with("+json") =>
+            {
+                Some(Format::Json)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Yaml => "application/yaml",
+            Format::Toml => "application/toml",
+            Format::Dotenv => "text/x-dotenv; charset=utf-8",
+            Format::Properties => "text/x-java-properties; charset=utf-8",
+        }
+    }
+
+    pub fn render(&self, value: &Value) -> Result<String, Error> {
+        match self {
+            Format::Json => {
+                serde_json::to_string_pretty(&JsonValue::from(value)).map_err(Error::Serde)
+            }
+            Format::Yaml => serde_yaml::to_string(&JsonValue::from(value))
+                .map_err(|err| self.unrepresentable(err.to_string())),
+            Format::Toml => {
+                let table = match to_toml(value, "$").map_err(|err| self.unrepresentable(err))? {
+                    toml::Value::Table(table) => table,
+                    _ => return Err(self.unrepresentable("root value must be an object")),
+                };
+
+                toml::to_string(&table).map_err(|err| self.unrepresentable(err.to_string()))
+            }
+            Format::Dotenv => {
+                let entries = flatten(value, |prefix, key| {
+                    let key = key
+                        .chars()
+                        .map(|c| {
+                            if c.is_ascii_alphanumeric() {
+                                c.to_ascii_uppercase()
+                            } else {
+                                '_'
+                            }
+                        })
+                        .collect::<String>();
+
+                    match prefix {
+                        Some(prefix) => format!("{}__{}", prefix, key),
+                        None => key,
+                    }
+                })
+                .map_err(|err| self.unrepresentable(err))?;
+
+                Ok(entries
+                    .into_iter()
+                    .map(|(key, value)| format!("{}={}\n", key, dotenv_value(&value)))
+                    .collect())
+            }
+            Format::Properties => {
+                let entries = flatten(value, |prefix, key| match prefix {
+                    Some(prefix) => format!("{}.{}", prefix, key),
+                    None => key.to_string(),
+                })
+                .map_err(|err| self.unrepresentable(err))?;
+
+                Ok(entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        format!(
+                            "{}={}\n",
+                            properties_escape(&key, true),
+                            properties_escape(&scalar_to_string(&value), false)
+                        )
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    // Parse errors report the line and column where they happened
+    pub fn parse(&self, input: &str) -> Result<JsonValue, Error> {
+        match self {
+            Format::Json => serde_json::from_str(input).map_err(|err| {
+                self.invalid_body(
+                    Some((err.line(), err.column())),
+                    strip_location(&err.to_string()),
+                )
+            }),
+            Format::Yaml => serde_yaml::from_str(input).map_err(|err| {
+                self.invalid_body(
+                    err.location()
+                        .map(|location| (location.line(), location.column())),
+                    strip_location(&err.to_string()),
+                )
+            }),
+            Format::Toml => {
+                let table: toml::Table = toml::from_str(input).map_err(|err| {
+                    self.invalid_body(
+                        err.span().map(|span| line_and_column(input, span.start)),
+                        err.message().to_string(),
+                    )
+                })?;
+
+                serde_json::to_value(table).map_err(Error::Serde)
+            }
+            Format::Dotenv | Format::Properties => {
+                Err(Error::UnsupportedMediaType(self.media_type().to_string()))
+            }
+        }
+    }
+
+    fn invalid_body(&self, location: Option<(usize, usize)>, reason: String) -> Error {
+        Error::InvalidBody(match location {
+            Some((line, column)) => {
+                format!("{} at line {}, column {}: {}", self, line, column, reason)
+            }
+            None => format!("{}: {}", self, reason),
+        })
+    }
+
+    fn unrepresentable<S: Into<String>>(&self, reason: S) -> Error {
+        Error::UnrepresentableValue {
+            format: self.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            "dotenv" | "env" => Ok(Format::Dotenv),
+            "properties" => Ok(Format::Properties),
+            _ => Err(Error::UnsupportedFormat(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Format::Json => "json",
+                Format::Yaml => "yaml",
+                Format::Toml => "toml",
+                Format::Dotenv => "dotenv",
+                Format::Properties => "properties",
+            }
+        )
+    }
+}
+
+// 1-based, as reported by serde_json and serde_yaml
+fn line_and_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset.min(input.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+
+    (line, column)
+}
+
+// Location is reported separately
+fn strip_location(message: &str) -> String {
+    match message.rfind(" at line ") {
+        Some(index) => message[..index].to_string(),
+        None => message.to_string(),
+    }
+}
+
+fn to_toml(value: &Value, path: &str) -> Result<toml::Value, String> {
+    Ok(match value {
+        Value::Null => return Err(format!("null at {} is not supported", path)),
+        Value::Bool(b) => toml::Value::Boolean(*b),
+        Value::Int(n) => toml::Value::Integer(*n),
+        Value::Float(n) => toml::Value::Float(*n),
+        Value::String(s) => toml::Value::String(s.to_string()),
+        Value::Array(items) => toml::Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| to_toml(item, &format!("{}.{}", path, i)))
+                .collect::<Result<Vec<toml::Value>, String>>()?,
+        ),
+        Value::Object(object) => toml::Value::Table(
+            object
+                .iter()
+                .map(|(key, item)| {
+                    Ok((
+                        key.to_string(),
+                        to_toml(item, &format!("{}.{}", path, key))?,
+                    ))
+                })
+                .collect::<Result<toml::Table, String>>()?,
+        ),
+    })
+}
+
+// Flattens nested objects and arrays into scalar entries. Keys that end up
+// being equal after formatting are reported as errors with both paths, never
+// overwritten.
+fn flatten<F>(value: &Value, format_key: F) -> Result<BTreeMap<String, Value>, String>
+where
+    F: Fn(Option<&str>, &str) -> String,
+{
+    fn walk<F>(
+        value: &Value,
+        prefix: Option<&str>,
+        path: &str,
+        format_key: &F,
+        entries: &mut BTreeMap<String, (String, Value)>,
+    ) -> Result<(), String>
+    where
+        F: Fn(Option<&str>, &str) -> String,
+    {
+        let children: Vec<(String, &Value)> = match value {
+            Value::Object(object) => object.iter().map(|(k, v)| (k.to_string(), v)).collect(),
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), v))
+                .collect(),
+            _ => {
+                let key = prefix.ok_or_else(|| "root value must be an object".to_string())?;
+
+                if let Some((other_path, _)) = entries.get(key) {
+                    return Err(format!(
+                        "{} and {} are both rendered as {}",
+                        other_path, path, key
+                    ));
+                }
+                entries.insert(key.to_string(), (path.to_string(), value.clone()));
+
+                return Ok(());
+            }
+        };
+
+        for (key, child) in children.into_iter() {
+            let child_key = format_key(prefix, &key);
+            walk(
+                child,
+                Some(&child_key),
+                &format!("{}.{}", path, key),
+                format_key,
+                entries,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    let mut entries = BTreeMap::new();
+    walk(value, None, "$", &format_key, &mut entries)?;
+
+    Ok(entries
+        .into_iter()
+        .map(|(key, (_, value))| (key, value))
+        .collect())
+}
+
+fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(b) => b.to_string(),
+        Value::Int(n) => n.to_string(),
+        Value::Float(n) => n.to_string(),
+        Value::String(s) => s.to_string(),
+        // Only scalars are flattened
+        _ => String::new(),
+    }
+}
+
+// Values with special characters are double quoted
+fn dotenv_value(value: &Value) -> String {
+    let value = scalar_to_string(value);
+
+    if value.is_empty()
+        || value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.,:/@+".contains(c))
+    {
+        return value;
+    }
+
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '$' => quoted.push_str("\\$"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+fn properties_escape(s: &str, key: bool) -> String {
+    let mut escaped = String::new();
+
+    for (i, c) in s.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ' ' if key || i == 0 => escaped.push_str("\\ "),
+            '=' | ':' | '#' | '!' if key => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if !c.is_ascii() => {
+                let mut buf = [0; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    escaped.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    fn value() -> Value {
+        json!({
+            "database": { "host": "db.local", "port": 5432, "password": "p@ss word" },
+            "hosts": ["a.local", "b.local"],
+            "debug": false,
+        })
+        .into()
+    }
+
+    #[test]
+    fn parse() {
+        let expected = json!({ "name": "api", "ports": [80, 443], "tls": { "enabled": true } });
+
+        assert_eq!(
+            Format::Json
+                .parse(r#"{ "name": "api", "ports": [80, 443], "tls": { "enabled": true } }"#)
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            Format::Yaml
+                .parse("name: api\nports:\n  - 80\n  - 443\ntls:\n  enabled: true\n")
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            Format::Toml
+                .parse("name = \"api\"\nports = [80, 443]\n\n[tls]\nenabled = true\n")
+                .unwrap(),
+            expected
+        );
+
+        let err = Format::Json.parse("{\n  \"name\": api\n}").unwrap_err();
+        assert!(matches!(err, Error::InvalidBody(_)));
+        assert!(err.to_string().contains("json at line 2, column 11"));
+
+        let err = Format::Yaml.parse("name: api\nports: [80\n").unwrap_err();
+        assert!(err.to_string().contains("yaml at line 3, column 1"));
+
+        let err = Format::Toml
+            .parse("name = \"api\"\nports = [80,\n")
+            .unwrap_err();
+        assert!(err.to_string().contains("toml at line 3, column 1"));
+
+        assert!(matches!(
+            Format::Dotenv.parse("NAME=api"),
+            Err(Error::UnsupportedMediaType(_))
+        ));
+    }
+
+    #[test]
+    fn negotiate() {
+        assert_eq!("yml".parse::<Format>().unwrap(), Format::Yaml);
+        assert!(matches!(
+            "xml".parse::<Format>(),
+            Err(Error::UnsupportedFormat(_))
+        ));
+
+        assert_eq!(
+            Format::from_accept("text/html, application/x-yaml;q=0.9, */*"),
+            Some(Format::Yaml)
+        );
+        assert_eq!(Format::from_accept("*/*"), None);
+    }
+
+    #[test]
+    fn render() {
+        assert_eq!(
+            Format::Yaml.render(&value()).unwrap(),
+            "database:\n  host: db.local\n  password: p@ss word\n  port: 5432\ndebug: false\nhosts:\n- a.local\n- b.local\n"
+        );
+
+        assert_eq!(
+            Format::Toml.render(&value()).unwrap(),
+            "debug = false\nhosts = [\"a.local\", \"b.local\"]\n\n[database]\nhost = \"db.local\"\npassword = \"p@ss word\"\nport = 5432\n"
+        );
+
+        assert_eq!(
+            Format::Dotenv.render(&value()).unwrap(),
+            "DATABASE__HOST=db.local\nDATABASE__PASSWORD=\"p@ss word\"\nDATABASE__PORT=5432\nDEBUG=false\nHOSTS__0=a.local\nHOSTS__1=b.local\n"
+        );
+
+        assert_eq!(
+            Format::Properties.render(&value()).unwrap(),
+            "database.host=db.local\ndatabase.password=p@ss word\ndatabase.port=5432\ndebug=false\nhosts.0=a.local\nhosts.1=b.local\n"
+        );
+    }
+
+    #[test]
+    fn unrepresentable_values() {
+        let err = Format::Toml
+            .render(&json!({ "tls": { "cert_path": null } }).into())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "value cannot be represented as toml: null at $.tls.cert_path is not supported"
+        );
+
+        assert!(Format::Toml.render(&Value::from(vec![1, 2])).is_err());
+        assert!(Format::Dotenv.render(&Value::from("str")).is_err());
+
+        // Both keys become A_B
+        assert!(Format::Dotenv
+            .render(&json!({ "a-b": 1, "a_b": 2 }).into())
+            .is_err());
+        let err = Format::Dotenv
+            .render(&json!({ "a_b": 1, "a.b": 2 }).into())
+            .unwrap_err();
+        assert!(matches!(err, Error::UnrepresentableValue { .. }));
+        assert_eq!(
+            err.to_string(),
+            "value cannot be represented as dotenv: $.a.b and $.a_b are both rendered as A_B"
+        );
+
+        // Nested keys collide as well
+        let err = Format::Dotenv
+            .render(&json!({ "db": { "host": "a" }, "DB__HOST": "b" }).into())
+            .unwrap_err();
+        assert!(err.to_string().contains("$.DB__HOST and $.db.host"));
+        assert!(Format::Properties
+            .render(&json!({ "a": { "b": 1 }, "a.b": 2 }).into())
+            .is_err());
+
+        // Null values are empty
+        assert_eq!(
+            Format::Dotenv.render(&json!({ "a": null }).into()).unwrap(),
+            "A=\n"
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::domain::{
+    errors::Error,
+    values::{Kind, Migration, Path, Value},
+};
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JsonMigration {
+    Rename { from: String, to: String },
+    SetDefault { path: String, value: JsonValue },
+    Remove { path: String },
+    Convert { path: String, to: Kind },
+}
+
+impl TryFrom<JsonValue> for Migration {
+    type Error = Error;
+
+    fn try_from(value: JsonValue) -> Result<Self, Self::Error> {
+        let migration: JsonMigration = serde_json::from_value(value)
+            .map_err(|err| Error::InvalidMigration(err.to_string()))?;
+
+        match migration {
+            JsonMigration::Rename { from, to } => {
+                Migration::rename(Path::parse(&from)?, Path::parse(&to)?)
+            }
+            JsonMigration::SetDefault { path, value } => {
+                Migration::set_default(Path::parse(&path)?, Value::from(value))
+            }
+            JsonMigration::Remove { path } => Migration::remove(Path::parse(&path)?),
+            JsonMigration::Convert { path, to } => Migration::convert(Path::parse(&path)?, to),
+        }
+    }
+}
+
+// Migrations are received as a JSON array and applied in order
+pub fn migrations_from_json(value: JsonValue) -> Result<Vec<Migration>, Error> {
+    match value {
+        JsonValue::Null => Ok(Vec::new()),
+        JsonValue::Array(items) => items.into_iter().map(Migration::try_from).collect(),
+        _ => Err(Error::InvalidMigration(
+            "migrations must be an array".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_from_json() {
+        let json: JsonValue = serde_json::from_str(
+            r#"[
+                { "op": "rename", "from": "$.host", "to": "$.server.host" },
+                { "op": "set_default", "path": "$.server.port", "value": 8080 },
+                { "op": "remove", "path": "$.debug" },
+                { "op": "convert", "path": "$.timeout", "to": "float" }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            migrations_from_json(json).unwrap(),
+            vec![
+                Migration::rename(
+                    Path::parse("host").unwrap(),
+                    Path::parse("server.host").unwrap()
+                )
+                .unwrap(),
+                Migration::set_default(Path::parse("server.port").unwrap(), Value::Int(8080))
+                    .unwrap(),
+                Migration::remove(Path::parse("debug").unwrap()).unwrap(),
+                Migration::convert(Path::parse("timeout").unwrap(), Kind::Float).unwrap(),
+            ]
+        );
+
+        assert!(migrations_from_json(JsonValue::Null).unwrap().is_empty());
+        assert!(migrations_from_json(serde_json::json!({ "op": "remove" })).is_err());
+        assert!(
+            migrations_from_json(serde_json::json!([{ "op": "move", "path": "$.a" }])).is_err()
+        );
+        assert!(
+            migrations_from_json(serde_json::json!([{ "op": "set_default", "path": "$.a" }]))
+                .is_err()
+        );
+    }
+}
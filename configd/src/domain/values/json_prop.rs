@@ -40,6 +40,8 @@ struct JsonProp {
     regex: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     split: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret: Option<bool>,
 }
 
 impl TryFrom<JsonValue> for Prop {
@@ -77,9 +79,13 @@ impl TryFrom<JsonValue> for Prop {
                             interval,
                             prop.split.unwrap_or(false),
                         ),
-                        JsonPropKind::String => {
-                            Prop::string(prop.required, default_value, allowed_values, prop.regex)
-                        }
+                        JsonPropKind::String => Prop::string(
+                            prop.required,
+                            default_value,
+                            allowed_values,
+                            prop.regex,
+                            prop.secret.unwrap_or(false),
+                        ),
                     };
                 }
 
@@ -120,6 +126,7 @@ impl TryFrom<Prop> for JsonValue {
                     interval: None,
                     regex: None,
                     split: None,
+                    secret: None,
                 };
 
                 let json_value = serde_json::to_value(&json_prop).map_err(Error::Serde)?;
@@ -148,6 +155,7 @@ impl TryFrom<Prop> for JsonValue {
                     }),
                     regex: None,
                     split: Some(split),
+                    secret: None,
                 };
 
                 let json_value = serde_json::to_value(&json_prop).map_err(Error::Serde)?;
@@ -176,6 +184,7 @@ impl TryFrom<Prop> for JsonValue {
                     }),
                     regex: None,
                     split: Some(split),
+                    secret: None,
                 };
 
                 let json_value = serde_json::to_value(&json_prop).map_err(Error::Serde)?;
@@ -190,6 +199,7 @@ impl TryFrom<Prop> for JsonValue {
                 default_value,
                 allowed_values,
                 regex,
+                secret,
             } => {
                 let json_prop = JsonProp {
                     kind: JsonPropKind::String,
@@ -200,6 +210,7 @@ impl TryFrom<Prop> for JsonValue {
                     interval: None,
                     regex,
                     split: None,
+                    secret: secret.then_some(true),
                 };
 
                 let json_value = serde_json::to_value(&json_prop).map_err(Error::Serde)?;
@@ -323,7 +334,8 @@ mod tests {
                             Value::String("stg".to_string()),
                             Value::String("prod".to_string()),
                         ]),
-                        None
+                        None,
+                        false
                     )
                     .unwrap(),
                 ),
@@ -346,6 +358,7 @@ mod tests {
                             Some(Value::String("http://localhost:1234".to_string())),
                             None,
                             Some("^http://[a-z]+:[0-9]{2,4}$".to_string()),
+                            false,
                         )
                         .unwrap(),
                     )
@@ -361,6 +374,7 @@ mod tests {
                                     Some(Value::String("http://localhost".to_string())),
                                     None,
                                     Some("^http://[a-z]+0[0-9]{1}$".to_string()),
+                                    false,
                                 )
                                 .unwrap(),
                             ),
@@ -387,7 +401,7 @@ mod tests {
                         ),
                         (
                             "name".to_string(),
-                            Prop::string(false, None, None, None).unwrap(),
+                            Prop::string(false, None, None, None, false).unwrap(),
                         ),
                     ])),),
                 ),
@@ -413,6 +427,7 @@ mod tests {
                         Value::String("prod".to_string()),
                     ]),
                     None,
+                    false,
                 )
                 .unwrap(),
             ),
@@ -435,6 +450,7 @@ mod tests {
                         Some(Value::String("http://localhost:1234".to_string())),
                         None,
                         Some("^http://[a-z]+:[0-9]{2,4}$".to_string()),
+                        false,
                     )
                     .unwrap(),
                 ),
@@ -450,6 +466,7 @@ mod tests {
                                 Some(Value::String("http://localhost".to_string())),
                                 None,
                                 Some("^http://[a-z]+0[0-9]{1}$".to_string()),
+                                false,
                             )
                             .unwrap(),
                         ),
@@ -476,7 +493,7 @@ mod tests {
                     ),
                     (
                         "name".to_string(),
-                        Prop::string(false, None, None, None).unwrap(),
+                        Prop::string(false, None, None, None, false).unwrap(),
                     ),
                 ]))),
             ),
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::domain::{
+    errors::Error,
+    values::{Path, Rule},
+};
+
+#[derive(Serialize, Deserialize)]
+struct JsonRule {
+    expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    paths: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+impl TryFrom<JsonValue> for Rule {
+    type Error = Error;
+
+    fn try_from(value: JsonValue) -> Result<Self, Self::Error> {
+        let rule: JsonRule = serde_json::from_value(value).map_err(Error::Serde)?;
+
+        let paths = rule
+            .paths
+            .map(|paths| {
+                paths
+                    .iter()
+                    .map(|path| Path::parse(path))
+                    .collect::<Result<Vec<Path>, Error>>()
+            })
+            .transpose()?;
+
+        Rule::new(rule.expression, paths, rule.message)
+    }
+}
+
+impl TryFrom<Rule> for JsonValue {
+    type Error = Error;
+
+    fn try_from(rule: Rule) -> Result<Self, Self::Error> {
+        let json_rule = JsonRule {
+            expression: rule.source().to_string(),
+            paths: rule
+                .paths()
+                .map(|paths| paths.iter().map(ToString::to_string).collect()),
+            message: rule.message().map(ToString::to_string),
+        };
+
+        serde_json::to_value(&json_rule).map_err(Error::Serde)
+    }
+}
+
+// Rules are stored and exchanged as a JSON array
+pub fn rules_from_json(value: JsonValue) -> Result<Vec<Rule>, Error> {
+    match value {
+        JsonValue::Null => Ok(Vec::new()),
+        JsonValue::Array(items) => items.into_iter().map(Rule::try_from).collect(),
+        _ => Err(Error::InvalidRule("rules must be an array".to_string())),
+    }
+}
+
+pub fn rules_to_json(rules: &[Rule]) -> Result<JsonValue, Error> {
+    Ok(JsonValue::Array(
+        rules
+            .iter()
+            .cloned()
+            .map(JsonValue::try_from)
+            .collect::<Result<Vec<JsonValue>, Error>>()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_from_and_to_json() {
+        let json: JsonValue = serde_json::from_str(
+            r#"[
+                {
+                    "expression": "max_connections >= min_connections"
+                },
+                {
+                    "expression": "!tls.enabled || tls.cert_path != null",
+                    "paths": ["$.tls.cert_path"],
+                    "message": "cert_path is required when TLS is enabled"
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let rules = rules_from_json(json.clone()).unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].source(), "max_connections >= min_connections");
+        assert!(rules[0].paths().is_none());
+        assert_eq!(
+            rules[1].paths().unwrap(),
+            &[Path::parse("tls.cert_path").unwrap()]
+        );
+
+        assert_eq!(rules_to_json(&rules).unwrap(), json);
+
+        assert!(rules_from_json(JsonValue::Null).unwrap().is_empty());
+        assert!(rules_from_json(serde_json::json!({ "expression": "a" })).is_err());
+        assert!(rules_from_json(serde_json::json!([{ "expression": "a >" }])).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+use crate::domain::{
+    errors::Error,
+    values::{Kind, Path, Value},
+};
+
+// Transformation applied to the data of every config when a schema evolves.
+// Missing paths are skipped so configs without the value are left untouched.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Migration {
+    Rename { from: Path, to: Path },
+    SetDefault { path: Path, value: Value },
+    Remove { path: Path },
+    Convert { path: Path, kind: Kind },
+}
+
+impl Migration {
+    pub fn rename(from: Path, to: Path) -> Result<Migration, Error> {
+        if from.is_root() || to.is_root() {
+            return Err(Error::InvalidMigration(
+                "cannot rename the root value".to_string(),
+            ));
+        }
+
+        Ok(Migration::Rename { from, to })
+    }
+
+    pub fn set_default(path: Path, value: Value) -> Result<Migration, Error> {
+        if value.is_null() {
+            return Err(Error::InvalidMigration(format!(
+                "default value for {} cannot be null",
+                path
+            )));
+        }
+
+        Ok(Migration::SetDefault { path, value })
+    }
+
+    pub fn remove(path: Path) -> Result<Migration, Error> {
+        if path.is_root() {
+            return Err(Error::InvalidMigration(
+                "cannot remove the root value".to_string(),
+            ));
+        }
+
+        Ok(Migration::Remove { path })
+    }
+
+    pub fn convert(path: Path, kind: Kind) -> Result<Migration, Error> {
+        if kind == Kind::Null || kind == Kind::Object {
+            return Err(Error::InvalidMigration(format!(
+                "cannot convert {} to {}",
+                path, kind
+            )));
+        }
+
+        Ok(Migration::Convert { path, kind })
+    }
+
+    pub fn apply(&self, data: &mut Value) -> Result<(), Error> {
+        match self {
+            Migration::Rename { from, to } => {
+                if let Some(value) = from.remove(data) {
+                    to.insert(data, value)?;
+                }
+            }
+            Migration::SetDefault { path, value } => {
+                if path.resolve(data).is_none_or(Value::is_null) {
+                    path.insert(data, value.clone())?;
+                }
+            }
+            Migration::Remove { path } => {
+                path.remove(data);
+            }
+            Migration::Convert { path, kind } => {
+                if let Some(value) = path.resolve_mut(data) {
+                    if !value.is_null() {
+                        *value = convert(value, *kind).ok_or_else(|| {
+                            Error::InvalidMigration(format!(
+                                "cannot convert {} at {} to {}",
+                                value.kind(),
+                                path,
+                                kind
+                            ))
+                        })?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn apply_migrations(migrations: &[Migration], data: &Value) -> Result<Value, Error> {
+    let mut data = data.clone();
+
+    for migration in migrations.iter() {
+        migration.apply(&mut data)?;
+    }
+
+    Ok(data)
+}
+
+fn convert(value: &Value, kind: Kind) -> Option<Value> {
+    if value.kind() == kind {
+        return Some(value.clone());
+    }
+
+    match (value, kind) {
+        (Value::Int(n), Kind::Float) => Some(Value::Float(*n as f64)),
+        (Value::Float(n), Kind::Int) => {
+            if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n <= i64::MAX as f64 {
+                Some(Value::Int(*n as i64))
+            } else {
+                None
+            }
+        }
+        (Value::Bool(b), Kind::String) => Some(Value::String(b.to_string())),
+        (Value::Int(n), Kind::String) => Some(Value::String(n.to_string())),
+        (Value::Float(n), Kind::String) => Some(Value::String(n.to_string())),
+        (Value::String(s), Kind::Bool) => s.trim().parse().ok().map(Value::Bool),
+        (Value::String(s), Kind::Int) => s.trim().parse().ok().map(Value::Int),
+        (Value::String(s), Kind::Float) => s.trim().parse().ok().map(Value::Float),
+        (value, Kind::Array) => Some(Value::Array(vec![value.clone()])),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn apply() {
+        let data = Value::Object(BTreeMap::from([
+            ("host".to_string(), Value::from("localhost")),
+            ("port".to_string(), Value::from("8080")),
+            ("debug".to_string(), Value::Bool(true)),
+        ]));
+
+        let migrations = vec![
+            Migration::rename(
+                Path::parse("host").unwrap(),
+                Path::parse("server.host").unwrap(),
+            )
+            .unwrap(),
+            Migration::convert(Path::parse("port").unwrap(), Kind::Int).unwrap(),
+            Migration::set_default(Path::parse("server.tls").unwrap(), Value::Bool(false)).unwrap(),
+            Migration::remove(Path::parse("debug").unwrap()).unwrap(),
+            // Missing paths are skipped
+            Migration::remove(Path::parse("missing").unwrap()).unwrap(),
+            Migration::convert(Path::parse("missing").unwrap(), Kind::Int).unwrap(),
+        ];
+
+        assert_eq!(
+            apply_migrations(&migrations, &data).unwrap(),
+            Value::Object(BTreeMap::from([
+                (
+                    "server".to_string(),
+                    Value::Object(BTreeMap::from([
+                        ("host".to_string(), Value::from("localhost")),
+                        ("tls".to_string(), Value::Bool(false)),
+                    ]))
+                ),
+                ("port".to_string(), Value::Int(8080)),
+            ]))
+        );
+
+        // Existing values are kept
+        let migration =
+            Migration::set_default(Path::parse("debug").unwrap(), Value::Bool(false)).unwrap();
+        assert_eq!(apply_migrations(&[migration], &data).unwrap(), data);
+
+        // Unconvertible values
+        let migration = Migration::convert(Path::parse("host").unwrap(), Kind::Int).unwrap();
+        assert!(matches!(
+            apply_migrations(&[migration], &data),
+            Err(Error::InvalidMigration(_))
+        ));
+
+        assert!(Migration::remove(Path::root()).is_err());
+        assert!(Migration::convert(Path::root(), Kind::Object).is_err());
+    }
+}
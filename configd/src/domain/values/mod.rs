@@ -1,11 +1,30 @@
+mod change;
+mod compatibility;
 mod diff;
+mod expression;
+mod format;
 mod interval;
+mod json_migration;
 mod json_prop;
+mod json_rule;
+mod migration;
+mod patch;
+mod path;
 mod prop;
+mod rule;
 mod value;
 
+pub use change::*;
+pub use compatibility::*;
 pub use diff::*;
+pub use expression::*;
+pub use format::*;
 pub use interval::*;
-pub use json_prop::*;
+pub use json_migration::*;
+pub use json_rule::*;
+pub use migration::*;
+pub use patch::*;
+pub use path::*;
 pub use prop::*;
+pub use rule::*;
 pub use value::*;
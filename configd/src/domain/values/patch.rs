@@ -0,0 +1,459 @@
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use std::{collections::BTreeMap, fmt};
+
+use crate::domain::{
+    errors::Error,
+    values::{Prop, Value},
+};
+
+// JSON Pointer (RFC 6901) as used by JSON Patch: `/a/b/0`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pointer {
+    tokens: Vec<String>,
+}
+
+impl Pointer {
+    fn parse(pointer: &str) -> Result<Pointer, Error> {
+        if pointer.is_empty() {
+            return Ok(Pointer { tokens: Vec::new() });
+        }
+
+        if !pointer.starts_with('/') {
+            return Err(Error::InvalidPatch(format!(
+                "pointer must start with '/': {}",
+                pointer
+            )));
+        }
+
+        Ok(Pointer {
+            tokens: pointer[1..]
+                .split('/')
+                .map(|token| token.replace("~1", "/").replace("~0", "~"))
+                .collect(),
+        })
+    }
+
+    fn is_prefix_of(&self, other: &Pointer) -> bool {
+        other.tokens.starts_with(&self.tokens)
+    }
+
+    fn get<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        let mut current = value;
+
+        for token in self.tokens.iter() {
+            current = match current {
+                Value::Object(object) => object.get(token)?,
+                Value::Array(items) => items.get(parse_index(token)?)?,
+                _ => return None,
+            };
+        }
+
+        Some(current)
+    }
+
+    fn get_mut<'a>(&self, value: &'a mut Value) -> Option<&'a mut Value> {
+        let mut current = value;
+
+        for token in self.tokens.iter() {
+            current = match current {
+                Value::Object(object) => object.get_mut(token)?,
+                Value::Array(items) => items.get_mut(parse_index(token)?)?,
+                _ => return None,
+            };
+        }
+
+        Some(current)
+    }
+
+    fn parent_mut<'a>(&self, value: &'a mut Value) -> Result<(&'a mut Value, &str), Error> {
+        let (last, parents) = self
+            .tokens
+            .split_last()
+            .ok_or_else(|| Error::InvalidPatch("root has no parent".to_string()))?;
+
+        let parent = Pointer {
+            tokens: parents.to_vec(),
+        }
+        .get_mut(value)
+        .ok_or_else(|| Error::InvalidPatch(format!("path not found: {}", self)))?;
+
+        Ok((parent, last))
+    }
+
+    fn add(&self, value: &mut Value, new_value: Value) -> Result<(), Error> {
+        if self.tokens.is_empty() {
+            *value = new_value;
+            return Ok(());
+        }
+
+        match self.parent_mut(value)? {
+            (Value::Object(object), key) => {
+                object.insert(key.to_string(), new_value);
+            }
+            (Value::Array(items), "-") => items.push(new_value),
+            (Value::Array(items), token) => match parse_index(token) {
+                Some(index) if index <= items.len() => items.insert(index, new_value),
+                _ => return Err(Error::InvalidPatch(format!("invalid index: {}", self))),
+            },
+            _ => return Err(Error::InvalidPatch(format!("path not found: {}", self))),
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, value: &mut Value) -> Result<Value, Error> {
+        if self.tokens.is_empty() {
+            return Ok(std::mem::replace(value, Value::Null));
+        }
+
+        let removed = match self.parent_mut(value)? {
+            (Value::Object(object), key) => object.remove(key),
+            (Value::Array(items), token) => match parse_index(token) {
+                Some(index) if index < items.len() => Some(items.remove(index)),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        removed.ok_or_else(|| Error::InvalidPatch(format!("path not found: {}", self)))
+    }
+}
+
+impl fmt::Display for Pointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in self.tokens.iter() {
+            write!(f, "/{}", token.replace('~', "~0").replace('/', "~1"))?;
+        }
+
+        Ok(())
+    }
+}
+
+// Leading zeros are not valid array indexes
+fn parse_index(token: &str) -> Option<usize> {
+    if token.len() > 1 && token.starts_with('0') {
+        return None;
+    }
+
+    token.parse().ok()
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JsonOperation {
+    Add { path: String, value: JsonValue },
+    Remove { path: String },
+    Replace { path: String, value: JsonValue },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: JsonValue },
+}
+
+// Single RFC 6902 operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOperation {
+    Add { path: Pointer, value: Value },
+    Remove { path: Pointer },
+    Replace { path: Pointer, value: Value },
+    Move { from: Pointer, path: Pointer },
+    Copy { from: Pointer, path: Pointer },
+    Test { path: Pointer, value: Value },
+}
+
+impl TryFrom<JsonOperation> for PatchOperation {
+    type Error = Error;
+
+    fn try_from(operation: JsonOperation) -> Result<Self, Self::Error> {
+        Ok(match operation {
+            JsonOperation::Add { path, value } => PatchOperation::Add {
+                path: Pointer::parse(&path)?,
+                value: value.into(),
+            },
+            JsonOperation::Remove { path } => PatchOperation::Remove {
+                path: Pointer::parse(&path)?,
+            },
+            JsonOperation::Replace { path, value } => PatchOperation::Replace {
+                path: Pointer::parse(&path)?,
+                value: value.into(),
+            },
+            JsonOperation::Move { from, path } => PatchOperation::Move {
+                from: Pointer::parse(&from)?,
+                path: Pointer::parse(&path)?,
+            },
+            JsonOperation::Copy { from, path } => PatchOperation::Copy {
+                from: Pointer::parse(&from)?,
+                path: Pointer::parse(&path)?,
+            },
+            JsonOperation::Test { path, value } => PatchOperation::Test {
+                path: Pointer::parse(&path)?,
+                value: value.into(),
+            },
+        })
+    }
+}
+
+impl PatchOperation {
+    fn apply(&self, value: &mut Value) -> Result<(), Error> {
+        match self {
+            PatchOperation::Add { path, value: new } => path.add(value, new.clone()),
+            PatchOperation::Remove { path } => path.remove(value).map(|_| ()),
+            PatchOperation::Replace { path, value: new } => {
+                let current = path
+                    .get_mut(value)
+                    .ok_or_else(|| Error::InvalidPatch(format!("path not found: {}", path)))?;
+                *current = new.clone();
+                Ok(())
+            }
+            PatchOperation::Move { from, path } => {
+                if from == path {
+                    return Ok(());
+                }
+
+                if from.is_prefix_of(path) {
+                    return Err(Error::InvalidPatch(format!(
+                        "cannot move {} into one of its children",
+                        from
+                    )));
+                }
+
+                let moved = from.remove(value)?;
+                path.add(value, moved)
+            }
+            PatchOperation::Copy { from, path } => {
+                let copied = from
+                    .get(value)
+                    .cloned()
+                    .ok_or_else(|| Error::InvalidPatch(format!("path not found: {}", from)))?;
+                path.add(value, copied)
+            }
+            PatchOperation::Test {
+                path,
+                value: expected,
+            } => match path.get(value) {
+                Some(current) if current.equals(expected) => Ok(()),
+                _ => Err(Error::PatchTestFailed(path.to_string())),
+            },
+        }
+    }
+}
+
+// Partial update of a config value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Patch {
+    // RFC 6902: operations are applied in order and fail as a whole
+    Json(Vec<PatchOperation>),
+    // RFC 7396: objects are merged recursively and null removes a key, unless
+    // it is applied against a prop declaring that key
+    Merge(Value),
+}
+
+impl Patch {
+    pub fn json(value: JsonValue) -> Result<Patch, Error> {
+        let operations: Vec<JsonOperation> =
+            serde_json::from_value(value).map_err(|err| Error::InvalidPatch(err.to_string()))?;
+
+        Ok(Patch::Json(
+            operations
+                .into_iter()
+                .map(PatchOperation::try_from)
+                .collect::<Result<Vec<PatchOperation>, Error>>()?,
+        ))
+    }
+
+    pub fn merge(value: JsonValue) -> Patch {
+        Patch::Merge(value.into())
+    }
+
+    // Declared props are required to be present, so a null in a merge patch
+    // clears them instead of removing them
+    pub fn apply_with_prop(&self, value: &Value, prop: Option<&Prop>) -> Result<Value, Error> {
+        match self {
+            Patch::Json(operations) => {
+                let mut value = value.clone();
+
+                for operation in operations.iter() {
+                    operation.apply(&mut value)?;
+                }
+
+                Ok(value)
+            }
+            Patch::Merge(patch) => Ok(merge(value, patch, prop)),
+        }
+    }
+}
+
+fn merge(target: &Value, patch: &Value, prop: Option<&Prop>) -> Value {
+    let patch = match patch {
+        Value::Object(patch) => patch,
+        _ => return patch.clone(),
+    };
+
+    let mut result = match target {
+        Value::Object(target) => target.clone(),
+        _ => BTreeMap::new(),
+    };
+
+    let props = match prop {
+        Some(Prop::Object(props)) => Some(props),
+        _ => None,
+    };
+
+    for (key, value) in patch.iter() {
+        let prop = props.and_then(|props| props.get(key));
+
+        if value.is_null() {
+            if prop.is_some() {
+                result.insert(key.to_string(), Value::Null);
+            } else {
+                result.remove(key);
+            }
+        } else {
+            let merged = merge(result.get(key).unwrap_or(&Value::Null), value, prop);
+            result.insert(key.to_string(), merged);
+        }
+    }
+
+    Value::Object(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    fn apply(patch: Patch, value: JsonValue) -> Result<JsonValue, Error> {
+        patch
+            .apply_with_prop(&value.into(), None)
+            .map(JsonValue::from)
+    }
+
+    #[test]
+    fn json_patch() {
+        let value = json!({
+            "env": "dev",
+            "hosts": ["a.local", "b.local"],
+            "tls": { "enabled": false, "cert~path": null },
+        });
+
+        let patch = Patch::json(json!([
+            { "op": "test", "path": "/env", "value": "dev" },
+            { "op": "replace", "path": "/env", "value": "prod" },
+            { "op": "add", "path": "/hosts/-", "value": "c.local" },
+            { "op": "add", "path": "/hosts/0", "value": "z.local" },
+            { "op": "remove", "path": "/hosts/1" },
+            { "op": "replace", "path": "/tls/enabled", "value": true },
+            { "op": "copy", "from": "/hosts/0", "path": "/tls/cert~0path" },
+            { "op": "move", "from": "/tls/cert~0path", "path": "/primary" },
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            apply(patch, value.clone()).unwrap(),
+            json!({
+                "env": "prod",
+                "hosts": ["z.local", "b.local", "c.local"],
+                "tls": { "enabled": true },
+                "primary": "z.local",
+            })
+        );
+
+        // Numbers are compared by value
+        let patch = Patch::json(json!([{ "op": "test", "path": "/n", "value": 1.0 }])).unwrap();
+        assert!(apply(patch, json!({ "n": 1 })).is_ok());
+
+        // Failures
+        let patch = Patch::json(json!([{ "op": "test", "path": "/env", "value": "prod" }]));
+        assert!(matches!(
+            apply(patch.unwrap(), value.clone()),
+            Err(Error::PatchTestFailed(_))
+        ));
+
+        for operations in [
+            json!([{ "op": "remove", "path": "/missing" }]),
+            json!([{ "op": "replace", "path": "/missing", "value": 1 }]),
+            json!([{ "op": "add", "path": "/hosts/5", "value": 1 }]),
+            json!([{ "op": "add", "path": "/missing/key", "value": 1 }]),
+            json!([{ "op": "move", "from": "/tls", "path": "/tls/inner" }]),
+        ] {
+            let patch = Patch::json(operations).unwrap();
+            assert!(matches!(
+                apply(patch, value.clone()),
+                Err(Error::InvalidPatch(_))
+            ));
+        }
+
+        assert!(Patch::json(json!([{ "op": "add", "path": "env" }])).is_err());
+        assert!(Patch::json(json!([{ "op": "rename", "path": "/env" }])).is_err());
+        assert!(Patch::json(json!({ "op": "remove", "path": "/env" })).is_err());
+    }
+
+    #[test]
+    fn merge_patch() {
+        let value = json!({
+            "env": "dev",
+            "hosts": ["a.local"],
+            "tls": { "enabled": false, "cert_path": "/etc/cert" },
+        });
+
+        let patch = Patch::merge(json!({
+            "env": "prod",
+            "hosts": ["b.local"],
+            "tls": { "enabled": true, "cert_path": null },
+        }));
+
+        assert_eq!(
+            apply(patch, value).unwrap(),
+            json!({
+                "env": "prod",
+                "hosts": ["b.local"],
+                "tls": { "enabled": true },
+            })
+        );
+
+        assert_eq!(
+            apply(Patch::merge(json!(["a"])), json!({ "a": 1 })).unwrap(),
+            json!(["a"])
+        );
+
+        // Declared props are cleared instead of removed
+        let prop = Prop::object(BTreeMap::from([
+            (
+                "env".to_string(),
+                Prop::string(true, None, None, None, false).unwrap(),
+            ),
+            (
+                "tls".to_string(),
+                Prop::object(BTreeMap::from([(
+                    "cert_path".to_string(),
+                    Prop::string(false, None, None, None, false).unwrap(),
+                )])),
+            ),
+        ]));
+        let patch = Patch::merge(json!({
+            "tls": { "cert_path": null, "key_path": null },
+            "debug": null,
+        }));
+
+        assert_eq!(
+            JsonValue::from(
+                patch
+                    .apply_with_prop(
+                        &json!({
+                            "env": "dev",
+                            "tls": { "cert_path": "/etc/cert", "key_path": "/etc/key" },
+                            "debug": true,
+                        })
+                        .into(),
+                        Some(&prop),
+                    )
+                    .unwrap()
+            ),
+            json!({
+                "env": "dev",
+                "tls": { "cert_path": null },
+            })
+        );
+    }
+}
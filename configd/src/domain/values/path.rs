@@ -0,0 +1,272 @@
+use std::fmt;
+
+use crate::domain::{errors::Error, values::Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Key(key) => write!(f, "{}", key),
+            PathSegment::Index(index) => write!(f, "{}", index),
+        }
+    }
+}
+
+// Path to a value using the same notation as Diff keys: `$.a.b.0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    segments: Vec<PathSegment>,
+}
+
+impl Path {
+    pub fn new(segments: Vec<PathSegment>) -> Path {
+        Path { segments }
+    }
+
+    pub fn root() -> Path {
+        Path::new(Vec::new())
+    }
+
+    // Accepts both `$.a.b.0` and `a.b.0`. Numeric segments are indexes.
+    pub fn parse(path: &str) -> Result<Path, Error> {
+        let path = path.trim();
+        let path = path
+            .strip_prefix("$.")
+            .or_else(|| if path == "$" { Some("") } else { None })
+            .unwrap_or(path);
+
+        if path.is_empty() {
+            return Ok(Path::root());
+        }
+
+        let segments = path
+            .split('.')
+            .map(|segment| {
+                if segment.is_empty() {
+                    return Err(Error::InvalidPath(path.to_string()));
+                }
+
+                Ok(segment
+                    .parse()
+                    .map(PathSegment::Index)
+                    .unwrap_or_else(|_| PathSegment::Key(segment.to_string())))
+            })
+            .collect::<Result<Vec<PathSegment>, Error>>()?;
+
+        Ok(Path::new(segments))
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    // Key relative to the root, as expected by Diff::add
+    pub fn key(&self) -> Option<String> {
+        if self.is_root() {
+            return None;
+        }
+
+        Some(
+            self.segments
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join("."),
+        )
+    }
+
+    pub fn resolve<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        let mut current = value;
+
+        for segment in self.segments.iter() {
+            current = match (segment, current) {
+                (PathSegment::Key(key), Value::Object(object)) => object.get(key)?,
+                (PathSegment::Index(index), Value::Array(items)) => items.get(*index)?,
+                (PathSegment::Index(index), Value::Object(object)) => {
+                    object.get(&index.to_string())?
+                }
+                _ => return None,
+            };
+        }
+
+        Some(current)
+    }
+
+    pub fn resolve_mut<'a>(&self, value: &'a mut Value) -> Option<&'a mut Value> {
+        let mut current = value;
+
+        for segment in self.segments.iter() {
+            current = match (segment, current) {
+                (PathSegment::Key(key), Value::Object(object)) => object.get_mut(key)?,
+                (PathSegment::Index(index), Value::Array(items)) => items.get_mut(*index)?,
+                (PathSegment::Index(index), Value::Object(object)) => {
+                    object.get_mut(&index.to_string())?
+                }
+                _ => return None,
+            };
+        }
+
+        Some(current)
+    }
+
+    // Sets the value, creating missing or null parent objects on the way. An
+    // index can only replace an existing item or append one at the end.
+    pub fn insert(&self, value: &mut Value, new_value: Value) -> Result<(), Error> {
+        let mut current = value;
+
+        for segment in self.segments.iter() {
+            if current.is_null() {
+                *current = Value::Object(Default::default());
+            }
+
+            current = match (segment, current) {
+                (PathSegment::Key(key), Value::Object(object)) => {
+                    object.entry(key.to_string()).or_insert(Value::Null)
+                }
+                (PathSegment::Index(index), Value::Object(object)) => {
+                    object.entry(index.to_string()).or_insert(Value::Null)
+                }
+                (PathSegment::Index(index), Value::Array(items)) => {
+                    if *index == items.len() {
+                        items.push(Value::Null);
+                    }
+
+                    items
+                        .get_mut(*index)
+                        .ok_or_else(|| Error::InvalidPath(self.to_string()))?
+                }
+                _ => return Err(Error::InvalidPath(self.to_string())),
+            };
+        }
+
+        *current = new_value;
+
+        Ok(())
+    }
+
+    // Removes and returns the value. Removing the root leaves null behind.
+    pub fn remove(&self, value: &mut Value) -> Option<Value> {
+        let (last, parents) = match self.segments.split_last() {
+            Some(split) => split,
+            None => return Some(std::mem::replace(value, Value::Null)),
+        };
+
+        match (last, Path::new(parents.to_vec()).resolve_mut(value)?) {
+            (PathSegment::Key(key), Value::Object(object)) => object.remove(key),
+            (PathSegment::Index(index), Value::Object(object)) => object.remove(&index.to_string()),
+            (PathSegment::Index(index), Value::Array(items)) => {
+                if *index < items.len() {
+                    Some(items.remove(*index))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "$")?;
+
+        for segment in self.segments.iter() {
+            write!(f, ".{}", segment)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn parse_and_resolve() {
+        let value = Value::Object(BTreeMap::from([(
+            "tls".to_string(),
+            Value::Object(BTreeMap::from([(
+                "hosts".to_string(),
+                Value::Array(vec![Value::String("a.local".to_string())]),
+            )])),
+        )]));
+
+        let path = Path::parse("$.tls.hosts.0").unwrap();
+        assert_eq!(path, Path::parse("tls.hosts.0").unwrap());
+        assert_eq!(path.to_string(), "$.tls.hosts.0");
+        assert_eq!(path.key(), Some("tls.hosts.0".to_string()));
+        assert_eq!(
+            path.resolve(&value),
+            Some(&Value::String("a.local".to_string()))
+        );
+
+        assert!(Path::parse("$").unwrap().is_root());
+        assert_eq!(Path::parse("$").unwrap().resolve(&value), Some(&value));
+        assert_eq!(Path::parse("tls.missing").unwrap().resolve(&value), None);
+
+        assert!(Path::parse("tls..hosts").is_err());
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut value = Value::Object(BTreeMap::from([(
+            "hosts".to_string(),
+            Value::Array(vec![Value::from("a.local")]),
+        )]));
+
+        Path::parse("tls.enabled")
+            .unwrap()
+            .insert(&mut value, Value::Bool(true))
+            .unwrap();
+        Path::parse("hosts.1")
+            .unwrap()
+            .insert(&mut value, Value::from("b.local"))
+            .unwrap();
+        assert!(Path::parse("hosts.5")
+            .unwrap()
+            .insert(&mut value, Value::from("c.local"))
+            .is_err());
+        assert!(Path::parse("hosts.0.name")
+            .unwrap()
+            .insert(&mut value, Value::from("c.local"))
+            .is_err());
+
+        assert_eq!(
+            Path::parse("tls.enabled").unwrap().resolve(&value),
+            Some(&Value::Bool(true))
+        );
+
+        assert_eq!(
+            Path::parse("hosts.0").unwrap().remove(&mut value),
+            Some(Value::from("a.local"))
+        );
+        assert_eq!(Path::parse("hosts.4").unwrap().remove(&mut value), None);
+        assert_eq!(Path::parse("missing").unwrap().remove(&mut value), None);
+
+        assert_eq!(
+            value,
+            Value::Object(BTreeMap::from([
+                (
+                    "hosts".to_string(),
+                    Value::Array(vec![Value::from("b.local")])
+                ),
+                (
+                    "tls".to_string(),
+                    Value::Object(BTreeMap::from([("enabled".to_string(), Value::Bool(true))]))
+                ),
+            ]))
+        );
+    }
+}
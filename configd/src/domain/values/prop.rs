@@ -3,7 +3,7 @@ use std::collections::BTreeMap;
 
 use crate::domain::{
     errors::Error,
-    values::{Diff, Interval, Kind, Reason, Value},
+    values::{Diff, Interval, Kind, Value, Violation},
 };
 
 #[derive(Debug, PartialEq, Clone)]
@@ -31,6 +31,7 @@ pub enum Prop {
         default_value: Option<Value>,
         allowed_values: Option<Vec<Value>>,
         regex: Option<String>,
+        secret: bool,
     },
     Array(Box<Prop>),
     Object(BTreeMap<String, Prop>),
@@ -150,6 +151,7 @@ impl Prop {
         default_value: Option<Value>,
         allowed_values: Option<Vec<Value>>,
         regex: Option<String>,
+        secret: bool,
     ) -> Result<Prop, Error> {
         if let Some(default_value) = &default_value {
             if default_value.kind() != Kind::String {
@@ -176,6 +178,7 @@ impl Prop {
             default_value,
             allowed_values,
             regex,
+            secret,
         })
     }
 
@@ -187,6 +190,17 @@ impl Prop {
         Prop::Object(props)
     }
 
+    pub fn kind(&self) -> Kind {
+        match self {
+            Prop::Bool { .. } => Kind::Bool,
+            Prop::Int { .. } => Kind::Int,
+            Prop::Float { .. } => Kind::Float,
+            Prop::String { .. } => Kind::String,
+            Prop::Array(_) => Kind::Array,
+            Prop::Object(_) => Kind::Object,
+        }
+    }
+
     pub fn is_required(&self) -> bool {
         match self {
             Prop::Bool { required, .. }
@@ -229,6 +243,13 @@ impl Prop {
         }
     }
 
+    pub fn is_secret(&self) -> bool {
+        match self {
+            Prop::String { secret, .. } => *secret,
+            _ => false,
+        }
+    }
+
     pub fn split(&self) -> bool {
         match self {
             Prop::Int { split, .. } | Prop::Float { split, .. } => *split,
@@ -242,17 +263,21 @@ impl Prop {
 
     fn validate_with_key(&self, value: &Value, key: String) -> Diff {
         let mut diff = Diff::new(key);
+        let secret = self.is_secret();
 
         // Null values
         if value.is_null() {
             if self.is_required() && self.default_value().is_none() {
-                diff.add(Reason::NullValue, None);
+                diff.add(Violation::null_value(self.kind()), None);
             }
         } else {
             // Allowed values
             if let Some(allowed_values) = self.allowed_values() {
                 if allowed_values.iter().all(|v| v != value) {
-                    diff.add(Reason::NotAllowedValue, None)
+                    diff.add(
+                        Violation::not_allowed_value(allowed_values, value, secret),
+                        None,
+                    )
                 }
             }
 
@@ -260,48 +285,54 @@ impl Prop {
             match self {
                 Prop::Bool { .. } => {
                     if value.kind() != Kind::Bool {
-                        diff.add(Reason::NotABool, None);
+                        diff.add(Violation::mismatched_kind(Kind::Bool, value, secret), None);
                     }
                 }
                 Prop::Int { interval, .. } => {
-                    if let Value::Int(value) = value {
+                    if let Value::Int(num) = value {
                         if let Some(interval) = interval {
-                            if !interval.validate(*value as f64) {
-                                diff.add(Reason::NotInInterval, None)
+                            if !interval.validate(*num as f64) {
+                                diff.add(Violation::not_in_interval(interval, value), None)
                             }
                         }
                     } else {
-                        diff.add(Reason::NotAnInt, None);
+                        diff.add(Violation::mismatched_kind(Kind::Int, value, secret), None);
                     }
                 }
                 Prop::Float { interval, .. } => {
-                    if let Value::Float(value) = value {
+                    if let Value::Float(num) = value {
                         if let Some(interval) = interval {
-                            if !interval.validate(*value) {
-                                diff.add(Reason::NotInInterval, None)
+                            if !interval.validate(*num) {
+                                diff.add(Violation::not_in_interval(interval, value), None)
                             }
                         }
-                    } else if let Value::Int(value) = value {
+                    } else if let Value::Int(num) = value {
                         if let Some(interval) = interval {
-                            if !interval.validate(*value as f64) {
-                                diff.add(Reason::NotInInterval, None)
+                            if !interval.validate(*num as f64) {
+                                diff.add(Violation::not_in_interval(interval, value), None)
                             }
                         }
                     } else {
-                        diff.add(Reason::NotAFloat, None);
+                        diff.add(Violation::mismatched_kind(Kind::Float, value, secret), None);
                     }
                 }
                 Prop::String { regex, .. } => {
-                    if let Value::String(value) = value {
+                    if let Value::String(s) = value {
                         if let Some(regex) = regex {
-                            if let Ok(regex) = Regex::new(regex) {
-                                if !regex.is_match(value) {
-                                    diff.add(Reason::UnmatchedRegex, None);
+                            if let Ok(compiled) = Regex::new(regex) {
+                                if !compiled.is_match(s) {
+                                    diff.add(
+                                        Violation::unmatched_regex(regex, value, secret),
+                                        None,
+                                    );
                                 }
                             }
                         }
                     } else {
-                        diff.add(Reason::NotAString, None);
+                        diff.add(
+                            Violation::mismatched_kind(Kind::String, value, secret),
+                            None,
+                        );
                     }
                 }
                 Prop::Array(prop) => {
@@ -310,7 +341,7 @@ impl Prop {
                             diff.merge(prop.validate_with_key(item, i.to_string()));
                         }
                     } else {
-                        diff.add(Reason::NotAnArray, None);
+                        diff.add(Violation::mismatched_kind(Kind::Array, value, secret), None);
                     }
                 }
                 Prop::Object(props) => {
@@ -319,17 +350,20 @@ impl Prop {
                             if let Some(prop) = props.get(key) {
                                 diff.merge(prop.validate_with_key(item, key.to_string()))
                             } else {
-                                diff.add(Reason::UnknownProp, Some(key.to_string()));
+                                diff.add(Violation::unknown_prop(), Some(key.to_string()));
                             }
                         }
 
                         for key in props.keys() {
                             if !object.contains_key(key) {
-                                diff.add(Reason::MissingProp, Some(key.to_string()));
+                                diff.add(Violation::missing_prop(), Some(key.to_string()));
                             }
                         }
                     } else {
-                        diff.add(Reason::NotAnObject, None);
+                        diff.add(
+                            Violation::mismatched_kind(Kind::Object, value, secret),
+                            None,
+                        );
                     }
                 }
             }
@@ -393,7 +427,7 @@ impl Prop {
 mod tests {
     use super::*;
 
-    use std::collections::HashMap;
+    use crate::domain::values::Reason;
 
     #[test]
     fn validate() {
@@ -407,7 +441,7 @@ mod tests {
             .unwrap()
             .validate(&Value::Null)
             .is_empty());
-        assert!(Prop::string(false, None, None, None)
+        assert!(Prop::string(false, None, None, None, false)
             .unwrap()
             .validate(&Value::Null)
             .is_empty());
@@ -429,7 +463,7 @@ mod tests {
             .unwrap()
             .validate(&Value::Null)
             .is_empty());
-        assert!(!Prop::string(true, None, None, None)
+        assert!(!Prop::string(true, None, None, None, false)
             .unwrap()
             .validate(&Value::Null)
             .is_empty());
@@ -453,7 +487,8 @@ mod tests {
                 Value::String("stg".to_string()),
                 Value::String("prod".to_string()),
             ]),
-            None
+            None,
+            false
         )
         .unwrap()
         .validate(&Value::String("dev".to_string()))
@@ -466,7 +501,8 @@ mod tests {
                 Value::String("stg".to_string()),
                 Value::String("prod".to_string()),
             ]),
-            None
+            None,
+            false
         )
         .unwrap()
         .validate(&Value::String("other".to_string()))
@@ -479,7 +515,8 @@ mod tests {
                 Value::String("stg".to_string()),
                 Value::String("prod".to_string()),
             ]),
-            None
+            None,
+            false
         )
         .unwrap()
         .validate(&Value::Int(3))
@@ -504,7 +541,8 @@ mod tests {
             true,
             None,
             None,
-            Some("^http://[a-z]+:[0-9]{2,4}$".to_string())
+            Some("^http://[a-z]+:[0-9]{2,4}$".to_string()),
+            false
         )
         .unwrap()
         .validate(&Value::String("http://localhost:8080".to_string()))
@@ -513,7 +551,8 @@ mod tests {
             true,
             None,
             None,
-            Some("^http://[a-z]+:[0-9]{2,4}$".to_string())
+            Some("^http://[a-z]+:[0-9]{2,4}$".to_string()),
+            false
         )
         .unwrap()
         .validate(&Value::String("http://loc4lh0st:8080".to_string()))
@@ -522,7 +561,8 @@ mod tests {
             true,
             None,
             None,
-            Some("^http://[a-z]+:[0-9]{2,4}$".to_string())
+            Some("^http://[a-z]+:[0-9]{2,4}$".to_string()),
+            false
         )
         .unwrap()
         .validate(&Value::String("localhost:8080".to_string()))
@@ -543,6 +583,7 @@ mod tests {
                         Value::String("prod".to_string()),
                     ]),
                     None,
+                    false,
                 )
                 .unwrap(),
             ),
@@ -582,7 +623,7 @@ mod tests {
             (
                 "arr".to_string(),
                 Prop::array(
-                    Prop::string(true, None, None, Some("^asd[0-9]+$".to_string())).unwrap(),
+                    Prop::string(true, None, None, Some("^asd[0-9]+$".to_string()), false).unwrap(),
                 ),
             ),
             (
@@ -619,8 +660,8 @@ mod tests {
         ])));
 
         assert_eq!(
-            diff.diffs(),
-            &HashMap::from([
+            diff.reasons(),
+            BTreeMap::from([
                 ("$.env".to_string(), vec![Reason::NotAllowedValue]),
                 ("$.obj.prop1".to_string(), vec![Reason::NullValue]),
                 ("$.obj.prop2".to_string(), vec![Reason::NotInInterval]),
@@ -637,6 +678,34 @@ mod tests {
         )
     }
 
+    #[test]
+    fn secret_values_are_masked() {
+        let prop = Prop::object(BTreeMap::from([(
+            "password".to_string(),
+            Prop::string(true, None, None, Some("^.{12,}$".to_string()), true).unwrap(),
+        )]));
+
+        let diff = prop.validate(&Value::from(BTreeMap::from([(
+            "password",
+            Value::from("hunter2"),
+        )])));
+        let violations = diff.diffs().get("$.password").unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].reason(), Reason::UnmatchedRegex);
+        assert_eq!(violations[0].regex(), Some("^.{12,}$"));
+        assert!(!violations[0].message().contains("hunter2"));
+        assert_ne!(violations[0].value(), Some(&"hunter2".into()));
+
+        let diff = prop.validate(&Value::from(BTreeMap::from([("password", Value::Null)])));
+        let violations = diff.diffs().get("$.password").unwrap();
+        assert_eq!(violations[0].reason(), Reason::NullValue);
+        assert_eq!(violations[0].expected(), Some(Kind::String));
+        assert_eq!(
+            violations[0].message(),
+            "value is required: expected string, found null"
+        );
+    }
+
     #[test]
     fn populate() {
         let prop = Prop::object(BTreeMap::from([
@@ -647,12 +716,13 @@ mod tests {
                     Some(Value::String("str_default".to_string())),
                     None,
                     None,
+                    false,
                 )
                 .unwrap(),
             ),
             (
                 "str2".to_string(),
-                Prop::string(true, None, None, None).unwrap(),
+                Prop::string(true, None, None, None, false).unwrap(),
             ),
             (
                 "arr".to_string(),
@@ -0,0 +1,139 @@
+use crate::domain::{
+    errors::Error,
+    values::{Diff, Expression, Path, Value, Violation},
+};
+
+// Cross-field rule evaluated against the whole config
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    expression: Expression,
+    source: String,
+    paths: Option<Vec<Path>>,
+    message: Option<String>,
+}
+
+impl Rule {
+    pub fn new(
+        source: String,
+        paths: Option<Vec<Path>>,
+        message: Option<String>,
+    ) -> Result<Rule, Error> {
+        let expression = Expression::parse(&source)?;
+
+        Ok(Rule {
+            expression,
+            source,
+            paths,
+            message,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn paths(&self) -> Option<&[Path]> {
+        self.paths.as_deref()
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    // Paths where a failure is reported: the explicit ones or, if not given,
+    // every path referenced by the expression.
+    pub fn targets(&self) -> Vec<&Path> {
+        match &self.paths {
+            Some(paths) => paths.iter().collect(),
+            None => self.expression.paths(),
+        }
+    }
+
+    // Evaluation errors (e.g. comparing a string with an int) fail the rule
+    pub fn check(&self, value: &Value) -> bool {
+        matches!(self.expression.evaluate(value), Ok(Value::Bool(true)))
+    }
+
+    pub fn validate(&self, value: &Value) -> Diff {
+        let mut diff = Diff::new("$".to_string());
+
+        if !self.check(value) {
+            let targets = self.targets();
+
+            if targets.is_empty() {
+                diff.add(Violation::unsatisfied_rule(self), None);
+            }
+
+            for path in targets.into_iter() {
+                diff.add(Violation::unsatisfied_rule(self), path.key());
+            }
+        }
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use crate::domain::values::Reason;
+
+    #[test]
+    fn validate() {
+        let rule = Rule::new("max_connections >= min_connections".to_string(), None, None).unwrap();
+
+        assert!(rule
+            .validate(&Value::Object(BTreeMap::from([
+                ("min_connections".to_string(), Value::Int(2)),
+                ("max_connections".to_string(), Value::Int(8)),
+            ])))
+            .is_empty());
+
+        let diff = rule.validate(&Value::Object(BTreeMap::from([
+            ("min_connections".to_string(), Value::Int(8)),
+            ("max_connections".to_string(), Value::Int(2)),
+        ])));
+        assert_eq!(
+            diff.reasons(),
+            BTreeMap::from([
+                (
+                    "$.max_connections".to_string(),
+                    vec![Reason::UnsatisfiedRule]
+                ),
+                (
+                    "$.min_connections".to_string(),
+                    vec![Reason::UnsatisfiedRule]
+                ),
+            ])
+        );
+
+        // Explicit paths
+        let rule = Rule::new(
+            "!tls.enabled || tls.cert_path != null".to_string(),
+            Some(vec![Path::parse("$.tls.cert_path").unwrap()]),
+            Some("cert_path is required when TLS is enabled".to_string()),
+        )
+        .unwrap();
+
+        let diff = rule.validate(&Value::Object(BTreeMap::from([(
+            "tls".to_string(),
+            Value::Object(BTreeMap::from([("enabled".to_string(), Value::Bool(true))])),
+        )])));
+        assert_eq!(
+            diff.reasons(),
+            BTreeMap::from([("$.tls.cert_path".to_string(), vec![Reason::UnsatisfiedRule])])
+        );
+
+        // Rules without paths are reported at the root
+        let rule = Rule::new("1 > 2".to_string(), None, None).unwrap();
+        assert_eq!(
+            rule.validate(&Value::Null).reasons(),
+            BTreeMap::from([("$".to_string(), vec![Reason::UnsatisfiedRule])])
+        );
+
+        assert!(Rule::new("a >".to_string(), None, None).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use serde_json::{Number, Value as JsonValue};
 use sha2::{Digest, Sha256};
 use std::{
@@ -6,7 +7,8 @@ use std::{
 };
 
 // Value & Kind
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Kind {
     Null,
     Bool,
@@ -63,6 +65,22 @@ impl Value {
         self == &Value::Null
     }
 
+    // JSON numbers are equal regardless of being written as int or float
+    pub fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Int(a), Value::Float(b)) | (Value::Float(b), Value::Int(a)) => *a as f64 == *b,
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.equals(b))
+            }
+            (Value::Object(a), Value::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, a)| b.get(key).is_some_and(|b| a.equals(b)))
+            }
+            (a, b) => a == b,
+        }
+    }
+
     pub fn checksum(&self) -> String {
         let json: JsonValue = self.into();
 
@@ -218,6 +236,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn from() {
         assert_eq!(Value::from(true), Value::Bool(true));
         assert_eq!(Value::from(123), Value::Int(123));
@@ -0,0 +1,1249 @@
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::{net::IpAddr, sync::Arc};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{metadata::MetadataMap, Code, Request, Response, Status};
+
+use crate::{
+    application::{
+        ChangeConfigPassword, ChangeConfigPasswordCommand, CreateConfig, CreateConfigCommand,
+        CreateSchema, CreateSchemaCommand, DeleteConfig, DeleteConfigCommand, DeleteConfigPassword,
+        DeleteConfigPasswordCommand, DeleteSchema, DeleteSchemaCommand, DiffConfigs,
+        DiffConfigsCommand, GetConfig, GetConfigCommand, GetConfigResponse, GetSchema,
+        GetSchemaCommand, ListConfigs, ListConfigsCommand, ListDeletedConfigs,
+        ListDeletedConfigsCommand, ListDeletedSchemas, ListDeletedSchemasCommand, ListSchemas,
+        ListSchemasCommand, PatchConfig, PatchConfigCommand, PatchFormat, PurgeConfig,
+        PurgeConfigCommand, PurgeSchema, PurgeSchemaCommand, RestoreConfig, RestoreConfigCommand,
+        RestoreSchema, RestoreSchemaCommand, UpdateConfig, UpdateConfigCommand, UpdateSchema,
+        UpdateSchemaCommand, ValidateConfig, ValidateConfigCommand,
+    },
+    container::Container,
+    domain::{
+        configs::{current_client, with_client},
+        errors::Error,
+        events::{Event, Handler},
+    },
+    handlers::ErrorDto,
+    infrastructure::metrics,
+    tls::{ClientIdentity, TlsConnectInfo},
+};
+
+pub mod proto {
+    tonic::include_proto!("configd.v1");
+}
+
+use proto::configd_server::Configd;
+
+// Error
+impl From<Error> for Status {
+    fn from(err: Error) -> Status {
+        let code = match err {
+            Error::SchemaNotFound(_) | Error::ConfigNotFound(_) => Code::NotFound,
+            Error::Unauthorized => Code::Unauthenticated,
+            Error::SchemaAlreadyExists(_) | Error::ConfigAlreadyExists(_) => Code::AlreadyExists,
+            Error::SchemaContainsConfigs(_)
+            | Error::BreakingSchemaChange(_)
+            | Error::PatchTestFailed(_) => Code::FailedPrecondition,
+            Error::EmptyId
+            | Error::EmptyName
+            | Error::EmptyInterval
+            | Error::MismatchedKinds { .. }
+            | Error::InvalidArray
+            | Error::UnknownRootProp
+            | Error::InvalidRule(_)
+            | Error::InvalidPath(_)
+            | Error::InvalidMigration(_)
+            | Error::InvalidConfig(_)
+            | Error::InvalidPatch(_)
+            | Error::UnsupportedFormat(_)
+            | Error::UnsupportedMediaType(_)
+            | Error::UnrepresentableValue { .. }
+            | Error::InvalidBody(_)
+            | Error::InvalidLimit(_)
+            | Error::InvalidCursor
+            | Error::InvalidSort(_)
+            | Error::InvalidInclude(_) => Code::InvalidArgument,
+            Error::PayloadTooLarge(_) | Error::RateLimited(_) => Code::ResourceExhausted,
+            Error::PageOutOfRange => Code::OutOfRange,
+            // Retried with the current version
+            Error::SchemaModified(_) | Error::ConfigModified(_) => Code::Aborted,
+            _ => Code::Internal,
+        };
+
+        if code == Code::Internal {
+            tracing::error!(error = %err, "request failed");
+        }
+
+        if let Error::InvalidConfig(diff) = &err {
+            metrics().record_violations(diff.diffs());
+        }
+
+        // Details carry the same body as REST errors
+        let details = serde_json::to_vec(&ErrorDto::from(&err)).unwrap_or_default();
+        let mut status = Status::with_details(code, err.to_string(), details.into());
+        if let Ok(value) = err.code().parse() {
+            status.metadata_mut().insert("x-configd-error-code", value);
+        }
+        if let Error::RateLimited(retry_after) = err {
+            status
+                .metadata_mut()
+                .insert("retry-after", retry_after.to_string().parse().unwrap());
+        }
+
+        status
+    }
+}
+
+// Changes
+#[derive(Deserialize)]
+struct ConfigKey {
+    schema_id: String,
+    id: String,
+}
+
+#[derive(Clone)]
+enum Change {
+    Config { schema_id: String, id: String },
+    // Populated configs change with their schema
+    Schema { id: String },
+    // Any config could have changed
+    Resynced,
+}
+
+// Forwards config events to every watcher. The bus has no unsubscription, so
+// a single handler is registered and watchers subscribe to the channel.
+#[derive(Clone)]
+struct ConfigChanges {
+    sender: broadcast::Sender<Change>,
+}
+
+#[async_trait]
+impl Handler for ConfigChanges {
+    async fn handle(&self, event: &Event) -> Result<(), Error> {
+        let change = if event.topic() == "events.resynced" {
+            Change::Resynced
+        } else if event.topic().starts_with("schema.") {
+            Change::Schema {
+                id: event.entity_id().to_string(),
+            }
+        } else {
+            let key: ConfigKey = event.deserialize_payload()?;
+            Change::Config {
+                schema_id: key.schema_id,
+                id: key.id,
+            }
+        };
+
+        // Fails only when nobody is watching
+        let _ = self.sender.send(change);
+
+        Ok(())
+    }
+}
+
+// Service
+pub struct GrpcService {
+    container: Arc<Container>,
+    changes: broadcast::Sender<Change>,
+}
+
+impl GrpcService {
+    pub async fn new(container: Arc<Container>) -> Result<GrpcService, Error> {
+        let (changes, _) = broadcast::channel(1024);
+
+        for subject in ["config.*", "schema.*", "events.resynced"] {
+            container
+                .event_subscriber
+                .subscribe(
+                    subject,
+                    Box::new(ConfigChanges {
+                        sender: changes.clone(),
+                    }),
+                )
+                .await?;
+        }
+
+        Ok(GrpcService { container, changes })
+    }
+
+    fn get_config(&self) -> GetConfig {
+        GetConfig::new(
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+            self.container.access_store.clone(),
+            self.container.password_lockout.clone(),
+        )
+    }
+}
+
+#[tonic::async_trait]
+impl Configd for GrpcService {
+    type WatchConfigStream = ReceiverStream<Result<proto::Config, Status>>;
+
+    // Schemas
+    async fn list_schemas(
+        &self,
+        req: Request<proto::ListSchemasRequest>,
+    ) -> Result<Response<proto::ListSchemasResponse>, Status> {
+        let req = req.into_inner();
+
+        let serv = ListSchemas::new(
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+        );
+
+        let res = serv
+            .exec(ListSchemasCommand {
+                name: req.name,
+                has_invalid_configs: req.has_invalid_configs,
+                updated_since: req
+                    .updated_since
+                    .map(|updated_since| datetime("updated_since", updated_since))
+                    .transpose()?,
+                sort: req.sort,
+                order: req.order,
+                cursor: req.cursor,
+                offset: req.offset,
+                limit: req.limit,
+                include: req.include,
+            })
+            .await?;
+
+        Ok(Response::new(proto::ListSchemasResponse {
+            offset: res.offset,
+            limit: res.limit,
+            total: res.total,
+            next_cursor: res.next_cursor,
+            data: res
+                .data
+                .into_iter()
+                .map(|schema| proto::Schema {
+                    id: schema.id,
+                    name: schema.name,
+                    schema: schema.schema.to_string(),
+                    rules: schema.rules.to_string(),
+                    configs: schema
+                        .configs
+                        .into_iter()
+                        .flatten()
+                        .map(|config| proto::SchemaConfig {
+                            id: config.id,
+                            name: config.name,
+                            valid: config.valid,
+                            checksum: config.checksum,
+                            requires_password: config.requires_password,
+                            created_at: Some(timestamp(config.created_at)),
+                            updated_at: Some(timestamp(config.updated_at)),
+                            version: config.version,
+                        })
+                        .collect(),
+                    created_at: Some(timestamp(schema.created_at)),
+                    updated_at: Some(timestamp(schema.updated_at)),
+                    version: schema.version,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn get_schema(
+        &self,
+        req: Request<proto::GetSchemaRequest>,
+    ) -> Result<Response<proto::Schema>, Status> {
+        let req = req.into_inner();
+
+        let serv = GetSchema::new(
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+        );
+
+        let schema = serv
+            .exec(GetSchemaCommand {
+                schema_id: req.schema_id,
+                include: req.include,
+            })
+            .await?;
+
+        Ok(Response::new(proto::Schema {
+            id: schema.id,
+            name: schema.name,
+            schema: schema.schema.to_string(),
+            rules: schema.rules.to_string(),
+            configs: schema
+                .configs
+                .into_iter()
+                .flatten()
+                .map(|config| proto::SchemaConfig {
+                    id: config.id,
+                    name: config.name,
+                    valid: config.valid,
+                    checksum: config.checksum,
+                    requires_password: config.requires_password,
+                    created_at: Some(timestamp(config.created_at)),
+                    updated_at: Some(timestamp(config.updated_at)),
+                    version: config.version,
+                })
+                .collect(),
+            created_at: Some(timestamp(schema.created_at)),
+            updated_at: Some(timestamp(schema.updated_at)),
+            version: schema.version,
+        }))
+    }
+
+    async fn create_schema(
+        &self,
+        req: Request<proto::CreateSchemaRequest>,
+    ) -> Result<Response<proto::CreateSchemaResponse>, Status> {
+        let req = req.into_inner();
+
+        let serv = CreateSchema::new(
+            self.container.event_publisher.clone(),
+            self.container.schema_repository.clone(),
+        );
+
+        let res = serv
+            .exec(CreateSchemaCommand {
+                name: req.name,
+                schema: parse_json("schema", &req.schema)?,
+                rules: req
+                    .rules
+                    .map(|rules| parse_json("rules", &rules))
+                    .transpose()?
+                    .unwrap_or(JsonValue::Null),
+            })
+            .await?;
+
+        Ok(Response::new(proto::CreateSchemaResponse { id: res.id }))
+    }
+
+    async fn update_schema(
+        &self,
+        req: Request<proto::UpdateSchemaRequest>,
+    ) -> Result<Response<proto::UpdateSchemaResponse>, Status> {
+        let req = req.into_inner();
+
+        let serv = UpdateSchema::new(
+            self.container.event_publisher.clone(),
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+        );
+
+        let res = serv
+            .exec(UpdateSchemaCommand {
+                schema_id: req.schema_id,
+                schema: parse_json("schema", &req.schema)?,
+                rules: req
+                    .rules
+                    .map(|rules| parse_json("rules", &rules))
+                    .transpose()?,
+                migrations: req
+                    .migrations
+                    .map(|migrations| parse_json("migrations", &migrations))
+                    .transpose()?
+                    .unwrap_or(JsonValue::Null),
+                force: req.force,
+                dry_run: req.dry_run,
+            })
+            .await?;
+
+        Ok(Response::new(proto::UpdateSchemaResponse {
+            id: res.id,
+            compatibility: to_json(&res.compatibility)
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            applied: res.applied,
+            affected_configs: res
+                .affected_configs
+                .into_iter()
+                .map(|config| proto::AffectedConfig {
+                    id: config.id,
+                    name: config.name,
+                    diffs: to_json(&config.diffs).to_string(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn delete_schema(
+        &self,
+        req: Request<proto::DeleteSchemaRequest>,
+    ) -> Result<Response<proto::DeleteSchemaResponse>, Status> {
+        let req = req.into_inner();
+
+        let serv = DeleteSchema::new(
+            self.container.event_publisher.clone(),
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+        );
+
+        let res = serv
+            .exec(DeleteSchemaCommand {
+                schema_id: req.schema_id,
+            })
+            .await?;
+
+        Ok(Response::new(proto::DeleteSchemaResponse {
+            schema_id: res.schema_id,
+        }))
+    }
+
+    async fn validate_config(
+        &self,
+        req: Request<proto::ValidateConfigRequest>,
+    ) -> Result<Response<proto::ValidateConfigResponse>, Status> {
+        let req = req.into_inner();
+
+        let serv = ValidateConfig::new(self.container.schema_repository.clone());
+
+        let res = serv
+            .exec(ValidateConfigCommand {
+                schema_id: req.schema_id,
+                data: parse_json("data", &req.data)?,
+            })
+            .await?;
+        metrics().record_violations(&res.diffs);
+
+        Ok(Response::new(proto::ValidateConfigResponse {
+            diffs: to_json(&res.diffs).to_string(),
+        }))
+    }
+
+    // Configs
+    async fn list_configs(
+        &self,
+        req: Request<proto::ListConfigsRequest>,
+    ) -> Result<Response<proto::ListConfigsResponse>, Status> {
+        let req = req.into_inner();
+
+        let serv = ListConfigs::new(
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+        );
+
+        let res = serv
+            .exec(ListConfigsCommand {
+                schema_id: req.schema_id,
+                name: req.name,
+                valid: req.valid,
+                requires_password: req.requires_password,
+                sort: req.sort,
+                order: req.order,
+                cursor: req.cursor,
+                offset: req.offset,
+                limit: req.limit,
+            })
+            .await?;
+
+        Ok(Response::new(proto::ListConfigsResponse {
+            offset: res.offset,
+            limit: res.limit,
+            total: res.total,
+            next_cursor: res.next_cursor,
+            data: res
+                .data
+                .into_iter()
+                .map(|config| proto::SchemaConfig {
+                    id: config.id,
+                    name: config.name,
+                    valid: config.valid,
+                    checksum: config.checksum,
+                    requires_password: config.requires_password,
+                    created_at: Some(timestamp(config.created_at)),
+                    updated_at: Some(timestamp(config.updated_at)),
+                    version: config.version,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn get_config(
+        &self,
+        req: Request<proto::GetConfigRequest>,
+    ) -> Result<Response<proto::Config>, Status> {
+        let identity = client_identity(&req);
+        let cmd = get_config_command(req.metadata(), identity.as_ref(), req.get_ref());
+
+        self.container
+            .limit_config_read(
+                remote_ip(&req),
+                identity.as_ref(),
+                cmd.source.as_deref(),
+                cmd.instance.as_deref(),
+            )
+            .await?;
+
+        let res = self.get_config().exec(cmd).await?;
+
+        Ok(Response::new(config_message(res)))
+    }
+
+    async fn create_config(
+        &self,
+        req: Request<proto::CreateConfigRequest>,
+    ) -> Result<Response<proto::ConfigRef>, Status> {
+        let req = req.into_inner();
+
+        let serv = CreateConfig::new(
+            self.container.event_publisher.clone(),
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+        );
+
+        let res = serv
+            .exec(CreateConfigCommand {
+                schema_id: req.schema_id,
+                name: req.name,
+                data: parse_json("data", &req.data)?,
+                password: req.password,
+            })
+            .await?;
+
+        Ok(Response::new(proto::ConfigRef {
+            schema_id: res.schema_id,
+            config_id: res.config_id,
+        }))
+    }
+
+    async fn update_config(
+        &self,
+        req: Request<proto::UpdateConfigRequest>,
+    ) -> Result<Response<proto::ConfigRef>, Status> {
+        let password = metadata(req.metadata(), "x-configd-password");
+        let req = req.into_inner();
+
+        let serv = UpdateConfig::new(
+            self.container.event_publisher.clone(),
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+            self.container.password_lockout.clone(),
+        );
+
+        let res = serv
+            .exec(UpdateConfigCommand {
+                schema_id: req.schema_id,
+                config_id: req.config_id,
+                data: parse_json("data", &req.data)?,
+                password,
+            })
+            .await?;
+
+        Ok(Response::new(proto::ConfigRef {
+            schema_id: res.schema_id,
+            config_id: res.config_id,
+        }))
+    }
+
+    async fn patch_config(
+        &self,
+        req: Request<proto::PatchConfigRequest>,
+    ) -> Result<Response<proto::ConfigRef>, Status> {
+        let password = metadata(req.metadata(), "x-configd-password");
+        let req = req.into_inner();
+        let format = match req.format() {
+            proto::PatchFormat::MergePatch => PatchFormat::MergePatch,
+            proto::PatchFormat::JsonPatch => PatchFormat::JsonPatch,
+        };
+
+        let serv = PatchConfig::new(
+            self.container.event_publisher.clone(),
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+            self.container.password_lockout.clone(),
+        );
+
+        let res = serv
+            .exec(PatchConfigCommand {
+                schema_id: req.schema_id,
+                config_id: req.config_id,
+                format,
+                patch: parse_json("patch", &req.patch)?,
+                password,
+            })
+            .await?;
+
+        Ok(Response::new(proto::ConfigRef {
+            schema_id: res.schema_id,
+            config_id: res.config_id,
+        }))
+    }
+
+    async fn delete_config(
+        &self,
+        req: Request<proto::ConfigRef>,
+    ) -> Result<Response<proto::ConfigRef>, Status> {
+        let password = metadata(req.metadata(), "x-configd-password");
+        let req = req.into_inner();
+
+        let serv = DeleteConfig::new(
+            self.container.event_publisher.clone(),
+            self.container.config_repository.clone(),
+            self.container.password_lockout.clone(),
+        );
+
+        let res = serv
+            .exec(DeleteConfigCommand {
+                schema_id: req.schema_id,
+                config_id: req.config_id,
+                password,
+            })
+            .await?;
+
+        Ok(Response::new(proto::ConfigRef {
+            schema_id: res.schema_id,
+            config_id: res.config_id,
+        }))
+    }
+
+    async fn change_config_password(
+        &self,
+        req: Request<proto::ChangeConfigPasswordRequest>,
+    ) -> Result<Response<proto::ConfigRef>, Status> {
+        let old_password = metadata(req.metadata(), "x-configd-password");
+        let req = req.into_inner();
+
+        let serv = ChangeConfigPassword::new(
+            self.container.event_publisher.clone(),
+            self.container.config_repository.clone(),
+            self.container.password_lockout.clone(),
+        );
+
+        let res = serv
+            .exec(ChangeConfigPasswordCommand {
+                schema_id: req.schema_id,
+                config_id: req.config_id,
+                old_password,
+                new_password: req.new_password,
+            })
+            .await?;
+
+        Ok(Response::new(proto::ConfigRef {
+            schema_id: res.schema_id,
+            config_id: res.config_id,
+        }))
+    }
+
+    async fn delete_config_password(
+        &self,
+        req: Request<proto::ConfigRef>,
+    ) -> Result<Response<proto::ConfigRef>, Status> {
+        let password = metadata(req.metadata(), "x-configd-password");
+        let req = req.into_inner();
+
+        let serv = DeleteConfigPassword::new(
+            self.container.event_publisher.clone(),
+            self.container.config_repository.clone(),
+            self.container.password_lockout.clone(),
+        );
+
+        let res = serv
+            .exec(DeleteConfigPasswordCommand {
+                schema_id: req.schema_id,
+                config_id: req.config_id,
+                password,
+            })
+            .await?;
+
+        Ok(Response::new(proto::ConfigRef {
+            schema_id: res.schema_id,
+            config_id: res.config_id,
+        }))
+    }
+
+    async fn diff_configs(
+        &self,
+        req: Request<proto::DiffConfigsRequest>,
+    ) -> Result<Response<proto::DiffConfigsResponse>, Status> {
+        let password = metadata(req.metadata(), "x-configd-password");
+        let other_password = metadata(req.metadata(), "x-configd-other-password");
+        let req = req.into_inner();
+
+        let serv = DiffConfigs::new(
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+            self.container.access_store.clone(),
+            self.container.password_lockout.clone(),
+        );
+
+        let res = serv
+            .exec(DiffConfigsCommand {
+                schema_id: req.schema_id,
+                config_id: req.config_id,
+                other_config_id: req.other_config_id,
+                populate: Some(req.populate),
+                password,
+                other_password,
+            })
+            .await?;
+
+        Ok(Response::new(proto::DiffConfigsResponse {
+            schema_id: res.schema_id,
+            config_id: res.config_id,
+            other_config_id: res.other_config_id,
+            changes: to_json(&res.changes).to_string(),
+        }))
+    }
+
+    // Trash
+    async fn list_deleted_schemas(
+        &self,
+        req: Request<proto::ListDeletedSchemasRequest>,
+    ) -> Result<Response<proto::ListDeletedSchemasResponse>, Status> {
+        let req = req.into_inner();
+
+        let serv = ListDeletedSchemas::new(self.container.schema_repository.clone());
+
+        let res = serv
+            .exec(ListDeletedSchemasCommand {
+                name: req.name,
+                sort: req.sort,
+                order: req.order,
+                cursor: req.cursor,
+                offset: req.offset,
+                limit: req.limit,
+            })
+            .await?;
+
+        Ok(Response::new(proto::ListDeletedSchemasResponse {
+            offset: res.offset,
+            limit: res.limit,
+            total: res.total,
+            next_cursor: res.next_cursor,
+            data: res
+                .data
+                .into_iter()
+                .map(|schema| proto::DeletedSchema {
+                    id: schema.id,
+                    name: schema.name,
+                    created_at: Some(timestamp(schema.created_at)),
+                    updated_at: Some(timestamp(schema.updated_at)),
+                    deleted_at: Some(timestamp(schema.deleted_at)),
+                    version: schema.version,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn restore_schema(
+        &self,
+        req: Request<proto::RestoreSchemaRequest>,
+    ) -> Result<Response<proto::RestoreSchemaResponse>, Status> {
+        let req = req.into_inner();
+
+        let serv = RestoreSchema::new(
+            self.container.event_publisher.clone(),
+            self.container.schema_repository.clone(),
+        );
+
+        let res = serv
+            .exec(RestoreSchemaCommand {
+                schema_id: req.schema_id,
+            })
+            .await?;
+
+        Ok(Response::new(proto::RestoreSchemaResponse {
+            schema_id: res.schema_id,
+        }))
+    }
+
+    async fn purge_schema(
+        &self,
+        req: Request<proto::PurgeSchemaRequest>,
+    ) -> Result<Response<proto::PurgeSchemaResponse>, Status> {
+        let req = req.into_inner();
+
+        let serv = PurgeSchema::new(
+            self.container.event_publisher.clone(),
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+        );
+
+        let res = serv
+            .exec(PurgeSchemaCommand {
+                schema_id: req.schema_id,
+            })
+            .await?;
+
+        Ok(Response::new(proto::PurgeSchemaResponse {
+            schema_id: res.schema_id,
+            purged_configs: res.purged_configs,
+        }))
+    }
+
+    async fn list_deleted_configs(
+        &self,
+        req: Request<proto::ListDeletedConfigsRequest>,
+    ) -> Result<Response<proto::ListDeletedConfigsResponse>, Status> {
+        let req = req.into_inner();
+
+        let serv = ListDeletedConfigs::new(
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+        );
+
+        let res = serv
+            .exec(ListDeletedConfigsCommand {
+                schema_id: req.schema_id,
+                name: req.name,
+                sort: req.sort,
+                order: req.order,
+                cursor: req.cursor,
+                offset: req.offset,
+                limit: req.limit,
+            })
+            .await?;
+
+        Ok(Response::new(proto::ListDeletedConfigsResponse {
+            offset: res.offset,
+            limit: res.limit,
+            total: res.total,
+            next_cursor: res.next_cursor,
+            data: res
+                .data
+                .into_iter()
+                .map(|config| proto::DeletedConfig {
+                    id: config.id,
+                    name: config.name,
+                    valid: config.valid,
+                    requires_password: config.requires_password,
+                    created_at: Some(timestamp(config.created_at)),
+                    updated_at: Some(timestamp(config.updated_at)),
+                    deleted_at: Some(timestamp(config.deleted_at)),
+                    version: config.version,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn restore_config(
+        &self,
+        req: Request<proto::ConfigRef>,
+    ) -> Result<Response<proto::RestoreConfigResponse>, Status> {
+        let password = metadata(req.metadata(), "x-configd-password");
+        let req = req.into_inner();
+
+        let serv = RestoreConfig::new(
+            self.container.event_publisher.clone(),
+            self.container.schema_repository.clone(),
+            self.container.config_repository.clone(),
+            self.container.password_lockout.clone(),
+        );
+
+        let res = serv
+            .exec(RestoreConfigCommand {
+                schema_id: req.schema_id,
+                config_id: req.config_id,
+                password,
+            })
+            .await?;
+
+        Ok(Response::new(proto::RestoreConfigResponse {
+            schema_id: res.schema_id,
+            config_id: res.config_id,
+            valid: res.valid,
+        }))
+    }
+
+    async fn purge_config(
+        &self,
+        req: Request<proto::ConfigRef>,
+    ) -> Result<Response<proto::ConfigRef>, Status> {
+        let password = metadata(req.metadata(), "x-configd-password");
+        let req = req.into_inner();
+
+        let serv = PurgeConfig::new(
+            self.container.event_publisher.clone(),
+            self.container.config_repository.clone(),
+            self.container.password_lockout.clone(),
+        );
+
+        let res = serv
+            .exec(PurgeConfigCommand {
+                schema_id: req.schema_id,
+                config_id: req.config_id,
+                password,
+            })
+            .await?;
+
+        Ok(Response::new(proto::ConfigRef {
+            schema_id: res.schema_id,
+            config_id: res.config_id,
+        }))
+    }
+
+    // The stream ends with an error when the config cannot be read anymore,
+    // e.g. when it is deleted
+    async fn watch_config(
+        &self,
+        req: Request<proto::GetConfigRequest>,
+    ) -> Result<Response<Self::WatchConfigStream>, Status> {
+        let metadata = req.metadata().clone();
+        let identity = client_identity(&req);
+        let ip = remote_ip(&req);
+        let req = req.into_inner();
+        let get_config = self.get_config();
+
+        // Subscribed before the first read so no change is missed
+        let mut changes = self.changes.subscribe();
+
+        // Only opening the stream counts as a read, and the first one fails
+        // the call instead of the stream
+        let cmd = get_config_command(&metadata, identity.as_ref(), &req);
+        self.container
+            .limit_config_read(
+                ip,
+                identity.as_ref(),
+                cmd.source.as_deref(),
+                cmd.instance.as_deref(),
+            )
+            .await?;
+        let res = get_config.exec(cmd).await?;
+
+        let (sender, receiver) = mpsc::channel(16);
+        let tasks = self.container.background_tasks.clone();
+
+        // Re-reads check the password on behalf of the same client
+        let client = current_client();
+        tokio::spawn(with_client(client, async move {
+            let mut last = (res.checksum.clone(), res.version);
+            if sender.send(Ok(config_message(res))).await.is_err() {
+                return;
+            }
+
+            loop {
+                tokio::select! {
+                    _ = sender.closed() => return,
+                    // Clients are expected to reconnect to another node
+                    _ = tasks.closed() => {
+                        let _ = sender.send(Err(Status::unavailable("server shutting down"))).await;
+                        return;
+                    }
+                    change = changes.recv() => match change {
+                        Ok(Change::Config { schema_id, id })
+                            if schema_id == req.schema_id && id == req.config_id => {}
+                        Ok(Change::Schema { id }) if req.populate && id == req.schema_id => {}
+                        Ok(Change::Resynced) => {}
+                        Ok(_) => continue,
+                        // Missed changes could include this config
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => return,
+                    },
+                }
+
+                let cmd = GetConfigCommand {
+                    reread: true,
+                    ..get_config_command(&metadata, identity.as_ref(), &req)
+                };
+                match get_config.exec(cmd).await {
+                    Ok(res) => {
+                        // Accesses are not considered changes
+                        if (res.checksum.clone(), res.version) == last {
+                            continue;
+                        }
+
+                        last = (res.checksum.clone(), res.version);
+                        if sender.send(Ok(config_message(res))).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(err) => {
+                        let _ = sender.send(Err(err.into())).await;
+                        return;
+                    }
+                }
+            }
+        }));
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+fn metadata(metadata: &MetadataMap, key: &str) -> Option<String> {
+    metadata
+        .get(key)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+// Set when the server requires client certificates
+fn client_identity<T>(req: &Request<T>) -> Option<ClientIdentity> {
+    req.extensions()
+        .get::<TlsConnectInfo>()
+        .and_then(|info| info.identity.clone())
+}
+
+// Served over TLS, the address is only in the connect info
+fn remote_ip<T>(req: &Request<T>) -> Option<IpAddr> {
+    req.remote_addr()
+        .or_else(|| {
+            req.extensions()
+                .get::<TlsConnectInfo>()
+                .and_then(|info| info.remote_addr)
+        })
+        .map(|addr| addr.ip())
+}
+
+// The client certificate identity takes precedence over the source header
+fn get_config_command(
+    metadata_map: &MetadataMap,
+    identity: Option<&ClientIdentity>,
+    req: &proto::GetConfigRequest,
+) -> GetConfigCommand {
+    GetConfigCommand {
+        schema_id: req.schema_id.clone(),
+        config_id: req.config_id.clone(),
+        source: identity
+            .map(|identity| identity.0.clone())
+            .or_else(|| metadata(metadata_map, "x-configd-source")),
+        instance: metadata(metadata_map, "x-configd-instance"),
+        password: metadata(metadata_map, "x-configd-password"),
+        populate: Some(req.populate),
+        reread: false,
+    }
+}
+
+fn config_message(res: GetConfigResponse) -> proto::Config {
+    proto::Config {
+        schema_id: res.schema_id,
+        id: res.id,
+        name: res.name,
+        data: res.data.to_string(),
+        valid: res.valid,
+        checksum: res.checksum,
+        requires_password: res.requires_password,
+        accesses: res
+            .accesses
+            .into_iter()
+            .map(|access| proto::Access {
+                source: access.source,
+                instance: access.instance,
+                timestamp: Some(timestamp(access.timestamp)),
+                previous: access.previous.map(timestamp),
+            })
+            .collect(),
+        created_at: Some(timestamp(res.created_at)),
+        updated_at: Some(timestamp(res.updated_at)),
+        version: res.version,
+    }
+}
+
+fn parse_json(field: &str, value: &str) -> Result<JsonValue, Error> {
+    serde_json::from_str(value).map_err(|err| Error::InvalidBody(format!("{}: {}", field, err)))
+}
+
+fn to_json<T: Serialize>(value: &T) -> JsonValue {
+    serde_json::to_value(value).unwrap_or(JsonValue::Null)
+}
+
+fn timestamp(datetime: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: datetime.timestamp(),
+        nanos: datetime.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn datetime(field: &str, timestamp: prost_types::Timestamp) -> Result<DateTime<Utc>, Error> {
+    u32::try_from(timestamp.nanos)
+        .ok()
+        .and_then(|nanos| Utc.timestamp_opt(timestamp.seconds, nanos).single())
+        .ok_or_else(|| Error::InvalidBody(format!("{}: invalid timestamp", field)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+    use tokio_stream::StreamExt;
+
+    use crate::{
+        config::{Config, Cors, Environment, EventBus, LogFormat, Storage},
+        domain::shared::Id,
+    };
+
+    #[test]
+    fn status() {
+        let status = Status::from(Error::ConfigNotFound(Id::new("dev").unwrap()));
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(
+            status.metadata().get("x-configd-error-code").unwrap(),
+            "config_not_found"
+        );
+
+        let details: JsonValue = serde_json::from_slice(status.details()).unwrap();
+        assert_eq!(details["code"], "config_not_found");
+
+        assert_eq!(
+            Status::from(Error::InvalidBody("data".to_string())).code(),
+            Code::InvalidArgument
+        );
+        assert_eq!(
+            Status::from(Error::Unauthorized).code(),
+            Code::Unauthenticated
+        );
+    }
+
+    async fn service() -> GrpcService {
+        let container = Container::build(&Config {
+            env: Environment::Dev,
+            addr: "127.0.0.1:8080".parse().unwrap(),
+            grpc_addr: "127.0.0.1:8081".parse().unwrap(),
+            storage: Storage::InMem,
+            event_bus: EventBus::Local,
+            pool_max_connections: 10,
+            cors: Cors::Any,
+            tls: None,
+            max_body_size: 1024 * 1024,
+            request_timeout: Duration::from_secs(30),
+            rate_limit: 0,
+            ip_rate_limit: 0,
+            password_max_attempts: 5,
+            password_lockout: Duration::from_secs(30),
+            trash_retention: Duration::ZERO,
+            access_flush_interval: Duration::from_secs(60),
+            cache_capacity: 10,
+            cache_ttl: Duration::from_secs(60),
+            log_level: "info".to_string(),
+            log_format: LogFormat::Json,
+            shutdown_timeout: Duration::from_secs(30),
+        })
+        .await
+        .unwrap();
+        GrpcService::new(Arc::new(container)).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn watch_config() {
+        let service = service().await;
+
+        service
+            .create_schema(Request::new(proto::CreateSchemaRequest {
+                name: "App".to_string(),
+                schema: r#"{"port": {"$schema": {"kind": "int", "required": true}}}"#.to_string(),
+                rules: None,
+            }))
+            .await
+            .unwrap();
+        service
+            .create_config(Request::new(proto::CreateConfigRequest {
+                schema_id: "app".to_string(),
+                name: "Dev".to_string(),
+                data: r#"{"port": 1}"#.to_string(),
+                password: None,
+            }))
+            .await
+            .unwrap();
+
+        let mut req = Request::new(proto::GetConfigRequest {
+            schema_id: "app".to_string(),
+            config_id: "dev".to_string(),
+            populate: false,
+        });
+        req.metadata_mut()
+            .insert("x-configd-source", "billing".parse().unwrap());
+        req.metadata_mut()
+            .insert("x-configd-instance", "instance#01".parse().unwrap());
+        let mut stream = service.watch_config(req).await.unwrap().into_inner();
+
+        let config = stream.next().await.unwrap().unwrap();
+        assert_eq!(config.data, r#"{"port":1}"#);
+        assert_eq!(config.accesses.len(), 1);
+        let access = config.accesses[0].clone();
+
+        service
+            .update_config(Request::new(proto::UpdateConfigRequest {
+                schema_id: "app".to_string(),
+                config_id: "dev".to_string(),
+                data: r#"{"port": 2}"#.to_string(),
+            }))
+            .await
+            .unwrap();
+
+        let config = tokio::time::timeout(Duration::from_secs(1), stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.data, r#"{"port":2}"#);
+        assert_eq!(config.version, 2);
+
+        // Re-reads are not accesses
+        assert_eq!(config.accesses, vec![access]);
+        assert!(config.accesses[0].previous.is_none());
+
+        service
+            .delete_config(Request::new(proto::ConfigRef {
+                schema_id: "app".to_string(),
+                config_id: "dev".to_string(),
+            }))
+            .await
+            .unwrap();
+
+        let status = tokio::time::timeout(Duration::from_secs(1), stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn watch_populated_config() {
+        let service = service().await;
+
+        service
+            .create_schema(Request::new(proto::CreateSchemaRequest {
+                name: "App".to_string(),
+                schema: r#"{"port": {"$schema": {"kind": "int", "required": false}}}"#.to_string(),
+                rules: None,
+            }))
+            .await
+            .unwrap();
+        service
+            .create_config(Request::new(proto::CreateConfigRequest {
+                schema_id: "app".to_string(),
+                name: "Dev".to_string(),
+                data: r#"{"port": null}"#.to_string(),
+                password: None,
+            }))
+            .await
+            .unwrap();
+
+        let mut stream = service
+            .watch_config(Request::new(proto::GetConfigRequest {
+                schema_id: "app".to_string(),
+                config_id: "dev".to_string(),
+                populate: true,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let config = stream.next().await.unwrap().unwrap();
+        assert_eq!(config.data, r#"{"port":null}"#);
+
+        // Only the schema changes
+        service
+            .update_schema(Request::new(proto::UpdateSchemaRequest {
+                schema_id: "app".to_string(),
+                schema: r#"{"port": {"$schema": {"kind": "int", "required": false, "default_value": 8080}}}"#
+                    .to_string(),
+                rules: None,
+                migrations: None,
+                force: false,
+                dry_run: false,
+            }))
+            .await
+            .unwrap();
+
+        let config = tokio::time::timeout(Duration::from_secs(1), stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.data, r#"{"port":8080}"#);
+        assert_eq!(config.version, 1);
+    }
+}
@@ -1,22 +1,35 @@
+use async_trait::async_trait;
 use axum::{
-    extract::{Extension, Json, Path, Query},
-    http::{header, StatusCode},
+    body::HttpBody,
+    extract::{ConnectInfo, Extension, FromRequest, Json, MatchedPath, Path, Query, RequestParts},
+    http::{header, Request, StatusCode},
+    middleware::Next,
     response::{IntoResponse, Response},
 };
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc};
+use hyper::body::Buf;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::BTreeMap, net::SocketAddr, sync::Arc, time::Instant};
 
 use crate::{
     application::{
-        ChangeConfigPassword, ChangeConfigPasswordCommand, CreateConfig, CreateConfigCommand,
-        CreateSchema, CreateSchemaCommand, DeleteConfig, DeleteConfigCommand, DeleteConfigPassword,
-        DeleteConfigPasswordCommand, DeleteSchema, DeleteSchemaCommand, GetConfig,
-        GetConfigCommand, GetSchema, GetSchemaCommand, ListSchemas, ListSchemasCommand,
-        UpdateConfig, UpdateConfigCommand, UpdateSchema, UpdateSchemaCommand, ValidateConfig,
-        ValidateConfigCommand,
+        ChangeConfigPassword, ChangeConfigPasswordCommand, CollectStats, CreateConfig,
+        CreateConfigCommand, CreateSchema, CreateSchemaCommand, DeleteConfig, DeleteConfigCommand,
+        DeleteConfigPassword, DeleteConfigPasswordCommand, DeleteSchema, DeleteSchemaCommand,
+        DiffConfigs, DiffConfigsCommand, GetConfig, GetConfigCommand, GetSchema, GetSchemaCommand,
+        ListConfigs, ListConfigsCommand, ListDeletedConfigs, ListDeletedConfigsCommand,
+        ListDeletedSchemas, ListDeletedSchemasCommand, ListSchemas, ListSchemasCommand,
+        PatchConfig, PatchConfigCommand, PatchFormat, PurgeConfig, PurgeConfigCommand, PurgeSchema,
+        PurgeSchemaCommand, RestoreConfig, RestoreConfigCommand, RestoreSchema,
+        RestoreSchemaCommand, UpdateConfig, UpdateConfigCommand, UpdateSchema, UpdateSchemaCommand,
+        ValidateConfig, ValidateConfigCommand,
     },
     container::Container,
-    domain::{errors::Error, values::Reason},
+    domain::{
+        errors::Error,
+        values::{Format, Violation},
+    },
+    infrastructure::{metrics, CacheStats},
+    tls::ClientIdentity,
 };
 
 // Error
@@ -25,7 +38,33 @@ pub struct ErrorDto {
     pub code: String,
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub diffs: Option<HashMap<String, Vec<Reason>>>,
+    pub diffs: Option<BTreeMap<String, Vec<Violation>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub affected_configs: Option<BTreeMap<String, BTreeMap<String, Vec<Violation>>>>,
+}
+
+impl From<&Error> for ErrorDto {
+    fn from(err: &Error) -> ErrorDto {
+        ErrorDto {
+            code: err.code().to_string(),
+            message: err.to_string(),
+            diffs: if let Error::InvalidConfig(diff) = err {
+                Some(diff.diffs().clone())
+            } else {
+                None
+            },
+            affected_configs: if let Error::BreakingSchemaChange(affected_configs) = err {
+                Some(
+                    affected_configs
+                        .iter()
+                        .map(|(id, diff)| (id.to_string(), diff.diffs().clone()))
+                        .collect(),
+                )
+            } else {
+                None
+            },
+        }
+    }
 }
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
@@ -38,38 +77,231 @@ impl IntoResponse for Error {
             | Error::MismatchedKinds { .. }
             | Error::InvalidArray
             | Error::UnknownRootProp
+            | Error::InvalidRule(_)
+            | Error::InvalidPath(_)
+            | Error::InvalidMigration(_)
             | Error::SchemaAlreadyExists(_)
             | Error::SchemaContainsConfigs(_)
             | Error::ConfigAlreadyExists(_)
-            | Error::InvalidConfig(_) => StatusCode::BAD_REQUEST,
+            | Error::InvalidConfig(_)
+            | Error::InvalidPatch(_)
+            | Error::UnsupportedFormat(_)
+            | Error::InvalidBody(_)
+            | Error::InvalidLimit(_)
+            | Error::InvalidCursor
+            | Error::InvalidSort(_)
+            | Error::InvalidInclude(_) => StatusCode::BAD_REQUEST,
+            Error::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Error::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            Error::UnrepresentableValue { .. } => StatusCode::NOT_ACCEPTABLE,
+            Error::BreakingSchemaChange(_)
+            | Error::PatchTestFailed(_)
+            | Error::SchemaModified(_)
+            | Error::ConfigModified(_) => StatusCode::CONFLICT,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
-        let body = ErrorDto {
-            code: self.code().to_string(),
-            message: self.to_string(),
-            diffs: if let Error::InvalidConfig(diff) = self {
-                Some(diff.diffs().clone())
-            } else {
-                None
-            },
+        if status.is_server_error() {
+            tracing::error!(error = %self, "request failed");
+        }
+
+        if let Error::InvalidConfig(diff) = &self {
+            metrics().record_violations(diff.diffs());
+        }
+
+        let mut res = (status, Json(ErrorDto::from(&self))).into_response();
+        if let Error::RateLimited(retry_after) = self {
+            res.headers_mut()
+                .insert(header::RETRY_AFTER, header::HeaderValue::from(retry_after));
+        }
+
+        res
+    }
+}
+
+// Maximum request body size, added as an extension
+#[derive(Clone, Copy)]
+pub struct BodyLimit(pub usize);
+
+// Request body in JSON, YAML or TOML depending on the Content-Type header.
+// JSON is assumed when it is missing.
+pub struct Payload<T>(pub T);
+
+#[async_trait]
+impl<T, B> FromRequest<B> for Payload<T>
+where
+    T: DeserializeOwned,
+    B: HttpBody + Send,
+    B::Data: Send,
+    B::Error: Into<axum::BoxError>,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let format = match req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .map(|header| header.to_str())
+            .transpose()
+            .unwrap_or(None)
+            .and_then(|header| header.split(';').next())
+            .map(|media_type| media_type.trim())
+        {
+            Some(media_type) => Format::from_media_type(media_type)
+                .ok_or_else(|| Error::UnsupportedMediaType(media_type.to_string()))?,
+            None => Format::Json,
         };
 
-        (status, Json(body)).into_response()
+        // Declared sizes are rejected before reading the body
+        let limit = req.extensions().get::<BodyLimit>().map(|limit| limit.0);
+        if let Some(limit) = limit {
+            let content_length = req
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<usize>().ok());
+            if content_length.is_some_and(|length| length > limit) {
+                return Err(Error::PayloadTooLarge(limit));
+            }
+        }
+
+        let body = req
+            .take_body()
+            .ok_or_else(|| Error::InvalidBody("body already extracted".to_string()))?;
+        let bytes = read_body(body, limit).await?;
+        let input =
+            std::str::from_utf8(&bytes).map_err(|err| Error::InvalidBody(err.to_string()))?;
+
+        let value = format.parse(input)?;
+
+        Ok(Payload(
+            serde_json::from_value(value).map_err(|err| Error::InvalidBody(err.to_string()))?,
+        ))
     }
 }
 
+// Chunked bodies have no declared size, so the limit is checked while
+// reading and the rest of the body is never buffered
+async fn read_body<B>(body: B, limit: Option<usize>) -> Result<Vec<u8>, Error>
+where
+    B: HttpBody,
+    B::Error: Into<axum::BoxError>,
+{
+    tokio::pin!(body);
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let mut chunk = chunk.map_err(|err| Error::InvalidBody(err.into().to_string()))?;
+
+        if let Some(limit) = limit {
+            if bytes.len() + chunk.remaining() > limit {
+                return Err(Error::PayloadTooLarge(limit));
+            }
+        }
+
+        while chunk.has_remaining() {
+            let part = chunk.chunk();
+            let len = part.len();
+            bytes.extend_from_slice(part);
+            chunk.advance(len);
+        }
+    }
+
+    Ok(bytes)
+}
+
+// Conditional requests. If-None-Match uses weak comparison.
+fn if_none_match(headers: &header::HeaderMap, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|header| header.to_str().ok())
+        .flat_map(|header| header.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+fn not_modified(etag: String) -> Response {
+    (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response()
+}
+
+// Requests are labeled by their route, not by their path, to keep the
+// number of series bounded
+pub async fn track_metrics<B>(req: Request<B>, next: Next<B>) -> Response {
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+
+    let start = Instant::now();
+    let res = next.run(req).await;
+    metrics().record_request(&method, &route, res.status().as_u16(), start.elapsed());
+
+    res
+}
+
 // General
 pub async fn health() -> &'static str {
     "OK"
 }
 
+pub async fn render_metrics(
+    Extension(container): Extension<Arc<Container>>,
+) -> Result<impl IntoResponse, Error> {
+    let serv = CollectStats::new(
+        container.schema_repository.clone(),
+        container.config_repository.clone(),
+        container.access_store.clone(),
+    );
+
+    let stats = serv.exec().await?;
+
+    let metrics = metrics();
+    metrics.schemas.set(stats.schemas as i64);
+    metrics.configs.set(stats.configs as i64);
+    metrics.invalid_configs.set(stats.invalid_configs as i64);
+    metrics.active_instances.set(stats.active_instances as i64);
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.render(),
+    ))
+}
+
+#[derive(Serialize)]
+pub struct CacheStatsDto {
+    pub schemas: Option<CacheStats>,
+    pub configs: Option<CacheStats>,
+}
+
+pub async fn cache_stats(Extension(container): Extension<Arc<Container>>) -> impl IntoResponse {
+    let schemas = match &container.schema_cache {
+        Some(cache) => Some(cache.stats().await),
+        None => None,
+    };
+    let configs = match &container.config_cache {
+        Some(cache) => Some(cache.stats().await),
+        None => None,
+    };
+
+    (StatusCode::OK, Json(CacheStatsDto { schemas, configs }))
+}
+
 // Schema
 pub async fn list_schemas(
     Query(cmd): Query<ListSchemasCommand>,
     Extension(container): Extension<Arc<Container>>,
 ) -> Result<impl IntoResponse, Error> {
-    let serv = ListSchemas::new(container.schema_repository.clone());
+    let serv = ListSchemas::new(
+        container.schema_repository.clone(),
+        container.config_repository.clone(),
+    );
 
     let res = serv.exec(cmd).await?;
 
@@ -78,17 +310,28 @@ pub async fn list_schemas(
 
 pub async fn get_schema_by_id(
     Path(schema_id): Path<String>,
+    Query(mut cmd): Query<GetSchemaCommand>,
+    headers: header::HeaderMap,
     Extension(container): Extension<Arc<Container>>,
-) -> Result<impl IntoResponse, Error> {
-    let serv = GetSchema::new(container.schema_repository.clone());
+) -> Result<Response, Error> {
+    let serv = GetSchema::new(
+        container.schema_repository.clone(),
+        container.config_repository.clone(),
+    );
 
-    let res = serv.exec(GetSchemaCommand { schema_id }).await?;
+    cmd.schema_id = schema_id;
+    let res = serv.exec(cmd).await?;
 
-    Ok((StatusCode::OK, Json(res)))
+    let etag = res.etag();
+    if if_none_match(&headers, &etag) {
+        return Ok(not_modified(etag));
+    }
+
+    Ok((StatusCode::OK, [(header::ETAG, etag)], Json(res)).into_response())
 }
 
 pub async fn create_schema(
-    Json(cmd): Json<CreateSchemaCommand>,
+    Payload(cmd): Payload<CreateSchemaCommand>,
     Extension(container): Extension<Arc<Container>>,
 ) -> Result<impl IntoResponse, Error> {
     let serv = CreateSchema::new(
@@ -101,16 +344,26 @@ pub async fn create_schema(
     Ok((StatusCode::CREATED, Json(res)))
 }
 
+#[derive(Deserialize)]
+pub struct UpdateSchemaQuery {
+    force: Option<bool>,
+    dry_run: Option<bool>,
+}
+
 pub async fn update_schema(
     Path(schema_id): Path<String>,
-    Json(mut cmd): Json<UpdateSchemaCommand>,
+    Query(query): Query<UpdateSchemaQuery>,
+    Payload(mut cmd): Payload<UpdateSchemaCommand>,
     Extension(container): Extension<Arc<Container>>,
 ) -> Result<impl IntoResponse, Error> {
     cmd.schema_id = schema_id;
+    cmd.force = query.force.unwrap_or(false);
+    cmd.dry_run = query.dry_run.unwrap_or(false);
 
     let serv = UpdateSchema::new(
         container.event_publisher.clone(),
         container.schema_repository.clone(),
+        container.config_repository.clone(),
     );
 
     let res = serv.exec(cmd).await?;
@@ -125,6 +378,7 @@ pub async fn delete_schema(
     let serv = DeleteSchema::new(
         container.event_publisher.clone(),
         container.schema_repository.clone(),
+        container.config_repository.clone(),
     );
 
     let res = serv.exec(DeleteSchemaCommand { schema_id }).await?;
@@ -135,7 +389,7 @@ pub async fn delete_schema(
 // Config
 pub async fn validate_config(
     Path(schema_id): Path<String>,
-    Json(mut cmd): Json<ValidateConfigCommand>,
+    Payload(mut cmd): Payload<ValidateConfigCommand>,
     Extension(container): Extension<Arc<Container>>,
 ) -> Result<impl IntoResponse, Error> {
     cmd.schema_id = schema_id;
@@ -143,6 +397,7 @@ pub async fn validate_config(
     let serv = ValidateConfig::new(container.schema_repository.clone());
 
     let res = serv.exec(cmd).await?;
+    metrics().record_violations(&res.diffs);
 
     Ok((StatusCode::OK, Json(res)))
 }
@@ -152,49 +407,171 @@ pub struct PopulateQuery {
     populate: Option<bool>,
 }
 
+#[derive(Deserialize)]
+pub struct GetConfigQuery {
+    populate: Option<bool>,
+    format: Option<String>,
+}
+
+// JSON returns the whole config while other formats only render its data
 pub async fn get_config_by_id(
     Path((schema_id, config_id)): Path<(String, String)>,
-    Query(cmd): Query<PopulateQuery>,
+    Query(cmd): Query<GetConfigQuery>,
     headers: header::HeaderMap,
+    identity: Option<Extension<ClientIdentity>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     Extension(container): Extension<Arc<Container>>,
-) -> Result<impl IntoResponse, Error> {
+) -> Result<Response, Error> {
+    let format = match cmd.format {
+        Some(format) => format.parse()?,
+        None => headers
+            .get(header::ACCEPT)
+            .map(|header| header.to_str())
+            .transpose()
+            .unwrap_or(None)
+            .and_then(Format::from_accept)
+            .unwrap_or(Format::Json),
+    };
+
+    // A verified client certificate cannot be spoofed by the header
+    let source = match identity {
+        Some(Extension(ref identity)) => Some(identity.0.clone()),
+        None => headers
+            .get("X-Configd-Source")
+            .map(|header| header.to_str())
+            .transpose()
+            .unwrap_or(None)
+            .map(|header| header.to_string()),
+    };
+    let instance = headers
+        .get("X-Configd-Instance")
+        .map(|header| header.to_str())
+        .transpose()
+        .unwrap_or(None)
+        .map(|header| header.to_string());
+
+    container
+        .limit_config_read(
+            connect_info.map(|ConnectInfo(addr)| addr.ip()),
+            identity.as_ref().map(|Extension(identity)| identity),
+            source.as_deref(),
+            instance.as_deref(),
+        )
+        .await?;
+
     let serv = GetConfig::new(
-        container.event_publisher.clone(),
         container.schema_repository.clone(),
+        container.config_repository.clone(),
+        container.access_store.clone(),
+        container.password_lockout.clone(),
     );
 
     let res = serv
         .exec(GetConfigCommand {
             schema_id,
             config_id,
-            source: headers
-                .get("X-Configd-Source")
+            source,
+            instance,
+            password: headers
+                .get("X-Configd-Password")
                 .map(|header| header.to_str())
                 .transpose()
                 .unwrap_or(None)
                 .map(|header| header.to_string()),
-            instance: headers
-                .get("X-Configd-Instance")
+            populate: cmd.populate,
+            reread: false,
+        })
+        .await?;
+
+    // The access is registered even if the config was not modified
+    let etag = match format {
+        Format::Json => res.etag(),
+        format => format!("{}-{}\"", res.etag().trim_end_matches('"'), format),
+    };
+    if if_none_match(&headers, &etag) {
+        return Ok(not_modified(etag));
+    }
+
+    if format == Format::Json {
+        return Ok((
+            StatusCode::OK,
+            [(header::ETAG, etag), (header::VARY, "Accept".to_string())],
+            Json(res),
+        )
+            .into_response());
+    }
+
+    let body = format.render(&res.data.into())?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, format.media_type().to_string()),
+            (header::ETAG, etag),
+            (header::VARY, "Accept".to_string()),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+pub async fn diff_configs(
+    Path((schema_id, config_id, other_config_id)): Path<(String, String, String)>,
+    Query(cmd): Query<PopulateQuery>,
+    headers: header::HeaderMap,
+    Extension(container): Extension<Arc<Container>>,
+) -> Result<impl IntoResponse, Error> {
+    let serv = DiffConfigs::new(
+        container.schema_repository.clone(),
+        container.config_repository.clone(),
+        container.access_store.clone(),
+        container.password_lockout.clone(),
+    );
+
+    let res = serv
+        .exec(DiffConfigsCommand {
+            schema_id,
+            config_id,
+            other_config_id,
+            populate: cmd.populate,
+            password: headers
+                .get("X-Configd-Password")
                 .map(|header| header.to_str())
                 .transpose()
                 .unwrap_or(None)
                 .map(|header| header.to_string()),
-            password: headers
-                .get("X-Configd-Password")
+            other_password: headers
+                .get("X-Configd-Other-Password")
                 .map(|header| header.to_str())
                 .transpose()
                 .unwrap_or(None)
                 .map(|header| header.to_string()),
-            populate: cmd.populate,
         })
         .await?;
 
     Ok((StatusCode::OK, Json(res)))
 }
 
+pub async fn list_configs(
+    Path(schema_id): Path<String>,
+    Query(mut cmd): Query<ListConfigsCommand>,
+    Extension(container): Extension<Arc<Container>>,
+) -> Result<impl IntoResponse, Error> {
+    cmd.schema_id = schema_id;
+
+    let serv = ListConfigs::new(
+        container.schema_repository.clone(),
+        container.config_repository.clone(),
+    );
+
+    let res = serv.exec(cmd).await?;
+
+    Ok((StatusCode::OK, Json(res)))
+}
+
 pub async fn create_config(
     Path(schema_id): Path<String>,
-    Json(mut cmd): Json<CreateConfigCommand>,
+    Payload(mut cmd): Payload<CreateConfigCommand>,
     Extension(container): Extension<Arc<Container>>,
 ) -> Result<impl IntoResponse, Error> {
     cmd.schema_id = schema_id;
@@ -202,6 +579,7 @@ pub async fn create_config(
     let serv = CreateConfig::new(
         container.event_publisher.clone(),
         container.schema_repository.clone(),
+        container.config_repository.clone(),
     );
 
     let res = serv.exec(cmd).await?;
@@ -211,7 +589,7 @@ pub async fn create_config(
 
 pub async fn update_config(
     Path((schema_id, config_id)): Path<(String, String)>,
-    Json(mut cmd): Json<UpdateConfigCommand>,
+    Payload(mut cmd): Payload<UpdateConfigCommand>,
     headers: header::HeaderMap,
     Extension(container): Extension<Arc<Container>>,
 ) -> Result<impl IntoResponse, Error> {
@@ -227,6 +605,8 @@ pub async fn update_config(
     let serv = UpdateConfig::new(
         container.event_publisher.clone(),
         container.schema_repository.clone(),
+        container.config_repository.clone(),
+        container.password_lockout.clone(),
     );
 
     let res = serv.exec(cmd).await?;
@@ -234,9 +614,55 @@ pub async fn update_config(
     Ok((StatusCode::OK, Json(res)))
 }
 
+pub async fn patch_config(
+    Path((schema_id, config_id)): Path<(String, String)>,
+    Payload(patch): Payload<serde_json::Value>,
+    headers: header::HeaderMap,
+    Extension(container): Extension<Arc<Container>>,
+) -> Result<impl IntoResponse, Error> {
+    // Merge Patch is the default for plain JSON bodies
+    let format = match headers
+        .get(header::CONTENT_TYPE)
+        .map(|header| header.to_str())
+        .transpose()
+        .unwrap_or(None)
+        .and_then(|header| header.split(';').next())
+        .map(|media_type| media_type.trim())
+    {
+        Some(media_type) if media_type.eq_ignore_ascii_case("application/json-patch+json") => {
+            PatchFormat::JsonPatch
+        }
+        _ => PatchFormat::MergePatch,
+    };
+
+    let serv = PatchConfig::new(
+        container.event_publisher.clone(),
+        container.schema_repository.clone(),
+        container.config_repository.clone(),
+        container.password_lockout.clone(),
+    );
+
+    let res = serv
+        .exec(PatchConfigCommand {
+            schema_id,
+            config_id,
+            format,
+            patch,
+            password: headers
+                .get("X-Configd-Password")
+                .map(|header| header.to_str())
+                .transpose()
+                .unwrap_or(None)
+                .map(|header| header.to_string()),
+        })
+        .await?;
+
+    Ok((StatusCode::OK, Json(res)))
+}
+
 pub async fn change_config_password(
     Path((schema_id, config_id)): Path<(String, String)>,
-    Json(mut cmd): Json<ChangeConfigPasswordCommand>,
+    Payload(mut cmd): Payload<ChangeConfigPasswordCommand>,
     headers: header::HeaderMap,
     Extension(container): Extension<Arc<Container>>,
 ) -> Result<impl IntoResponse, Error> {
@@ -251,7 +677,8 @@ pub async fn change_config_password(
 
     let serv = ChangeConfigPassword::new(
         container.event_publisher.clone(),
-        container.schema_repository.clone(),
+        container.config_repository.clone(),
+        container.password_lockout.clone(),
     );
 
     let res = serv.exec(cmd).await?;
@@ -266,7 +693,8 @@ pub async fn delete_config_password(
 ) -> Result<impl IntoResponse, Error> {
     let serv = DeleteConfigPassword::new(
         container.event_publisher.clone(),
-        container.schema_repository.clone(),
+        container.config_repository.clone(),
+        container.password_lockout.clone(),
     );
 
     let res = serv
@@ -302,11 +730,627 @@ pub async fn delete_config(
     };
 
     let serv = DeleteConfig::new(
+        container.event_publisher.clone(),
+        container.config_repository.clone(),
+        container.password_lockout.clone(),
+    );
+
+    let res = serv.exec(cmd).await?;
+
+    Ok((StatusCode::OK, Json(res)))
+}
+
+// Trash
+pub async fn list_deleted_schemas(
+    Query(cmd): Query<ListDeletedSchemasCommand>,
+    Extension(container): Extension<Arc<Container>>,
+) -> Result<impl IntoResponse, Error> {
+    let serv = ListDeletedSchemas::new(container.schema_repository.clone());
+
+    let res = serv.exec(cmd).await?;
+
+    Ok((StatusCode::OK, Json(res)))
+}
+
+pub async fn restore_schema(
+    Path(schema_id): Path<String>,
+    Extension(container): Extension<Arc<Container>>,
+) -> Result<impl IntoResponse, Error> {
+    let serv = RestoreSchema::new(
+        container.event_publisher.clone(),
+        container.schema_repository.clone(),
+    );
+
+    let res = serv.exec(RestoreSchemaCommand { schema_id }).await?;
+
+    Ok((StatusCode::OK, Json(res)))
+}
+
+pub async fn purge_schema(
+    Path(schema_id): Path<String>,
+    Extension(container): Extension<Arc<Container>>,
+) -> Result<impl IntoResponse, Error> {
+    let serv = PurgeSchema::new(
+        container.event_publisher.clone(),
+        container.schema_repository.clone(),
+        container.config_repository.clone(),
+    );
+
+    let res = serv.exec(PurgeSchemaCommand { schema_id }).await?;
+
+    Ok((StatusCode::OK, Json(res)))
+}
+
+pub async fn list_deleted_configs(
+    Path(schema_id): Path<String>,
+    Query(mut cmd): Query<ListDeletedConfigsCommand>,
+    Extension(container): Extension<Arc<Container>>,
+) -> Result<impl IntoResponse, Error> {
+    cmd.schema_id = schema_id;
+
+    let serv = ListDeletedConfigs::new(
+        container.schema_repository.clone(),
+        container.config_repository.clone(),
+    );
+
+    let res = serv.exec(cmd).await?;
+
+    Ok((StatusCode::OK, Json(res)))
+}
+
+pub async fn restore_config(
+    Path((schema_id, config_id)): Path<(String, String)>,
+    headers: header::HeaderMap,
+    Extension(container): Extension<Arc<Container>>,
+) -> Result<impl IntoResponse, Error> {
+    let cmd = RestoreConfigCommand {
+        schema_id,
+        config_id,
+        password: headers
+            .get("X-Configd-Password")
+            .map(|header| header.to_str())
+            .transpose()
+            .unwrap_or(None)
+            .map(|header| header.to_string()),
+    };
+
+    let serv = RestoreConfig::new(
         container.event_publisher.clone(),
         container.schema_repository.clone(),
+        container.config_repository.clone(),
+        container.password_lockout.clone(),
+    );
+
+    let res = serv.exec(cmd).await?;
+
+    Ok((StatusCode::OK, Json(res)))
+}
+
+pub async fn purge_config(
+    Path((schema_id, config_id)): Path<(String, String)>,
+    headers: header::HeaderMap,
+    Extension(container): Extension<Arc<Container>>,
+) -> Result<impl IntoResponse, Error> {
+    let cmd = PurgeConfigCommand {
+        schema_id,
+        config_id,
+        password: headers
+            .get("X-Configd-Password")
+            .map(|header| header.to_str())
+            .transpose()
+            .unwrap_or(None)
+            .map(|header| header.to_string()),
+    };
+
+    let serv = PurgeConfig::new(
+        container.event_publisher.clone(),
+        container.config_repository.clone(),
+        container.password_lockout.clone(),
     );
 
     let res = serv.exec(cmd).await?;
 
     Ok((StatusCode::OK, Json(res)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::{json, Value as JsonValue};
+    use std::time::Duration;
+
+    use crate::{
+        config::{Config, Cors, Environment, EventBus, LogFormat, Storage},
+        domain::shared::Id,
+    };
+
+    async fn container() -> Arc<Container> {
+        container_with_limits(0, 0).await
+    }
+
+    async fn container_with_limits(rate_limit: u32, ip_rate_limit: u32) -> Arc<Container> {
+        let container = Container::build(&Config {
+            env: Environment::Dev,
+            addr: "127.0.0.1:8080".parse().unwrap(),
+            grpc_addr: "127.0.0.1:8081".parse().unwrap(),
+            storage: Storage::InMem,
+            event_bus: EventBus::Local,
+            pool_max_connections: 10,
+            cors: Cors::Any,
+            tls: None,
+            max_body_size: 1024 * 1024,
+            request_timeout: Duration::from_secs(30),
+            rate_limit,
+            ip_rate_limit,
+            password_max_attempts: 5,
+            password_lockout: Duration::from_secs(30),
+            trash_retention: Duration::ZERO,
+            access_flush_interval: Duration::from_secs(60),
+            cache_capacity: 10,
+            cache_ttl: Duration::from_secs(60),
+            log_level: "info".to_string(),
+            log_format: LogFormat::Json,
+            shutdown_timeout: Duration::from_secs(30),
+        })
+        .await
+        .unwrap();
+        let container = Arc::new(container);
+
+        create_schema(
+            Payload(
+                serde_json::from_value(json!({
+                    "name": "App",
+                    "schema": {"port": {"$schema": {"kind": "int", "required": true}}},
+                }))
+                .unwrap(),
+            ),
+            Extension(container.clone()),
+        )
+        .await
+        .unwrap();
+        create_config(
+            Path("app".to_string()),
+            Payload(
+                serde_json::from_value(json!({"name": "Dev", "data": {"port": 8080}})).unwrap(),
+            ),
+            Extension(container.clone()),
+        )
+        .await
+        .unwrap();
+
+        container
+    }
+
+    fn headers(values: &[(&'static str, &str)]) -> header::HeaderMap {
+        let mut headers = header::HeaderMap::new();
+        for (name, value) in values {
+            headers.insert(*name, value.parse().unwrap());
+        }
+
+        headers
+    }
+
+    async fn get_config(
+        container: &Arc<Container>,
+        format: Option<&str>,
+        headers: header::HeaderMap,
+    ) -> Response {
+        get_config_by_id(
+            Path(("app".to_string(), "dev".to_string())),
+            Query(GetConfigQuery {
+                populate: None,
+                format: format.map(str::to_string),
+            }),
+            headers,
+            None,
+            None,
+            Extension(container.clone()),
+        )
+        .await
+        .unwrap()
+    }
+
+    fn etag(res: &Response) -> String {
+        res.headers()
+            .get(header::ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    async fn body(res: Response) -> JsonValue {
+        let mut body = res.into_body();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn if_none_match_weak_comparison() {
+        let etag = "W/\"1-abc\"";
+
+        assert!(if_none_match(
+            &headers(&[("if-none-match", "\"0-xyz\", \"1-abc\"")]),
+            etag
+        ));
+        assert!(if_none_match(&headers(&[("if-none-match", "*")]), etag));
+        assert!(!if_none_match(
+            &headers(&[("if-none-match", "W/\"0-abc\"")]),
+            etag
+        ));
+        assert!(!if_none_match(&headers(&[]), etag));
+    }
+
+    #[tokio::test]
+    async fn get_config_rate_limited() {
+        let container = container_with_limits(1, 2).await;
+        let read = |headers: header::HeaderMap, ip: &str| {
+            get_config_by_id(
+                Path(("app".to_string(), "dev".to_string())),
+                Query(GetConfigQuery {
+                    populate: None,
+                    format: None,
+                }),
+                headers,
+                None,
+                Some(ConnectInfo(SocketAddr::new(ip.parse().unwrap(), 4000))),
+                Extension(container.clone()),
+            )
+        };
+        let retry_after = |res: Response| {
+            assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+            res.headers().get(header::RETRY_AFTER).unwrap().clone()
+        };
+        let client = || {
+            headers(&[
+                ("x-configd-source", "billing"),
+                ("x-configd-instance", "instance#01"),
+            ])
+        };
+
+        // By source and instance, whatever the IP address
+        assert!(read(client(), "10.0.0.1").await.is_ok());
+        let res = read(client(), "10.0.0.2")
+            .await
+            .unwrap_err()
+            .into_response();
+        assert_eq!(retry_after(res), "1");
+
+        // By IP address, whatever the client
+        assert!(read(headers(&[]), "10.0.0.3").await.is_ok());
+        assert!(read(headers(&[]), "10.0.0.3").await.is_ok());
+        let res = read(headers(&[]), "10.0.0.3")
+            .await
+            .unwrap_err()
+            .into_response();
+        assert_eq!(retry_after(res), "1");
+    }
+
+    #[tokio::test]
+    async fn get_config_not_modified() {
+        let container = container().await;
+
+        let res = get_config(&container, None, headers(&[])).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Accept");
+        let etag = etag(&res);
+
+        let res = get_config(
+            &container,
+            None,
+            headers(&[
+                ("if-none-match", &etag),
+                ("x-configd-instance", "instance#01"),
+            ]),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(self::etag(&res), etag);
+
+        // The access is registered even if the config was not modified
+        let accesses = container
+            .access_store
+            .active(&Id::new("app").unwrap(), &Id::new("dev").unwrap())
+            .await
+            .unwrap();
+        assert!(accesses
+            .iter()
+            .any(|access| access.instance().value() == "instance#01"));
+    }
+
+    #[tokio::test]
+    async fn get_config_format_etag() {
+        let container = container().await;
+
+        let json = get_config(&container, None, headers(&[])).await;
+        let yaml = get_config(&container, Some("yaml"), headers(&[])).await;
+        assert_eq!(yaml.headers().get(header::VARY).unwrap(), "Accept");
+        assert_ne!(etag(&json), etag(&yaml));
+        assert!(etag(&yaml).ends_with("-yaml\""));
+
+        // Each format is only matched by its own tag
+        let res = get_config(
+            &container,
+            Some("yaml"),
+            headers(&[("if-none-match", &etag(&json))]),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = get_config(
+            &container,
+            None,
+            headers(&[
+                ("accept", "application/yaml"),
+                ("if-none-match", &etag(&yaml)),
+            ]),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    async fn get_config_etag_changes_on_revalidation() {
+        let container = container().await;
+
+        let res = get_config(&container, None, headers(&[])).await;
+        let old_etag = etag(&res);
+
+        // The data doesn't change but the config becomes invalid
+        update_schema(
+            Path("app".to_string()),
+            Query(UpdateSchemaQuery {
+                force: Some(true),
+                dry_run: None,
+            }),
+            Payload(
+                serde_json::from_value(json!({
+                    "schema": {
+                        "port": {"$schema": {"kind": "int", "required": true}},
+                        "host": {"$schema": {"kind": "string", "required": true}},
+                    },
+                }))
+                .unwrap(),
+            ),
+            Extension(container.clone()),
+        )
+        .await
+        .unwrap();
+
+        // Configs are revalidated in the background
+        let mut res = get_config(&container, None, headers(&[])).await;
+        for _ in 0..50 {
+            if etag(&res) != old_etag {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            res = get_config(&container, None, headers(&[])).await;
+        }
+        assert_ne!(etag(&res), old_etag);
+        assert_eq!(body(res).await["valid"], false);
+
+        let res = get_config(&container, None, headers(&[("if-none-match", &old_etag)])).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn get_schema_etag() {
+        let container = container().await;
+
+        let get_schema = |include: Option<&str>, headers: header::HeaderMap| {
+            get_schema_by_id(
+                Path("app".to_string()),
+                Query(GetSchemaCommand {
+                    schema_id: String::new(),
+                    include: include.map(str::to_string),
+                }),
+                headers,
+                Extension(container.clone()),
+            )
+        };
+
+        let res = get_schema(Some(""), headers(&[])).await.unwrap();
+        assert_eq!(etag(&res), "W/\"1\"");
+
+        let res = get_schema(None, headers(&[])).await.unwrap();
+        let old_etag = etag(&res);
+
+        let res = get_schema(None, headers(&[("if-none-match", &old_etag)]))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        // Config changes don't change the schema version
+        update_config(
+            Path(("app".to_string(), "dev".to_string())),
+            Payload(serde_json::from_value(json!({"data": {"port": 8081}})).unwrap()),
+            headers(&[]),
+            Extension(container.clone()),
+        )
+        .await
+        .unwrap();
+
+        let res = get_schema(None, headers(&[("if-none-match", &old_etag)]))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_ne!(etag(&res), old_etag);
+        assert!(etag(&res).starts_with("W/\"1-"));
+    }
+
+    #[tokio::test]
+    async fn payload() {
+        let request = |content_type: &str, body: &str| {
+            let mut req = Request::builder()
+                .header(header::CONTENT_TYPE, content_type)
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap();
+            req.extensions_mut().insert(BodyLimit(32));
+            RequestParts::new(req)
+        };
+
+        let Payload(value) = Payload::<JsonValue>::from_request(&mut request(
+            "application/merge-patch+json",
+            r#"{"port": null}"#,
+        ))
+        .await
+        .unwrap();
+        assert_eq!(value, json!({ "port": null }));
+
+        let Payload(value) =
+            Payload::<JsonValue>::from_request(&mut request("application/yaml", "port: 8080"))
+                .await
+                .unwrap();
+        assert_eq!(value, json!({ "port": 8080 }));
+
+        let err = Payload::<JsonValue>::from_request(&mut request(
+            "application/json",
+            &format!(r#"{{"name": "{}"}}"#, "a".repeat(32)),
+        ))
+        .await
+        .err()
+        .unwrap();
+        assert!(matches!(err, Error::PayloadTooLarge(32)));
+
+        // An endless chunked body is rejected once it crosses the limit
+        let chunks = tokio_stream::iter(std::iter::repeat_with(|| {
+            Ok::<_, std::io::Error>(r#"{"name": "aaaaaaaa"}"#)
+        }));
+        let mut req = Request::builder()
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::wrap_stream(chunks))
+            .unwrap();
+        req.extensions_mut().insert(BodyLimit(32));
+        let err = Payload::<JsonValue>::from_request(&mut RequestParts::new(req))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::PayloadTooLarge(32)));
+    }
+
+    #[tokio::test]
+    async fn patch_config_clears_optional_props() {
+        let container = container().await;
+
+        update_schema(
+            Path("app".to_string()),
+            Query(UpdateSchemaQuery {
+                force: None,
+                dry_run: None,
+            }),
+            Payload(
+                serde_json::from_value(json!({
+                    "schema": {
+                        "port": {"$schema": {"kind": "int", "required": true}},
+                        "host": {"$schema": {"kind": "string", "required": false}},
+                    },
+                    "migrations": [{"op": "set_default", "path": "$.host", "value": "localhost"}],
+                }))
+                .unwrap(),
+            ),
+            Extension(container.clone()),
+        )
+        .await
+        .unwrap();
+
+        let patch = |patch: JsonValue| {
+            patch_config(
+                Path(("app".to_string(), "dev".to_string())),
+                Payload(patch),
+                headers(&[("content-type", "application/merge-patch+json")]),
+                Extension(container.clone()),
+            )
+        };
+
+        patch(json!({ "host": null })).await.unwrap();
+
+        let res = get_config(&container, None, headers(&[])).await;
+        let config = body(res).await;
+        assert_eq!(config["data"], json!({ "port": 8080, "host": null }));
+        assert_eq!(config["valid"], true);
+
+        // Required props cannot be cleared
+        let err = patch(json!({ "port": null })).await.err().unwrap();
+        assert!(matches!(err, Error::InvalidConfig(_)));
+
+        // Media types are case-insensitive and may have parameters
+        patch_config(
+            Path(("app".to_string(), "dev".to_string())),
+            Payload(json!([{ "op": "replace", "path": "/port", "value": 9090 }])),
+            headers(&[("content-type", "Application/JSON-Patch+JSON; charset=utf-8")]),
+            Extension(container.clone()),
+        )
+        .await
+        .unwrap();
+
+        let res = get_config(&container, None, headers(&[])).await;
+        assert_eq!(body(res).await["data"]["port"], 9090);
+    }
+
+    #[tokio::test]
+    async fn diff_configs_with_passwords() {
+        let container = container().await;
+
+        for (name, port, password) in [("Stg", 8081, "stg-secret"), ("Prod", 8080, "prod-secret")] {
+            create_config(
+                Path("app".to_string()),
+                Payload(
+                    serde_json::from_value(json!({
+                        "name": name,
+                        "data": {"port": port},
+                        "password": password,
+                    }))
+                    .unwrap(),
+                ),
+                Extension(container.clone()),
+            )
+            .await
+            .unwrap();
+        }
+
+        let diff = |other_config_id: &str, headers: header::HeaderMap| {
+            diff_configs(
+                Path((
+                    "app".to_string(),
+                    "stg".to_string(),
+                    other_config_id.to_string(),
+                )),
+                Query(PopulateQuery { populate: None }),
+                headers,
+                Extension(container.clone()),
+            )
+        };
+
+        let res = diff(
+            "prod",
+            headers(&[
+                ("x-configd-password", "stg-secret"),
+                ("x-configd-other-password", "prod-secret"),
+            ]),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        let changes = body(res).await["changes"].clone();
+        assert_eq!(changes.as_array().unwrap().len(), 1);
+        assert_eq!(changes[0]["path"], "$.port");
+
+        // The password is used for both configs when the other one is missing
+        let err = diff("prod", headers(&[("x-configd-password", "stg-secret")]))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::Unauthorized));
+
+        assert!(
+            diff("dev", headers(&[("x-configd-password", "stg-secret")]))
+                .await
+                .is_ok()
+        );
+    }
+}
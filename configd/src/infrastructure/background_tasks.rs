@@ -0,0 +1,125 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::{watch, Notify};
+
+// Tasks spawned outside of a request, like async event handlers. On shutdown
+// they are signaled to stop taking new work and awaited up to a deadline.
+pub struct BackgroundTasks {
+    running: AtomicUsize,
+    idle: Notify,
+    closing: watch::Sender<bool>,
+    closed: watch::Receiver<bool>,
+}
+
+impl BackgroundTasks {
+    pub fn new() -> BackgroundTasks {
+        let (closing, closed) = watch::channel(false);
+
+        BackgroundTasks {
+            running: AtomicUsize::new(0),
+            idle: Notify::new(),
+            closing,
+            closed,
+        }
+    }
+
+    pub fn spawn<F>(self: &Arc<Self>, f: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.running.fetch_add(1, Ordering::SeqCst);
+
+        // Released even if the task panics
+        let guard = Running(self.clone());
+        tokio::spawn(async move {
+            f.await;
+            drop(guard);
+        });
+    }
+
+    pub fn running(&self) -> usize {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    pub fn close(&self) {
+        let _ = self.closing.send(true);
+    }
+
+    // Resolves once close is called
+    pub async fn closed(&self) {
+        let mut closed = self.closed.clone();
+        while !*closed.borrow() {
+            if closed.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    // Returns false if some tasks were still running at the deadline
+    pub async fn wait(&self, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let idle = self.idle.notified();
+                if self.running() == 0 {
+                    return;
+                }
+
+                idle.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+}
+
+struct Running(Arc<BackgroundTasks>);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        if self.0.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn wait_for_tasks() {
+        let tasks = Arc::new(BackgroundTasks::new());
+
+        tasks.spawn(async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        });
+        assert_eq!(tasks.running(), 1);
+        assert!(!tasks.wait(Duration::from_millis(10)).await);
+
+        assert!(tasks.wait(Duration::from_secs(1)).await);
+        assert_eq!(tasks.running(), 0);
+
+        // Panics are released too
+        tasks.spawn(async { panic!("handler failed") });
+        assert!(tasks.wait(Duration::from_secs(1)).await);
+    }
+
+    #[tokio::test]
+    async fn close() {
+        let tasks = Arc::new(BackgroundTasks::new());
+
+        let closed = {
+            let tasks = tasks.clone();
+            tokio::spawn(async move { tasks.closed().await })
+        };
+
+        tasks.close();
+        closed.await.unwrap();
+    }
+}
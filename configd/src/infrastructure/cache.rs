@@ -0,0 +1,222 @@
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::Hash,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+use crate::domain::errors::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+struct Entry<V> {
+    value: V,
+    inserted_at: Instant,
+    last_used: Instant,
+}
+
+struct State<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    // Incremented on every invalidation. Values loaded while it changed may be
+    // stale, so they are not stored.
+    generation: u64,
+}
+
+// Bounded cache with a time to live per entry. The least recently used entry
+// is evicted when it is full.
+pub struct Cache<K, V> {
+    capacity: usize,
+    ttl: Duration,
+    state: Mutex<State<K, V>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new(capacity: usize, ttl: Duration) -> Cache<K, V> {
+        Cache {
+            capacity,
+            ttl,
+            state: Mutex::new(State {
+                entries: HashMap::new(),
+                generation: 0,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    // Missing values are not cached
+    pub async fn get_or_load<F, Fut>(&self, key: &K, load: F) -> Result<Option<V>, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<V>, Error>>,
+    {
+        let generation = {
+            let mut state = self.state.lock().await;
+
+            if let Some(entry) = state.entries.get_mut(key) {
+                if entry.inserted_at.elapsed() < self.ttl {
+                    entry.last_used = Instant::now();
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(Some(entry.value.clone()));
+                }
+
+                state.entries.remove(key);
+            }
+
+            state.generation
+        };
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let value = load().await?;
+
+        if let Some(value) = &value {
+            let mut state = self.state.lock().await;
+
+            if state.generation == generation && self.capacity > 0 {
+                if !state.entries.contains_key(key) && state.entries.len() >= self.capacity {
+                    self.evict(&mut state);
+                }
+
+                let now = Instant::now();
+                state.entries.insert(
+                    key.clone(),
+                    Entry {
+                        value: value.clone(),
+                        inserted_at: now,
+                        last_used: now,
+                    },
+                );
+            }
+        }
+
+        Ok(value)
+    }
+
+    pub async fn invalidate(&self, key: &K) {
+        let mut state = self.state.lock().await;
+
+        state.generation += 1;
+        state.entries.remove(key);
+    }
+
+    // Used when invalidations could have been missed
+    pub async fn clear(&self) {
+        let mut state = self.state.lock().await;
+
+        state.generation += 1;
+        state.entries.clear();
+    }
+
+    pub async fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.state.lock().await.entries.len(),
+            capacity: self.capacity,
+        }
+    }
+
+    fn evict(&self, state: &mut State<K, V>) {
+        state
+            .entries
+            .retain(|_, entry| entry.inserted_at.elapsed() < self.ttl);
+
+        if state.entries.len() < self.capacity {
+            return;
+        }
+
+        let least_recently_used = state
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+
+        if let Some(key) = least_recently_used {
+            state.entries.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn load(value: i64) -> Result<Option<i64>, Error> {
+        Ok(Some(value))
+    }
+
+    #[tokio::test]
+    async fn hits_and_misses() {
+        let cache: Cache<&str, i64> = Cache::new(2, Duration::from_secs(60));
+
+        assert_eq!(cache.get_or_load(&"a", || load(1)).await.unwrap(), Some(1));
+        assert_eq!(cache.get_or_load(&"a", || load(2)).await.unwrap(), Some(1));
+        assert_eq!(cache.get_or_load(&"b", || load(2)).await.unwrap(), Some(2));
+
+        // "b" is the least recently used one
+        cache.get_or_load(&"a", || load(1)).await.unwrap();
+        cache.get_or_load(&"c", || load(3)).await.unwrap();
+        assert_eq!(cache.get_or_load(&"b", || load(4)).await.unwrap(), Some(4));
+
+        // Missing values are not cached
+        cache
+            .get_or_load(&"d", || async { Ok(None) })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            cache.stats().await,
+            CacheStats {
+                hits: 2,
+                misses: 5,
+                entries: 2,
+                capacity: 2,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn expiration_and_invalidation() {
+        let cache: Cache<&str, i64> = Cache::new(10, Duration::from_millis(20));
+
+        cache.get_or_load(&"a", || load(1)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(cache.get_or_load(&"a", || load(2)).await.unwrap(), Some(2));
+
+        cache.invalidate(&"a").await;
+        assert_eq!(cache.get_or_load(&"a", || load(3)).await.unwrap(), Some(3));
+
+        // A value loaded while an invalidation happens is not stored
+        cache.invalidate(&"a").await;
+        let value = cache
+            .get_or_load(&"a", || async {
+                cache.invalidate(&"a").await;
+                Ok(Some(4))
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, Some(4));
+        assert_eq!(cache.get_or_load(&"a", || load(5)).await.unwrap(), Some(5));
+
+        cache.get_or_load(&"b", || load(1)).await.unwrap();
+        cache.clear().await;
+        assert_eq!(cache.get_or_load(&"a", || load(6)).await.unwrap(), Some(6));
+        assert_eq!(cache.get_or_load(&"b", || load(2)).await.unwrap(), Some(2));
+    }
+}
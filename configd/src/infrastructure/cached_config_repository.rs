@@ -0,0 +1,101 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::{
+    domain::{
+        configs::{Config, ConfigCount, ConfigQuery, ConfigRepository},
+        errors::Error,
+        events::{Event, Handler},
+        shared::{Id, Page},
+    },
+    infrastructure::Cache,
+};
+
+// Every config event identifies the config it belongs to
+#[derive(Deserialize)]
+struct ConfigKey {
+    schema_id: String,
+    id: String,
+}
+
+// Read-through cache over any config repository, invalidated the same way as
+// the CachedSchemaRepository
+#[derive(Clone)]
+pub struct CachedConfigRepository {
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    cache: Arc<Cache<(Id, Id), Config>>,
+}
+
+impl CachedConfigRepository {
+    pub fn new(
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+        cache: Arc<Cache<(Id, Id), Config>>,
+    ) -> CachedConfigRepository {
+        CachedConfigRepository {
+            config_repository,
+            cache,
+        }
+    }
+}
+
+#[async_trait]
+impl ConfigRepository for CachedConfigRepository {
+    async fn find(&self, schema_id: &Id, query: &ConfigQuery) -> Result<Page<Config>, Error> {
+        self.config_repository.find(schema_id, query).await
+    }
+
+    async fn find_by_id(&self, schema_id: &Id, id: &Id) -> Result<Option<Config>, Error> {
+        self.cache
+            .get_or_load(&(schema_id.clone(), id.clone()), || {
+                self.config_repository.find_by_id(schema_id, id)
+            })
+            .await
+    }
+
+    async fn find_deleted_by_id(&self, schema_id: &Id, id: &Id) -> Result<Option<Config>, Error> {
+        self.config_repository
+            .find_deleted_by_id(schema_id, id)
+            .await
+    }
+
+    // Deleted configs are not cached
+    async fn exists(&self, schema_id: &Id, id: &Id) -> Result<bool, Error> {
+        if self.find_by_id(schema_id, id).await?.is_some() {
+            return Ok(true);
+        }
+
+        self.config_repository.exists(schema_id, id).await
+    }
+
+    async fn count(&self) -> Result<ConfigCount, Error> {
+        self.config_repository.count().await
+    }
+
+    async fn save(&self, config: &mut Config) -> Result<(), Error> {
+        let res = self.config_repository.save(config).await;
+
+        self.cache
+            .invalidate(&(config.schema_id().clone(), config.id().clone()))
+            .await;
+
+        res
+    }
+}
+
+#[async_trait]
+impl Handler for CachedConfigRepository {
+    async fn handle(&self, event: &Event) -> Result<(), Error> {
+        if event.topic() == "events.resynced" {
+            self.cache.clear().await;
+        } else if event.topic().starts_with("config.") {
+            let key: ConfigKey = event.deserialize_payload()?;
+
+            self.cache
+                .invalidate(&(Id::new(key.schema_id)?, Id::new(key.id)?))
+                .await;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::{
+    domain::{
+        configs::Config,
+        errors::Error,
+        events::{Event, Handler},
+        schemas::{Schema, SchemaQuery, SchemaRepository},
+        shared::{Id, Page},
+    },
+    infrastructure::Cache,
+};
+
+// Read-through cache over any schema repository. Entries are invalidated when
+// a schema is saved and when schema events are received, so changes made by
+// other instances are seen too. Everything is dropped when events could have
+// been missed.
+#[derive(Clone)]
+pub struct CachedSchemaRepository {
+    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    cache: Arc<Cache<Id, Schema>>,
+    // Configs saved along with their schema
+    config_cache: Arc<Cache<(Id, Id), Config>>,
+}
+
+impl CachedSchemaRepository {
+    pub fn new(
+        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+        cache: Arc<Cache<Id, Schema>>,
+        config_cache: Arc<Cache<(Id, Id), Config>>,
+    ) -> CachedSchemaRepository {
+        CachedSchemaRepository {
+            schema_repository,
+            cache,
+            config_cache,
+        }
+    }
+}
+
+#[async_trait]
+impl SchemaRepository for CachedSchemaRepository {
+    async fn find(&self, query: &SchemaQuery) -> Result<Page<Schema>, Error> {
+        self.schema_repository.find(query).await
+    }
+
+    async fn find_by_id(&self, id: &Id) -> Result<Option<Schema>, Error> {
+        self.cache
+            .get_or_load(id, || self.schema_repository.find_by_id(id))
+            .await
+    }
+
+    async fn find_deleted_by_id(&self, id: &Id) -> Result<Option<Schema>, Error> {
+        self.schema_repository.find_deleted_by_id(id).await
+    }
+
+    // Deleted schemas are not cached
+    async fn exists(&self, id: &Id) -> Result<bool, Error> {
+        if self.find_by_id(id).await?.is_some() {
+            return Ok(true);
+        }
+
+        self.schema_repository.exists(id).await
+    }
+
+    async fn count(&self) -> Result<u64, Error> {
+        self.schema_repository.count().await
+    }
+
+    async fn save(&self, schema: &mut Schema) -> Result<(), Error> {
+        let res = self.schema_repository.save(schema).await;
+
+        // Invalidated even on failure, the cached version may be outdated
+        self.cache.invalidate(schema.id()).await;
+
+        res
+    }
+
+    async fn save_with_configs(
+        &self,
+        schema: &mut Schema,
+        configs: &mut [Config],
+    ) -> Result<(), Error> {
+        let res = self
+            .schema_repository
+            .save_with_configs(schema, configs)
+            .await;
+
+        self.cache.invalidate(schema.id()).await;
+        for config in configs.iter() {
+            self.config_cache
+                .invalidate(&(config.schema_id().clone(), config.id().clone()))
+                .await;
+        }
+
+        res
+    }
+}
+
+#[async_trait]
+impl Handler for CachedSchemaRepository {
+    async fn handle(&self, event: &Event) -> Result<(), Error> {
+        if event.topic() == "events.resynced" {
+            self.cache.clear().await;
+        } else if event.topic().starts_with("schema.") {
+            self.cache.invalidate(&Id::new(event.entity_id())?).await;
+        }
+
+        Ok(())
+    }
+}
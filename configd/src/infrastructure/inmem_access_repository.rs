@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    configs::{Access, AccessRepository},
+    errors::Error,
+    shared::Id,
+};
+
+pub struct InMemAccessRepository {
+    items: RwLock<HashMap<(Id, Id), Vec<Access>>>,
+}
+
+impl InMemAccessRepository {
+    pub fn new() -> InMemAccessRepository {
+        InMemAccessRepository {
+            items: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl AccessRepository for InMemAccessRepository {
+    async fn find_active_by_config(
+        &self,
+        schema_id: &Id,
+        config_id: &Id,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Access>, Error> {
+        Ok(self
+            .items
+            .read()
+            .await
+            .get(&(schema_id.clone(), config_id.clone()))
+            .into_iter()
+            .flatten()
+            .filter(|access| access.expires_at() >= now)
+            .cloned()
+            .collect())
+    }
+
+    async fn save(&self, schema_id: &Id, config_id: &Id, accesses: &[Access]) -> Result<(), Error> {
+        let mut items = self.items.write().await;
+        let saved_accesses = items
+            .entry((schema_id.clone(), config_id.clone()))
+            .or_default();
+
+        for access in accesses.iter() {
+            match saved_accesses.iter_mut().find(|a| a.equals(access)) {
+                Some(saved) if saved.timestamp() < access.timestamp() => *saved = access.clone(),
+                Some(_) => {}
+                None => saved_accesses.push(access.clone()),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn delete_by_config(&self, schema_id: &Id, config_id: &Id) -> Result<(), Error> {
+        self.items
+            .write()
+            .await
+            .remove(&(schema_id.clone(), config_id.clone()));
+
+        Ok(())
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<(), Error> {
+        let mut items = self.items.write().await;
+
+        for accesses in items.values_mut() {
+            accesses.retain(|access| access.expires_at() >= now);
+        }
+        items.retain(|_, accesses| !accesses.is_empty());
+
+        Ok(())
+    }
+
+    async fn count_active_instances(&self, now: DateTime<Utc>) -> Result<u64, Error> {
+        let items = self.items.read().await;
+
+        let instances: HashSet<(&Id, &Id)> = items
+            .values()
+            .flatten()
+            .filter(|access| access.expires_at() >= now)
+            .map(|access| (access.source(), access.instance()))
+            .collect();
+
+        Ok(instances.len() as u64)
+    }
+}
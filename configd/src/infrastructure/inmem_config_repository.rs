@@ -0,0 +1,192 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    configs::{Config, ConfigCount, ConfigQuery, ConfigRepository},
+    errors::Error,
+    shared::{Id, Page, Version},
+};
+
+pub struct InMemConfigRepository {
+    items: RwLock<Items>,
+}
+
+impl InMemConfigRepository {
+    pub fn new() -> InMemConfigRepository {
+        InMemConfigRepository {
+            items: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    // Stores none of the configs if any of them was modified, as the SQL
+    // repositories do within a transaction
+    pub async fn save_all(&self, configs: &mut [Config]) -> Result<(), Error> {
+        let mut items = self.items.write().await;
+
+        for config in configs.iter() {
+            check_version(&items, config)?;
+        }
+
+        for config in configs.iter() {
+            store(&mut items, config)?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn has_configs_deleted_before(
+        &self,
+        schema_id: &Id,
+        deleted_before: &DateTime<Utc>,
+    ) -> bool {
+        self.items.read().await.values().any(|config| {
+            config.schema_id() == schema_id
+                && config
+                    .timestamps()
+                    .deleted_at()
+                    .is_some_and(|deleted_at| deleted_at < deleted_before)
+        })
+    }
+
+    pub async fn has_invalid_configs(&self, schema_id: &Id) -> bool {
+        self.items.read().await.values().any(|config| {
+            config.schema_id() == schema_id
+                && config.timestamps().deleted_at().is_none()
+                && !config.is_valid()
+        })
+    }
+}
+
+#[async_trait]
+impl ConfigRepository for InMemConfigRepository {
+    async fn find(&self, schema_id: &Id, query: &ConfigQuery) -> Result<Page<Config>, Error> {
+        let items = self.items.read().await;
+        let mut configs: Vec<&Config> = items
+            .values()
+            .filter(|config| config.schema_id() == schema_id && query.matches(config))
+            .collect();
+        configs.sort_by(|a, b| query.compare(a, b));
+
+        Page::new(
+            query.offset,
+            query.limit,
+            configs.len() as u64,
+            configs
+                .into_iter()
+                .filter(|config| query.is_after_cursor(config))
+                .skip(query.offset as usize)
+                .take(query.limit as usize)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    async fn find_by_id(&self, schema_id: &Id, id: &Id) -> Result<Option<Config>, Error> {
+        Ok(self
+            .items
+            .read()
+            .await
+            .get(&(schema_id.to_string(), id.to_string()))
+            .filter(|config| config.timestamps().deleted_at().is_none())
+            .cloned())
+    }
+
+    async fn find_deleted_by_id(&self, schema_id: &Id, id: &Id) -> Result<Option<Config>, Error> {
+        Ok(self
+            .items
+            .read()
+            .await
+            .get(&(schema_id.to_string(), id.to_string()))
+            .filter(|config| config.timestamps().deleted_at().is_some())
+            .cloned())
+    }
+
+    async fn exists(&self, schema_id: &Id, id: &Id) -> Result<bool, Error> {
+        Ok(self
+            .items
+            .read()
+            .await
+            .contains_key(&(schema_id.to_string(), id.to_string())))
+    }
+
+    async fn count(&self) -> Result<ConfigCount, Error> {
+        let items = self.items.read().await;
+
+        let mut count = ConfigCount::default();
+        for config in items
+            .values()
+            .filter(|config| config.timestamps().deleted_at().is_none())
+        {
+            count.total += 1;
+            if !config.is_valid() {
+                count.invalid += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    async fn save(&self, config: &mut Config) -> Result<(), Error> {
+        let mut items = self.items.write().await;
+
+        check_version(&items, config)?;
+        store(&mut items, config)
+    }
+}
+
+type Items = BTreeMap<(String, String), Config>;
+
+fn key(config: &Config) -> (String, String) {
+    (config.schema_id().to_string(), config.id().to_string())
+}
+
+// Only changed from the version it was loaded with, as the SQL repositories do
+fn check_version(items: &Items, config: &Config) -> Result<(), Error> {
+    let created = config
+        .events()
+        .iter()
+        .any(|event| event.topic() == "config.created");
+    if created || config.events().is_empty() {
+        return Ok(());
+    }
+
+    let current = items
+        .get(&key(config))
+        .map(|stored| stored.version().value());
+    if current != Some(config.version().original()) {
+        return Err(Error::ConfigModified(config.id().clone()));
+    }
+
+    Ok(())
+}
+
+fn store(items: &mut Items, config: &Config) -> Result<(), Error> {
+    if config
+        .events()
+        .iter()
+        .any(|event| event.topic() == "config.purged")
+    {
+        items.remove(&key(config));
+        return Ok(());
+    }
+
+    // Stored without the recorded events and as a fresh version, as the
+    // other repositories do
+    let stored = Config::new(
+        config.schema_id().clone(),
+        config.id().clone(),
+        config.name().to_string(),
+        config.data().clone(),
+        config.is_valid(),
+        config.password().cloned(),
+        config.timestamps().clone(),
+        Version::new(config.version().value())?,
+        None,
+    )?;
+
+    items.insert(key(config), stored);
+
+    Ok(())
+}
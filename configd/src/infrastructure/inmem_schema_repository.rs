@@ -1,63 +1,176 @@
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 use tokio::sync::RwLock;
 
-use crate::domain::{
-    errors::Error,
-    schemas::{Schema, SchemaRepository},
-    shared::{Id, Page},
+use crate::{
+    domain::{
+        configs::Config,
+        errors::Error,
+        schemas::{Schema, SchemaQuery, SchemaRepository},
+        shared::{Id, Page, Version},
+    },
+    infrastructure::InMemConfigRepository,
 };
 
 pub struct InMemSchemaRepository {
     items: RwLock<HashMap<Id, Schema>>,
+    // Needed to filter schemas by their configs and to save them along with
+    // the schema, as the SQL repositories do with the configs table
+    config_repository: Arc<InMemConfigRepository>,
 }
 
 impl InMemSchemaRepository {
-    pub fn new() -> InMemSchemaRepository {
+    pub fn new(config_repository: Arc<InMemConfigRepository>) -> InMemSchemaRepository {
         InMemSchemaRepository {
             items: RwLock::new(HashMap::new()),
+            config_repository,
         }
     }
 }
 
 #[async_trait]
 impl SchemaRepository for InMemSchemaRepository {
-    async fn find(&self, offset: Option<u64>, limit: Option<u64>) -> Result<Page<Schema>, Error> {
-        let offset = offset.unwrap_or(0);
-        let mut limit = limit.unwrap_or(10);
-        if limit > 100 {
-            limit = 100;
-        }
-
+    async fn find(&self, query: &SchemaQuery) -> Result<Page<Schema>, Error> {
         let items = self.items.read().await;
 
+        let mut schemas = Vec::new();
+        for schema in items.values().filter(|schema| query.matches(schema)) {
+            if let Some(has_invalid_configs) = query.has_invalid_configs {
+                let invalid = self
+                    .config_repository
+                    .has_invalid_configs(schema.id())
+                    .await;
+                if invalid != has_invalid_configs {
+                    continue;
+                }
+            }
+
+            if let Some(configs_deleted_before) = &query.configs_deleted_before {
+                let deleted = self
+                    .config_repository
+                    .has_configs_deleted_before(schema.id(), configs_deleted_before)
+                    .await;
+                if !deleted {
+                    continue;
+                }
+            }
+
+            schemas.push(schema);
+        }
+        schemas.sort_by(|a, b| query.compare(a, b));
+
         Page::new(
-            offset,
-            limit,
-            items.len() as u64,
-            items
-                .values()
-                .skip(offset as usize)
-                .take(limit as usize)
-                .map(Clone::clone)
+            query.offset,
+            query.limit,
+            schemas.len() as u64,
+            schemas
+                .into_iter()
+                .filter(|schema| query.is_after_cursor(schema))
+                .skip(query.offset as usize)
+                .take(query.limit as usize)
+                .cloned()
                 .collect(),
         )
     }
 
     async fn find_by_id(&self, id: &Id) -> Result<Option<Schema>, Error> {
-        Ok(self.items.read().await.get(id).cloned())
+        Ok(self
+            .items
+            .read()
+            .await
+            .get(id)
+            .filter(|schema| schema.timestamps().deleted_at().is_none())
+            .cloned())
+    }
+
+    async fn find_deleted_by_id(&self, id: &Id) -> Result<Option<Schema>, Error> {
+        Ok(self
+            .items
+            .read()
+            .await
+            .get(id)
+            .filter(|schema| schema.timestamps().deleted_at().is_some())
+            .cloned())
     }
 
     async fn exists(&self, id: &Id) -> Result<bool, Error> {
         Ok(self.items.read().await.contains_key(id))
     }
 
-    async fn save(&self, schema: &mut Schema) -> Result<(), Error> {
-        self.items
-            .write()
+    async fn count(&self) -> Result<u64, Error> {
+        Ok(self
+            .items
+            .read()
             .await
-            .insert(schema.id().clone(), schema.clone());
+            .values()
+            .filter(|schema| schema.timestamps().deleted_at().is_none())
+            .count() as u64)
+    }
 
-        Ok(())
+    async fn save(&self, schema: &mut Schema) -> Result<(), Error> {
+        let mut items = self.items.write().await;
+
+        check_version(&items, schema)?;
+        store(&mut items, schema)
     }
+
+    // The schema is locked while the configs are saved
+    async fn save_with_configs(
+        &self,
+        schema: &mut Schema,
+        configs: &mut [Config],
+    ) -> Result<(), Error> {
+        let mut items = self.items.write().await;
+
+        check_version(&items, schema)?;
+        self.config_repository.save_all(configs).await?;
+        store(&mut items, schema)
+    }
+}
+
+// Only changed from the version it was loaded with, as the SQL repositories do
+fn check_version(items: &HashMap<Id, Schema>, schema: &Schema) -> Result<(), Error> {
+    let created = schema
+        .events()
+        .iter()
+        .any(|event| event.topic() == "schema.created");
+    if created {
+        return Ok(());
+    }
+
+    let current = items
+        .get(schema.id())
+        .map(|stored| stored.version().value());
+    if current != Some(schema.version().original()) {
+        return Err(Error::SchemaModified(schema.id().clone()));
+    }
+
+    Ok(())
+}
+
+fn store(items: &mut HashMap<Id, Schema>, schema: &Schema) -> Result<(), Error> {
+    if schema
+        .events()
+        .iter()
+        .any(|event| event.topic() == "schema.purged")
+    {
+        items.remove(schema.id());
+        return Ok(());
+    }
+
+    // Stored without the recorded events and as a fresh version, as the
+    // other repositories do
+    let stored = Schema::new(
+        schema.id().clone(),
+        schema.name().to_string(),
+        schema.root_prop().clone(),
+        schema.rules().to_vec(),
+        schema.timestamps().clone(),
+        Version::new(schema.version().value())?,
+        None,
+    )?;
+
+    items.insert(schema.id().clone(), stored);
+
+    Ok(())
 }
@@ -1,10 +1,14 @@
 use async_trait::async_trait;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-
-use crate::domain::{
-    errors::Error,
-    events::{Event, Handler, Publisher, Subscriber},
+use tracing::Instrument;
+
+use crate::{
+    domain::{
+        errors::Error,
+        events::{with_correlation_id, Event, Handler, Publisher, Subscriber},
+    },
+    infrastructure::BackgroundTasks,
 };
 
 struct Subscription {
@@ -14,22 +18,24 @@ struct Subscription {
 
 #[derive(Clone)]
 pub struct LocalEventBus {
-    sync: bool,
+    // Async handlers are spawned as tracked tasks
+    tasks: Option<Arc<BackgroundTasks>>,
 
     subscriptions: Arc<RwLock<Vec<Subscription>>>,
 }
 
 impl LocalEventBus {
+    #[allow(dead_code)]
     pub fn new_sync() -> LocalEventBus {
         LocalEventBus {
-            sync: true,
+            tasks: None,
             subscriptions: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
-    pub fn new_async() -> LocalEventBus {
+    pub fn new_async(tasks: Arc<BackgroundTasks>) -> LocalEventBus {
         LocalEventBus {
-            sync: false,
+            tasks: Some(tasks),
             subscriptions: Arc::new(RwLock::new(Vec::new())),
         }
     }
@@ -54,6 +60,22 @@ fn subject_has_topic(subject: &str, topic: &str) -> bool {
     })
 }
 
+// Handlers run in the span of the event, and the events they publish keep the
+// correlation id of the original one
+async fn handle(handler: &dyn Handler, event: &Event) -> Result<(), Error> {
+    let correlation_id = event.correlation_id().unwrap_or(event.id()).to_string();
+    let span = tracing::info_span!(
+        "event",
+        event_id = event.id(),
+        topic = event.topic(),
+        correlation_id = correlation_id.as_str(),
+    );
+
+    with_correlation_id(correlation_id.clone(), handler.handle(event))
+        .instrument(span)
+        .await
+}
+
 #[async_trait]
 impl Publisher for LocalEventBus {
     async fn publish(&self, events: &[Event]) -> Result<(), Error> {
@@ -62,14 +84,16 @@ impl Publisher for LocalEventBus {
         for event in events {
             for subscription in subscriptions.iter() {
                 if subject_has_topic(&subscription.subject, event.topic()) {
-                    if self.sync {
-                        subscription.handler.handle(event).await?;
-                    } else {
+                    if let Some(tasks) = &self.tasks {
                         let event = event.clone();
-                        let subscription = subscription.handler.clone();
-                        tokio::spawn(async move {
-                            subscription.handle(&event).await.unwrap();
+                        let handler = subscription.handler.clone();
+                        tasks.spawn(async move {
+                            if let Err(err) = handle(handler.as_ref(), &event).await {
+                                tracing::error!(error = %err, "could not handle event");
+                            }
                         });
+                    } else {
+                        handle(subscription.handler.as_ref(), event).await?;
                     }
                 }
             }
@@ -184,4 +208,22 @@ mod tests {
 
         assert_eq!(*counter.count.lock().await, 2);
     }
+
+    #[tokio::test]
+    async fn tracked_async_handlers() {
+        let tasks = Arc::new(BackgroundTasks::new());
+        let event_bus = LocalEventBus::new_async(tasks.clone());
+        let counter = Counter::new();
+
+        event_bus
+            .subscribe("topic.*", Box::new(counter.clone()))
+            .await
+            .unwrap();
+
+        let event = Event::create("entity#01", "topic.code", &3).unwrap();
+        event_bus.publish(&[event]).await.unwrap();
+
+        assert!(tasks.wait(std::time::Duration::from_secs(1)).await);
+        assert_eq!(*counter.count.lock().await, 3);
+    }
 }
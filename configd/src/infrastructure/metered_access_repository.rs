@@ -0,0 +1,83 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::{sync::Arc, time::Instant};
+
+use crate::{
+    domain::{
+        configs::{Access, AccessRepository},
+        errors::Error,
+        shared::Id,
+    },
+    infrastructure::metrics,
+};
+
+const REPOSITORY: &str = "access";
+
+pub struct MeteredAccessRepository {
+    access_repository: Arc<dyn AccessRepository + Sync + Send>,
+}
+
+impl MeteredAccessRepository {
+    pub fn new(
+        access_repository: Arc<dyn AccessRepository + Sync + Send>,
+    ) -> MeteredAccessRepository {
+        MeteredAccessRepository { access_repository }
+    }
+}
+
+#[async_trait]
+impl AccessRepository for MeteredAccessRepository {
+    async fn find_active_by_config(
+        &self,
+        schema_id: &Id,
+        config_id: &Id,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Access>, Error> {
+        let start = Instant::now();
+        let res = self
+            .access_repository
+            .find_active_by_config(schema_id, config_id, now)
+            .await;
+        metrics().record_query(REPOSITORY, "find_active_by_config", start.elapsed());
+
+        res
+    }
+
+    async fn save(&self, schema_id: &Id, config_id: &Id, accesses: &[Access]) -> Result<(), Error> {
+        let start = Instant::now();
+        let res = self
+            .access_repository
+            .save(schema_id, config_id, accesses)
+            .await;
+        metrics().record_query(REPOSITORY, "save", start.elapsed());
+
+        res
+    }
+
+    async fn delete_by_config(&self, schema_id: &Id, config_id: &Id) -> Result<(), Error> {
+        let start = Instant::now();
+        let res = self
+            .access_repository
+            .delete_by_config(schema_id, config_id)
+            .await;
+        metrics().record_query(REPOSITORY, "delete_by_config", start.elapsed());
+
+        res
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<(), Error> {
+        let start = Instant::now();
+        let res = self.access_repository.delete_expired(now).await;
+        metrics().record_query(REPOSITORY, "delete_expired", start.elapsed());
+
+        res
+    }
+
+    async fn count_active_instances(&self, now: DateTime<Utc>) -> Result<u64, Error> {
+        let start = Instant::now();
+        let res = self.access_repository.count_active_instances(now).await;
+        metrics().record_query(REPOSITORY, "count_active_instances", start.elapsed());
+
+        res
+    }
+}
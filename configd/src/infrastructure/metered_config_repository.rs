@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use std::{sync::Arc, time::Instant};
+
+use crate::{
+    domain::{
+        configs::{Config, ConfigCount, ConfigQuery, ConfigRepository},
+        errors::Error,
+        shared::{Id, Page},
+    },
+    infrastructure::metrics,
+};
+
+const REPOSITORY: &str = "config";
+
+pub struct MeteredConfigRepository {
+    config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+}
+
+impl MeteredConfigRepository {
+    pub fn new(
+        config_repository: Arc<dyn ConfigRepository + Sync + Send>,
+    ) -> MeteredConfigRepository {
+        MeteredConfigRepository { config_repository }
+    }
+}
+
+#[async_trait]
+impl ConfigRepository for MeteredConfigRepository {
+    async fn find(&self, schema_id: &Id, query: &ConfigQuery) -> Result<Page<Config>, Error> {
+        let start = Instant::now();
+        let res = self.config_repository.find(schema_id, query).await;
+        metrics().record_query(REPOSITORY, "find", start.elapsed());
+
+        res
+    }
+
+    async fn find_by_id(&self, schema_id: &Id, id: &Id) -> Result<Option<Config>, Error> {
+        let start = Instant::now();
+        let res = self.config_repository.find_by_id(schema_id, id).await;
+        metrics().record_query(REPOSITORY, "find_by_id", start.elapsed());
+
+        res
+    }
+
+    async fn find_deleted_by_id(&self, schema_id: &Id, id: &Id) -> Result<Option<Config>, Error> {
+        let start = Instant::now();
+        let res = self
+            .config_repository
+            .find_deleted_by_id(schema_id, id)
+            .await;
+        metrics().record_query(REPOSITORY, "find_deleted_by_id", start.elapsed());
+
+        res
+    }
+
+    async fn exists(&self, schema_id: &Id, id: &Id) -> Result<bool, Error> {
+        let start = Instant::now();
+        let res = self.config_repository.exists(schema_id, id).await;
+        metrics().record_query(REPOSITORY, "exists", start.elapsed());
+
+        res
+    }
+
+    async fn count(&self) -> Result<ConfigCount, Error> {
+        let start = Instant::now();
+        let res = self.config_repository.count().await;
+        metrics().record_query(REPOSITORY, "count", start.elapsed());
+
+        res
+    }
+
+    async fn save(&self, config: &mut Config) -> Result<(), Error> {
+        let start = Instant::now();
+        let res = self.config_repository.save(config).await;
+        metrics().record_query(REPOSITORY, "save", start.elapsed());
+
+        res
+    }
+}
@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::{
+    domain::{
+        errors::Error,
+        events::{Event, Handler, Publisher, Subscriber},
+    },
+    infrastructure::metrics,
+};
+
+// Counts published events and failed handlers of any event bus
+pub struct MeteredEventBus {
+    publisher: Arc<dyn Publisher + Sync + Send>,
+    subscriber: Arc<dyn Subscriber + Sync + Send>,
+}
+
+impl MeteredEventBus {
+    pub fn new(
+        publisher: Arc<dyn Publisher + Sync + Send>,
+        subscriber: Arc<dyn Subscriber + Sync + Send>,
+    ) -> MeteredEventBus {
+        MeteredEventBus {
+            publisher,
+            subscriber,
+        }
+    }
+}
+
+#[async_trait]
+impl Publisher for MeteredEventBus {
+    async fn publish(&self, events: &[Event]) -> Result<(), Error> {
+        self.publisher.publish(events).await?;
+
+        for event in events {
+            metrics().record_event_published(event.topic());
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Subscriber for MeteredEventBus {
+    async fn subscribe(&self, subject: &str, handler: Box<dyn Handler>) -> Result<(), Error> {
+        self.subscriber
+            .subscribe(subject, Box::new(MeteredHandler { handler }))
+            .await
+    }
+}
+
+struct MeteredHandler {
+    handler: Box<dyn Handler>,
+}
+
+#[async_trait]
+impl Handler for MeteredHandler {
+    async fn handle(&self, event: &Event) -> Result<(), Error> {
+        let res = self.handler.handle(event).await;
+
+        if res.is_err() {
+            metrics().record_handler_failure(event.topic());
+        }
+
+        res
+    }
+}
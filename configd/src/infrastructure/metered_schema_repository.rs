@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use std::{sync::Arc, time::Instant};
+
+use crate::{
+    domain::{
+        configs::Config,
+        errors::Error,
+        schemas::{Schema, SchemaQuery, SchemaRepository},
+        shared::{Id, Page},
+    },
+    infrastructure::metrics,
+};
+
+const REPOSITORY: &str = "schema";
+
+// Records the latency of every query of the wrapped repository
+pub struct MeteredSchemaRepository {
+    schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+}
+
+impl MeteredSchemaRepository {
+    pub fn new(
+        schema_repository: Arc<dyn SchemaRepository + Sync + Send>,
+    ) -> MeteredSchemaRepository {
+        MeteredSchemaRepository { schema_repository }
+    }
+}
+
+#[async_trait]
+impl SchemaRepository for MeteredSchemaRepository {
+    async fn find(&self, query: &SchemaQuery) -> Result<Page<Schema>, Error> {
+        let start = Instant::now();
+        let res = self.schema_repository.find(query).await;
+        metrics().record_query(REPOSITORY, "find", start.elapsed());
+
+        res
+    }
+
+    async fn find_by_id(&self, id: &Id) -> Result<Option<Schema>, Error> {
+        let start = Instant::now();
+        let res = self.schema_repository.find_by_id(id).await;
+        metrics().record_query(REPOSITORY, "find_by_id", start.elapsed());
+
+        res
+    }
+
+    async fn find_deleted_by_id(&self, id: &Id) -> Result<Option<Schema>, Error> {
+        let start = Instant::now();
+        let res = self.schema_repository.find_deleted_by_id(id).await;
+        metrics().record_query(REPOSITORY, "find_deleted_by_id", start.elapsed());
+
+        res
+    }
+
+    async fn exists(&self, id: &Id) -> Result<bool, Error> {
+        let start = Instant::now();
+        let res = self.schema_repository.exists(id).await;
+        metrics().record_query(REPOSITORY, "exists", start.elapsed());
+
+        res
+    }
+
+    async fn count(&self) -> Result<u64, Error> {
+        let start = Instant::now();
+        let res = self.schema_repository.count().await;
+        metrics().record_query(REPOSITORY, "count", start.elapsed());
+
+        res
+    }
+
+    async fn save(&self, schema: &mut Schema) -> Result<(), Error> {
+        let start = Instant::now();
+        let res = self.schema_repository.save(schema).await;
+        metrics().record_query(REPOSITORY, "save", start.elapsed());
+
+        res
+    }
+
+    async fn save_with_configs(
+        &self,
+        schema: &mut Schema,
+        configs: &mut [Config],
+    ) -> Result<(), Error> {
+        let start = Instant::now();
+        let res = self
+            .schema_repository
+            .save_with_configs(schema, configs)
+            .await;
+        metrics().record_query(REPOSITORY, "save_with_configs", start.elapsed());
+
+        res
+    }
+}
@@ -0,0 +1,200 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use std::{collections::BTreeMap, sync::OnceLock, time::Duration};
+
+use crate::domain::values::Violation;
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+// Process wide, so errors can be counted wherever they are rendered
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+pub struct Metrics {
+    registry: Registry,
+
+    // HTTP
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+
+    // Domain
+    validation_failures: IntCounterVec,
+    events_published: IntCounterVec,
+    handler_failures: IntCounterVec,
+    repository_query_duration: HistogramVec,
+
+    // Gauges, updated on every scrape
+    pub schemas: IntGauge,
+    pub configs: IntGauge,
+    pub invalid_configs: IntGauge,
+    pub active_instances: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Metrics {
+        let registry = Registry::new_custom(Some("configd".to_string()), None).unwrap();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency by route",
+            ),
+            &["method", "route"],
+        )
+        .unwrap();
+        let validation_failures = IntCounterVec::new(
+            Opts::new(
+                "validation_failures_total",
+                "Config validation violations by reason",
+            ),
+            &["reason"],
+        )
+        .unwrap();
+        let events_published = IntCounterVec::new(
+            Opts::new("events_published_total", "Published events by topic"),
+            &["topic"],
+        )
+        .unwrap();
+        let handler_failures = IntCounterVec::new(
+            Opts::new(
+                "event_handler_failures_total",
+                "Failed event handlers by topic",
+            ),
+            &["topic"],
+        )
+        .unwrap();
+        let repository_query_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "repository_query_duration_seconds",
+                "Repository query latency by operation",
+            )
+            .buckets(vec![
+                0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+            ]),
+            &["repository", "operation"],
+        )
+        .unwrap();
+        let schemas = IntGauge::new("schemas", "Stored schemas").unwrap();
+        let configs = IntGauge::new("configs", "Stored configs").unwrap();
+        let invalid_configs =
+            IntGauge::new("invalid_configs", "Configs not valid under their schema").unwrap();
+        let active_instances =
+            IntGauge::new("active_instances", "Instances that recently read a config").unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry
+            .register(Box::new(http_request_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(validation_failures.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(events_published.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(handler_failures.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(repository_query_duration.clone()))
+            .unwrap();
+        registry.register(Box::new(schemas.clone())).unwrap();
+        registry.register(Box::new(configs.clone())).unwrap();
+        registry
+            .register(Box::new(invalid_configs.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(active_instances.clone()))
+            .unwrap();
+
+        Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            validation_failures,
+            events_published,
+            handler_failures,
+            repository_query_duration,
+            schemas,
+            configs,
+            invalid_configs,
+            active_instances,
+        }
+    }
+
+    pub fn record_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[method, route])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn record_violations(&self, diffs: &BTreeMap<String, Vec<Violation>>) {
+        for violation in diffs.values().flatten() {
+            let reason = serde_json::to_value(violation.reason()).unwrap_or_default();
+
+            self.validation_failures
+                .with_label_values(&[reason.as_str().unwrap_or_default()])
+                .inc();
+        }
+    }
+
+    pub fn record_event_published(&self, topic: &str) {
+        self.events_published.with_label_values(&[topic]).inc();
+    }
+
+    pub fn record_handler_failure(&self, topic: &str) {
+        self.handler_failures.with_label_values(&[topic]).inc();
+    }
+
+    pub fn record_query(&self, repository: &str, operation: &str, elapsed: Duration) {
+        self.repository_query_duration
+            .with_label_values(&[repository, operation])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    // Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .unwrap();
+
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        let metrics = Metrics::new();
+
+        metrics.record_request("GET", "/schemas/:schema_id", 200, Duration::from_millis(3));
+        metrics.record_violations(&BTreeMap::from([(
+            "$.port".to_string(),
+            vec![Violation::missing_prop()],
+        )]));
+        metrics.record_event_published("config.created");
+        metrics.configs.set(3);
+
+        let output = metrics.render();
+
+        assert!(output.contains(
+            r#"configd_http_requests_total{method="GET",route="/schemas/:schema_id",status="200"} 1"#
+        ));
+        assert!(output.contains(r#"configd_validation_failures_total{reason="missing_prop"} 1"#));
+        assert!(output.contains(r#"configd_events_published_total{topic="config.created"} 1"#));
+        assert!(output.contains("configd_configs 3"));
+    }
+}
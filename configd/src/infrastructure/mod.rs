@@ -1,11 +1,45 @@
+mod background_tasks;
+mod cache;
+mod cached_config_repository;
+mod cached_schema_repository;
+mod inmem_access_repository;
+mod inmem_config_repository;
 mod inmem_schema_repository;
 mod local_event_bus;
+mod metered_access_repository;
+mod metered_config_repository;
+mod metered_event_bus;
+mod metered_schema_repository;
+mod metrics;
+mod postgres_access_repository;
+mod postgres_config_repository;
+mod postgres_event_bus;
 mod postgres_schema_repository;
+mod rate_limiter;
+mod sqlite_access_repository;
+mod sqlite_config_repository;
 mod sqlite_schema_repository;
 mod sqlx_models;
 
+pub use background_tasks::*;
+pub use cache::*;
+pub use cached_config_repository::*;
+pub use cached_schema_repository::*;
+pub use inmem_access_repository::*;
+pub use inmem_config_repository::*;
 pub use inmem_schema_repository::*;
 pub use local_event_bus::*;
+pub use metered_access_repository::*;
+pub use metered_config_repository::*;
+pub use metered_event_bus::*;
+pub use metered_schema_repository::*;
+pub use metrics::*;
+pub use postgres_access_repository::*;
+pub use postgres_config_repository::*;
+pub use postgres_event_bus::*;
 pub use postgres_schema_repository::*;
+pub use rate_limiter::*;
+pub use sqlite_access_repository::*;
+pub use sqlite_config_repository::*;
 pub use sqlite_schema_repository::*;
 pub use sqlx_models::*;
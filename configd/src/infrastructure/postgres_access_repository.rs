@@ -0,0 +1,147 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::{
+    domain::{
+        configs::{Access, AccessRepository},
+        errors::Error,
+        shared::Id,
+    },
+    infrastructure::SqlxAccess,
+};
+
+pub struct PostgresAccessRepository {
+    pool: PgPool,
+}
+
+impl PostgresAccessRepository {
+    pub async fn new(pool: PgPool) -> Result<PostgresAccessRepository, Error> {
+        sqlx::query(
+            "
+            CREATE TABLE IF NOT EXISTS accesses(
+              schema_id VARCHAR(255) NOT NULL,
+              id VARCHAR(255) NOT NULL,
+              source TEXT NOT NULL,
+              instance TEXT NOT NULL,
+              timestamp TIMESTAMP WITH TIME ZONE NOT NULL,
+              previous TIMESTAMP WITH TIME ZONE,
+              expires_at TIMESTAMP WITH TIME ZONE,
+              PRIMARY KEY (schema_id, id, source, instance)
+            );
+            ",
+        )
+        .execute(&pool)
+        .await
+        .map_err(Error::Database)?;
+
+        // Accesses saved before they expired, deleted on the next flush
+        sqlx::query(
+            "ALTER TABLE accesses ADD COLUMN IF NOT EXISTS expires_at TIMESTAMP WITH TIME ZONE",
+        )
+        .execute(&pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(PostgresAccessRepository { pool })
+    }
+}
+
+#[async_trait]
+impl AccessRepository for PostgresAccessRepository {
+    async fn find_active_by_config(
+        &self,
+        schema_id: &Id,
+        config_id: &Id,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Access>, Error> {
+        let sqlx_accesses: Vec<SqlxAccess> = sqlx::query_as(
+            "SELECT * FROM accesses WHERE schema_id = $1 AND id = $2 AND expires_at >= $3",
+        )
+        .bind(schema_id.value())
+        .bind(config_id.value())
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx_accesses
+            .into_iter()
+            .map(SqlxAccess::into_domain)
+            .collect()
+    }
+
+    async fn save(&self, schema_id: &Id, config_id: &Id, accesses: &[Access]) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        for access in accesses.iter() {
+            sqlx::query(
+                "
+                INSERT INTO accesses(
+                    schema_id,
+                    id,
+                    source,
+                    instance,
+                    timestamp,
+                    previous,
+                    expires_at
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (schema_id, id, source, instance) DO UPDATE SET
+                    timestamp = excluded.timestamp,
+                    previous = excluded.previous,
+                    expires_at = excluded.expires_at
+                WHERE accesses.timestamp < excluded.timestamp
+                ",
+            )
+            .bind(schema_id.value())
+            .bind(config_id.value())
+            .bind(access.source().value())
+            .bind(access.instance().value())
+            .bind(access.timestamp())
+            .bind(access.previous())
+            .bind(access.expires_at())
+            .execute(&mut tx)
+            .await
+            .map_err(Error::Database)?;
+        }
+
+        tx.commit().await.map_err(Error::Database)
+    }
+
+    async fn delete_by_config(&self, schema_id: &Id, config_id: &Id) -> Result<(), Error> {
+        sqlx::query("DELETE FROM accesses WHERE schema_id = $1 AND id = $2")
+            .bind(schema_id.value())
+            .bind(config_id.value())
+            .execute(&self.pool)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<(), Error> {
+        sqlx::query("DELETE FROM accesses WHERE expires_at IS NULL OR expires_at < $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    async fn count_active_instances(&self, now: DateTime<Utc>) -> Result<u64, Error> {
+        let count: i64 = sqlx::query_scalar(
+            "
+            SELECT COUNT(*) FROM (
+                SELECT DISTINCT source, instance FROM accesses WHERE expires_at >= $1
+            ) AS instances
+            ",
+        )
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(count as u64)
+    }
+}
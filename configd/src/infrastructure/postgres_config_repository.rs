@@ -0,0 +1,408 @@
+use async_trait::async_trait;
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder, Row, Transaction};
+
+use crate::{
+    domain::{
+        configs::{
+            Config, ConfigCount, ConfigCreated, ConfigDataChanged, ConfigDeleted,
+            ConfigPasswordChanged, ConfigPasswordDeleted, ConfigPurged, ConfigQuery,
+            ConfigRepository, ConfigRestored, ConfigRevalidated, ConfigSort,
+        },
+        errors::Error,
+        shared::{Id, Page, SortOrder, SortValue},
+    },
+    infrastructure::{contains_pattern, SqlxConfig},
+};
+
+pub struct PostgresConfigRepository {
+    pool: PgPool,
+}
+
+impl PostgresConfigRepository {
+    pub async fn new(pool: PgPool) -> Result<PostgresConfigRepository, Error> {
+        sqlx::query(
+            "
+            CREATE TABLE IF NOT EXISTS configs(
+              schema_id VARCHAR(255) NOT NULL,
+              id VARCHAR(255) NOT NULL,
+              name TEXT NOT NULL,
+              data JSON NOT NULL,
+              valid BOOLEAN NOT NULL,
+              password TEXT,
+              created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+              updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
+              version INTEGER NOT NULL,
+              deleted_at TIMESTAMP WITH TIME ZONE,
+              PRIMARY KEY (schema_id, id)
+            );
+            ",
+        )
+        .execute(&pool)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx::query(
+            "ALTER TABLE configs ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE",
+        )
+        .execute(&pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(PostgresConfigRepository { pool })
+    }
+
+    // Applies the recorded events within the transaction, so other
+    // repositories can save along with the config
+    pub async fn save_in(tx: &mut Transaction<'_, Postgres>, config: &Config) -> Result<(), Error> {
+        // Only changed from the version it was loaded with, which also locks
+        // the row until the transaction ends
+        let created = config
+            .events()
+            .iter()
+            .any(|event| event.topic() == "config.created");
+        if !created && !config.events().is_empty() {
+            let res = sqlx::query(
+                "
+                UPDATE configs
+                SET version = $3
+                WHERE schema_id = $1 AND id = $2 AND version = $4
+                ",
+            )
+            .bind(config.schema_id().value())
+            .bind(config.id().value())
+            .bind(config.version().value())
+            .bind(config.version().original())
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+
+            if res.rows_affected() == 0 {
+                return Err(Error::ConfigModified(config.id().clone()));
+            }
+        }
+
+        for event in config.events() {
+            let query = match event.topic() {
+                "config.created" => {
+                    let payload: ConfigCreated = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        INSERT INTO configs (
+                            schema_id,
+                            id,
+                            name,
+                            data,
+                            valid,
+                            password,
+                            created_at,
+                            updated_at,
+                            version
+                        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 1)
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(payload.name)
+                    .bind(payload.data)
+                    .bind(payload.valid)
+                    .bind(payload.password)
+                    .bind(event.timestamp())
+                    .bind(event.timestamp())
+                }
+                "config.data_changed" => {
+                    let payload: ConfigDataChanged = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        UPDATE configs
+                        SET
+                            data = $3,
+                            valid = $4,
+                            updated_at = $5
+                        WHERE
+                            schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(payload.data)
+                    .bind(payload.valid)
+                    .bind(event.timestamp())
+                }
+                "config.revalidated" => {
+                    let payload: ConfigRevalidated = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        UPDATE configs
+                        SET
+                            valid = $3,
+                            updated_at = $4
+                        WHERE
+                            schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(payload.valid)
+                    .bind(event.timestamp())
+                }
+                "config.password_changed" => {
+                    let payload: ConfigPasswordChanged = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        UPDATE configs
+                        SET
+                            password = $3,
+                            updated_at = $4
+                        WHERE
+                            schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(payload.password)
+                    .bind(event.timestamp())
+                }
+                "config.password_deleted" => {
+                    let payload: ConfigPasswordDeleted = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        UPDATE configs
+                        SET
+                            password = null,
+                            updated_at = $3
+                        WHERE
+                            schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(event.timestamp())
+                }
+                "config.deleted" => {
+                    let payload: ConfigDeleted = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        UPDATE configs
+                        SET
+                            deleted_at = $3
+                        WHERE
+                            schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(event.timestamp())
+                }
+                "config.restored" => {
+                    let payload: ConfigRestored = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        UPDATE configs
+                        SET
+                            updated_at = $3,
+                            deleted_at = NULL
+                        WHERE
+                            schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(event.timestamp())
+                }
+                "config.purged" => {
+                    let payload: ConfigPurged = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        DELETE FROM configs
+                        WHERE schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                }
+                _ => continue,
+            };
+
+            query.execute(&mut *tx).await.map_err(Error::Database)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ConfigRepository for PostgresConfigRepository {
+    async fn find(&self, schema_id: &Id, query: &ConfigQuery) -> Result<Page<Config>, Error> {
+        let mut count_query = QueryBuilder::new("SELECT COUNT(*) FROM configs WHERE schema_id = ");
+        count_query.push_bind(schema_id.value());
+        push_filters(&mut count_query, query);
+
+        let count: i64 = count_query
+            .build()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(Error::Database)?
+            .get(0);
+
+        let mut select_query = QueryBuilder::new("SELECT * FROM configs WHERE schema_id = ");
+        select_query.push_bind(schema_id.value());
+        push_filters(&mut select_query, query);
+
+        let (column, operator, direction) = sorting(query);
+        if let Some(cursor) = &query.cursor {
+            select_query.push(format!(" AND ({} {} ", column, operator));
+            push_sort_value(&mut select_query, cursor.value());
+            select_query.push(format!(" OR ({} = ", column));
+            push_sort_value(&mut select_query, cursor.value());
+            select_query.push(format!(" AND id {} ", operator));
+            select_query.push_bind(cursor.id().to_string());
+            select_query.push("))");
+        }
+
+        select_query
+            .push(format!(
+                " ORDER BY {} {}, id {} LIMIT ",
+                column, direction, direction
+            ))
+            .push_bind(query.limit as i64)
+            .push(" OFFSET ")
+            .push_bind(query.offset as i64);
+
+        let configs = select_query
+            .build()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(Error::Database)?
+            .iter()
+            .map(|row| {
+                SqlxConfig::from_row(row)
+                    .map_err(Error::Database)
+                    .and_then(SqlxConfig::into_domain)
+            })
+            .collect::<Result<Vec<Config>, Error>>()?;
+
+        Page::new(query.offset, query.limit, count as u64, configs)
+    }
+
+    async fn find_by_id(&self, schema_id: &Id, id: &Id) -> Result<Option<Config>, Error> {
+        let sqlx_config: Option<SqlxConfig> = sqlx::query_as(
+            "SELECT * FROM configs WHERE schema_id = $1 AND id = $2 AND deleted_at IS NULL",
+        )
+        .bind(schema_id.value())
+        .bind(id.value())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx_config.map(SqlxConfig::into_domain).transpose()
+    }
+
+    async fn find_deleted_by_id(&self, schema_id: &Id, id: &Id) -> Result<Option<Config>, Error> {
+        let sqlx_config: Option<SqlxConfig> = sqlx::query_as(
+            "SELECT * FROM configs WHERE schema_id = $1 AND id = $2 AND deleted_at IS NOT NULL",
+        )
+        .bind(schema_id.value())
+        .bind(id.value())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx_config.map(SqlxConfig::into_domain).transpose()
+    }
+
+    async fn exists(&self, schema_id: &Id, id: &Id) -> Result<bool, Error> {
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM configs WHERE schema_id = $1 AND id = $2")
+                .bind(schema_id.value())
+                .bind(id.value())
+                .fetch_one(&self.pool)
+                .await
+                .map_err(Error::Database)?;
+
+        Ok(count > 0)
+    }
+
+    async fn count(&self) -> Result<ConfigCount, Error> {
+        let (total, invalid): (i64, i64) = sqlx::query_as(
+            "
+            SELECT COUNT(*), COALESCE(SUM(CASE WHEN valid THEN 0 ELSE 1 END), 0)
+            FROM configs
+            WHERE deleted_at IS NULL
+            ",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(ConfigCount {
+            total: total as u64,
+            invalid: invalid as u64,
+        })
+    }
+
+    async fn save(&self, config: &mut Config) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+        PostgresConfigRepository::save_in(&mut tx, config).await?;
+        tx.commit().await.map_err(Error::Database)
+    }
+}
+
+fn push_filters(builder: &mut QueryBuilder<Postgres>, query: &ConfigQuery) {
+    builder.push(if query.deleted {
+        " AND deleted_at IS NOT NULL"
+    } else {
+        " AND deleted_at IS NULL"
+    });
+
+    if let Some(deleted_before) = query.deleted_before {
+        builder.push(" AND deleted_at < ").push_bind(deleted_before);
+    }
+
+    if let Some(name) = &query.name {
+        builder
+            .push(" AND name ILIKE ")
+            .push_bind(contains_pattern(name))
+            .push(" ESCAPE '\\'");
+    }
+
+    if let Some(valid) = query.valid {
+        builder.push(" AND valid = ").push_bind(valid);
+    }
+
+    if let Some(requires_password) = query.requires_password {
+        builder.push(if requires_password {
+            " AND password IS NOT NULL"
+        } else {
+            " AND password IS NULL"
+        });
+    }
+}
+
+// Column, cursor operator and direction
+fn sorting(query: &ConfigQuery) -> (&'static str, &'static str, &'static str) {
+    let column = match query.sort {
+        ConfigSort::Name => "name COLLATE \"C\"",
+        ConfigSort::CreatedAt => "created_at",
+        ConfigSort::UpdatedAt => "updated_at",
+    };
+
+    match query.order {
+        SortOrder::Asc => (column, ">", "ASC"),
+        SortOrder::Desc => (column, "<", "DESC"),
+    }
+}
+
+fn push_sort_value(builder: &mut QueryBuilder<Postgres>, value: &SortValue) {
+    match value {
+        SortValue::Text(text) => builder.push_bind(text.clone()),
+        SortValue::Timestamp(timestamp) => builder.push_bind(*timestamp),
+    };
+}
@@ -0,0 +1,452 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgListener, PgPool};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    domain::{
+        errors::Error,
+        events::{Event, EventsResynced, Handler, Publishable, Publisher, Subscriber},
+        shared::Id,
+    },
+    infrastructure::{BackgroundTasks, LocalEventBus},
+};
+
+const CHANNEL: &str = "configd_events";
+
+// Between attempts to listen again after losing the connection
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+// NOTIFY payloads must be shorter than 8000 bytes
+const MAX_NOTIFICATION_SIZE: usize = 7000;
+
+#[derive(Serialize, Deserialize)]
+struct NotifiedEvent {
+    id: String,
+    entity_id: String,
+    topic: String,
+    payload: String,
+    timestamp: DateTime<Utc>,
+    correlation_id: Option<String>,
+}
+
+impl NotifiedEvent {
+    fn from_event(event: &Event) -> Result<NotifiedEvent, Error> {
+        Ok(NotifiedEvent {
+            id: event.id().to_string(),
+            entity_id: event.entity_id().to_string(),
+            topic: event.topic().to_string(),
+            payload: String::from_utf8(event.payload().to_vec())
+                .map_err(|_| Error::InvalidEvent)?,
+            timestamp: *event.timestamp(),
+            correlation_id: event.correlation_id().map(str::to_string),
+        })
+    }
+
+    fn into_event(self) -> Result<Event, Error> {
+        Event::new(
+            self.id,
+            self.entity_id,
+            self.topic,
+            self.payload.into_bytes(),
+            self.timestamp,
+            self.correlation_id,
+        )
+    }
+}
+
+// Events too large for a notification only carry their id and are read from
+// the events table
+#[derive(Serialize, Deserialize)]
+struct Notification {
+    node: String,
+    id: String,
+    event: Option<NotifiedEvent>,
+}
+
+// Payload of the notification of an event
+#[derive(Debug, PartialEq)]
+enum Encoded {
+    Inline(String),
+    // The event must be stored before notifying
+    Stored(String),
+}
+
+fn encode(node: &str, event: &Event) -> Result<Encoded, Error> {
+    let mut notification = Notification {
+        node: node.to_string(),
+        id: event.id().to_string(),
+        event: Some(NotifiedEvent::from_event(event)?),
+    };
+
+    let payload = serde_json::to_string(&notification).map_err(Error::Serde)?;
+    if payload.len() <= MAX_NOTIFICATION_SIZE {
+        return Ok(Encoded::Inline(payload));
+    }
+
+    notification.event = None;
+    let payload = serde_json::to_string(&notification).map_err(Error::Serde)?;
+    Ok(Encoded::Stored(payload))
+}
+
+// What a node does with a notification
+#[derive(Debug, PartialEq)]
+enum Received {
+    // Already dispatched when published
+    Own,
+    Event(Event),
+    // Read from the events table by id
+    Stored(String),
+}
+
+fn decode(node: &str, payload: &str) -> Result<Received, Error> {
+    let notification: Notification = serde_json::from_str(payload).map_err(Error::Serde)?;
+
+    if notification.node == node {
+        return Ok(Received::Own);
+    }
+
+    match notification.event {
+        Some(event) => Ok(Received::Event(event.into_event()?)),
+        None => Ok(Received::Stored(notification.id)),
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct SqlxEvent {
+    id: String,
+    entity_id: String,
+    topic: String,
+    payload: Vec<u8>,
+    timestamp: DateTime<Utc>,
+    correlation_id: Option<String>,
+}
+
+// Fans events out to every node sharing the database. Subscribers receive
+// the events of all nodes, while the local bus given on creation only receives
+// the ones published by this node, for handlers that must run once.
+#[derive(Clone)]
+pub struct PostgresEventBus {
+    pool: PgPool,
+    node: String,
+    local: Arc<LocalEventBus>,
+    cluster: LocalEventBus,
+}
+
+impl PostgresEventBus {
+    pub async fn new(
+        pool: PgPool,
+        local: Arc<LocalEventBus>,
+        tasks: Arc<BackgroundTasks>,
+    ) -> Result<PostgresEventBus, Error> {
+        sqlx::query(
+            "
+            CREATE TABLE IF NOT EXISTS events(
+              id VARCHAR(255) PRIMARY KEY,
+              entity_id VARCHAR(255) NOT NULL,
+              topic VARCHAR(255) NOT NULL,
+              payload BYTEA NOT NULL,
+              timestamp TIMESTAMP WITH TIME ZONE NOT NULL
+            );
+            ",
+        )
+        .execute(&pool)
+        .await
+        .map_err(Error::Database)?;
+        sqlx::query("ALTER TABLE events ADD COLUMN IF NOT EXISTS correlation_id VARCHAR(255)")
+            .execute(&pool)
+            .await
+            .map_err(Error::Database)?;
+
+        let mut listener = listen(&pool).await?;
+
+        let event_bus = PostgresEventBus {
+            pool,
+            node: Id::generate().to_string(),
+            local,
+            cluster: LocalEventBus::new_async(tasks.clone()),
+        };
+
+        let receiver = event_bus.clone();
+        tokio::spawn(async move {
+            loop {
+                // Other nodes' events are ignored once shutting down
+                let notification = tokio::select! {
+                    _ = tasks.closed() => return,
+                    notification = listener.try_recv() => notification,
+                };
+
+                match notification {
+                    Ok(Some(notification)) => {
+                        if let Err(err) = receiver.receive(notification.payload()).await {
+                            tracing::error!(error = %err, "could not receive event");
+                        }
+                        continue;
+                    }
+                    Ok(None) => tracing::warn!("lost connection to events"),
+                    Err(err) => tracing::error!(error = %err, "could not listen to events"),
+                }
+
+                // Notifications sent while disconnected are lost, so the
+                // subscribers resync once listening again
+                listener = loop {
+                    tokio::select! {
+                        _ = tasks.closed() => return,
+                        _ = tokio::time::sleep(RECONNECT_DELAY) => {}
+                    }
+
+                    match listen(&receiver.pool).await {
+                        Ok(listener) => break listener,
+                        Err(err) => tracing::error!(error = %err, "could not listen to events"),
+                    }
+                };
+
+                if let Err(err) = receiver.resync().await {
+                    tracing::error!(error = %err, "could not resync events");
+                }
+            }
+        });
+
+        Ok(event_bus)
+    }
+
+    async fn notify(&self, event: &Event) -> Result<(), Error> {
+        let payload = match encode(&self.node, event)? {
+            Encoded::Inline(payload) => payload,
+            Encoded::Stored(payload) => {
+                sqlx::query(
+                    "
+                    INSERT INTO events(id, entity_id, topic, payload, timestamp, correlation_id)
+                    VALUES ($1, $2, $3, $4, $5, $6)
+                    ",
+                )
+                .bind(event.id())
+                .bind(event.entity_id())
+                .bind(event.topic())
+                .bind(event.payload())
+                .bind(event.timestamp())
+                .bind(event.correlation_id())
+                .execute(&self.pool)
+                .await
+                .map_err(Error::Database)?;
+
+                // Every node has read them long before
+                sqlx::query("DELETE FROM events WHERE timestamp < NOW() - INTERVAL '1 hour'")
+                    .execute(&self.pool)
+                    .await
+                    .map_err(Error::Database)?;
+
+                payload
+            }
+        };
+
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(CHANNEL)
+            .bind(payload)
+            .execute(&self.pool)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    async fn resync(&self) -> Result<(), Error> {
+        let resynced = EventsResynced {
+            node: self.node.clone(),
+        };
+        let event = Event::create(resynced.entity_id(), resynced.topic(), &resynced)?;
+
+        self.cluster.publish(&[event]).await
+    }
+
+    async fn receive(&self, payload: &str) -> Result<(), Error> {
+        let event = match decode(&self.node, payload)? {
+            Received::Own => return Ok(()),
+            Received::Event(event) => event,
+            Received::Stored(id) => {
+                let event: SqlxEvent = sqlx::query_as("SELECT * FROM events WHERE id = $1")
+                    .bind(&id)
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(Error::Database)?;
+
+                Event::new(
+                    event.id,
+                    event.entity_id,
+                    event.topic,
+                    event.payload,
+                    event.timestamp,
+                    event.correlation_id,
+                )?
+            }
+        };
+
+        self.cluster.publish(&[event]).await
+    }
+}
+
+async fn listen(pool: &PgPool) -> Result<PgListener, Error> {
+    let mut listener = PgListener::connect_with(pool)
+        .await
+        .map_err(Error::Database)?;
+    listener.listen(CHANNEL).await.map_err(Error::Database)?;
+
+    Ok(listener)
+}
+
+#[async_trait]
+impl Publisher for PostgresEventBus {
+    // The events are published once their changes are committed, so a failed
+    // notification doesn't fail the request. Other nodes see the changes once
+    // their caches expire.
+    async fn publish(&self, events: &[Event]) -> Result<(), Error> {
+        self.local.publish(events).await?;
+        self.cluster.publish(events).await?;
+
+        for event in events {
+            if let Err(err) = self.notify(event).await {
+                tracing::error!(error = %err, event_id = event.id(), "could not notify event");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Subscriber for PostgresEventBus {
+    async fn subscribe(&self, subject: &str, handler: Box<dyn Handler>) -> Result<(), Error> {
+        self.cluster.subscribe(subject, handler).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use tokio::sync::Mutex;
+
+    #[derive(Clone)]
+    struct Recorder {
+        events: Arc<Mutex<Vec<Event>>>,
+    }
+
+    #[async_trait]
+    impl Handler for Recorder {
+        async fn handle(&self, event: &Event) -> Result<(), Error> {
+            self.events.lock().await.push(event.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn encode_and_decode() {
+        let event = Event::create("schema#01", "config.created", &"small").unwrap();
+
+        let payload = match encode("node#01", &event).unwrap() {
+            Encoded::Inline(payload) => payload,
+            encoded => panic!("unexpected {:?}", encoded),
+        };
+        assert!(payload.len() <= MAX_NOTIFICATION_SIZE);
+        assert_eq!(decode("node#02", &payload).unwrap(), Received::Event(event));
+
+        // Published by this node
+        assert_eq!(decode("node#01", &payload).unwrap(), Received::Own);
+    }
+
+    #[test]
+    fn encode_and_decode_large() {
+        let event = Event::create("schema#01", "config.created", &"x".repeat(10_000)).unwrap();
+
+        let payload = match encode("node#01", &event).unwrap() {
+            Encoded::Stored(payload) => payload,
+            encoded => panic!("unexpected {:?}", encoded),
+        };
+        assert!(payload.len() <= MAX_NOTIFICATION_SIZE);
+        assert_eq!(
+            decode("node#02", &payload).unwrap(),
+            Received::Stored(event.id().to_string())
+        );
+        assert_eq!(decode("node#01", &payload).unwrap(), Received::Own);
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert!(matches!(decode("node#01", "{"), Err(Error::Serde(_))));
+        assert!(matches!(
+            decode("node#01", r#"{"node": "node#02"}"#),
+            Err(Error::Serde(_))
+        ));
+    }
+
+    // Runs against the database in POSTGRES_TEST_URL with
+    // `cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn fan_out() {
+        let url = env::var("POSTGRES_TEST_URL").expect("POSTGRES_TEST_URL is not set");
+
+        let mut nodes = Vec::new();
+        for _ in 0..2 {
+            let pool = PgPool::connect(&url).await.unwrap();
+            let local = Arc::new(LocalEventBus::new_sync());
+            let event_bus =
+                PostgresEventBus::new(pool, local.clone(), Arc::new(BackgroundTasks::new()))
+                    .await
+                    .unwrap();
+
+            let local_events = Recorder {
+                events: Arc::new(Mutex::new(Vec::new())),
+            };
+            let cluster_events = Recorder {
+                events: Arc::new(Mutex::new(Vec::new())),
+            };
+            local
+                .subscribe("config.*", Box::new(local_events.clone()))
+                .await
+                .unwrap();
+            event_bus
+                .subscribe("config.*", Box::new(cluster_events.clone()))
+                .await
+                .unwrap();
+
+            nodes.push((event_bus, local_events, cluster_events));
+        }
+
+        let small = Event::create("schema#01", "config.created", &"small").unwrap();
+        let large = Event::create("schema#01", "config.created", &"x".repeat(10_000)).unwrap();
+        nodes[0]
+            .0
+            .publish(&[small.clone(), large.clone()])
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        for (i, (_, local_events, cluster_events)) in nodes.iter().enumerate() {
+            // Handlers are run concurrently, so the order is not kept
+            let mut received: Vec<(String, Vec<u8>)> = cluster_events
+                .events
+                .lock()
+                .await
+                .iter()
+                .map(|event| (event.id().to_string(), event.payload().to_vec()))
+                .collect();
+            received.sort();
+
+            let mut expected: Vec<(String, Vec<u8>)> = [&small, &large]
+                .iter()
+                .map(|event| (event.id().to_string(), event.payload().to_vec()))
+                .collect();
+            expected.sort();
+
+            assert_eq!(received, expected);
+            assert_eq!(
+                local_events.events.lock().await.len(),
+                if i == 0 { 2 } else { 0 }
+            );
+        }
+    }
+}
@@ -1,19 +1,17 @@
 use async_trait::async_trait;
-use sqlx::PgPool;
-use std::collections::HashMap;
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder, Row, Transaction};
 
 use crate::{
     domain::{
-        configs::Access,
+        configs::Config,
         errors::Error,
         schemas::{
-            ConfigAccessRemoved, ConfigAccessed, ConfigCreated, ConfigDataChanged, ConfigDeleted,
-            ConfigPasswordChanged, ConfigPasswordDeleted, ConfigRevalidated, Schema, SchemaCreated,
-            SchemaDeleted, SchemaRepository, SchemaRootPropChanged,
+            Schema, SchemaCreated, SchemaDeleted, SchemaPurged, SchemaQuery, SchemaRepository,
+            SchemaRestored, SchemaRootPropChanged, SchemaRulesChanged, SchemaSort,
         },
-        shared::{Id, Page},
+        shared::{Id, Page, SortOrder, SortValue},
     },
-    infrastructure::{SqlxAccess, SqlxConfig, SqlxSchema},
+    infrastructure::{contains_pattern, PostgresConfigRepository, SqlxSchema},
 };
 
 pub struct PostgresSchemaRepository {
@@ -28,9 +26,11 @@ impl PostgresSchemaRepository {
               id VARCHAR(255) PRIMARY KEY,
               name TEXT NOT NULL,
               root_prop JSON NOT NULL,
+              rules JSON NOT NULL DEFAULT '[]',
               created_at TIMESTAMP WITH TIME ZONE NOT NULL,
               updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
-              version INTEGER NOT NULL
+              version INTEGER NOT NULL,
+              deleted_at TIMESTAMP WITH TIME ZONE
             );
             ",
         )
@@ -39,37 +39,14 @@ impl PostgresSchemaRepository {
         .map_err(Error::Database)?;
 
         sqlx::query(
-            "
-            CREATE TABLE IF NOT EXISTS configs(
-              schema_id VARCHAR(255) NOT NULL,
-              id VARCHAR(255) NOT NULL,
-              name TEXT NOT NULL,
-              data JSON NOT NULL,
-              valid BOOLEAN NOT NULL,
-              password TEXT,
-              created_at TIMESTAMP WITH TIME ZONE NOT NULL,
-              updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
-              version INTEGER NOT NULL,
-              PRIMARY KEY (schema_id, id)
-            );
-            ",
+            "ALTER TABLE schemas ADD COLUMN IF NOT EXISTS rules JSON NOT NULL DEFAULT '[]'",
         )
         .execute(&pool)
         .await
         .map_err(Error::Database)?;
 
         sqlx::query(
-            "
-            CREATE TABLE IF NOT EXISTS accesses(
-              schema_id VARCHAR(255) NOT NULL,
-              id VARCHAR(255) NOT NULL,
-              source TEXT NOT NULL,
-              instance TEXT NOT NULL,
-              timestamp TIMESTAMP WITH TIME ZONE NOT NULL,
-              previous TIMESTAMP WITH TIME ZONE,
-              PRIMARY KEY (schema_id, id, source, instance)
-            );
-            ",
+            "ALTER TABLE schemas ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE",
         )
         .execute(&pool)
         .await
@@ -77,114 +54,29 @@ impl PostgresSchemaRepository {
 
         Ok(PostgresSchemaRepository { pool })
     }
-}
-
-#[async_trait]
-impl SchemaRepository for PostgresSchemaRepository {
-    async fn find(&self, offset: Option<u64>, limit: Option<u64>) -> Result<Page<Schema>, Error> {
-        let offset = offset.unwrap_or(0);
-        let mut limit = limit.unwrap_or(10);
-        if limit > 25 {
-            limit = 25;
-        }
 
-        let sqlite_schemas: Vec<SqlxSchema> = sqlx::query_as(
-            "
-                SELECT *
-                FROM schemas
-                LIMIT $1 OFFSET $2
-           ",
-        )
-        .bind(limit as i64)
-        .bind(offset as i64)
-        .fetch_all(&self.pool)
-        .await
-        .map_err(Error::Database)?;
-
-        let mut schemas = Vec::new();
-        for sqlite_schema in sqlite_schemas.into_iter() {
-            let sqlite_configs: Vec<SqlxConfig> =
-                sqlx::query_as("SELECT * FROM configs WHERE schema_id = $1")
-                    .bind(&sqlite_schema.id)
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(Error::Database)?;
-
-            let mut configs = HashMap::new();
-            for sqlite_config in sqlite_configs.into_iter() {
-                let sqlite_accesses: Vec<SqlxAccess> =
-                    sqlx::query_as("SELECT * FROM accesses WHERE schema_id = $1 AND id = $2")
-                        .bind(&sqlite_schema.id)
-                        .bind(&sqlite_config.id)
-                        .fetch_all(&self.pool)
-                        .await
-                        .map_err(Error::Database)?;
-
-                let accesses = sqlite_accesses
-                    .into_iter()
-                    .map(SqlxAccess::to_domain)
-                    .collect::<Result<Vec<Access>, Error>>()?;
-
-                let config = sqlite_config.to_domain(accesses)?;
-                configs.insert(config.id().clone(), config);
-            }
-
-            schemas.push(sqlite_schema.to_domain(configs)?);
-        }
-
-        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schemas")
-            .fetch_one(&self.pool)
-            .await
-            .map_err(Error::Database)?;
-
-        Page::new(offset, limit, count as u64, schemas)
-    }
-
-    async fn find_by_id(&self, id: &Id) -> Result<Option<Schema>, Error> {
-        let sqlite_schema: Option<SqlxSchema> = sqlx::query_as("SELECT * FROM schemas")
-            .bind(id.value())
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(Error::Database)?;
-
-        if let Some(sqlite_schema) = sqlite_schema {
-            let sqlite_configs: Vec<SqlxConfig> =
-                sqlx::query_as("SELECT * FROM configs WHERE schema_id = $1")
-                    .bind(&sqlite_schema.id)
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(Error::Database)?;
-
-            let mut configs = HashMap::new();
-            for sqlite_config in sqlite_configs.into_iter() {
-                let sqlite_accesses: Vec<SqlxAccess> =
-                    sqlx::query_as("SELECT * FROM accesses WHERE schema_id = $1 AND id = $2")
-                        .bind(&sqlite_schema.id)
-                        .bind(&sqlite_config.id)
-                        .fetch_all(&self.pool)
-                        .await
-                        .map_err(Error::Database)?;
-
-                let accesses = sqlite_accesses
-                    .into_iter()
-                    .map(SqlxAccess::to_domain)
-                    .collect::<Result<Vec<Access>, Error>>()?;
-
-                let config = sqlite_config.to_domain(accesses)?;
-                configs.insert(config.id().clone(), config);
+    // Applies the recorded events within the transaction
+    pub async fn save_in(tx: &mut Transaction<'_, Postgres>, schema: &Schema) -> Result<(), Error> {
+        // Only changed from the version it was loaded with, which also locks
+        // the row until the transaction ends
+        let created = schema
+            .events()
+            .iter()
+            .any(|event| event.topic() == "schema.created");
+        if !created {
+            let res = sqlx::query("UPDATE schemas SET version = $2 WHERE id = $1 AND version = $3")
+                .bind(schema.id().value())
+                .bind(schema.version().value())
+                .bind(schema.version().original())
+                .execute(&mut *tx)
+                .await
+                .map_err(Error::Database)?;
+
+            if res.rows_affected() == 0 {
+                return Err(Error::SchemaModified(schema.id().clone()));
             }
-
-            Ok(Some(sqlite_schema.to_domain(configs)?))
-        } else {
-            Ok(None)
         }
-    }
 
-    async fn exists(&self, id: &Id) -> Result<bool, Error> {
-        Ok(self.find_by_id(id).await?.is_some())
-    }
-
-    async fn save(&self, schema: &mut Schema) -> Result<(), Error> {
         for event in schema.events() {
             let query = match event.topic() {
                 // Schemas
@@ -197,15 +89,17 @@ impl SchemaRepository for PostgresSchemaRepository {
                             id,
                             name,
                             root_prop,
+                            rules,
                             created_at,
                             updated_at,
                             version
-                        ) VALUES ($1, $2, $3, $4, $5, 1)
+                        ) VALUES ($1, $2, $3, $4, $5, $6, 1)
                         ",
                     )
                     .bind(payload.id)
                     .bind(payload.name)
                     .bind(payload.root_prop)
+                    .bind(payload.rules)
                     .bind(event.timestamp())
                     .bind(event.timestamp())
                 }
@@ -217,8 +111,7 @@ impl SchemaRepository for PostgresSchemaRepository {
                         UPDATE schemas
                         SET
                             root_prop = $2,
-                            updated_at = $3,
-                            version = version + 1
+                            updated_at = $3
                         WHERE id = $1
                         ",
                     )
@@ -226,176 +119,61 @@ impl SchemaRepository for PostgresSchemaRepository {
                     .bind(payload.root_prop)
                     .bind(event.timestamp())
                 }
-                "schema.deleted" => {
-                    let payload: SchemaDeleted = event.deserialize_payload().unwrap();
-
-                    sqlx::query(
-                        "
-                        DELETE FROM schemas
-                        WHERE id = $1
-                        ",
-                    )
-                    .bind(payload.id)
-                }
-                // Configs
-                "config.created" => {
-                    let payload: ConfigCreated = event.deserialize_payload().unwrap();
-
-                    sqlx::query(
-                        "
-                        INSERT INTO configs (
-                            schema_id,
-                            id,
-                            name,
-                            data,
-                            valid,
-                            password,
-                            created_at,
-                            updated_at,
-                            version
-                        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 1)
-                        ",
-                    )
-                    .bind(payload.schema_id)
-                    .bind(payload.id)
-                    .bind(payload.name)
-                    .bind(payload.data)
-                    .bind(payload.valid)
-                    .bind(payload.password)
-                    .bind(event.timestamp())
-                    .bind(event.timestamp())
-                }
-                "config.data_changed" => {
-                    let payload: ConfigDataChanged = event.deserialize_payload().unwrap();
-
-                    sqlx::query(
-                        "
-                        UPDATE configs
-                        SET
-                            data = $3,
-                            valid = $4,
-                            updated_at = $5,
-                            version = version + 1
-                        WHERE
-                            schema_id = $1 AND id = $2
-                        ",
-                    )
-                    .bind(payload.schema_id)
-                    .bind(payload.id)
-                    .bind(payload.data)
-                    .bind(payload.valid)
-                    .bind(event.timestamp())
-                }
-                "config.revalidated" => {
-                    let payload: ConfigRevalidated = event.deserialize_payload().unwrap();
+                "schema.rules_changed" => {
+                    let payload: SchemaRulesChanged = event.deserialize_payload().unwrap();
 
                     sqlx::query(
                         "
-                        UPDATE configs
+                        UPDATE schemas
                         SET
-                            valid = $3,
-                            updated_at = $4,
-                            version = version + 1
-                        WHERE
-                            schema_id = $1 AND id = $2
+                            rules = $2,
+                            updated_at = $3
+                        WHERE id = $1
                         ",
                     )
-                    .bind(payload.schema_id)
                     .bind(payload.id)
-                    .bind(payload.valid)
+                    .bind(payload.rules)
                     .bind(event.timestamp())
                 }
-                "config.password_changed" => {
-                    let payload: ConfigPasswordChanged = event.deserialize_payload().unwrap();
+                "schema.deleted" => {
+                    let payload: SchemaDeleted = event.deserialize_payload().unwrap();
 
                     sqlx::query(
                         "
-                        UPDATE configs
+                        UPDATE schemas
                         SET
-                            password = $3,
-                            updated_at = $4,
-                            version = version + 1
-                        WHERE
-                            schema_id = $1 AND id = $2
+                            deleted_at = $2
+                        WHERE id = $1
                         ",
                     )
-                    .bind(payload.schema_id)
                     .bind(payload.id)
-                    .bind(payload.password)
                     .bind(event.timestamp())
                 }
-                "config.password_deleted" => {
-                    let payload: ConfigPasswordDeleted = event.deserialize_payload().unwrap();
+                "schema.restored" => {
+                    let payload: SchemaRestored = event.deserialize_payload().unwrap();
 
                     sqlx::query(
                         "
-                        UPDATE configs
+                        UPDATE schemas
                         SET
-                            password = null,
-                            updated_at = $3,
-                            version = version + 1
-                        WHERE
-                            schema_id = $1 AND id = $2
+                            updated_at = $2,
+                            deleted_at = NULL
+                        WHERE id = $1
                         ",
                     )
-                    .bind(payload.schema_id)
                     .bind(payload.id)
                     .bind(event.timestamp())
                 }
-                "config.deleted" => {
-                    let payload: ConfigDeleted = event.deserialize_payload().unwrap();
-
-                    sqlx::query(
-                        "
-                        DELETE FROM configs
-                        WHERE schema_id = $1 AND id = $2
-                        ",
-                    )
-                    .bind(payload.schema_id)
-                    .bind(payload.id)
-                }
-                // Accesses
-                "config.accessed" => {
-                    let payload: ConfigAccessed = event.deserialize_payload().unwrap();
+                "schema.purged" => {
+                    let payload: SchemaPurged = event.deserialize_payload().unwrap();
 
                     sqlx::query(
                         "
-                        INSERT INTO accesses(
-                            schema_id,
-                            id,
-                            source,
-                            instance,
-                            timestamp,
-                            previous
-                        ) VALUES ($1, $2, $3, $4, $5, $6)
-                        ON CONFLICT (schema_id, id, source, instance)
-                        DO
-                            UPDATE
-                            SET
-                                timestamp = $5,
-                                previous = $6
-                        ",
-                    )
-                    .bind(payload.schema_id)
-                    .bind(payload.id)
-                    .bind(payload.source)
-                    .bind(payload.instance)
-                    .bind(payload.timestamp)
-                    .bind(payload.previous)
-                }
-                "config.access_removed" => {
-                    let payload: ConfigAccessRemoved = event.deserialize_payload().unwrap();
-
-                    sqlx::query(
-                        "
-                        DELETE FROM accesses
-                        WHERE schema_id = $1 AND id = $2 AND source = $3 AND instance = $4
+                        DELETE FROM schemas
+                        WHERE id = $1
                         ",
                     )
-                    .bind(payload.schema_id)
                     .bind(payload.id)
-                    .bind(payload.source)
-                    .bind(payload.instance)
                 }
                 _ => sqlx::query(
                     "
@@ -409,9 +187,187 @@ impl SchemaRepository for PostgresSchemaRepository {
                 .bind(schema.timestamps().updated_at()),
             };
 
-            query.execute(&self.pool).await.map_err(Error::Database)?;
+            query.execute(&mut *tx).await.map_err(Error::Database)?;
         }
 
         Ok(())
     }
 }
+
+#[async_trait]
+impl SchemaRepository for PostgresSchemaRepository {
+    async fn find(&self, query: &SchemaQuery) -> Result<Page<Schema>, Error> {
+        let mut count_query = QueryBuilder::new("SELECT COUNT(*) FROM schemas WHERE 1 = 1");
+        push_filters(&mut count_query, query);
+
+        let count: i64 = count_query
+            .build()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(Error::Database)?
+            .get(0);
+
+        let mut select_query = QueryBuilder::new("SELECT * FROM schemas WHERE 1 = 1");
+        push_filters(&mut select_query, query);
+
+        let (column, operator, direction) = sorting(query);
+        if let Some(cursor) = &query.cursor {
+            select_query.push(format!(" AND ({} {} ", column, operator));
+            push_sort_value(&mut select_query, cursor.value());
+            select_query.push(format!(" OR ({} = ", column));
+            push_sort_value(&mut select_query, cursor.value());
+            select_query.push(format!(" AND id {} ", operator));
+            select_query.push_bind(cursor.id().to_string());
+            select_query.push("))");
+        }
+
+        select_query
+            .push(format!(
+                " ORDER BY {} {}, id {} LIMIT ",
+                column, direction, direction
+            ))
+            .push_bind(query.limit as i64)
+            .push(" OFFSET ")
+            .push_bind(query.offset as i64);
+
+        let schemas = select_query
+            .build()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(Error::Database)?
+            .iter()
+            .map(|row| {
+                SqlxSchema::from_row(row)
+                    .map_err(Error::Database)
+                    .and_then(SqlxSchema::into_domain)
+            })
+            .collect::<Result<Vec<Schema>, Error>>()?;
+
+        Page::new(query.offset, query.limit, count as u64, schemas)
+    }
+
+    async fn find_by_id(&self, id: &Id) -> Result<Option<Schema>, Error> {
+        let sqlite_schema: Option<SqlxSchema> =
+            sqlx::query_as("SELECT * FROM schemas WHERE id = $1 AND deleted_at IS NULL")
+                .bind(id.value())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(Error::Database)?;
+
+        sqlite_schema.map(SqlxSchema::into_domain).transpose()
+    }
+
+    async fn find_deleted_by_id(&self, id: &Id) -> Result<Option<Schema>, Error> {
+        let sqlite_schema: Option<SqlxSchema> =
+            sqlx::query_as("SELECT * FROM schemas WHERE id = $1 AND deleted_at IS NOT NULL")
+                .bind(id.value())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(Error::Database)?;
+
+        sqlite_schema.map(SqlxSchema::into_domain).transpose()
+    }
+
+    async fn exists(&self, id: &Id) -> Result<bool, Error> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schemas WHERE id = $1")
+            .bind(id.value())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(count > 0)
+    }
+
+    async fn count(&self) -> Result<u64, Error> {
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM schemas WHERE deleted_at IS NULL")
+                .fetch_one(&self.pool)
+                .await
+                .map_err(Error::Database)?;
+
+        Ok(count as u64)
+    }
+
+    async fn save(&self, schema: &mut Schema) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+        PostgresSchemaRepository::save_in(&mut tx, schema).await?;
+        tx.commit().await.map_err(Error::Database)
+    }
+
+    async fn save_with_configs(
+        &self,
+        schema: &mut Schema,
+        configs: &mut [Config],
+    ) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+        PostgresSchemaRepository::save_in(&mut tx, schema).await?;
+        for config in configs.iter() {
+            PostgresConfigRepository::save_in(&mut tx, config).await?;
+        }
+        tx.commit().await.map_err(Error::Database)
+    }
+}
+
+fn push_filters(builder: &mut QueryBuilder<Postgres>, query: &SchemaQuery) {
+    builder.push(if query.deleted {
+        " AND deleted_at IS NOT NULL"
+    } else {
+        " AND deleted_at IS NULL"
+    });
+
+    if let Some(deleted_before) = query.deleted_before {
+        builder.push(" AND deleted_at < ").push_bind(deleted_before);
+    }
+
+    if let Some(name) = &query.name {
+        builder
+            .push(" AND name ILIKE ")
+            .push_bind(contains_pattern(name))
+            .push(" ESCAPE '\\'");
+    }
+
+    if let Some(updated_since) = query.updated_since {
+        builder.push(" AND updated_at >= ").push_bind(updated_since);
+    }
+
+    if let Some(has_invalid_configs) = query.has_invalid_configs {
+        builder.push(if has_invalid_configs {
+            " AND EXISTS"
+        } else {
+            " AND NOT EXISTS"
+        });
+        builder.push(
+            " (SELECT 1 FROM configs WHERE configs.schema_id = schemas.id AND NOT configs.valid",
+        );
+        builder.push(" AND configs.deleted_at IS NULL)");
+    }
+
+    if let Some(configs_deleted_before) = query.configs_deleted_before {
+        builder
+            .push(" AND EXISTS (SELECT 1 FROM configs WHERE configs.schema_id = schemas.id")
+            .push(" AND configs.deleted_at < ")
+            .push_bind(configs_deleted_before)
+            .push(")");
+    }
+}
+
+// Column, cursor operator and direction
+fn sorting(query: &SchemaQuery) -> (&'static str, &'static str, &'static str) {
+    let column = match query.sort {
+        SchemaSort::Name => "name COLLATE \"C\"",
+        SchemaSort::CreatedAt => "created_at",
+        SchemaSort::UpdatedAt => "updated_at",
+    };
+
+    match query.order {
+        SortOrder::Asc => (column, ">", "ASC"),
+        SortOrder::Desc => (column, "<", "DESC"),
+    }
+}
+
+fn push_sort_value(builder: &mut QueryBuilder<Postgres>, value: &SortValue) {
+    match value {
+        SortValue::Text(text) => builder.push_bind(text.clone()),
+        SortValue::Timestamp(timestamp) => builder.push_bind(*timestamp),
+    };
+}
@@ -0,0 +1,138 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+use crate::domain::errors::Error;
+
+// Buckets beyond these share the overflow bucket
+const MAX_BUCKETS: usize = 10_000;
+
+// Full buckets are pruned at most this often, a drained one refills within it
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+struct State {
+    buckets: HashMap<String, Bucket>,
+    // Taken by new keys while every bucket is in use
+    overflow: Bucket,
+    swept_at: Instant,
+}
+
+// Token buckets by key. Every bucket holds up to rate tokens, refilled at rate
+// tokens per second, and every request takes one. Buckets still refilling are
+// never dropped, so rotating keys cannot reset the limit of another one.
+pub struct RateLimiter {
+    rate: f64,
+    state: Mutex<State>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: u32) -> RateLimiter {
+        let now = Instant::now();
+        RateLimiter {
+            rate: f64::from(rate),
+            state: Mutex::new(State {
+                buckets: HashMap::new(),
+                overflow: Bucket {
+                    tokens: f64::from(rate),
+                    updated_at: now,
+                },
+                swept_at: now,
+            }),
+        }
+    }
+
+    pub async fn check(&self, key: &str) -> Result<(), Error> {
+        let now = Instant::now();
+        let mut state = self.state.lock().await;
+        let state = &mut *state;
+
+        if state.buckets.len() >= MAX_BUCKETS
+            && !state.buckets.contains_key(key)
+            && now.duration_since(state.swept_at) >= SWEEP_INTERVAL
+        {
+            let rate = self.rate;
+            state
+                .buckets
+                .retain(|_, bucket| refill(bucket, rate, now) < rate);
+            state.swept_at = now;
+        }
+
+        let bucket = if state.buckets.len() < MAX_BUCKETS || state.buckets.contains_key(key) {
+            state.buckets.entry(key.to_string()).or_insert(Bucket {
+                tokens: self.rate,
+                updated_at: now,
+            })
+        } else {
+            &mut state.overflow
+        };
+        bucket.tokens = refill(bucket, self.rate, now);
+        bucket.updated_at = now;
+
+        if bucket.tokens < 1.0 {
+            // Seconds until the next token, rounded up
+            let retry_after = ((1.0 - bucket.tokens) / self.rate).ceil() as u64;
+            return Err(Error::RateLimited(retry_after));
+        }
+
+        bucket.tokens -= 1.0;
+
+        Ok(())
+    }
+}
+
+fn refill(bucket: &Bucket, rate: f64, now: Instant) -> f64 {
+    let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+    (bucket.tokens + elapsed * rate).min(rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn token_bucket() {
+        let limiter = RateLimiter::new(2);
+
+        assert!(limiter.check("client#01").await.is_ok());
+        assert!(limiter.check("client#01").await.is_ok());
+        assert!(matches!(
+            limiter.check("client#01").await,
+            Err(Error::RateLimited(1))
+        ));
+
+        // Keys have their own buckets
+        assert!(limiter.check("client#02").await.is_ok());
+
+        // Half a second refills one token
+        tokio::time::sleep(Duration::from_millis(510)).await;
+        assert!(limiter.check("client#01").await.is_ok());
+        assert!(limiter.check("client#01").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn overflow() {
+        let limiter = RateLimiter::new(1);
+
+        for i in 0..MAX_BUCKETS {
+            limiter.check(&format!("client#{}", i)).await.unwrap();
+        }
+
+        // Drained buckets are kept, new keys share the overflow one
+        assert!(limiter.check("client#0").await.is_err());
+        assert!(limiter.check("new#01").await.is_ok());
+        assert!(limiter.check("new#02").await.is_err());
+
+        // Refilled buckets make room
+        tokio::time::sleep(Duration::from_millis(1010)).await;
+        assert!(limiter.check("new#02").await.is_ok());
+        assert!(limiter.check("new#03").await.is_ok());
+        assert!(limiter.state.lock().await.buckets.len() <= 2);
+    }
+}
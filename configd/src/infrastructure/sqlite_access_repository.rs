@@ -0,0 +1,288 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+
+use crate::{
+    domain::{
+        configs::{Access, AccessRepository},
+        errors::Error,
+        shared::Id,
+    },
+    infrastructure::SqlxAccess,
+};
+
+pub struct SQLiteAccessRepository {
+    pool: SqlitePool,
+}
+
+impl SQLiteAccessRepository {
+    pub async fn new(pool: SqlitePool) -> Result<SQLiteAccessRepository, Error> {
+        sqlx::query(
+            "
+            CREATE TABLE IF NOT EXISTS accesses(
+              schema_id VARCHAR(255) NOT NULL,
+              id VARCHAR(255) NOT NULL,
+              source TEXT NOT NULL,
+              instance TEXT NOT NULL,
+              timestamp TIMESTAMP WITH TIME ZONE NOT NULL,
+              previous TIMESTAMP WITH TIME ZONE,
+              expires_at TIMESTAMP WITH TIME ZONE,
+              PRIMARY KEY (schema_id, id, source, instance)
+            );
+            ",
+        )
+        .execute(&pool)
+        .await
+        .map_err(Error::Database)?;
+
+        // Accesses saved before they expired, deleted on the next flush
+        let has_expires_at: u32 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pragma_table_info('accesses') WHERE name = 'expires_at'",
+        )
+        .fetch_one(&pool)
+        .await
+        .map_err(Error::Database)?;
+
+        if has_expires_at == 0 {
+            sqlx::query("ALTER TABLE accesses ADD COLUMN expires_at TIMESTAMP WITH TIME ZONE")
+                .execute(&pool)
+                .await
+                .map_err(Error::Database)?;
+        }
+
+        Ok(SQLiteAccessRepository { pool })
+    }
+}
+
+#[async_trait]
+impl AccessRepository for SQLiteAccessRepository {
+    async fn find_active_by_config(
+        &self,
+        schema_id: &Id,
+        config_id: &Id,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Access>, Error> {
+        let sqlx_accesses: Vec<SqlxAccess> = sqlx::query_as(
+            "SELECT * FROM accesses WHERE schema_id = $1 AND id = $2 AND expires_at >= $3",
+        )
+        .bind(schema_id.value())
+        .bind(config_id.value())
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx_accesses
+            .into_iter()
+            .map(SqlxAccess::into_domain)
+            .collect()
+    }
+
+    async fn save(&self, schema_id: &Id, config_id: &Id, accesses: &[Access]) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        for access in accesses.iter() {
+            sqlx::query(
+                "
+                INSERT INTO accesses(
+                    schema_id,
+                    id,
+                    source,
+                    instance,
+                    timestamp,
+                    previous,
+                    expires_at
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (schema_id, id, source, instance) DO UPDATE SET
+                    timestamp = excluded.timestamp,
+                    previous = excluded.previous,
+                    expires_at = excluded.expires_at
+                WHERE accesses.timestamp < excluded.timestamp
+                ",
+            )
+            .bind(schema_id.value())
+            .bind(config_id.value())
+            .bind(access.source().value())
+            .bind(access.instance().value())
+            .bind(access.timestamp())
+            .bind(access.previous())
+            .bind(access.expires_at())
+            .execute(&mut tx)
+            .await
+            .map_err(Error::Database)?;
+        }
+
+        tx.commit().await.map_err(Error::Database)
+    }
+
+    async fn delete_by_config(&self, schema_id: &Id, config_id: &Id) -> Result<(), Error> {
+        sqlx::query("DELETE FROM accesses WHERE schema_id = $1 AND id = $2")
+            .bind(schema_id.value())
+            .bind(config_id.value())
+            .execute(&self.pool)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<(), Error> {
+        sqlx::query("DELETE FROM accesses WHERE expires_at IS NULL OR expires_at < $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    async fn count_active_instances(&self, now: DateTime<Utc>) -> Result<u64, Error> {
+        let count: i64 = sqlx::query_scalar(
+            "
+            SELECT COUNT(*) FROM (
+                SELECT DISTINCT source, instance FROM accesses WHERE expires_at >= $1
+            ) AS instances
+            ",
+        )
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(count as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Duration;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn repository() -> SQLiteAccessRepository {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        SQLiteAccessRepository::new(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn save_upserts_accesses() {
+        let repository = repository().await;
+
+        let schema_id = Id::new("schema-01").unwrap();
+        let config_id = Id::new("config-01").unwrap();
+        let source = Id::new("source").unwrap();
+        let now = Utc::now();
+
+        let access = |instance: &str, timestamp| {
+            Access::new(source.clone(), Id::new(instance).unwrap(), timestamp, None)
+        };
+
+        repository
+            .save(
+                &schema_id,
+                &config_id,
+                &[access("instance#01", now), access("instance#02", now)],
+            )
+            .await
+            .unwrap();
+
+        // Other accesses are kept and older ones don't overwrite newer ones
+        repository
+            .save(
+                &schema_id,
+                &config_id,
+                &[
+                    access("instance#01", now + Duration::seconds(5)),
+                    access("instance#02", now - Duration::seconds(5)),
+                ],
+            )
+            .await
+            .unwrap();
+        repository
+            .save(&schema_id, &config_id, &[access("instance#03", now)])
+            .await
+            .unwrap();
+
+        let mut accesses = repository
+            .find_active_by_config(&schema_id, &config_id, now)
+            .await
+            .unwrap();
+        accesses.sort_by(|a, b| a.instance().value().cmp(b.instance().value()));
+
+        assert_eq!(accesses.len(), 3);
+        assert_eq!(*accesses[0].timestamp(), now + Duration::seconds(5));
+        assert_eq!(*accesses[1].timestamp(), now);
+        assert_eq!(accesses[2].instance().value(), "instance#03");
+    }
+
+    #[tokio::test]
+    async fn delete_accesses() {
+        let repository = repository().await;
+
+        let schema_id = Id::new("schema-01").unwrap();
+        let config_ids = [Id::new("config-01").unwrap(), Id::new("config-02").unwrap()];
+        let now = Utc::now();
+
+        let access = |instance: &str, timestamp| {
+            Access::new(
+                Id::new("source").unwrap(),
+                Id::new(instance).unwrap(),
+                timestamp,
+                None,
+            )
+        };
+
+        for config_id in config_ids.iter() {
+            repository
+                .save(
+                    &schema_id,
+                    config_id,
+                    &[
+                        access("instance#01", now),
+                        access("instance#02", now - Duration::minutes(5)),
+                    ],
+                )
+                .await
+                .unwrap();
+        }
+
+        // Expired accesses are not found and then deleted
+        let accesses = repository
+            .find_active_by_config(&schema_id, &config_ids[0], now)
+            .await
+            .unwrap();
+        assert_eq!(accesses.len(), 1);
+        assert_eq!(accesses[0].instance().value(), "instance#01");
+
+        repository.delete_expired(now).await.unwrap();
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM accesses")
+            .fetch_one(&repository.pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        // Only the accesses of the deleted config are removed
+        repository
+            .delete_by_config(&schema_id, &config_ids[0])
+            .await
+            .unwrap();
+        assert!(repository
+            .find_active_by_config(&schema_id, &config_ids[0], now)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            repository
+                .find_active_by_config(&schema_id, &config_ids[1], now)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,528 @@
+use async_trait::async_trait;
+use sqlx::{FromRow, QueryBuilder, Row, Sqlite, SqlitePool, Transaction};
+
+use crate::{
+    domain::{
+        configs::{
+            Config, ConfigCount, ConfigCreated, ConfigDataChanged, ConfigDeleted,
+            ConfigPasswordChanged, ConfigPasswordDeleted, ConfigPurged, ConfigQuery,
+            ConfigRepository, ConfigRestored, ConfigRevalidated, ConfigSort,
+        },
+        errors::Error,
+        shared::{Id, Page, SortOrder, SortValue},
+    },
+    infrastructure::{contains_pattern, SqlxConfig},
+};
+
+pub struct SQLiteConfigRepository {
+    pool: SqlitePool,
+}
+
+impl SQLiteConfigRepository {
+    pub async fn new(pool: SqlitePool) -> Result<SQLiteConfigRepository, Error> {
+        sqlx::query(
+            "
+            CREATE TABLE IF NOT EXISTS configs(
+              schema_id VARCHAR(255) NOT NULL,
+              id VARCHAR(255) NOT NULL,
+              name TEXT NOT NULL,
+              data JSON NOT NULL,
+              valid BOOLEAN NOT NULL,
+              password TEXT,
+              created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+              updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
+              version INTEGER NOT NULL,
+              deleted_at TIMESTAMP WITH TIME ZONE,
+              PRIMARY KEY (schema_id, id)
+            );
+            ",
+        )
+        .execute(&pool)
+        .await
+        .map_err(Error::Database)?;
+
+        // Configs created before soft deletes
+        let has_deleted_at: u32 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pragma_table_info('configs') WHERE name = 'deleted_at'",
+        )
+        .fetch_one(&pool)
+        .await
+        .map_err(Error::Database)?;
+
+        if has_deleted_at == 0 {
+            sqlx::query("ALTER TABLE configs ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE")
+                .execute(&pool)
+                .await
+                .map_err(Error::Database)?;
+        }
+
+        Ok(SQLiteConfigRepository { pool })
+    }
+
+    // Applies the recorded events within the transaction, so other
+    // repositories can save along with the config
+    pub async fn save_in(tx: &mut Transaction<'_, Sqlite>, config: &Config) -> Result<(), Error> {
+        // Only changed from the version it was loaded with, which also locks
+        // the row until the transaction ends
+        let created = config
+            .events()
+            .iter()
+            .any(|event| event.topic() == "config.created");
+        if !created && !config.events().is_empty() {
+            let res = sqlx::query(
+                "
+                UPDATE configs
+                SET version = $3
+                WHERE schema_id = $1 AND id = $2 AND version = $4
+                ",
+            )
+            .bind(config.schema_id().value())
+            .bind(config.id().value())
+            .bind(config.version().value())
+            .bind(config.version().original())
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+
+            if res.rows_affected() == 0 {
+                return Err(Error::ConfigModified(config.id().clone()));
+            }
+        }
+
+        for event in config.events() {
+            let query = match event.topic() {
+                "config.created" => {
+                    let payload: ConfigCreated = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        INSERT INTO configs (
+                            schema_id,
+                            id,
+                            name,
+                            data,
+                            valid,
+                            password,
+                            created_at,
+                            updated_at,
+                            version
+                        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 1)
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(payload.name)
+                    .bind(payload.data)
+                    .bind(payload.valid)
+                    .bind(payload.password)
+                    .bind(event.timestamp())
+                    .bind(event.timestamp())
+                }
+                "config.data_changed" => {
+                    let payload: ConfigDataChanged = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        UPDATE configs
+                        SET
+                            data = $3,
+                            valid = $4,
+                            updated_at = $5
+                        WHERE
+                            schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(payload.data)
+                    .bind(payload.valid)
+                    .bind(event.timestamp())
+                }
+                "config.revalidated" => {
+                    let payload: ConfigRevalidated = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        UPDATE configs
+                        SET
+                            valid = $3,
+                            updated_at = $4
+                        WHERE
+                            schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(payload.valid)
+                    .bind(event.timestamp())
+                }
+                "config.password_changed" => {
+                    let payload: ConfigPasswordChanged = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        UPDATE configs
+                        SET
+                            password = $3,
+                            updated_at = $4
+                        WHERE
+                            schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(payload.password)
+                    .bind(event.timestamp())
+                }
+                "config.password_deleted" => {
+                    let payload: ConfigPasswordDeleted = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        UPDATE configs
+                        SET
+                            password = null,
+                            updated_at = $3
+                        WHERE
+                            schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(event.timestamp())
+                }
+                "config.deleted" => {
+                    let payload: ConfigDeleted = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        UPDATE configs
+                        SET
+                            deleted_at = $3
+                        WHERE
+                            schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(event.timestamp())
+                }
+                "config.restored" => {
+                    let payload: ConfigRestored = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        UPDATE configs
+                        SET
+                            updated_at = $3,
+                            deleted_at = NULL
+                        WHERE
+                            schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                    .bind(event.timestamp())
+                }
+                "config.purged" => {
+                    let payload: ConfigPurged = event.deserialize_payload().unwrap();
+
+                    sqlx::query(
+                        "
+                        DELETE FROM configs
+                        WHERE schema_id = $1 AND id = $2
+                        ",
+                    )
+                    .bind(payload.schema_id)
+                    .bind(payload.id)
+                }
+                _ => continue,
+            };
+
+            query.execute(&mut *tx).await.map_err(Error::Database)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ConfigRepository for SQLiteConfigRepository {
+    async fn find(&self, schema_id: &Id, query: &ConfigQuery) -> Result<Page<Config>, Error> {
+        let mut count_query = QueryBuilder::new("SELECT COUNT(*) FROM configs WHERE schema_id = ");
+        count_query.push_bind(schema_id.value());
+        push_filters(&mut count_query, query);
+
+        let count: u32 = count_query
+            .build()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(Error::Database)?
+            .get(0);
+
+        let mut select_query = QueryBuilder::new("SELECT * FROM configs WHERE schema_id = ");
+        select_query.push_bind(schema_id.value());
+        push_filters(&mut select_query, query);
+
+        let (column, operator, direction) = sorting(query);
+        if let Some(cursor) = &query.cursor {
+            select_query.push(format!(" AND ({} {} ", column, operator));
+            push_sort_value(&mut select_query, cursor.value());
+            select_query.push(format!(" OR ({} = ", column));
+            push_sort_value(&mut select_query, cursor.value());
+            select_query.push(format!(" AND id {} ", operator));
+            select_query.push_bind(cursor.id().to_string());
+            select_query.push("))");
+        }
+
+        select_query
+            .push(format!(
+                " ORDER BY {} {}, id {} LIMIT ",
+                column, direction, direction
+            ))
+            .push_bind(query.limit as u32)
+            .push(" OFFSET ")
+            .push_bind(query.offset as u32);
+
+        let configs = select_query
+            .build()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(Error::Database)?
+            .iter()
+            .map(|row| {
+                SqlxConfig::from_row(row)
+                    .map_err(Error::Database)
+                    .and_then(SqlxConfig::into_domain)
+            })
+            .collect::<Result<Vec<Config>, Error>>()?;
+
+        Page::new(query.offset, query.limit, count as u64, configs)
+    }
+
+    async fn find_by_id(&self, schema_id: &Id, id: &Id) -> Result<Option<Config>, Error> {
+        let sqlx_config: Option<SqlxConfig> = sqlx::query_as(
+            "SELECT * FROM configs WHERE schema_id = $1 AND id = $2 AND deleted_at IS NULL",
+        )
+        .bind(schema_id.value())
+        .bind(id.value())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx_config.map(SqlxConfig::into_domain).transpose()
+    }
+
+    async fn find_deleted_by_id(&self, schema_id: &Id, id: &Id) -> Result<Option<Config>, Error> {
+        let sqlx_config: Option<SqlxConfig> = sqlx::query_as(
+            "SELECT * FROM configs WHERE schema_id = $1 AND id = $2 AND deleted_at IS NOT NULL",
+        )
+        .bind(schema_id.value())
+        .bind(id.value())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx_config.map(SqlxConfig::into_domain).transpose()
+    }
+
+    async fn exists(&self, schema_id: &Id, id: &Id) -> Result<bool, Error> {
+        let count: u32 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM configs WHERE schema_id = $1 AND id = $2")
+                .bind(schema_id.value())
+                .bind(id.value())
+                .fetch_one(&self.pool)
+                .await
+                .map_err(Error::Database)?;
+
+        Ok(count > 0)
+    }
+
+    async fn count(&self) -> Result<ConfigCount, Error> {
+        let (total, invalid): (i64, i64) = sqlx::query_as(
+            "
+            SELECT COUNT(*), COALESCE(SUM(CASE WHEN valid THEN 0 ELSE 1 END), 0)
+            FROM configs
+            WHERE deleted_at IS NULL
+            ",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(ConfigCount {
+            total: total as u64,
+            invalid: invalid as u64,
+        })
+    }
+
+    async fn save(&self, config: &mut Config) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+        SQLiteConfigRepository::save_in(&mut tx, config).await?;
+        tx.commit().await.map_err(Error::Database)
+    }
+}
+
+fn push_filters(builder: &mut QueryBuilder<Sqlite>, query: &ConfigQuery) {
+    builder.push(if query.deleted {
+        " AND deleted_at IS NOT NULL"
+    } else {
+        " AND deleted_at IS NULL"
+    });
+
+    if let Some(deleted_before) = query.deleted_before {
+        builder.push(" AND deleted_at < ").push_bind(deleted_before);
+    }
+
+    if let Some(name) = &query.name {
+        builder
+            .push(" AND name LIKE ")
+            .push_bind(contains_pattern(name))
+            .push(" ESCAPE '\\'");
+    }
+
+    if let Some(valid) = query.valid {
+        builder.push(" AND valid = ").push_bind(valid);
+    }
+
+    if let Some(requires_password) = query.requires_password {
+        builder.push(if requires_password {
+            " AND password IS NOT NULL"
+        } else {
+            " AND password IS NULL"
+        });
+    }
+}
+
+// Column, cursor operator and direction
+fn sorting(query: &ConfigQuery) -> (&'static str, &'static str, &'static str) {
+    let column = match query.sort {
+        ConfigSort::Name => "name",
+        ConfigSort::CreatedAt => "created_at",
+        ConfigSort::UpdatedAt => "updated_at",
+    };
+
+    match query.order {
+        SortOrder::Asc => (column, ">", "ASC"),
+        SortOrder::Desc => (column, "<", "DESC"),
+    }
+}
+
+fn push_sort_value(builder: &mut QueryBuilder<Sqlite>, value: &SortValue) {
+    match value {
+        SortValue::Text(text) => builder.push_bind(text.clone()),
+        SortValue::Timestamp(timestamp) => builder.push_bind(*timestamp),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use std::collections::BTreeMap;
+
+    use crate::domain::values::Value;
+
+    // A single connection, as every in-memory connection has its own database
+    async fn repository() -> SQLiteConfigRepository {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        SQLiteConfigRepository::new(pool).await.unwrap()
+    }
+
+    fn port(port: i64) -> Value {
+        Value::Object(BTreeMap::from([("port".to_string(), Value::Int(port))]))
+    }
+
+    fn config(id: &str, name: &str, valid: bool) -> Config {
+        Config::create(
+            Id::new("schema#01").unwrap(),
+            Id::new(id).unwrap(),
+            name.to_string(),
+            port(1),
+            valid,
+            None,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn concurrent_saves() {
+        let repository = repository().await;
+        let schema_id = Id::new("schema#01").unwrap();
+        let id = Id::new("config#01").unwrap();
+
+        repository
+            .save(&mut config("config#01", "Config", true))
+            .await
+            .unwrap();
+
+        let mut first = repository
+            .find_by_id(&schema_id, &id)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut second = first.clone();
+
+        first.change_data(port(2), true).unwrap();
+        first.revalidate(false).unwrap();
+        repository.save(&mut first).await.unwrap();
+
+        // The second update was based on the first version
+        second.change_data(port(3), true).unwrap();
+        assert!(matches!(
+            repository.save(&mut second).await,
+            Err(Error::ConfigModified(_))
+        ));
+
+        // Both events of the first save were applied as one version
+        let stored = repository
+            .find_by_id(&schema_id, &id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.data(), &port(2));
+        assert!(!stored.is_valid());
+        assert_eq!(stored.version().value(), 2);
+    }
+
+    #[tokio::test]
+    async fn count() {
+        let repository = repository().await;
+
+        assert_eq!(repository.count().await.unwrap(), ConfigCount::default());
+
+        repository
+            .save(&mut config("config#01", "Config 1", true))
+            .await
+            .unwrap();
+        repository
+            .save(&mut config("config#02", "Config 2", false))
+            .await
+            .unwrap();
+
+        let mut deleted = config("config#03", "Config 3", false);
+        repository.save(&mut deleted).await.unwrap();
+        let mut deleted = repository
+            .find_by_id(deleted.schema_id(), deleted.id())
+            .await
+            .unwrap()
+            .unwrap();
+        deleted.delete(None).unwrap();
+        repository.save(&mut deleted).await.unwrap();
+
+        assert_eq!(
+            repository.count().await.unwrap(),
+            ConfigCount {
+                total: 2,
+                invalid: 1,
+            }
+        );
+    }
+}
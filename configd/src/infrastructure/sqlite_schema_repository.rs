@@ -1,19 +1,17 @@
 use async_trait::async_trait;
-use sqlx::SqlitePool;
-use std::collections::HashMap;
+use sqlx::{FromRow, QueryBuilder, Row, Sqlite, SqlitePool, Transaction};
 
 use crate::{
     domain::{
-        configs::Access,
+        configs::Config,
         errors::Error,
         schemas::{
-            ConfigAccessRemoved, ConfigAccessed, ConfigCreated, ConfigDataChanged, ConfigDeleted,
-            ConfigPasswordChanged, ConfigPasswordDeleted, ConfigRevalidated, Schema, SchemaCreated,
-            SchemaDeleted, SchemaRepository, SchemaRootPropChanged,
+            Schema, SchemaCreated, SchemaDeleted, SchemaPurged, SchemaQuery, SchemaRepository,
+            SchemaRestored, SchemaRootPropChanged, SchemaRulesChanged, SchemaSort,
         },
-        shared::{Id, Page},
+        shared::{Id, Page, SortOrder, SortValue},
     },
-    infrastructure::{SqlxAccess, SqlxConfig, SqlxSchema},
+    infrastructure::{contains_pattern, SQLiteConfigRepository, SqlxSchema},
 };
 
 pub struct SQLiteSchemaRepository {
@@ -28,32 +26,11 @@ impl SQLiteSchemaRepository {
               id VARCHAR(255) PRIMARY KEY,
               name TEXT NOT NULL,
               root_prop JSON NOT NULL,
-              created_at TIMESTAMP WITH TIME ZONE NOT NULL,
-              updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
-              version INTEGER NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS configs(
-              schema_id VARCHAR(255) NOT NULL,
-              id VARCHAR(255) NOT NULL,
-              name TEXT NOT NULL,
-              data JSON NOT NULL,
-              valid BOOLEAN NOT NULL,
-              password TEXT,
+              rules JSON NOT NULL DEFAULT '[]',
               created_at TIMESTAMP WITH TIME ZONE NOT NULL,
               updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
               version INTEGER NOT NULL,
-              PRIMARY KEY (schema_id, id)
-            );
-
-            CREATE TABLE IF NOT EXISTS accesses(
-              schema_id VARCHAR(255) NOT NULL,
-              id VARCHAR(255) NOT NULL,
-              source TEXT NOT NULL,
-              instance TEXT NOT NULL,
-              timestamp TIMESTAMP WITH TIME ZONE NOT NULL,
-              previous TIMESTAMP WITH TIME ZONE,
-              PRIMARY KEY (schema_id, id, source, instance)
+              deleted_at TIMESTAMP WITH TIME ZONE
             );
             ",
         )
@@ -61,116 +38,61 @@ impl SQLiteSchemaRepository {
         .await
         .map_err(Error::Database)?;
 
-        Ok(SQLiteSchemaRepository { pool })
-    }
-}
+        // Schemas created before rules existed
+        let has_rules: u32 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pragma_table_info('schemas') WHERE name = 'rules'",
+        )
+        .fetch_one(&pool)
+        .await
+        .map_err(Error::Database)?;
 
-#[async_trait]
-impl SchemaRepository for SQLiteSchemaRepository {
-    async fn find(&self, offset: Option<u64>, limit: Option<u64>) -> Result<Page<Schema>, Error> {
-        let offset = offset.unwrap_or(0);
-        let mut limit = limit.unwrap_or(10);
-        if limit > 25 {
-            limit = 25;
+        if has_rules == 0 {
+            sqlx::query("ALTER TABLE schemas ADD COLUMN rules JSON NOT NULL DEFAULT '[]'")
+                .execute(&pool)
+                .await
+                .map_err(Error::Database)?;
         }
 
-        let sqlite_schemas: Vec<SqlxSchema> = sqlx::query_as(
-            "
-                SELECT *
-                FROM schemas
-                LIMIT $1 OFFSET $2
-           ",
+        // Schemas created before soft deletes
+        let has_deleted_at: u32 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pragma_table_info('schemas') WHERE name = 'deleted_at'",
         )
-        .bind(limit as u32)
-        .bind(offset as u32)
-        .fetch_all(&self.pool)
+        .fetch_one(&pool)
         .await
         .map_err(Error::Database)?;
 
-        let mut schemas = Vec::new();
-        for sqlite_schema in sqlite_schemas.into_iter() {
-            let sqlite_configs: Vec<SqlxConfig> =
-                sqlx::query_as("SELECT * FROM configs WHERE schema_id = $1")
-                    .bind(&sqlite_schema.id)
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(Error::Database)?;
-
-            let mut configs = HashMap::new();
-            for sqlite_config in sqlite_configs.into_iter() {
-                let sqlite_accesses: Vec<SqlxAccess> =
-                    sqlx::query_as("SELECT * FROM accesses WHERE schema_id = $1 AND id = $2")
-                        .bind(&sqlite_schema.id)
-                        .bind(&sqlite_config.id)
-                        .fetch_all(&self.pool)
-                        .await
-                        .map_err(Error::Database)?;
-
-                let accesses = sqlite_accesses
-                    .into_iter()
-                    .map(SqlxAccess::to_domain)
-                    .collect::<Result<Vec<Access>, Error>>()?;
-
-                let config = sqlite_config.to_domain(accesses)?;
-                configs.insert(config.id().clone(), config);
-            }
-
-            schemas.push(sqlite_schema.to_domain(configs)?);
+        if has_deleted_at == 0 {
+            sqlx::query("ALTER TABLE schemas ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE")
+                .execute(&pool)
+                .await
+                .map_err(Error::Database)?;
         }
 
-        let count: u32 = sqlx::query_scalar("SELECT COUNT(*) FROM schemas")
-            .fetch_one(&self.pool)
-            .await
-            .map_err(Error::Database)?;
-
-        Page::new(offset, limit, count as u64, schemas)
+        Ok(SQLiteSchemaRepository { pool })
     }
 
-    async fn find_by_id(&self, id: &Id) -> Result<Option<Schema>, Error> {
-        let sqlite_schema: Option<SqlxSchema> = sqlx::query_as("SELECT * FROM schemas")
-            .bind(id.value())
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(Error::Database)?;
-
-        if let Some(sqlite_schema) = sqlite_schema {
-            let sqlite_configs: Vec<SqlxConfig> =
-                sqlx::query_as("SELECT * FROM configs WHERE schema_id = $1")
-                    .bind(&sqlite_schema.id)
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(Error::Database)?;
-
-            let mut configs = HashMap::new();
-            for sqlite_config in sqlite_configs.into_iter() {
-                let sqlite_accesses: Vec<SqlxAccess> =
-                    sqlx::query_as("SELECT * FROM accesses WHERE schema_id = $1 AND id = $2")
-                        .bind(&sqlite_schema.id)
-                        .bind(&sqlite_config.id)
-                        .fetch_all(&self.pool)
-                        .await
-                        .map_err(Error::Database)?;
-
-                let accesses = sqlite_accesses
-                    .into_iter()
-                    .map(SqlxAccess::to_domain)
-                    .collect::<Result<Vec<Access>, Error>>()?;
-
-                let config = sqlite_config.to_domain(accesses)?;
-                configs.insert(config.id().clone(), config);
+    // Applies the recorded events within the transaction
+    pub async fn save_in(tx: &mut Transaction<'_, Sqlite>, schema: &Schema) -> Result<(), Error> {
+        // Only changed from the version it was loaded with, which also locks
+        // the row until the transaction ends
+        let created = schema
+            .events()
+            .iter()
+            .any(|event| event.topic() == "schema.created");
+        if !created {
+            let res = sqlx::query("UPDATE schemas SET version = $2 WHERE id = $1 AND version = $3")
+                .bind(schema.id().value())
+                .bind(schema.version().value())
+                .bind(schema.version().original())
+                .execute(&mut *tx)
+                .await
+                .map_err(Error::Database)?;
+
+            if res.rows_affected() == 0 {
+                return Err(Error::SchemaModified(schema.id().clone()));
             }
-
-            Ok(Some(sqlite_schema.to_domain(configs)?))
-        } else {
-            Ok(None)
         }
-    }
-
-    async fn exists(&self, id: &Id) -> Result<bool, Error> {
-        Ok(self.find_by_id(id).await?.is_some())
-    }
 
-    async fn save(&self, schema: &mut Schema) -> Result<(), Error> {
         for event in schema.events() {
             let query = match event.topic() {
                 // Schemas
@@ -183,15 +105,17 @@ impl SchemaRepository for SQLiteSchemaRepository {
                             id,
                             name,
                             root_prop,
+                            rules,
                             created_at,
                             updated_at,
                             version
-                        ) VALUES ($1, $2, $3, $4, $5, 1)
+                        ) VALUES ($1, $2, $3, $4, $5, $6, 1)
                         ",
                     )
                     .bind(payload.id)
                     .bind(payload.name)
                     .bind(payload.root_prop)
+                    .bind(payload.rules)
                     .bind(event.timestamp())
                     .bind(event.timestamp())
                 }
@@ -203,8 +127,7 @@ impl SchemaRepository for SQLiteSchemaRepository {
                         UPDATE schemas
                         SET
                             root_prop = $2,
-                            updated_at = $3,
-                            version = version + 1
+                            updated_at = $3
                         WHERE id = $1
                         ",
                     )
@@ -212,181 +135,61 @@ impl SchemaRepository for SQLiteSchemaRepository {
                     .bind(payload.root_prop)
                     .bind(event.timestamp())
                 }
-                "schema.deleted" => {
-                    let payload: SchemaDeleted = event.deserialize_payload().unwrap();
-
-                    sqlx::query(
-                        "
-                        DELETE FROM schemas
-                        WHERE id = $1
-                        ",
-                    )
-                    .bind(payload.id)
-                }
-                // Configs
-                "config.created" => {
-                    let payload: ConfigCreated = event.deserialize_payload().unwrap();
-
-                    sqlx::query(
-                        "
-                        INSERT INTO configs (
-                            schema_id,
-                            id,
-                            name,
-                            data,
-                            valid,
-                            password,
-                            created_at,
-                            updated_at,
-                            version
-                        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 1)
-                        ",
-                    )
-                    .bind(payload.schema_id)
-                    .bind(payload.id)
-                    .bind(payload.name)
-                    .bind(payload.data)
-                    .bind(payload.valid)
-                    .bind(payload.password)
-                    .bind(event.timestamp())
-                    .bind(event.timestamp())
-                }
-                "config.data_changed" => {
-                    let payload: ConfigDataChanged = event.deserialize_payload().unwrap();
+                "schema.rules_changed" => {
+                    let payload: SchemaRulesChanged = event.deserialize_payload().unwrap();
 
                     sqlx::query(
                         "
-                        UPDATE configs
-                        SET
-                            data = $3,
-                            valid = $4,
-                            updated_at = $5,
-                            version = version + 1
-                        WHERE
-                            schema_id = $1 AND id = $2
-                        ",
-                    )
-                    .bind(payload.schema_id)
-                    .bind(payload.id)
-                    .bind(payload.data)
-                    .bind(payload.valid)
-                    .bind(event.timestamp())
-                }
-                "config.revalidated" => {
-                    let payload: ConfigRevalidated = event.deserialize_payload().unwrap();
-
-                    sqlx::query(
-                        "
-                        UPDATE configs
+                        UPDATE schemas
                         SET
-                            valid = $3,
-                            updated_at = $4,
-                            version = version + 1
-                        WHERE
-                            schema_id = $1 AND id = $2
+                            rules = $2,
+                            updated_at = $3
+                        WHERE id = $1
                         ",
                     )
-                    .bind(payload.schema_id)
                     .bind(payload.id)
-                    .bind(payload.valid)
+                    .bind(payload.rules)
                     .bind(event.timestamp())
                 }
-                "config.password_changed" => {
-                    let payload: ConfigPasswordChanged = event.deserialize_payload().unwrap();
+                "schema.deleted" => {
+                    let payload: SchemaDeleted = event.deserialize_payload().unwrap();
 
                     sqlx::query(
                         "
-                        UPDATE configs
+                        UPDATE schemas
                         SET
-                            password = $3,
-                            updated_at = $4,
-                            version = version + 1
-                        WHERE
-                            schema_id = $1 AND id = $2
+                            deleted_at = $2
+                        WHERE id = $1
                         ",
                     )
-                    .bind(payload.schema_id)
                     .bind(payload.id)
-                    .bind(payload.password)
                     .bind(event.timestamp())
                 }
-                "config.password_deleted" => {
-                    let payload: ConfigPasswordDeleted = event.deserialize_payload().unwrap();
+                "schema.restored" => {
+                    let payload: SchemaRestored = event.deserialize_payload().unwrap();
 
                     sqlx::query(
                         "
-                        UPDATE configs
+                        UPDATE schemas
                         SET
-                            password = null,
-                            updated_at = $3,
-                            version = version + 1
-                        WHERE
-                            schema_id = $1 AND id = $2
+                            updated_at = $2,
+                            deleted_at = NULL
+                        WHERE id = $1
                         ",
                     )
-                    .bind(payload.schema_id)
                     .bind(payload.id)
                     .bind(event.timestamp())
                 }
-                "config.deleted" => {
-                    let payload: ConfigDeleted = event.deserialize_payload().unwrap();
-
-                    sqlx::query(
-                        "
-                        DELETE FROM configs
-                        WHERE schema_id = $1 AND id = $2
-                        ",
-                    )
-                    .bind(payload.schema_id)
-                    .bind(payload.id)
-                }
-                // Accesses
-                "config.accessed" => {
-                    let payload: ConfigAccessed = event.deserialize_payload().unwrap();
-
-                    sqlx::query(
-                        "
-                        INSERT INTO accesses(
-                            schema_id,
-                            id,
-                            source,
-                            instance,
-                            timestamp,
-                            previous
-                        ) VALUES ($1, $2, $3, $4, $5, $6)
-                        ON CONFLICT
-                        DO
-                            UPDATE
-                            SET
-                                timestamp = $5,
-                                previous = $6
-                            WHERE
-                                schema_id = $1
-                                AND id = $2
-                                AND source = $3
-                                AND instance = $4
-                        ",
-                    )
-                    .bind(payload.schema_id)
-                    .bind(payload.id)
-                    .bind(payload.source)
-                    .bind(payload.instance)
-                    .bind(payload.timestamp)
-                    .bind(payload.previous)
-                }
-                "config.access_removed" => {
-                    let payload: ConfigAccessRemoved = event.deserialize_payload().unwrap();
+                "schema.purged" => {
+                    let payload: SchemaPurged = event.deserialize_payload().unwrap();
 
                     sqlx::query(
                         "
-                        DELETE FROM accesses
-                        WHERE schema_id = $1 AND id = $2 AND source = $3 AND instance = $4
+                        DELETE FROM schemas
+                        WHERE id = $1
                         ",
                     )
-                    .bind(payload.schema_id)
                     .bind(payload.id)
-                    .bind(payload.source)
-                    .bind(payload.instance)
                 }
                 _ => sqlx::query(
                     "
@@ -400,9 +203,360 @@ impl SchemaRepository for SQLiteSchemaRepository {
                 .bind(schema.timestamps().updated_at()),
             };
 
-            query.execute(&self.pool).await.map_err(Error::Database)?;
+            query.execute(&mut *tx).await.map_err(Error::Database)?;
         }
 
         Ok(())
     }
 }
+
+#[async_trait]
+impl SchemaRepository for SQLiteSchemaRepository {
+    async fn find(&self, query: &SchemaQuery) -> Result<Page<Schema>, Error> {
+        let mut count_query = QueryBuilder::new("SELECT COUNT(*) FROM schemas WHERE 1 = 1");
+        push_filters(&mut count_query, query);
+
+        let count: u32 = count_query
+            .build()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(Error::Database)?
+            .get(0);
+
+        let mut select_query = QueryBuilder::new("SELECT * FROM schemas WHERE 1 = 1");
+        push_filters(&mut select_query, query);
+
+        let (column, operator, direction) = sorting(query);
+        if let Some(cursor) = &query.cursor {
+            select_query.push(format!(" AND ({} {} ", column, operator));
+            push_sort_value(&mut select_query, cursor.value());
+            select_query.push(format!(" OR ({} = ", column));
+            push_sort_value(&mut select_query, cursor.value());
+            select_query.push(format!(" AND id {} ", operator));
+            select_query.push_bind(cursor.id().to_string());
+            select_query.push("))");
+        }
+
+        select_query
+            .push(format!(
+                " ORDER BY {} {}, id {} LIMIT ",
+                column, direction, direction
+            ))
+            .push_bind(query.limit as u32)
+            .push(" OFFSET ")
+            .push_bind(query.offset as u32);
+
+        let schemas = select_query
+            .build()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(Error::Database)?
+            .iter()
+            .map(|row| {
+                SqlxSchema::from_row(row)
+                    .map_err(Error::Database)
+                    .and_then(SqlxSchema::into_domain)
+            })
+            .collect::<Result<Vec<Schema>, Error>>()?;
+
+        Page::new(query.offset, query.limit, count as u64, schemas)
+    }
+
+    async fn find_by_id(&self, id: &Id) -> Result<Option<Schema>, Error> {
+        let sqlite_schema: Option<SqlxSchema> =
+            sqlx::query_as("SELECT * FROM schemas WHERE id = $1 AND deleted_at IS NULL")
+                .bind(id.value())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(Error::Database)?;
+
+        sqlite_schema.map(SqlxSchema::into_domain).transpose()
+    }
+
+    async fn find_deleted_by_id(&self, id: &Id) -> Result<Option<Schema>, Error> {
+        let sqlite_schema: Option<SqlxSchema> =
+            sqlx::query_as("SELECT * FROM schemas WHERE id = $1 AND deleted_at IS NOT NULL")
+                .bind(id.value())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(Error::Database)?;
+
+        sqlite_schema.map(SqlxSchema::into_domain).transpose()
+    }
+
+    async fn exists(&self, id: &Id) -> Result<bool, Error> {
+        let count: u32 = sqlx::query_scalar("SELECT COUNT(*) FROM schemas WHERE id = $1")
+            .bind(id.value())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(count > 0)
+    }
+
+    async fn count(&self) -> Result<u64, Error> {
+        let count: u32 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM schemas WHERE deleted_at IS NULL")
+                .fetch_one(&self.pool)
+                .await
+                .map_err(Error::Database)?;
+
+        Ok(count as u64)
+    }
+
+    async fn save(&self, schema: &mut Schema) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+        SQLiteSchemaRepository::save_in(&mut tx, schema).await?;
+        tx.commit().await.map_err(Error::Database)
+    }
+
+    async fn save_with_configs(
+        &self,
+        schema: &mut Schema,
+        configs: &mut [Config],
+    ) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+        SQLiteSchemaRepository::save_in(&mut tx, schema).await?;
+        for config in configs.iter() {
+            SQLiteConfigRepository::save_in(&mut tx, config).await?;
+        }
+        tx.commit().await.map_err(Error::Database)
+    }
+}
+
+fn push_filters(builder: &mut QueryBuilder<Sqlite>, query: &SchemaQuery) {
+    builder.push(if query.deleted {
+        " AND deleted_at IS NOT NULL"
+    } else {
+        " AND deleted_at IS NULL"
+    });
+
+    if let Some(deleted_before) = query.deleted_before {
+        builder.push(" AND deleted_at < ").push_bind(deleted_before);
+    }
+
+    if let Some(name) = &query.name {
+        builder
+            .push(" AND name LIKE ")
+            .push_bind(contains_pattern(name))
+            .push(" ESCAPE '\\'");
+    }
+
+    if let Some(updated_since) = query.updated_since {
+        builder.push(" AND updated_at >= ").push_bind(updated_since);
+    }
+
+    if let Some(has_invalid_configs) = query.has_invalid_configs {
+        builder.push(if has_invalid_configs {
+            " AND EXISTS"
+        } else {
+            " AND NOT EXISTS"
+        });
+        builder.push(
+            " (SELECT 1 FROM configs WHERE configs.schema_id = schemas.id AND NOT configs.valid",
+        );
+        builder.push(" AND configs.deleted_at IS NULL)");
+    }
+
+    if let Some(configs_deleted_before) = query.configs_deleted_before {
+        builder
+            .push(" AND EXISTS (SELECT 1 FROM configs WHERE configs.schema_id = schemas.id")
+            .push(" AND configs.deleted_at < ")
+            .push_bind(configs_deleted_before)
+            .push(")");
+    }
+}
+
+// Column, cursor operator and direction
+fn sorting(query: &SchemaQuery) -> (&'static str, &'static str, &'static str) {
+    let column = match query.sort {
+        SchemaSort::Name => "name",
+        SchemaSort::CreatedAt => "created_at",
+        SchemaSort::UpdatedAt => "updated_at",
+    };
+
+    match query.order {
+        SortOrder::Asc => (column, ">", "ASC"),
+        SortOrder::Desc => (column, "<", "DESC"),
+    }
+}
+
+fn push_sort_value(builder: &mut QueryBuilder<Sqlite>, value: &SortValue) {
+    match value {
+        SortValue::Text(text) => builder.push_bind(text.clone()),
+        SortValue::Timestamp(timestamp) => builder.push_bind(*timestamp),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::{Duration, Utc};
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::collections::BTreeMap;
+
+    use crate::{
+        domain::{
+            configs::ConfigRepository,
+            values::{Prop, Rule, Value},
+        },
+        infrastructure::SQLiteConfigRepository,
+    };
+
+    // A single connection, as every in-memory connection has its own database
+    async fn repositories() -> (SQLiteSchemaRepository, SQLiteConfigRepository) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        (
+            SQLiteSchemaRepository::new(pool.clone()).await.unwrap(),
+            SQLiteConfigRepository::new(pool).await.unwrap(),
+        )
+    }
+
+    fn port(port: i64) -> Value {
+        Value::Object(BTreeMap::from([("port".to_string(), Value::Int(port))]))
+    }
+
+    #[tokio::test]
+    async fn save_with_configs_is_atomic() {
+        let (schema_repository, config_repository) = repositories().await;
+        let schema_id = Id::new("schema#01").unwrap();
+
+        let mut schema = Schema::create(
+            schema_id.clone(),
+            "Schema".to_string(),
+            Prop::object(BTreeMap::from([(
+                "port".to_string(),
+                Prop::int(true, None, None, None, false).unwrap(),
+            )])),
+            Vec::new(),
+        )
+        .unwrap();
+        schema_repository.save(&mut schema).await.unwrap();
+
+        for id in ["config#01", "config#02"] {
+            let mut config = Config::create(
+                schema_id.clone(),
+                Id::new(id).unwrap(),
+                id.to_string(),
+                port(80),
+                true,
+                None,
+            )
+            .unwrap();
+            config_repository.save(&mut config).await.unwrap();
+        }
+
+        let mut schema = schema_repository
+            .find_by_id(&schema_id)
+            .await
+            .unwrap()
+            .unwrap();
+        schema
+            .change_rules(vec![
+                Rule::new("port > 1000".to_string(), None, None).unwrap()
+            ])
+            .unwrap();
+
+        let mut configs = Vec::new();
+        for id in ["config#01", "config#02"] {
+            let mut config = config_repository
+                .find_by_id(&schema_id, &Id::new(id).unwrap())
+                .await
+                .unwrap()
+                .unwrap();
+            config.change_data(port(8080), true).unwrap();
+            configs.push(config);
+        }
+
+        // The second config is modified meanwhile, so its save fails
+        let mut other = configs[1].clone();
+        config_repository.save(&mut other).await.unwrap();
+
+        assert!(matches!(
+            schema_repository
+                .save_with_configs(&mut schema, &mut configs)
+                .await,
+            Err(Error::ConfigModified(_))
+        ));
+
+        let stored = schema_repository
+            .find_by_id(&schema_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stored.rules().is_empty());
+        assert_eq!(stored.version().value(), 1);
+
+        let stored = config_repository
+            .find_by_id(&schema_id, &Id::new("config#01").unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.data(), &port(80));
+        assert_eq!(stored.version().value(), 1);
+
+        // Schemas are only changed from the version they were loaded with
+        schema_repository.save(&mut schema.clone()).await.unwrap();
+        assert!(matches!(
+            schema_repository.save(&mut schema).await,
+            Err(Error::SchemaModified(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn configs_deleted_before() {
+        let (schema_repository, config_repository) = repositories().await;
+        let schema_id = Id::new("schema#01").unwrap();
+
+        let mut schema = Schema::create(
+            schema_id.clone(),
+            "Schema".to_string(),
+            Prop::object(BTreeMap::from([(
+                "port".to_string(),
+                Prop::int(true, None, None, None, false).unwrap(),
+            )])),
+            Vec::new(),
+        )
+        .unwrap();
+        schema_repository.save(&mut schema).await.unwrap();
+
+        let mut config = Config::create(
+            schema_id.clone(),
+            Id::new("config#01").unwrap(),
+            "Config".to_string(),
+            port(80),
+            true,
+            None,
+        )
+        .unwrap();
+        config_repository.save(&mut config).await.unwrap();
+
+        let find = |configs_deleted_before| {
+            let query = SchemaQuery {
+                configs_deleted_before: Some(configs_deleted_before),
+                ..SchemaQuery::default()
+            };
+            let schema_repository = &schema_repository;
+            async move { schema_repository.find(&query).await.unwrap().total() }
+        };
+        let later = Utc::now() + Duration::hours(1);
+        assert_eq!(find(later).await, 0);
+
+        let mut config = config_repository
+            .find_by_id(&schema_id, config.id())
+            .await
+            .unwrap()
+            .unwrap();
+        config.delete(None).unwrap();
+        config_repository.save(&mut config).await.unwrap();
+
+        assert_eq!(find(later).await, 1);
+        assert_eq!(find(Utc::now() - Duration::hours(1)).await, 0);
+    }
+}
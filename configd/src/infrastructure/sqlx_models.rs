@@ -1,13 +1,13 @@
 use chrono::{DateTime, Utc};
 use serde_json::Value as JsonValue;
 use sqlx::FromRow;
-use std::collections::HashMap;
 
 use crate::domain::{
     configs::{Access, Config, Password},
     errors::Error,
     schemas::Schema,
     shared::{Id, Timestamps, Version},
+    values::rules_from_json,
 };
 
 #[derive(FromRow)]
@@ -19,7 +19,7 @@ pub struct SqlxAccess {
 }
 
 impl SqlxAccess {
-    pub fn to_domain(self) -> Result<Access, Error> {
+    pub fn into_domain(self) -> Result<Access, Error> {
         Ok(Access::new(
             Id::new(self.source)?,
             Id::new(self.instance)?,
@@ -31,6 +31,7 @@ impl SqlxAccess {
 
 #[derive(FromRow)]
 pub struct SqlxConfig {
+    pub schema_id: String,
     pub id: String,
     pub name: String,
     pub data: JsonValue,
@@ -39,19 +40,21 @@ pub struct SqlxConfig {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub version: i32,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl SqlxConfig {
-    pub fn to_domain(self, accesses: Vec<Access>) -> Result<Config, Error> {
+    pub fn into_domain(self) -> Result<Config, Error> {
         Config::new(
+            Id::new(self.schema_id)?,
             Id::new(self.id)?,
             self.name,
             self.data.into(),
             self.valid,
             self.password.map(Password::new).transpose()?,
-            accesses,
-            Timestamps::new(self.created_at, self.updated_at, None)?,
+            Timestamps::new(self.created_at, self.updated_at, self.deleted_at)?,
             Version::new(self.version.into())?,
+            None,
         )
     }
 }
@@ -61,21 +64,33 @@ pub struct SqlxSchema {
     pub id: String,
     pub name: String,
     pub root_prop: JsonValue,
+    pub rules: JsonValue,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub version: i32,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl SqlxSchema {
-    pub fn to_domain(self, configs: HashMap<Id, Config>) -> Result<Schema, Error> {
+    pub fn into_domain(self) -> Result<Schema, Error> {
         Schema::new(
             Id::new(self.id)?,
             self.name,
             self.root_prop.try_into()?,
-            configs,
-            Timestamps::new(self.created_at, self.updated_at, None)?,
+            rules_from_json(self.rules)?,
+            Timestamps::new(self.created_at, self.updated_at, self.deleted_at)?,
             Version::new(self.version.into())?,
             None,
         )
     }
 }
+
+// LIKE pattern matching any value containing the text, escaped with '\'
+pub fn contains_pattern(text: &str) -> String {
+    format!(
+        "%{}%",
+        text.replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    )
+}
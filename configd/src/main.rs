@@ -2,38 +2,178 @@ mod application;
 mod config;
 mod container;
 mod domain;
+mod grpc;
 mod handlers;
 mod infrastructure;
+mod telemetry;
+mod tls;
 
 use axum::{
-    http::{header, Method},
-    routing::{get, post},
+    body::Body,
+    extract::ConnectInfo,
+    http::{header, Method, Request},
+    middleware,
+    routing::{delete, get, post},
     Extension, Router, Server,
 };
-use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
+use clap::Parser;
+use hyper::service::make_service_fn;
+use std::{convert::Infallible, future::Future, net::SocketAddr, pin::Pin, process, sync::Arc};
+use tokio::{
+    net::TcpListener,
+    signal::unix::{signal, SignalKind},
+    time::{self, Instant},
+};
+use tower::ServiceExt;
+use tower_http::{
+    cors::{AllowOrigin, Any, CorsLayer},
+    timeout::TimeoutLayer,
+};
 
-use crate::{config::Config, container::Container};
+use crate::{
+    config::{Args, Config, Cors, Environment, Storage},
+    container::Container,
+    grpc::{proto::configd_server::ConfigdServer, GrpcService},
+    handlers::BodyLimit,
+    telemetry::RequestIdLayer,
+    tls::{TlsAcceptor, TlsConn},
+};
+
+type ServerFuture<E> = Pin<Box<dyn Future<Output = Result<(), E>> + Send>>;
 
 #[tokio::main]
 async fn main() {
-    let config = Config::load().unwrap();
+    let args = Args::parse();
+    let check_config = args.check_config;
+
+    let config = match Config::load(args) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    };
+
+    if check_config {
+        println!("configuration is valid");
+        return;
+    }
+
+    telemetry::init(&config);
+
+    if matches!(config.storage, Storage::InMem) && config.env != Environment::Dev {
+        tracing::warn!("in-mem storage loses every schema and config on restart");
+    }
+
+    let container = match Container::build(&config).await {
+        Ok(container) => Arc::new(container),
+        Err(err) => {
+            tracing::error!(error = %err, "could not start");
+            process::exit(1);
+        }
+    };
+    let tasks = container.background_tasks.clone();
+
+    // Closing the background tasks stops both servers from accepting
+    // connections and ends the WatchConfig streams
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(err) => {
+            tracing::error!(error = %err, "could not listen to signals");
+            process::exit(1);
+        }
+    };
+    let signaled_tasks = tasks.clone();
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = terminate.recv() => {}
+            _ = tokio::signal::ctrl_c() => {}
+        }
 
-    let container = Arc::new(Container::build(&config).await.unwrap());
+        tracing::info!("shutting down");
+        signaled_tasks.close();
+    });
+
+    // Both servers share the certificates
+    let acceptor = match config.tls {
+        Some(ref tls) => match TlsAcceptor::new(tls) {
+            Ok(acceptor) => {
+                let acceptor = Arc::new(acceptor);
+                tokio::spawn(acceptor.clone().watch(tasks.clone()));
+                Some(acceptor)
+            }
+            Err(err) => {
+                tracing::error!(error = %err, "could not load certificates");
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    // gRPC is served on its own port
+    let grpc_service = match GrpcService::new(container.clone()).await {
+        Ok(grpc_service) => grpc_service,
+        Err(err) => {
+            tracing::error!(error = %err, "could not start gRPC");
+            process::exit(1);
+        }
+    };
+    let grpc_addr = config.grpc_addr;
+    tracing::info!(
+        addr = %grpc_addr,
+        tls = acceptor.is_some(),
+        "gRPC listening"
+    );
+    let grpc_tasks = tasks.clone();
+    let grpc_shutdown = async move { grpc_tasks.closed().await };
+    let grpc_router = tonic::transport::Server::builder()
+        .timeout(config.request_timeout)
+        .layer(RequestIdLayer)
+        .add_service(ConfigdServer::new(grpc_service));
+    let grpc_server: ServerFuture<tonic::transport::Error> = match acceptor {
+        Some(ref acceptor) => {
+            let listener = bind(grpc_addr).await;
+            Box::pin(
+                grpc_router
+                    .serve_with_incoming_shutdown(acceptor.incoming(listener), grpc_shutdown),
+            )
+        }
+        None => Box::pin(grpc_router.serve_with_shutdown(grpc_addr, grpc_shutdown)),
+    };
 
     let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
         .allow_headers([
             header::ACCEPT,
             header::CONTENT_TYPE,
+            header::IF_NONE_MATCH,
             header::HeaderName::from_bytes(b"X-Configd-Source").unwrap(),
             header::HeaderName::from_bytes(b"X-Configd-Instance").unwrap(),
             header::HeaderName::from_bytes(b"X-Configd-Password").unwrap(),
+            header::HeaderName::from_static(telemetry::REQUEST_ID_HEADER),
         ])
-        .allow_origin(Any);
+        .expose_headers([
+            header::ETAG,
+            header::HeaderName::from_static(telemetry::REQUEST_ID_HEADER),
+        ])
+        .allow_origin(match config.cors {
+            Cors::Any => AllowOrigin::from(Any),
+            // Only visible ASCII origins pass the config validation
+            Cors::Origins(ref origins) => {
+                AllowOrigin::list(origins.iter().map(|origin| origin.parse().unwrap()))
+            }
+        });
 
     let app = Router::new()
         .route("/health", get(handlers::health))
+        .route("/cache", get(handlers::cache_stats))
+        .route("/metrics", get(handlers::render_metrics))
         .route(
             "/schemas",
             get(handlers::list_schemas).post(handlers::create_schema),
@@ -44,13 +184,21 @@ async fn main() {
                 .put(handlers::update_schema)
                 .delete(handlers::delete_schema),
         )
-        .route("/schemas/:schema_id/configs", post(handlers::create_config))
+        .route(
+            "/schemas/:schema_id/configs",
+            get(handlers::list_configs).post(handlers::create_config),
+        )
         .route(
             "/schemas/:schema_id/configs/:config_id",
             get(handlers::get_config_by_id)
                 .put(handlers::update_config)
+                .patch(handlers::patch_config)
                 .delete(handlers::delete_config),
         )
+        .route(
+            "/schemas/:schema_id/configs/:config_id/diff/:other_config_id",
+            get(handlers::diff_configs),
+        )
         .route(
             "/schemas/:schema_id/configs/:config_id/password",
             post(handlers::change_config_password).delete(handlers::delete_config_password),
@@ -59,13 +207,136 @@ async fn main() {
             "/schemas/:schema_id/validate",
             post(handlers::validate_config),
         )
-        .layer(Extension(container))
+        .route("/trash/schemas", get(handlers::list_deleted_schemas))
+        .route("/trash/schemas/:schema_id", delete(handlers::purge_schema))
+        .route(
+            "/trash/schemas/:schema_id/restore",
+            post(handlers::restore_schema),
+        )
+        .route(
+            "/trash/schemas/:schema_id/configs",
+            get(handlers::list_deleted_configs),
+        )
+        .route(
+            "/trash/schemas/:schema_id/configs/:config_id",
+            delete(handlers::purge_config),
+        )
+        .route(
+            "/trash/schemas/:schema_id/configs/:config_id/restore",
+            post(handlers::restore_config),
+        )
+        .route_layer(middleware::from_fn(handlers::track_metrics))
+        .layer(Extension(container.clone()))
+        .layer(Extension(BodyLimit(config.max_body_size)))
+        .layer(TimeoutLayer::new(config.request_timeout))
+        .layer(RequestIdLayer)
         .layer(cors);
 
-    let addr = format!("{}:{}", config.host, config.port);
-    println!("Listening on {}", addr);
-    Server::bind(&addr.parse().unwrap())
-        .serve(app.into_make_service())
-        .await
-        .unwrap();
+    let addr = config.addr;
+    tracing::info!(addr = %addr, tls = acceptor.is_some(), "listening");
+    let rest_tasks = tasks.clone();
+    let rest_shutdown = async move { rest_tasks.closed().await };
+    let rest_server: ServerFuture<hyper::Error> = match acceptor {
+        Some(ref acceptor) => {
+            let listener = bind(addr).await;
+            let incoming = hyper::server::accept::from_stream(acceptor.incoming(listener));
+
+            // The client certificate identity is attached to every request of
+            // the connection
+            let make_service = make_service_fn(move |conn: &TlsConn| {
+                let remote_addr = conn.remote_addr();
+                let identity = conn.identity().cloned();
+                let app = app.clone().map_request(move |mut req: Request<Body>| {
+                    req.extensions_mut().insert(ConnectInfo(remote_addr));
+                    if let Some(ref identity) = identity {
+                        req.extensions_mut().insert(identity.clone());
+                    }
+                    req
+                });
+
+                async move { Ok::<_, Infallible>(app) }
+            });
+
+            Box::pin(
+                Server::builder(incoming)
+                    .serve(make_service)
+                    .with_graceful_shutdown(rest_shutdown),
+            )
+        }
+        None => match Server::try_bind(&addr) {
+            Ok(builder) => Box::pin(
+                builder
+                    .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                    .with_graceful_shutdown(rest_shutdown),
+            ),
+            Err(err) => {
+                tracing::error!(error = %err, addr = %addr, "could not listen");
+                process::exit(1);
+            }
+        },
+    };
+
+    // A failed server stops the other one and the process exits with an error
+    let servers_tasks = tasks.clone();
+    let servers = async {
+        let (grpc_res, rest_res) = tokio::join!(
+            async {
+                let res = grpc_server.await;
+                if let Err(ref err) = res {
+                    tracing::error!(error = %err, "gRPC server failed");
+                    servers_tasks.close();
+                }
+                res.is_ok()
+            },
+            async {
+                let res = rest_server.await;
+                if let Err(ref err) = res {
+                    tracing::error!(error = %err, "server failed");
+                    servers_tasks.close();
+                }
+                res.is_ok()
+            },
+        );
+
+        grpc_res && rest_res
+    };
+    tokio::pin!(servers);
+
+    // In-flight requests and background tasks share the deadline. The servers
+    // only stop by themselves once drained.
+    let (deadline, served) = tokio::select! {
+        served = &mut servers => (Instant::now() + config.shutdown_timeout, served),
+        _ = tasks.closed() => {
+            let deadline = Instant::now() + config.shutdown_timeout;
+            let served = match time::timeout_at(deadline, &mut servers).await {
+                Ok(served) => served,
+                Err(_) => {
+                    tracing::warn!("requests still in flight at the deadline");
+                    true
+                }
+            };
+
+            (deadline, served)
+        }
+    };
+
+    container
+        .shutdown(deadline.saturating_duration_since(Instant::now()))
+        .await;
+
+    tracing::info!("stopped");
+
+    if !served {
+        process::exit(1);
+    }
+}
+
+async fn bind(addr: SocketAddr) -> TcpListener {
+    match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            tracing::error!(error = %err, addr = %addr, "could not listen");
+            process::exit(1);
+        }
+    }
 }
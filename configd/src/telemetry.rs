@@ -0,0 +1,191 @@
+use axum::{
+    extract::ConnectInfo,
+    http::{header::HeaderName, HeaderValue, Request, Response},
+};
+use std::{
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+use tonic::transport::server::TcpConnectInfo;
+use tower::{Layer, Service};
+use tracing::Instrument;
+use tracing_subscriber::EnvFilter;
+
+use crate::{
+    config::{Config, LogFormat},
+    domain::{configs::with_client, events::with_correlation_id, shared::Id},
+    tls::{ClientIdentity, TlsConnectInfo},
+};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// Longer ids, or with other characters, are replaced by a generated one
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+pub fn init(config: &Config) {
+    let builder = tracing_subscriber::fmt().with_env_filter(EnvFilter::new(&config.log_level));
+
+    match config.log_format {
+        LogFormat::Json => builder.json().init(),
+        LogFormat::Text => builder.init(),
+    }
+}
+
+// REST connections carry the identity itself, gRPC ones the connect info
+fn client_identity<B>(req: &Request<B>) -> Option<ClientIdentity> {
+    req.extensions()
+        .get::<ClientIdentity>()
+        .cloned()
+        .or_else(|| {
+            req.extensions()
+                .get::<TlsConnectInfo>()
+                .and_then(|info| info.identity.clone())
+        })
+}
+
+// Verified identity, otherwise the IP address the request comes from
+fn client<B>(req: &Request<B>) -> Option<String> {
+    if let Some(identity) = client_identity(req) {
+        return Some(identity.0);
+    }
+
+    let extensions = req.extensions();
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr)
+        .or_else(|| {
+            extensions
+                .get::<TlsConnectInfo>()
+                .and_then(|info| info.remote_addr)
+        })
+        .or_else(|| {
+            extensions
+                .get::<TcpConnectInfo>()
+                .and_then(|info| info.remote_addr())
+        })
+        .map(|addr| addr.ip().to_string())
+}
+
+// Client ids end up in logs and events, so they are kept short and printable
+fn is_valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LENGTH
+        && request_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+// Gives every REST and gRPC request an id, taken from the X-Request-Id header
+// when present. The request runs in a span carrying it, and the events it
+// publishes use it as correlation id.
+#[derive(Clone)]
+pub struct RequestIdLayer;
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestId<S>;
+
+    fn layer(&self, inner: S) -> RequestId<S> {
+        RequestId { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct RequestId<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RequestId<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let client_request_id = req.headers().get(REQUEST_ID_HEADER).and_then(|value| {
+            value
+                .to_str()
+                .ok()
+                .filter(|request_id| is_valid_request_id(request_id))
+                .map(|request_id| (request_id.to_string(), value.clone()))
+        });
+        let (request_id, header_value) = match client_request_id {
+            Some(client_request_id) => client_request_id,
+            None => {
+                let request_id = Id::generate().to_string();
+                let header_value = HeaderValue::from_str(&request_id)
+                    .expect("generated ids are valid header values");
+
+                (request_id, header_value)
+            }
+        };
+        req.headers_mut()
+            .insert(REQUEST_ID_HEADER, header_value.clone());
+
+        let header = |name: &str| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let source = client_identity(&req)
+            .map(|identity| identity.0)
+            .unwrap_or_else(|| header("x-configd-source"));
+        let span = tracing::info_span!(
+            "request",
+            request_id = request_id.as_str(),
+            method = %req.method(),
+            path = req.uri().path(),
+            source = source.as_str(),
+            instance = header("x-configd-instance").as_str(),
+        );
+
+        let client = client(&req);
+        let start = Instant::now();
+        let fut = self.inner.call(req);
+
+        Box::pin(
+            with_correlation_id(request_id, async move {
+                let mut res = with_client(client, fut).await?;
+
+                tracing::info!(
+                    status = res.status().as_u16(),
+                    elapsed_ms = start.elapsed().as_millis() as u64,
+                    "request completed"
+                );
+
+                res.headers_mut()
+                    .insert(HeaderName::from_static(REQUEST_ID_HEADER), header_value);
+
+                Ok(res)
+            })
+            .instrument(span),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_ids() {
+        assert!(is_valid_request_id("01GAZ6-req_1.a:b"));
+        assert!(is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LENGTH)));
+
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LENGTH + 1)));
+        assert!(!is_valid_request_id("id with spaces"));
+        assert!(!is_valid_request_id("id\"injected\""));
+    }
+}
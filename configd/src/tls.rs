@@ -0,0 +1,538 @@
+use std::{
+    fs, io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Mutex, RwLock},
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+use tokio_rustls::{
+    rustls::{
+        server::AllowAnyAuthenticatedClient, Certificate, PrivateKey, RootCertStore, ServerConfig,
+    },
+    server::TlsStream,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use x509_parser::{extensions::GeneralName, prelude::*};
+
+use crate::{
+    config::{ConfigError, Tls},
+    infrastructure::BackgroundTasks,
+};
+
+// How often the certificate files are checked for changes
+const RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Bounds of the pause after failing to accept, while out of file descriptors
+// for example
+const MIN_ACCEPT_BACKOFF: Duration = Duration::from_millis(5);
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+// Identity of verified client certificates without any name
+pub const ANONYMOUS_IDENTITY: &str = "anonymous";
+
+// Verified client certificate, replaces the X-Configd-Source header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientIdentity(pub String);
+
+// Connect info of gRPC requests received over TLS
+#[derive(Debug, Clone)]
+pub struct TlsConnectInfo {
+    pub remote_addr: Option<SocketAddr>,
+    pub identity: Option<ClientIdentity>,
+}
+
+// Terminates TLS for both servers. The certificates are reloaded when their
+// files change, new connections use the new ones.
+pub struct TlsAcceptor {
+    tls: Tls,
+    config: RwLock<Arc<ServerConfig>>,
+    modified: Mutex<Option<SystemTime>>,
+}
+
+impl TlsAcceptor {
+    pub fn new(tls: &Tls) -> Result<TlsAcceptor, ConfigError> {
+        let acceptor = TlsAcceptor {
+            tls: tls.clone(),
+            config: RwLock::new(Arc::new(server_config(tls)?)),
+            modified: Mutex::new(None),
+        };
+        *acceptor.modified.lock().unwrap() = acceptor.files_modified();
+
+        Ok(acceptor)
+    }
+
+    pub fn reload(&self) -> Result<(), ConfigError> {
+        let config = server_config(&self.tls)?;
+        *self.config.write().unwrap() = Arc::new(config);
+
+        Ok(())
+    }
+
+    // Latest modification of the certificate files
+    fn files_modified(&self) -> Option<SystemTime> {
+        [
+            Some(&self.tls.cert),
+            Some(&self.tls.key),
+            self.tls.client_ca.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+        .max()
+    }
+
+    pub async fn watch(self: Arc<Self>, tasks: Arc<BackgroundTasks>) {
+        let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = tasks.closed() => return,
+                _ = interval.tick() => {}
+            }
+
+            let modified = self.files_modified();
+            if modified == *self.modified.lock().unwrap() {
+                continue;
+            }
+
+            // Files could be halfway written, the current certificates are
+            // kept until the new ones load
+            match self.reload() {
+                Ok(()) => {
+                    *self.modified.lock().unwrap() = modified;
+                    tracing::info!("certificates reloaded");
+                }
+                Err(err) => tracing::error!(error = %err, "could not reload certificates"),
+            }
+        }
+    }
+
+    // Accepted connections, handshaken concurrently
+    pub fn incoming(
+        self: &Arc<Self>,
+        listener: TcpListener,
+    ) -> ReceiverStream<io::Result<TlsConn>> {
+        let (sender, receiver) = mpsc::channel(64);
+
+        let acceptor = self.clone();
+        tokio::spawn(async move {
+            let mut backoff = MIN_ACCEPT_BACKOFF;
+            loop {
+                let res = tokio::select! {
+                    _ = sender.closed() => return,
+                    res = listener.accept() => res,
+                };
+                let (stream, remote_addr) = match res {
+                    Ok(accepted) => {
+                        backoff = MIN_ACCEPT_BACKOFF;
+                        accepted
+                    }
+                    // The client went away before being accepted
+                    Err(err) if is_connection_error(&err) => {
+                        tracing::debug!(error = %err, "connection aborted");
+                        continue;
+                    }
+                    Err(err) => {
+                        tracing::error!(error = %err, "could not accept connection");
+                        tokio::select! {
+                            _ = sender.closed() => return,
+                            _ = tokio::time::sleep(backoff) => {}
+                        }
+                        backoff = (backoff * 2).min(MAX_ACCEPT_BACKOFF);
+                        continue;
+                    }
+                };
+
+                let config = acceptor.config.read().unwrap().clone();
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    let handshake = tokio_rustls::TlsAcceptor::from(config).accept(stream);
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake).await {
+                        Ok(Ok(stream)) => {
+                            let _ = sender.send(Ok(TlsConn::new(stream, remote_addr))).await;
+                        }
+                        Ok(Err(err)) => tracing::debug!(error = %err, "TLS handshake failed"),
+                        Err(_) => tracing::debug!("TLS handshake timed out"),
+                    }
+                });
+            }
+        });
+
+        ReceiverStream::new(receiver)
+    }
+}
+
+fn is_connection_error(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+    )
+}
+
+fn server_config(tls: &Tls) -> Result<ServerConfig, ConfigError> {
+    let certs = read_certs(&tls.cert)?;
+    let key = read_key(&tls.key)?;
+
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match tls.client_ca {
+        Some(ref client_ca) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(client_ca)? {
+                roots
+                    .add(&cert)
+                    .map_err(|err| invalid_tls(client_ca, err.to_string()))?;
+            }
+
+            builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots))
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut config = builder
+        .with_single_cert(certs, key)
+        .map_err(|err| invalid_tls(&tls.cert, err.to_string()))?;
+    // gRPC requires HTTP/2, REST clients mostly speak HTTP/1.1
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(config)
+}
+
+fn invalid_tls(path: &str, reason: String) -> ConfigError {
+    ConfigError::InvalidTls {
+        path: path.to_string(),
+        reason,
+    }
+}
+
+fn read_certs(path: &str) -> Result<Vec<Certificate>, ConfigError> {
+    let pem = fs::read(path).map_err(|err| invalid_tls(path, err.to_string()))?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .map_err(|err| invalid_tls(path, err.to_string()))?;
+    if certs.is_empty() {
+        return Err(invalid_tls(path, "no certificates found".to_string()));
+    }
+
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn read_key(path: &str) -> Result<PrivateKey, ConfigError> {
+    let pem = fs::read(path).map_err(|err| invalid_tls(path, err.to_string()))?;
+
+    let mut reader = pem.as_slice();
+    loop {
+        match rustls_pemfile::read_one(&mut reader)
+            .map_err(|err| invalid_tls(path, err.to_string()))?
+        {
+            Some(rustls_pemfile::Item::PKCS8Key(key))
+            | Some(rustls_pemfile::Item::RSAKey(key))
+            | Some(rustls_pemfile::Item::ECKey(key)) => return Ok(PrivateKey(key)),
+            Some(_) => {}
+            None => return Err(invalid_tls(path, "no private key found".to_string())),
+        }
+    }
+}
+
+// The first DNS, URI or email SAN, otherwise the subject common name
+fn client_identity(cert: &Certificate) -> Option<ClientIdentity> {
+    let (_, cert) = X509Certificate::from_der(&cert.0).ok()?;
+
+    if let Ok(Some(san)) = cert.subject_alternative_name() {
+        for name in san.value.general_names.iter() {
+            if let GeneralName::DNSName(name)
+            | GeneralName::URI(name)
+            | GeneralName::RFC822Name(name) = name
+            {
+                return Some(ClientIdentity(name.to_string()));
+            }
+        }
+    }
+
+    let common_name = cert
+        .subject()
+        .iter_common_name()
+        .next()
+        .and_then(|cn| cn.as_str().ok())
+        .map(|cn| ClientIdentity(cn.to_string()));
+
+    common_name
+}
+
+pub struct TlsConn {
+    stream: TlsStream<TcpStream>,
+    remote_addr: SocketAddr,
+    identity: Option<ClientIdentity>,
+}
+
+impl TlsConn {
+    // Clients verified without a name never fall back to the source header
+    fn new(stream: TlsStream<TcpStream>, remote_addr: SocketAddr) -> TlsConn {
+        let identity = stream
+            .get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .map(|cert| {
+                client_identity(cert)
+                    .unwrap_or_else(|| ClientIdentity(ANONYMOUS_IDENTITY.to_string()))
+            });
+
+        TlsConn {
+            stream,
+            remote_addr,
+            identity,
+        }
+    }
+
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+
+    pub fn identity(&self) -> Option<&ClientIdentity> {
+        self.identity.as_ref()
+    }
+}
+
+impl tonic::transport::server::Connected for TlsConn {
+    type ConnectInfo = TlsConnectInfo;
+
+    fn connect_info(&self) -> TlsConnectInfo {
+        TlsConnectInfo {
+            remote_addr: Some(self.remote_addr),
+            identity: self.identity.clone(),
+        }
+    }
+}
+
+impl AsyncRead for TlsConn {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TlsConn {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rcgen::{
+        BasicConstraints, Certificate as Cert, CertificateParams, DistinguishedName, IsCa, SanType,
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::rustls::{ClientConfig, ServerName};
+    use tokio_stream::StreamExt;
+
+    struct Pki {
+        dir: std::path::PathBuf,
+        ca: Cert,
+    }
+
+    impl Pki {
+        fn new(name: &str) -> Pki {
+            let dir = std::env::temp_dir().join(format!("configd-tls-{}", name));
+            fs::create_dir_all(&dir).unwrap();
+
+            let mut params = CertificateParams::new(Vec::new());
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let ca = Cert::from_params(params).unwrap();
+            fs::write(dir.join("ca.pem"), ca.serialize_pem().unwrap()).unwrap();
+
+            Pki { dir, ca }
+        }
+
+        // Writes name.pem and name.key signed by the CA
+        fn issue(&self, name: &str, sans: Vec<SanType>) -> Cert {
+            let mut params = CertificateParams::new(Vec::new());
+            params.distinguished_name = DistinguishedName::new();
+            params.subject_alt_names = sans;
+            let cert = Cert::from_params(params).unwrap();
+
+            fs::write(
+                self.dir.join(format!("{}.pem", name)),
+                cert.serialize_pem_with_signer(&self.ca).unwrap(),
+            )
+            .unwrap();
+            fs::write(
+                self.dir.join(format!("{}.key", name)),
+                cert.serialize_private_key_pem(),
+            )
+            .unwrap();
+
+            cert
+        }
+
+        fn path(&self, file: &str) -> String {
+            self.dir.join(file).display().to_string()
+        }
+
+        fn client(&self, cert: Option<&Cert>) -> tokio_rustls::TlsConnector {
+            let mut roots = RootCertStore::empty();
+            roots
+                .add(&Certificate(self.ca.serialize_der().unwrap()))
+                .unwrap();
+
+            let builder = ClientConfig::builder()
+                .with_safe_defaults()
+                .with_root_certificates(roots);
+            let config = match cert {
+                Some(cert) => builder
+                    .with_single_cert(
+                        vec![Certificate(
+                            cert.serialize_der_with_signer(&self.ca).unwrap(),
+                        )],
+                        PrivateKey(cert.serialize_private_key_der()),
+                    )
+                    .unwrap(),
+                None => builder.with_no_client_auth(),
+            };
+
+            tokio_rustls::TlsConnector::from(Arc::new(config))
+        }
+    }
+
+    async fn serve(
+        acceptor: &Arc<TlsAcceptor>,
+    ) -> (SocketAddr, ReceiverStream<io::Result<TlsConn>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        (addr, acceptor.incoming(listener))
+    }
+
+    #[tokio::test]
+    async fn client_certificate_identity() {
+        let pki = Pki::new("mtls");
+        pki.issue("server", vec![SanType::DnsName("localhost".to_string())]);
+        let client = pki.issue("client", vec![SanType::DnsName("billing".to_string())]);
+        let unnamed = pki.issue(
+            "unnamed",
+            vec![SanType::IpAddress("127.0.0.1".parse().unwrap())],
+        );
+
+        let acceptor = Arc::new(
+            TlsAcceptor::new(&Tls {
+                cert: pki.path("server.pem"),
+                key: pki.path("server.key"),
+                client_ca: Some(pki.path("ca.pem")),
+            })
+            .unwrap(),
+        );
+        let (addr, mut incoming) = serve(&acceptor).await;
+
+        // Authenticated client
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = pki
+            .client(Some(&client))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+            .unwrap();
+        stream.write_all(b"ping").await.unwrap();
+
+        let mut conn = incoming.next().await.unwrap().unwrap();
+        assert_eq!(
+            conn.identity(),
+            Some(&ClientIdentity("billing".to_string()))
+        );
+
+        let mut buf = [0; 4];
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        // Certificates without any DNS, URI or email name nor common name get
+        // an explicit identity
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let _stream = pki
+            .client(Some(&unnamed))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+            .unwrap();
+
+        let conn = incoming.next().await.unwrap().unwrap();
+        assert_eq!(
+            conn.identity(),
+            Some(&ClientIdentity(ANONYMOUS_IDENTITY.to_string()))
+        );
+
+        // Clients without certificate are rejected
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let res = async {
+            let mut stream = pki
+                .client(None)
+                .connect(ServerName::try_from("localhost").unwrap(), stream)
+                .await?;
+            stream.write_all(b"ping").await?;
+            stream.read(&mut buf).await
+        }
+        .await;
+        assert!(matches!(res, Err(_) | Ok(0)));
+    }
+
+    #[tokio::test]
+    async fn reload() {
+        let pki = Pki::new("reload");
+        pki.issue("server", vec![SanType::DnsName("localhost".to_string())]);
+
+        let acceptor = Arc::new(
+            TlsAcceptor::new(&Tls {
+                cert: pki.path("server.pem"),
+                key: pki.path("server.key"),
+                client_ca: None,
+            })
+            .unwrap(),
+        );
+        let (addr, mut incoming) = serve(&acceptor).await;
+
+        // The certificate is only valid for other names now
+        pki.issue("server", vec![SanType::DnsName("configd".to_string())]);
+        acceptor.reload().unwrap();
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let stream = pki
+            .client(None)
+            .connect(ServerName::try_from("configd").unwrap(), stream)
+            .await
+            .unwrap();
+        assert!(incoming.next().await.unwrap().is_ok());
+        drop(stream);
+
+        // Invalid files keep the current certificates
+        fs::write(pki.path("server.pem"), "invalid").unwrap();
+        assert!(acceptor.reload().is_err());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        assert!(pki
+            .client(None)
+            .connect(ServerName::try_from("configd").unwrap(), stream)
+            .await
+            .is_ok());
+    }
+}
@@ -0,0 +1,38 @@
+use configd_client::{Error as ClientError, ErrorCode};
+use std::process::ExitCode;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("profiles: {0}")]
+    Profiles(String),
+    #[error("file: {0}")]
+    File(String),
+    #[error("{0}")]
+    Client(#[from] ClientError),
+    #[error("config does not match the schema")]
+    ValidationFailed,
+}
+
+impl Error {
+    // Exit codes documented in the help
+    pub fn exit_code(&self) -> ExitCode {
+        let code = match self {
+            Error::Profiles(_) | Error::File(_) => 2,
+            Error::ValidationFailed => 3,
+            Error::Client(err) if err.is_unavailable() => 5,
+            Error::Client(err) => match err.code() {
+                Some(
+                    ErrorCode::InvalidConfig
+                    | ErrorCode::BreakingSchemaChange
+                    | ErrorCode::PatchTestFailed,
+                ) => 3,
+                Some(ErrorCode::SchemaNotFound | ErrorCode::ConfigNotFound) => 4,
+                Some(ErrorCode::Unauthorized) => 6,
+                _ => 1,
+            },
+        };
+
+        ExitCode::from(code)
+    }
+}
@@ -0,0 +1,474 @@
+mod errors;
+mod output;
+mod profiles;
+
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use configd_client::{
+    Client, ClientConfig, ListConfigsQuery, ListSchemasQuery, ListTrashQuery, Violation,
+};
+use serde_json::Value as JsonValue;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use crate::{
+    errors::Error,
+    output::{render_diffs, render_error, Output},
+    profiles::Profiles,
+};
+
+const EXIT_CODES: &str = "Exit codes:
+  0  success
+  1  request failed
+  2  invalid arguments, profiles or files
+  3  validation failed or breaking schema change
+  4  schema or config not found
+  5  server unavailable
+  6  unauthorized";
+
+#[derive(Parser)]
+#[command(name = "configdctl", about = "Manage configd schemas and configs", after_help = EXIT_CODES)]
+struct Cli {
+    /// Profile from ~/.config/configdctl/config.toml
+    #[arg(long, global = true, env = "CONFIGD_PROFILE")]
+    profile: Option<String>,
+    #[arg(long, global = true, env = "CONFIGD_URL")]
+    url: Option<String>,
+    #[arg(long, global = true, env = "CONFIGD_PASSWORD", hide_env_values = true)]
+    password: Option<String>,
+    #[arg(long, short, global = true, value_enum, default_value = "json")]
+    output: Output,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage schemas
+    #[command(subcommand)]
+    Schema(SchemaCommand),
+    /// Manage configs
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Validate data against a schema without storing it
+    Validate {
+        schema_id: String,
+        /// JSON or YAML file with the data, - for stdin
+        #[arg(long, short)]
+        file: String,
+    },
+    /// Compare two configs of a schema
+    Diff {
+        schema_id: String,
+        config_id: String,
+        other_config_id: String,
+        /// Password of the other config, --password is used when missing
+        #[arg(long)]
+        other_password: Option<String>,
+        #[arg(long)]
+        populate: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SchemaCommand {
+    List {
+        /// Part of the name, case-insensitive
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        has_invalid_configs: Option<bool>,
+        /// RFC 3339 timestamp
+        #[arg(long)]
+        updated_since: Option<DateTime<Utc>>,
+        /// name, created_at or updated_at
+        #[arg(long)]
+        sort: Option<String>,
+        /// asc or desc
+        #[arg(long)]
+        order: Option<String>,
+        /// next_cursor of the previous page
+        #[arg(long)]
+        cursor: Option<String>,
+        #[arg(long)]
+        offset: Option<u64>,
+        #[arg(long)]
+        limit: Option<u64>,
+        /// Relations to embed, comma-separated (configs), all when missing
+        #[arg(long)]
+        include: Option<String>,
+    },
+    Get {
+        schema_id: String,
+    },
+    Create {
+        #[arg(long, short)]
+        file: String,
+    },
+    Update {
+        schema_id: String,
+        #[arg(long, short)]
+        file: String,
+        #[arg(long)]
+        force: bool,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    Delete {
+        schema_id: String,
+    },
+    /// List deleted schemas
+    Trash {
+        /// Part of the name, case-insensitive
+        #[arg(long)]
+        name: Option<String>,
+        /// name, created_at or updated_at
+        #[arg(long)]
+        sort: Option<String>,
+        /// asc or desc
+        #[arg(long)]
+        order: Option<String>,
+        /// next_cursor of the previous page
+        #[arg(long)]
+        cursor: Option<String>,
+        #[arg(long)]
+        offset: Option<u64>,
+        #[arg(long)]
+        limit: Option<u64>,
+    },
+    Restore {
+        schema_id: String,
+    },
+    /// Delete a deleted schema for good, with its deleted configs
+    Purge {
+        schema_id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    List {
+        schema_id: String,
+        /// Part of the name, case-insensitive
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        valid: Option<bool>,
+        #[arg(long)]
+        requires_password: Option<bool>,
+        /// name, created_at or updated_at
+        #[arg(long)]
+        sort: Option<String>,
+        /// asc or desc
+        #[arg(long)]
+        order: Option<String>,
+        /// next_cursor of the previous page
+        #[arg(long)]
+        cursor: Option<String>,
+        #[arg(long)]
+        offset: Option<u64>,
+        #[arg(long)]
+        limit: Option<u64>,
+    },
+    Get {
+        schema_id: String,
+        config_id: String,
+    },
+    Create {
+        schema_id: String,
+        #[arg(long, short)]
+        file: String,
+    },
+    Update {
+        schema_id: String,
+        config_id: String,
+        #[arg(long, short)]
+        file: String,
+    },
+    Delete {
+        schema_id: String,
+        config_id: String,
+    },
+    /// Change the password, the current one is given with --password
+    Password {
+        schema_id: String,
+        config_id: String,
+        #[arg(long, env = "CONFIGD_NEW_PASSWORD", hide_env_values = true)]
+        new_password: String,
+    },
+    /// List deleted configs of a schema
+    Trash {
+        schema_id: String,
+        /// Part of the name, case-insensitive
+        #[arg(long)]
+        name: Option<String>,
+        /// name, created_at or updated_at
+        #[arg(long)]
+        sort: Option<String>,
+        /// asc or desc
+        #[arg(long)]
+        order: Option<String>,
+        /// next_cursor of the previous page
+        #[arg(long)]
+        cursor: Option<String>,
+        #[arg(long)]
+        offset: Option<u64>,
+        #[arg(long)]
+        limit: Option<u64>,
+    },
+    Restore {
+        schema_id: String,
+        config_id: String,
+    },
+    /// Delete a deleted config for good
+    Purge {
+        schema_id: String,
+        config_id: String,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let output = cli.output;
+
+    match run(cli).await {
+        Ok(value) => {
+            if !value.is_null() {
+                print!("{}", output.render(&value));
+                if output == Output::Json {
+                    println!();
+                }
+            }
+
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprint!("{}", render_error(&err));
+            err.exit_code()
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<JsonValue, Error> {
+    let profile = Profiles::load()?.resolve(cli.profile.as_deref(), cli.url, cli.password)?;
+
+    let client = Client::new(ClientConfig {
+        url: profile.url.unwrap_or_default(),
+        source: "configdctl".to_string(),
+        instance: "configdctl".to_string(),
+        password: profile.password,
+        cache_dir: None,
+    })?;
+
+    let value = match cli.command {
+        Command::Schema(cmd) => match cmd {
+            SchemaCommand::List {
+                name,
+                has_invalid_configs,
+                updated_since,
+                sort,
+                order,
+                cursor,
+                offset,
+                limit,
+                include,
+            } => {
+                client
+                    .list_schemas(&ListSchemasQuery {
+                        name,
+                        has_invalid_configs,
+                        updated_since,
+                        sort,
+                        order,
+                        cursor,
+                        offset,
+                        limit,
+                        include,
+                    })
+                    .await?
+            }
+            SchemaCommand::Get { schema_id } => client.get_schema(&schema_id).await?,
+            SchemaCommand::Create { file } => client.create_schema(&read_file(&file)?).await?,
+            SchemaCommand::Update {
+                schema_id,
+                file,
+                force,
+                dry_run,
+            } => {
+                client
+                    .update_schema(&schema_id, &read_file(&file)?, force, dry_run)
+                    .await?
+            }
+            SchemaCommand::Delete { schema_id } => client.delete_schema(&schema_id).await?,
+            SchemaCommand::Trash {
+                name,
+                sort,
+                order,
+                cursor,
+                offset,
+                limit,
+            } => {
+                client
+                    .list_deleted_schemas(&ListTrashQuery {
+                        name,
+                        sort,
+                        order,
+                        cursor,
+                        offset,
+                        limit,
+                    })
+                    .await?
+            }
+            SchemaCommand::Restore { schema_id } => client.restore_schema(&schema_id).await?,
+            SchemaCommand::Purge { schema_id } => client.purge_schema(&schema_id).await?,
+        },
+        Command::Config(cmd) => match cmd {
+            ConfigCommand::List {
+                schema_id,
+                name,
+                valid,
+                requires_password,
+                sort,
+                order,
+                cursor,
+                offset,
+                limit,
+            } => {
+                client
+                    .list_configs(
+                        &schema_id,
+                        &ListConfigsQuery {
+                            name,
+                            valid,
+                            requires_password,
+                            sort,
+                            order,
+                            cursor,
+                            offset,
+                            limit,
+                        },
+                    )
+                    .await?
+            }
+            ConfigCommand::Get {
+                schema_id,
+                config_id,
+            } => serde_json::to_value(client.get_config(&schema_id, &config_id).await?)
+                .map_err(configd_client::Error::Serde)?,
+            ConfigCommand::Create { schema_id, file } => {
+                client.create_config(&schema_id, &read_file(&file)?).await?
+            }
+            ConfigCommand::Update {
+                schema_id,
+                config_id,
+                file,
+            } => {
+                client
+                    .update_config(&schema_id, &config_id, &read_file(&file)?)
+                    .await?
+            }
+            ConfigCommand::Delete {
+                schema_id,
+                config_id,
+            } => client.delete_config(&schema_id, &config_id).await?,
+            ConfigCommand::Password {
+                schema_id,
+                config_id,
+                new_password,
+            } => {
+                client
+                    .change_config_password(&schema_id, &config_id, &new_password)
+                    .await?
+            }
+            ConfigCommand::Trash {
+                schema_id,
+                name,
+                sort,
+                order,
+                cursor,
+                offset,
+                limit,
+            } => {
+                client
+                    .list_deleted_configs(
+                        &schema_id,
+                        &ListTrashQuery {
+                            name,
+                            sort,
+                            order,
+                            cursor,
+                            offset,
+                            limit,
+                        },
+                    )
+                    .await?
+            }
+            ConfigCommand::Restore {
+                schema_id,
+                config_id,
+            } => client.restore_config(&schema_id, &config_id).await?,
+            ConfigCommand::Purge {
+                schema_id,
+                config_id,
+            } => client.purge_config(&schema_id, &config_id).await?,
+        },
+        Command::Validate { schema_id, file } => {
+            let res = client
+                .validate_config(&schema_id, &read_file(&file)?)
+                .await?;
+
+            // Violations are reported like a rejected config
+            let diffs: BTreeMap<String, Vec<Violation>> =
+                serde_json::from_value(res["diffs"].clone()).unwrap_or_default();
+            if !diffs.is_empty() {
+                eprint!("{}", render_diffs(&diffs, 0));
+                return Err(Error::ValidationFailed);
+            }
+
+            res
+        }
+        Command::Diff {
+            schema_id,
+            config_id,
+            other_config_id,
+            other_password,
+            populate,
+        } => {
+            client
+                .diff_configs(
+                    &schema_id,
+                    &config_id,
+                    &other_config_id,
+                    other_password.as_deref(),
+                    populate,
+                )
+                .await?
+        }
+    };
+
+    Ok(value)
+}
+
+// JSON files are parsed as JSON, anything else as YAML
+fn read_file(path: &str) -> Result<JsonValue, Error> {
+    let content = if path == "-" {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|err| Error::File(err.to_string()))?;
+        content
+    } else {
+        fs::read_to_string(path).map_err(|err| Error::File(format!("{}: {}", path, err)))?
+    };
+
+    if path.ends_with(".json") {
+        serde_json::from_str(&content).map_err(|err| Error::File(format!("{}: {}", path, err)))
+    } else {
+        serde_yaml::from_str(&content).map_err(|err| Error::File(format!("{}: {}", path, err)))
+    }
+}
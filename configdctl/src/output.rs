@@ -0,0 +1,88 @@
+use clap::ValueEnum;
+use configd_client::{Error as ClientError, Violation};
+use serde_json::Value as JsonValue;
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::errors::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Output {
+    Json,
+    Yaml,
+}
+
+impl Output {
+    pub fn render(&self, value: &JsonValue) -> String {
+        match self {
+            Output::Json => serde_json::to_string_pretty(value).unwrap(),
+            Output::Yaml => serde_yaml::to_string(value).unwrap(),
+        }
+    }
+}
+
+// Violations are listed by path, one per line
+pub fn render_diffs(diffs: &BTreeMap<String, Vec<Violation>>, indent: usize) -> String {
+    let mut out = String::new();
+    let pad = " ".repeat(indent);
+
+    for (path, violations) in diffs {
+        let _ = writeln!(out, "{}{}", pad, if path.is_empty() { "." } else { path });
+        for violation in violations {
+            let _ = writeln!(
+                out,
+                "{}  - {}: {}",
+                pad, violation.reason, violation.message
+            );
+        }
+    }
+
+    out
+}
+
+pub fn render_error(err: &Error) -> String {
+    let mut out = format!("error: {}\n", err);
+
+    if let Error::Client(ClientError::Api {
+        diffs,
+        affected_configs,
+        ..
+    }) = err
+    {
+        out.push_str(&render_diffs(diffs, 2));
+
+        for (config_id, diffs) in affected_configs {
+            let _ = writeln!(out, "  config {}", config_id);
+            out.push_str(&render_diffs(diffs, 4));
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs() {
+        let violation = |reason: &str, message: &str| Violation {
+            reason: reason.to_string(),
+            message: message.to_string(),
+        };
+
+        let mut diffs = BTreeMap::new();
+        diffs.insert(
+            "port".to_string(),
+            vec![violation("mismatched_kinds", "expected int, found string")],
+        );
+        diffs.insert(
+            "".to_string(),
+            vec![violation("missing_prop", "host is required")],
+        );
+
+        assert_eq!(
+            render_diffs(&diffs, 2),
+            "  .\n    - missing_prop: host is required\n  port\n    - mismatched_kinds: expected int, found string\n"
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use serde::Deserialize;
+use std::{collections::BTreeMap, env, fs, path::PathBuf};
+
+use crate::errors::Error;
+
+const DEFAULT_URL: &str = "http://localhost:8080";
+
+// Server settings selected with --profile
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Profile {
+    pub url: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Profiles {
+    default_profile: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+impl Profiles {
+    // CONFIGDCTL_CONFIG or ~/.config/configdctl/config.toml. A missing file
+    // means no profiles.
+    pub fn load() -> Result<Profiles, Error> {
+        let path = match env::var("CONFIGDCTL_CONFIG") {
+            Ok(path) => PathBuf::from(path),
+            Err(_) => match env::var("HOME") {
+                Ok(home) => PathBuf::from(home).join(".config/configdctl/config.toml"),
+                Err(_) => return Ok(Profiles::default()),
+            },
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(content) => Profiles::parse(&content),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Profiles::default()),
+            Err(err) => Err(Error::Profiles(format!("{}: {}", path.display(), err))),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Profiles, Error> {
+        toml::from_str(content).map_err(|err| Error::Profiles(err.to_string()))
+    }
+
+    // Flags take precedence over the profile
+    pub fn resolve(
+        &self,
+        name: Option<&str>,
+        url: Option<String>,
+        password: Option<String>,
+    ) -> Result<Profile, Error> {
+        let profile = match name.or(self.default_profile.as_deref()) {
+            Some(name) => self
+                .profiles
+                .get(name)
+                .cloned()
+                .ok_or_else(|| Error::Profiles(format!("profile not found: {}", name)))?,
+            None => Profile::default(),
+        };
+
+        Ok(Profile {
+            url: url
+                .or(profile.url)
+                .or_else(|| Some(DEFAULT_URL.to_string())),
+            password: password.or(profile.password),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve() {
+        let profiles = Profiles::parse(
+            r#"
+            default_profile = "local"
+
+            [profiles.local]
+            url = "http://localhost:8080"
+
+            [profiles.prod]
+            url = "https://configd.example.com"
+            password = "secret"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            profiles.resolve(None, None, None).unwrap(),
+            Profile {
+                url: Some("http://localhost:8080".to_string()),
+                password: None,
+            }
+        );
+        assert_eq!(
+            profiles
+                .resolve(Some("prod"), None, Some("other".to_string()))
+                .unwrap(),
+            Profile {
+                url: Some("https://configd.example.com".to_string()),
+                password: Some("other".to_string()),
+            }
+        );
+        assert!(profiles.resolve(Some("stg"), None, None).is_err());
+
+        assert_eq!(
+            Profiles::default().resolve(None, None, None).unwrap().url,
+            Some(DEFAULT_URL.to_string())
+        );
+    }
+}
@@ -0,0 +1,67 @@
+#![allow(dead_code)]
+
+use serde::Deserialize;
+use std::time::Duration;
+
+use configd_client::{Client, ClientConfig};
+
+#[derive(Debug, Deserialize)]
+struct Database {
+    name: String,
+    host: String,
+    port: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Limits {
+    rate_limit: f64,
+    page: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct MyConfig {
+    env: String,
+    databases: Vec<Database>,
+    limits: Limits,
+}
+
+#[tokio::main]
+async fn main() {
+    let client = Client::new(ClientConfig {
+        url: "http://localhost:8080".to_string(),
+        source: "Example".to_string(),
+        instance: format!("instance#{}", std::process::id()),
+        password: Some("passwd123".to_string()),
+        cache_dir: Some(std::env::temp_dir().join("configd")),
+    })
+    .unwrap();
+
+    let _watch = client
+        .watch(
+            "custom-schema",
+            "dev",
+            Duration::from_secs(2),
+            |res| match res {
+                Ok(config) => {
+                    let valid = if config.valid { "VALID" } else { "INVALID" };
+
+                    println!("##### {} ({}) - {}", config.name, config.id, valid);
+                    println!("· Checksum: {}", config.checksum);
+                    println!("· Accesses: {}", config.accesses.len());
+                    println!("· Updated at: {}", config.updated_at);
+                    println!("· Version: {}", config.version);
+
+                    match config.deserialize::<MyConfig>() {
+                        Ok(my_config) => println!("· Data: {:?}", my_config),
+                        Err(err) => println!("· Error: {}", err),
+                    }
+                }
+                Err(err) => println!("error: {} ({:?})", err, err.code()),
+            },
+        )
+        .unwrap();
+
+    tokio::signal::ctrl_c().await.unwrap();
+
+    println!("DONE");
+}
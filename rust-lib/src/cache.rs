@@ -0,0 +1,144 @@
+use std::path::PathBuf;
+use tokio::fs;
+
+use crate::{config::Config, errors::Error};
+
+// Last known good configs, stored as JSON files by schema and config
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    pub fn new(dir: PathBuf) -> DiskCache {
+        DiskCache { dir }
+    }
+
+    pub async fn load(&self, schema_id: &str, config_id: &str) -> Result<Option<Config>, Error> {
+        match fs::read(self.path(schema_id, config_id)).await {
+            Ok(content) => Ok(Some(
+                serde_json::from_slice(&content).map_err(Error::Serde)?,
+            )),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Error::Cache(err)),
+        }
+    }
+
+    // Stored under the requested ids, whatever the server returned. Invalid
+    // configs are not stored.
+    pub async fn store(
+        &self,
+        schema_id: &str,
+        config_id: &str,
+        config: &Config,
+    ) -> Result<(), Error> {
+        if !config.valid {
+            return Ok(());
+        }
+
+        let path = self.path(schema_id, config_id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.map_err(Error::Cache)?;
+        }
+
+        // Written to a temporary file first so readers never see partial content
+        let tmp_path = path.with_extension("json.tmp");
+        let content = serde_json::to_vec(config).map_err(Error::Serde)?;
+        fs::write(&tmp_path, content).await.map_err(Error::Cache)?;
+        fs::rename(&tmp_path, &path).await.map_err(Error::Cache)?;
+
+        Ok(())
+    }
+
+    fn path(&self, schema_id: &str, config_id: &str) -> PathBuf {
+        self.dir
+            .join(file_name(schema_id))
+            .join(format!("{}.json", file_name(config_id)))
+    }
+}
+
+// Ids are percent-encoded so they cannot leave the cache directory. A leading
+// dot is encoded too, for "." and "..".
+fn file_name(id: &str) -> String {
+    let mut name = String::with_capacity(id.len());
+    for (i, byte) in id.bytes().enumerate() {
+        if byte.is_ascii_alphanumeric()
+            || matches!(byte, b'-' | b'_' | b'#')
+            || (byte == b'.' && i > 0)
+        {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Utc;
+    use serde_json::json;
+    use std::process;
+
+    fn config(valid: bool, version: i64) -> Config {
+        Config {
+            schema_id: "schema".to_string(),
+            id: "dev".to_string(),
+            name: "Dev".to_string(),
+            data: json!({ "port": 8080 }),
+            valid,
+            checksum: "checksum".to_string(),
+            requires_password: false,
+            accesses: Vec::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            version,
+        }
+    }
+
+    #[tokio::test]
+    async fn store_and_load() {
+        let dir = std::env::temp_dir().join(format!("configd-client-{}", process::id()));
+        let cache = DiskCache::new(dir.clone());
+
+        assert!(cache.load("schema", "dev").await.unwrap().is_none());
+
+        cache
+            .store("schema", "dev", &config(true, 1))
+            .await
+            .unwrap();
+        assert_eq!(
+            cache.load("schema", "dev").await.unwrap().unwrap().version,
+            1
+        );
+
+        // The last valid config is kept
+        cache
+            .store("schema", "dev", &config(false, 2))
+            .await
+            .unwrap();
+        assert_eq!(
+            cache.load("schema", "dev").await.unwrap().unwrap().version,
+            1
+        );
+
+        fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[test]
+    fn paths_stay_in_dir() {
+        let cache = DiskCache::new(PathBuf::from("/cache"));
+
+        assert_eq!(
+            cache.path("schema#01", "dev.eu"),
+            PathBuf::from("/cache/schema#01/dev.eu.json")
+        );
+        assert_eq!(
+            cache.path("..", "../../etc/passwd"),
+            PathBuf::from("/cache/%2E./%2E.%2F..%2Fetc%2Fpasswd.json")
+        );
+    }
+}
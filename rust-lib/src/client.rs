@@ -0,0 +1,897 @@
+use chrono::{DateTime, Utc};
+use reqwest::{header, Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+
+use crate::{
+    cache::DiskCache,
+    config::Config,
+    errors::{Error, ErrorCode, Violation},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct ClientConfig {
+    pub url: String,
+    pub source: String,
+    pub instance: String,
+    pub password: Option<String>,
+    // Directory where the last known good configs are stored
+    pub cache_dir: Option<PathBuf>,
+}
+
+// Filters, sorting and pagination of the schema listing
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ListSchemasQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_invalid_configs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_since: Option<DateTime<Utc>>,
+    // name, created_at or updated_at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    // asc or desc
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+    // next_cursor of the previous page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    // Comma-separated relations to embed, all of them when missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include: Option<String>,
+}
+
+// Filters, sorting and pagination of the config listing of a schema
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ListConfigsQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requires_password: Option<bool>,
+    // name, created_at or updated_at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    // asc or desc
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+    // next_cursor of the previous page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+// Filters, sorting and pagination of deleted schemas and configs
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ListTrashQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    // name, created_at or updated_at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    // asc or desc
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+    // next_cursor of the previous page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ErrorDto {
+    code: String,
+    message: String,
+    #[serde(default)]
+    diffs: BTreeMap<String, Vec<Violation>>,
+    #[serde(default)]
+    affected_configs: BTreeMap<String, BTreeMap<String, Vec<Violation>>>,
+}
+
+#[derive(Clone)]
+pub struct Client {
+    url: String,
+    source: String,
+    instance: String,
+    password: Option<String>,
+    cache: Option<DiskCache>,
+    http_client: reqwest::Client,
+}
+
+impl Client {
+    pub fn new(config: ClientConfig) -> Result<Client, Error> {
+        if config.url.is_empty() {
+            return Err(Error::EmptyUrl);
+        }
+
+        if config.source.is_empty() {
+            return Err(Error::EmptySource);
+        }
+
+        if config.instance.is_empty() {
+            return Err(Error::EmptyInstance);
+        }
+
+        Ok(Client {
+            url: config.url.trim_end_matches('/').to_string(),
+            source: config.source,
+            instance: config.instance,
+            password: config.password.filter(|password| !password.is_empty()),
+            cache: config.cache_dir.map(DiskCache::new),
+            http_client: reqwest::Client::new(),
+        })
+    }
+
+    // Falls back to the last known good config when the server is unavailable
+    pub async fn get_config(&self, schema_id: &str, config_id: &str) -> Result<Config, Error> {
+        validate_ids(schema_id, config_id)?;
+
+        let res = self
+            .fetch_config(schema_id, config_id, None)
+            .await
+            .and_then(|res| res.ok_or(Error::UnexpectedNotModified));
+
+        match res {
+            Ok((config, _)) => {
+                self.store(schema_id, config_id, &config).await?;
+                Ok(config)
+            }
+            Err(err) => match self.fallback(schema_id, config_id, &err).await {
+                Some(config) => Ok(config),
+                None => Err(err),
+            },
+        }
+    }
+
+    // Polls the config in the background. The handler is called with the first
+    // config, every time it changes and with every error.
+    pub fn watch<F>(
+        &self,
+        schema_id: &str,
+        config_id: &str,
+        interval: Duration,
+        mut handler: F,
+    ) -> Result<Watch, Error>
+    where
+        F: FnMut(Result<Config, Error>) + Send + 'static,
+    {
+        validate_ids(schema_id, config_id)?;
+
+        if interval < Duration::from_secs(1) || interval > Duration::from_secs(60) {
+            return Err(Error::InvalidInterval);
+        }
+
+        let client = self.clone();
+        let schema_id = schema_id.to_string();
+        let config_id = config_id.to_string();
+        let latest = Arc::new(RwLock::new(None));
+        let watched = latest.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            let mut etag: Option<String> = None;
+            let mut last: Option<Config> = None;
+
+            loop {
+                interval.tick().await;
+
+                let config = match client
+                    .fetch_config(&schema_id, &config_id, etag.as_deref())
+                    .await
+                {
+                    Ok(Some((config, new_etag))) => {
+                        etag = new_etag;
+
+                        if let Err(err) = client.store(&schema_id, &config_id, &config).await {
+                            handler(Err(err));
+                        }
+
+                        config
+                    }
+                    Ok(None) => continue,
+                    Err(err) => {
+                        // The last known good config is only used on start
+                        if last.is_none() {
+                            if let Some(config) =
+                                client.fallback(&schema_id, &config_id, &err).await
+                            {
+                                last = Some(config.clone());
+                                *watched.write().unwrap() = Some(config.clone());
+                                handler(Ok(config));
+                                continue;
+                            }
+                        }
+
+                        handler(Err(err));
+                        continue;
+                    }
+                };
+
+                if let Some(last) = &last {
+                    if !last.has_changed(&config) {
+                        continue;
+                    }
+                }
+
+                last = Some(config.clone());
+                *watched.write().unwrap() = Some(config.clone());
+                handler(Ok(config));
+            }
+        });
+
+        Ok(Watch { latest, handle })
+    }
+
+    // Schemas
+    pub async fn list_schemas(&self, query: &ListSchemasQuery) -> Result<JsonValue, Error> {
+        self.send(self.request(Method::GET, "/schemas").query(query))
+            .await
+    }
+
+    pub async fn get_schema(&self, schema_id: &str) -> Result<JsonValue, Error> {
+        self.send(self.request(Method::GET, &format!("/schemas/{}", schema_id)))
+            .await
+    }
+
+    pub async fn create_schema(&self, body: &JsonValue) -> Result<JsonValue, Error> {
+        self.send(self.request(Method::POST, "/schemas").json(body))
+            .await
+    }
+
+    pub async fn update_schema(
+        &self,
+        schema_id: &str,
+        body: &JsonValue,
+        force: bool,
+        dry_run: bool,
+    ) -> Result<JsonValue, Error> {
+        self.send(
+            self.request(Method::PUT, &format!("/schemas/{}", schema_id))
+                .query(&[("force", force), ("dry_run", dry_run)])
+                .json(body),
+        )
+        .await
+    }
+
+    pub async fn delete_schema(&self, schema_id: &str) -> Result<JsonValue, Error> {
+        self.send(self.request(Method::DELETE, &format!("/schemas/{}", schema_id)))
+            .await
+    }
+
+    pub async fn list_configs(
+        &self,
+        schema_id: &str,
+        query: &ListConfigsQuery,
+    ) -> Result<JsonValue, Error> {
+        self.send(
+            self.request(Method::GET, &format!("/schemas/{}/configs", schema_id))
+                .query(query),
+        )
+        .await
+    }
+
+    pub async fn validate_config(
+        &self,
+        schema_id: &str,
+        body: &JsonValue,
+    ) -> Result<JsonValue, Error> {
+        self.send(
+            self.request(Method::POST, &format!("/schemas/{}/validate", schema_id))
+                .json(body),
+        )
+        .await
+    }
+
+    // Configs
+    pub async fn create_config(
+        &self,
+        schema_id: &str,
+        body: &JsonValue,
+    ) -> Result<JsonValue, Error> {
+        self.send(
+            self.request(Method::POST, &format!("/schemas/{}/configs", schema_id))
+                .json(body),
+        )
+        .await
+    }
+
+    pub async fn update_config(
+        &self,
+        schema_id: &str,
+        config_id: &str,
+        body: &JsonValue,
+    ) -> Result<JsonValue, Error> {
+        self.send(
+            self.request(
+                Method::PUT,
+                &format!("/schemas/{}/configs/{}", schema_id, config_id),
+            )
+            .json(body),
+        )
+        .await
+    }
+
+    pub async fn delete_config(
+        &self,
+        schema_id: &str,
+        config_id: &str,
+    ) -> Result<JsonValue, Error> {
+        self.send(self.request(
+            Method::DELETE,
+            &format!("/schemas/{}/configs/{}", schema_id, config_id),
+        ))
+        .await
+    }
+
+    // The configured password is used as the current one
+    pub async fn change_config_password(
+        &self,
+        schema_id: &str,
+        config_id: &str,
+        new_password: &str,
+    ) -> Result<JsonValue, Error> {
+        self.send(
+            self.request(
+                Method::POST,
+                &format!("/schemas/{}/configs/{}/password", schema_id, config_id),
+            )
+            .json(&json!({ "new_password": new_password })),
+        )
+        .await
+    }
+
+    // The configured password is used for the other config when it has no
+    // password of its own
+    pub async fn diff_configs(
+        &self,
+        schema_id: &str,
+        config_id: &str,
+        other_config_id: &str,
+        other_password: Option<&str>,
+        populate: bool,
+    ) -> Result<JsonValue, Error> {
+        let mut req = self
+            .request(
+                Method::GET,
+                &format!(
+                    "/schemas/{}/configs/{}/diff/{}",
+                    schema_id, config_id, other_config_id
+                ),
+            )
+            .query(&[("populate", populate)]);
+
+        if let Some(other_password) = other_password {
+            req = req.header("X-Configd-Other-Password", other_password);
+        }
+
+        self.send(req).await
+    }
+
+    // Trash
+    pub async fn list_deleted_schemas(&self, query: &ListTrashQuery) -> Result<JsonValue, Error> {
+        self.send(self.request(Method::GET, "/trash/schemas").query(query))
+            .await
+    }
+
+    pub async fn restore_schema(&self, schema_id: &str) -> Result<JsonValue, Error> {
+        self.send(self.request(
+            Method::POST,
+            &format!("/trash/schemas/{}/restore", schema_id),
+        ))
+        .await
+    }
+
+    // Deleted configs of the schema are purged with it
+    pub async fn purge_schema(&self, schema_id: &str) -> Result<JsonValue, Error> {
+        self.send(self.request(Method::DELETE, &format!("/trash/schemas/{}", schema_id)))
+            .await
+    }
+
+    pub async fn list_deleted_configs(
+        &self,
+        schema_id: &str,
+        query: &ListTrashQuery,
+    ) -> Result<JsonValue, Error> {
+        self.send(
+            self.request(
+                Method::GET,
+                &format!("/trash/schemas/{}/configs", schema_id),
+            )
+            .query(query),
+        )
+        .await
+    }
+
+    pub async fn restore_config(
+        &self,
+        schema_id: &str,
+        config_id: &str,
+    ) -> Result<JsonValue, Error> {
+        self.send(self.request(
+            Method::POST,
+            &format!("/trash/schemas/{}/configs/{}/restore", schema_id, config_id),
+        ))
+        .await
+    }
+
+    pub async fn purge_config(&self, schema_id: &str, config_id: &str) -> Result<JsonValue, Error> {
+        self.send(self.request(
+            Method::DELETE,
+            &format!("/trash/schemas/{}/configs/{}", schema_id, config_id),
+        ))
+        .await
+    }
+
+    // Returns None when the config was not modified, which only the etag
+    // allows
+    async fn fetch_config(
+        &self,
+        schema_id: &str,
+        config_id: &str,
+        etag: Option<&str>,
+    ) -> Result<Option<(Config, Option<String>)>, Error> {
+        let mut req = self
+            .request(
+                Method::GET,
+                &format!("/schemas/{}/configs/{}", schema_id, config_id),
+            )
+            .query(&[("populate", "true")]);
+
+        if let Some(etag) = etag {
+            req = req.header(header::IF_NONE_MATCH, etag);
+        }
+
+        let res = req.send().await.map_err(Error::Http)?;
+
+        if res.status() == StatusCode::NOT_MODIFIED {
+            return match etag {
+                Some(_) => Ok(None),
+                None => Err(Error::UnexpectedNotModified),
+            };
+        }
+
+        if !res.status().is_success() {
+            return Err(api_error(res).await);
+        }
+
+        let etag = res
+            .headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.to_string());
+        let body = res.bytes().await.map_err(Error::Http)?;
+        let config = serde_json::from_slice(&body).map_err(Error::Serde)?;
+
+        Ok(Some((config, etag)))
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut req = self
+            .http_client
+            .request(method, format!("{}{}", self.url, path))
+            .header(header::ACCEPT, "application/json")
+            .header("X-Configd-Source", &self.source)
+            .header("X-Configd-Instance", &self.instance);
+
+        if let Some(password) = &self.password {
+            req = req.header("X-Configd-Password", password);
+        }
+
+        req
+    }
+
+    async fn send(&self, req: RequestBuilder) -> Result<JsonValue, Error> {
+        let res = req.send().await.map_err(Error::Http)?;
+
+        if !res.status().is_success() {
+            return Err(api_error(res).await);
+        }
+
+        let body = res.bytes().await.map_err(Error::Http)?;
+        if body.is_empty() {
+            return Ok(JsonValue::Null);
+        }
+
+        serde_json::from_slice(&body).map_err(Error::Serde)
+    }
+
+    async fn store(&self, schema_id: &str, config_id: &str, config: &Config) -> Result<(), Error> {
+        match &self.cache {
+            Some(cache) => cache.store(schema_id, config_id, config).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn fallback(&self, schema_id: &str, config_id: &str, err: &Error) -> Option<Config> {
+        if !err.is_unavailable() {
+            return None;
+        }
+
+        match &self.cache {
+            Some(cache) => cache.load(schema_id, config_id).await.ok().flatten(),
+            None => None,
+        }
+    }
+}
+
+// Handle of a watched config. Polling stops when it is dropped.
+pub struct Watch {
+    latest: Arc<RwLock<Option<Config>>>,
+    handle: JoinHandle<()>,
+}
+
+impl Watch {
+    pub fn latest(&self) -> Option<Config> {
+        self.latest.read().unwrap().clone()
+    }
+
+    pub fn stop(self) {}
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn api_error(res: Response) -> Error {
+    let status = res.status().as_u16();
+    let body = match res.bytes().await {
+        Ok(body) => body,
+        Err(err) => return Error::Http(err),
+    };
+
+    match serde_json::from_slice::<ErrorDto>(&body) {
+        Ok(dto) => Error::Api {
+            status,
+            code: ErrorCode::from(dto.code.as_str()),
+            message: dto.message,
+            diffs: dto.diffs,
+            affected_configs: dto.affected_configs,
+        },
+        Err(_) => Error::Api {
+            status,
+            code: ErrorCode::Unknown(status.to_string()),
+            message: String::from_utf8_lossy(&body).to_string(),
+            diffs: BTreeMap::new(),
+            affected_configs: BTreeMap::new(),
+        },
+    }
+}
+
+fn validate_ids(schema_id: &str, config_id: &str) -> Result<(), Error> {
+    if schema_id.is_empty() {
+        return Err(Error::EmptySchemaId);
+    }
+
+    if config_id.is_empty() {
+        return Err(Error::EmptyConfigId);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{
+        extract::Extension,
+        http::{HeaderMap, StatusCode as HttpStatusCode},
+        response::IntoResponse,
+        routing::get,
+        Router,
+    };
+    use std::{net::TcpListener, process, sync::Mutex};
+    use tokio::sync::mpsc;
+
+    // Status, etag and body of every response of the mock server in turn, the
+    // last one is repeated
+    type Responses = Vec<(u16, Option<&'static str>, String)>;
+
+    struct MockServer {
+        url: String,
+        // If-None-Match of every request
+        etags: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    fn serve(responses: Responses) -> MockServer {
+        let etags = Arc::new(Mutex::new(Vec::new()));
+        let requests = etags.clone();
+        let responses = Arc::new(responses);
+
+        let app = Router::new()
+            .route(
+                "/schemas/:schema_id/configs/:config_id",
+                get(
+                    |Extension(requests): Extension<Arc<Mutex<Vec<Option<String>>>>>,
+                     headers: HeaderMap| async move {
+                        let etag = headers
+                            .get(header::IF_NONE_MATCH)
+                            .map(|etag| etag.to_str().unwrap().to_string());
+                        let mut requests = requests.lock().unwrap();
+                        requests.push(etag);
+
+                        let (status, etag, body) =
+                            &responses[(requests.len() - 1).min(responses.len() - 1)];
+                        let mut res_headers = HeaderMap::new();
+                        if let Some(etag) = etag {
+                            res_headers.insert(header::ETAG, etag.parse().unwrap());
+                        }
+
+                        (
+                            HttpStatusCode::from_u16(*status).unwrap(),
+                            res_headers,
+                            body.clone(),
+                        )
+                            .into_response()
+                    },
+                ),
+            )
+            .layer(Extension(requests));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        MockServer { url, etags }
+    }
+
+    fn config_body(checksum: &str, version: i64) -> String {
+        json!({
+            "schema_id": "schema",
+            "id": "dev",
+            "name": "Development",
+            "data": { "version": version },
+            "valid": true,
+            "checksum": checksum,
+            "requires_password": false,
+            "accesses": [],
+            "created_at": "2022-01-01T00:00:00Z",
+            "updated_at": "2022-01-01T00:00:00Z",
+            "version": version,
+        })
+        .to_string()
+    }
+
+    fn client_config() -> ClientConfig {
+        ClientConfig {
+            url: "http://localhost:8080/".to_string(),
+            source: "Example".to_string(),
+            instance: "instance#01".to_string(),
+            ..ClientConfig::default()
+        }
+    }
+
+    #[test]
+    fn new() {
+        assert!(matches!(
+            Client::new(ClientConfig {
+                url: String::new(),
+                ..client_config()
+            }),
+            Err(Error::EmptyUrl)
+        ));
+        assert!(matches!(
+            Client::new(ClientConfig {
+                source: String::new(),
+                ..client_config()
+            }),
+            Err(Error::EmptySource)
+        ));
+
+        let client = Client::new(client_config()).unwrap();
+        assert_eq!(client.url, "http://localhost:8080");
+    }
+
+    #[tokio::test]
+    async fn watch_validation() {
+        let client = Client::new(client_config()).unwrap();
+
+        assert!(matches!(
+            client.watch("", "dev", Duration::from_secs(1), |_| {}),
+            Err(Error::EmptySchemaId)
+        ));
+        assert!(matches!(
+            client.watch("schema", "dev", Duration::from_millis(10), |_| {}),
+            Err(Error::InvalidInterval)
+        ));
+    }
+
+    #[tokio::test]
+    async fn watch() {
+        let server = serve(vec![
+            (200, Some("\"e1\""), config_body("c1", 1)),
+            (304, None, String::new()),
+            // Only the accesses changed
+            (200, Some("\"e2\""), config_body("c1", 1)),
+            (200, Some("\"e3\""), config_body("c2", 2)),
+        ]);
+        let client = Client::new(ClientConfig {
+            url: server.url.clone(),
+            ..client_config()
+        })
+        .unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let watch = client
+            .watch("schema", "dev", Duration::from_secs(1), move |res| {
+                tx.send(res).unwrap();
+            })
+            .unwrap();
+
+        let config = rx.recv().await.unwrap().unwrap();
+        assert_eq!(config.checksum, "c1");
+
+        // Not modified and unchanged configs are skipped
+        let config = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.checksum, "c2");
+        assert_eq!(watch.latest().unwrap().checksum, "c2");
+
+        assert_eq!(
+            &server.etags.lock().unwrap()[..4],
+            &[
+                None,
+                Some("\"e1\"".to_string()),
+                Some("\"e1\"".to_string()),
+                Some("\"e2\"".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn watch_not_modified() {
+        let server = serve(vec![
+            (200, Some("\"e1\""), config_body("c1", 1)),
+            (304, None, String::new()),
+        ]);
+        let client = Client::new(ClientConfig {
+            url: server.url.clone(),
+            ..client_config()
+        })
+        .unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let watch = client
+            .watch("schema", "dev", Duration::from_secs(1), move |res| {
+                tx.send(res).unwrap();
+            })
+            .unwrap();
+        assert_eq!(rx.recv().await.unwrap().unwrap().version, 1);
+
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+
+        // The current config is kept
+        assert_eq!(server.etags.lock().unwrap()[1].as_deref(), Some("\"e1\""));
+        assert!(rx.try_recv().is_err());
+        assert_eq!(watch.latest().unwrap().version, 1);
+    }
+
+    #[tokio::test]
+    async fn get_config_fallback() {
+        let dir = std::env::temp_dir().join(format!("configd-client-fallback-{}", process::id()));
+        let server = serve(vec![(200, Some("\"e1\""), config_body("c1", 1))]);
+        let client = Client::new(ClientConfig {
+            url: server.url.clone(),
+            cache_dir: Some(dir.clone()),
+            ..client_config()
+        })
+        .unwrap();
+        assert_eq!(client.get_config("schema", "dev").await.unwrap().version, 1);
+
+        // Nothing listens on a released port
+        let url = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        let unreachable = Client::new(ClientConfig {
+            url,
+            cache_dir: Some(dir.clone()),
+            ..client_config()
+        })
+        .unwrap();
+        assert_eq!(
+            unreachable
+                .get_config("schema", "dev")
+                .await
+                .unwrap()
+                .checksum,
+            "c1"
+        );
+        assert!(matches!(
+            unreachable.get_config("schema", "prod").await,
+            Err(Error::Http(_))
+        ));
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn api_errors() {
+        let server = serve(vec![
+            (
+                404,
+                None,
+                json!({ "code": "config_not_found", "message": "config not found" }).to_string(),
+            ),
+            (
+                422,
+                None,
+                json!({
+                    "code": "invalid_config",
+                    "message": "invalid config",
+                    "diffs": { "port": [{ "reason": "type", "message": "not an integer" }] },
+                })
+                .to_string(),
+            ),
+            (502, None, "Bad Gateway".to_string()),
+        ]);
+        let client = Client::new(ClientConfig {
+            url: server.url.clone(),
+            ..client_config()
+        })
+        .unwrap();
+
+        match client.get_config("schema", "dev").await {
+            Err(Error::Api {
+                status,
+                code,
+                message,
+                ..
+            }) => {
+                assert_eq!(status, 404);
+                assert_eq!(code, ErrorCode::ConfigNotFound);
+                assert_eq!(message, "config not found");
+            }
+            res => panic!("unexpected result {:?}", res.map(|config| config.id)),
+        }
+
+        match client.get_config("schema", "dev").await {
+            Err(Error::Api { code, diffs, .. }) => {
+                assert_eq!(code, ErrorCode::InvalidConfig);
+                assert_eq!(diffs["port"][0].message, "not an integer");
+            }
+            res => panic!("unexpected result {:?}", res.map(|config| config.id)),
+        }
+
+        // Errors that are not the server's own keep the body
+        match client.get_config("schema", "dev").await {
+            Err(err @ Error::Api { .. }) => {
+                assert!(err.is_unavailable());
+                assert!(matches!(
+                    err,
+                    Error::Api { code: ErrorCode::Unknown(ref status), ref message, .. }
+                        if status == "502" && message == "Bad Gateway"
+                ));
+            }
+            res => panic!("unexpected result {:?}", res.map(|config| config.id)),
+        }
+    }
+}
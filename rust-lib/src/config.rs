@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::errors::Error;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Access {
+    pub source: String,
+    pub instance: String,
+    pub timestamp: DateTime<Utc>,
+    pub previous: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    pub schema_id: String,
+    pub id: String,
+    pub name: String,
+    pub data: JsonValue,
+    pub valid: bool,
+    pub checksum: String,
+    pub requires_password: bool,
+    pub accesses: Vec<Access>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
+}
+
+impl Config {
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        serde_json::from_value(self.data.clone()).map_err(Error::Serde)
+    }
+
+    // Accesses are ignored, they change on every read
+    pub fn has_changed(&self, other: &Config) -> bool {
+        self.checksum != other.checksum || self.version != other.version
+    }
+}
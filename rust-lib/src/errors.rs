@@ -0,0 +1,247 @@
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt, io};
+use thiserror::Error;
+
+// Validation failure of a config property
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Violation {
+    pub reason: String,
+    pub message: String,
+}
+
+// Mirrors the codes of the errors returned by the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorCode {
+    EmptyId,
+    EmptyName,
+    EmptyInterval,
+    InvalidTimestamps,
+    InvalidVersion,
+    Unauthorized,
+    RateLimited,
+    MismatchedKinds,
+    InvalidArray,
+    UnknownRootProp,
+    InvalidRule,
+    InvalidPath,
+    InvalidMigration,
+    SchemaNotFound,
+    SchemaAlreadyExists,
+    SchemaContainsConfigs,
+    SchemaModified,
+    ConfigNotFound,
+    ConfigAlreadyExists,
+    ConfigModified,
+    PageOutOfRange,
+    InvalidLimit,
+    InvalidCursor,
+    InvalidSort,
+    InvalidInclude,
+    InvalidPassword,
+    BreakingSchemaChange,
+    InvalidConfig,
+    UnsupportedFormat,
+    UnrepresentableValue,
+    UnsupportedMediaType,
+    InvalidBody,
+    PayloadTooLarge,
+    InvalidPatch,
+    PatchTestFailed,
+    InvalidEvent,
+    Serde,
+    Database,
+    Unknown(String),
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ErrorCode::EmptyId => "empty_id",
+            ErrorCode::EmptyName => "empty_name",
+            ErrorCode::EmptyInterval => "empty_interval",
+            ErrorCode::InvalidTimestamps => "invalid_timestamps",
+            ErrorCode::InvalidVersion => "invalid_version",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::MismatchedKinds => "mismatched_kinds",
+            ErrorCode::InvalidArray => "invalid_array",
+            ErrorCode::UnknownRootProp => "unknown_root_prop",
+            ErrorCode::InvalidRule => "invalid_rule",
+            ErrorCode::InvalidPath => "invalid_path",
+            ErrorCode::InvalidMigration => "invalid_migration",
+            ErrorCode::SchemaNotFound => "schema_not_found",
+            ErrorCode::SchemaAlreadyExists => "schema_already_exists",
+            ErrorCode::SchemaContainsConfigs => "schema_contains_configs",
+            ErrorCode::SchemaModified => "schema_modified",
+            ErrorCode::ConfigNotFound => "config_not_found",
+            ErrorCode::ConfigAlreadyExists => "config_already_exists",
+            ErrorCode::ConfigModified => "config_modified",
+            ErrorCode::PageOutOfRange => "page_out_of_range",
+            ErrorCode::InvalidLimit => "invalid_limit",
+            ErrorCode::InvalidCursor => "invalid_cursor",
+            ErrorCode::InvalidSort => "invalid_sort",
+            ErrorCode::InvalidInclude => "invalid_include",
+            ErrorCode::InvalidPassword => "invalid_password",
+            ErrorCode::BreakingSchemaChange => "breaking_schema_change",
+            ErrorCode::InvalidConfig => "invalid_config",
+            ErrorCode::UnsupportedFormat => "unsupported_format",
+            ErrorCode::UnrepresentableValue => "unrepresentable_value",
+            ErrorCode::UnsupportedMediaType => "unsupported_media_type",
+            ErrorCode::InvalidBody => "invalid_body",
+            ErrorCode::PayloadTooLarge => "payload_too_large",
+            ErrorCode::InvalidPatch => "invalid_patch",
+            ErrorCode::PatchTestFailed => "patch_test_failed",
+            ErrorCode::InvalidEvent => "invalid_event",
+            ErrorCode::Serde => "serde",
+            ErrorCode::Database => "database",
+            ErrorCode::Unknown(code) => code,
+        }
+    }
+}
+
+impl From<&str> for ErrorCode {
+    fn from(code: &str) -> ErrorCode {
+        match code {
+            "empty_id" => ErrorCode::EmptyId,
+            "empty_name" => ErrorCode::EmptyName,
+            "empty_interval" => ErrorCode::EmptyInterval,
+            "invalid_timestamps" => ErrorCode::InvalidTimestamps,
+            "invalid_version" => ErrorCode::InvalidVersion,
+            "unauthorized" => ErrorCode::Unauthorized,
+            "rate_limited" => ErrorCode::RateLimited,
+            "mismatched_kinds" => ErrorCode::MismatchedKinds,
+            "invalid_array" => ErrorCode::InvalidArray,
+            "unknown_root_prop" => ErrorCode::UnknownRootProp,
+            "invalid_rule" => ErrorCode::InvalidRule,
+            "invalid_path" => ErrorCode::InvalidPath,
+            "invalid_migration" => ErrorCode::InvalidMigration,
+            "schema_not_found" => ErrorCode::SchemaNotFound,
+            "schema_already_exists" => ErrorCode::SchemaAlreadyExists,
+            "schema_contains_configs" => ErrorCode::SchemaContainsConfigs,
+            "schema_modified" => ErrorCode::SchemaModified,
+            "config_not_found" => ErrorCode::ConfigNotFound,
+            "config_already_exists" => ErrorCode::ConfigAlreadyExists,
+            "config_modified" => ErrorCode::ConfigModified,
+            "page_out_of_range" => ErrorCode::PageOutOfRange,
+            "invalid_limit" => ErrorCode::InvalidLimit,
+            "invalid_cursor" => ErrorCode::InvalidCursor,
+            "invalid_sort" => ErrorCode::InvalidSort,
+            "invalid_include" => ErrorCode::InvalidInclude,
+            "invalid_password" => ErrorCode::InvalidPassword,
+            "breaking_schema_change" => ErrorCode::BreakingSchemaChange,
+            "invalid_config" => ErrorCode::InvalidConfig,
+            "unsupported_format" => ErrorCode::UnsupportedFormat,
+            "unrepresentable_value" => ErrorCode::UnrepresentableValue,
+            "unsupported_media_type" => ErrorCode::UnsupportedMediaType,
+            "invalid_body" => ErrorCode::InvalidBody,
+            "payload_too_large" => ErrorCode::PayloadTooLarge,
+            "invalid_patch" => ErrorCode::InvalidPatch,
+            "patch_test_failed" => ErrorCode::PatchTestFailed,
+            "invalid_event" => ErrorCode::InvalidEvent,
+            "serde" => ErrorCode::Serde,
+            "database" => ErrorCode::Database,
+            code => ErrorCode::Unknown(code.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    // Client
+    #[error("empty url")]
+    EmptyUrl,
+    #[error("empty source")]
+    EmptySource,
+    #[error("empty instance")]
+    EmptyInstance,
+    #[error("schema_id is required")]
+    EmptySchemaId,
+    #[error("config_id is required")]
+    EmptyConfigId,
+    #[error("interval must be between 1 second and 1 minute")]
+    InvalidInterval,
+
+    // Server
+    #[error("{code}: {message}")]
+    Api {
+        status: u16,
+        code: ErrorCode,
+        message: String,
+        // Violations by path of an invalid config
+        diffs: BTreeMap<String, Vec<Violation>>,
+        // Violations by config of a breaking schema change
+        affected_configs: BTreeMap<String, BTreeMap<String, Vec<Violation>>>,
+    },
+    // Sent by a broken server or proxy
+    #[error("not modified without an etag")]
+    UnexpectedNotModified,
+
+    // External
+    #[error("http: {0}")]
+    Http(#[source] reqwest::Error),
+    #[error("serde: {0}")]
+    Serde(#[source] serde_json::Error),
+    #[error("cache: {0}")]
+    Cache(#[source] io::Error),
+}
+
+impl Error {
+    pub fn code(&self) -> Option<&ErrorCode> {
+        match self {
+            Error::Api { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    // The server could not be reached or failed, so the last known good
+    // config can be used instead
+    pub fn is_unavailable(&self) -> bool {
+        match self {
+            Error::Http(_) => true,
+            Error::Api { status, .. } => *status >= 500,
+            Error::UnexpectedNotModified => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes() {
+        assert_eq!(
+            ErrorCode::from("config_not_found"),
+            ErrorCode::ConfigNotFound
+        );
+        assert_eq!(ErrorCode::ConfigNotFound.as_str(), "config_not_found");
+        assert_eq!(
+            ErrorCode::from("something_new"),
+            ErrorCode::Unknown("something_new".to_string())
+        );
+        assert_eq!(ErrorCode::from("something_new").as_str(), "something_new");
+    }
+
+    #[test]
+    fn unavailable() {
+        let api_error = |status| Error::Api {
+            status,
+            code: ErrorCode::Database,
+            message: "database error".to_string(),
+            diffs: BTreeMap::new(),
+            affected_configs: BTreeMap::new(),
+        };
+
+        assert!(api_error(500).is_unavailable());
+        assert!(!api_error(404).is_unavailable());
+        assert!(Error::UnexpectedNotModified.is_unavailable());
+        assert!(!Error::EmptyUrl.is_unavailable());
+    }
+}
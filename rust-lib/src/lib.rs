@@ -0,0 +1,8 @@
+mod cache;
+mod client;
+mod config;
+mod errors;
+
+pub use client::*;
+pub use config::*;
+pub use errors::*;